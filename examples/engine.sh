@@ -0,0 +1,8 @@
+# The engine cancels running requests once its input is closed, so we keep it open for a while.
+{
+    printf '%s\n' \
+        '{"jsonrpc": "2.0", "id": 1, "method": "validate_position", "params": {"position": "Herz-Solo von 0; EO GO HO SO EU GU HU SU | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __; ; ; 0000; 0; 0"}}' \
+        '{"jsonrpc": "2.0", "id": 2, "method": "rank_rules", "params": {"hand": "EO GO HO SO EU GU HU SU", "playerIndex": 0, "rules": ["Herz-Solo von 0", "Wenz von 0"]}}' \
+        '{"jsonrpc": "2.0", "id": 3, "method": "suggest_card", "params": {"position": "Herz-Solo von 0; EO GO HO SO EU GU HU SU | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __; ; ; 0000; 0; 0"}}'
+    sleep 30
+} | ./target/release/openschafkopf engine
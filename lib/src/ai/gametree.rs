@@ -441,8 +441,8 @@ impl<'rules, Pruner, TplStrategies, AlphaBetaPruner> SMinReachablePayoutBase<'ru
     }
 }
 
-macro_rules! define_and_impl_perminmaxstrategies{([$(($IsSome:ident, $emmstrategy:ident, $t_emmstrategy:ident, $ident_strategy:ident))*][$($ident_strategy_cmp:ident)*]) => {
-    #[derive(Clone, Copy)]
+macro_rules! define_and_impl_perminmaxstrategies{([$(($IsSome:ident, $emmstrategy:ident, $t_emmstrategy:ident, $ident_strategy:ident))*]) => {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum EMinMaxStrategy {
         $($emmstrategy,)*
     }
@@ -512,30 +512,15 @@ macro_rules! define_and_impl_perminmaxstrategies{([$(($IsSome:ident, $emmstrateg
             &[$((EMinMaxStrategy::$emmstrategy, (|slf: &Self| slf.$ident_strategy.as_ref().into_option()) as fn(&Self) -> Option<&T>),)*]
         }
         pub fn compare_canonical<PayoutStatsPayload: Ord+Copy>(&self, other: &Self, fn_loss_or_win: impl Fn(isize, PayoutStatsPayload)->std::cmp::Ordering) -> std::cmp::Ordering where T: Borrow<SPayoutStats<PayoutStatsPayload>> {
-            use std::cmp::Ordering::*;
-            fn compare_fractions((numerator_lhs, denominator_lhs): (u128, u128), (numerator_rhs, denominator_rhs): (u128, u128)) -> std::cmp::Ordering {
-                u128::cmp(&(numerator_lhs * denominator_rhs), &(denominator_lhs * numerator_rhs))
-            }
-            Equal
-                $(.then_with(|| self.$ident_strategy_cmp.as_ref().tuple_2(other.$ident_strategy_cmp.as_ref())
-                    .map_or_else(/*default*/|| Equal, |(lhs, rhs)| {
-                        let payoutstats_lhs = lhs.borrow();
-                        let payoutstats_rhs = rhs.borrow();
-                        let mapordn_lhs = payoutstats_lhs.counts(&fn_loss_or_win).map_into(|n| n.as_num::<u128>());
-                        let mapordn_rhs = payoutstats_rhs.counts(&fn_loss_or_win).map_into(|n| n.as_num::<u128>());
-                        let compare_winning_probability_internal = |ord| compare_fractions(
-                            (mapordn_lhs[ord], mapordn_lhs.iter().sum()),
-                            (mapordn_rhs[ord], mapordn_rhs.iter().sum()),
-                        );
-                        compare_winning_probability_internal(Greater)
-                            .then_with(|| compare_winning_probability_internal(Less).reverse())
-                            .then_with(|| match unwrap!(payoutstats_lhs.avg().partial_cmp(&payoutstats_rhs.avg())) {
-                                Greater => Greater,
-                                Less => Less,
-                                Equal => payoutstats_lhs.max().cmp(&payoutstats_rhs.max()),
-                            })
-                    })
-                ))*
+            compare_canonical(
+                &self.via_accessors().into_iter()
+                    .map(|(emmstrategy, t)| (emmstrategy, t.borrow()))
+                    .collect::<Vec<(_, &SPayoutStats<PayoutStatsPayload>)>>(),
+                &other.via_accessors().into_iter()
+                    .map(|(emmstrategy, t)| (emmstrategy, t.borrow()))
+                    .collect::<Vec<(_, &SPayoutStats<PayoutStatsPayload>)>>(),
+                fn_loss_or_win,
+            )
         }
     }
 
@@ -634,9 +619,58 @@ define_and_impl_perminmaxstrategies!(
         (IsSomeMaxSelfishMax, MaxSelfishMax, TMaxSelfishMax, omaxselfishmax)
         (IsSomeMaxMax, Max, TMax, omaxmax)
     ]
-    [omaxselfishmin omaxselfishmax omaxmin omaxmax ominmin]
 );
 
+// Compares payout stats of the strategies present in both lhs and rhs, more important strategies first:
+// Higher winning probability is better, then lower losing probability, then higher average, then higher maximum.
+pub fn compare_canonical<PayoutStatsPayload: Ord+Copy, PayoutStats: Borrow<SPayoutStats<PayoutStatsPayload>>>(
+    slctplemmstrategypayoutstats_lhs: &[(EMinMaxStrategy, PayoutStats)],
+    slctplemmstrategypayoutstats_rhs: &[(EMinMaxStrategy, PayoutStats)],
+    fn_loss_or_win: impl Fn(isize, PayoutStatsPayload)->std::cmp::Ordering,
+) -> std::cmp::Ordering {
+    use std::cmp::Ordering::*;
+    fn compare_fractions((numerator_lhs, denominator_lhs): (u128, u128), (numerator_rhs, denominator_rhs): (u128, u128)) -> std::cmp::Ordering {
+        u128::cmp(&(numerator_lhs * denominator_rhs), &(denominator_lhs * numerator_rhs))
+    }
+    fn payout_stats<PayoutStatsPayload: Ord, PayoutStats: Borrow<SPayoutStats<PayoutStatsPayload>>>(
+        slctplemmstrategypayoutstats: &[(EMinMaxStrategy, PayoutStats)],
+        emmstrategy: EMinMaxStrategy,
+    ) -> Option<&SPayoutStats<PayoutStatsPayload>> {
+        slctplemmstrategypayoutstats.iter()
+            .find(|(emmstrategy_payoutstats, _payoutstats)| *emmstrategy_payoutstats==emmstrategy)
+            .map(|(_emmstrategy, payoutstats)| payoutstats.borrow())
+    }
+    [
+        EMinMaxStrategy::MaxSelfishMin,
+        EMinMaxStrategy::MaxSelfishMax,
+        EMinMaxStrategy::MaxMin,
+        EMinMaxStrategy::Max,
+        EMinMaxStrategy::MinMin,
+    ].into_iter().fold(Equal, |ord, emmstrategy| ord.then_with(|| {
+        match (
+            payout_stats::<PayoutStatsPayload, _>(slctplemmstrategypayoutstats_lhs, emmstrategy),
+            payout_stats::<PayoutStatsPayload, _>(slctplemmstrategypayoutstats_rhs, emmstrategy),
+        ) {
+            (Some(payoutstats_lhs), Some(payoutstats_rhs)) => {
+                let mapordn_lhs = payoutstats_lhs.counts(&fn_loss_or_win).map_into(|n| n.as_num::<u128>());
+                let mapordn_rhs = payoutstats_rhs.counts(&fn_loss_or_win).map_into(|n| n.as_num::<u128>());
+                let compare_winning_probability_internal = |ord| compare_fractions(
+                    (mapordn_lhs[ord], mapordn_lhs.iter().sum()),
+                    (mapordn_rhs[ord], mapordn_rhs.iter().sum()),
+                );
+                compare_winning_probability_internal(Greater)
+                    .then_with(|| compare_winning_probability_internal(Less).reverse())
+                    .then_with(|| match unwrap!(payoutstats_lhs.avg().partial_cmp(&payoutstats_rhs.avg())) {
+                        Greater => Greater,
+                        Less => Less,
+                        Equal => payoutstats_lhs.max().cmp(&payoutstats_rhs.max()),
+                    })
+            },
+            _ => Equal,
+        }
+    }))
+}
+
 macro_rules! impl_perminmaxstrategy{(
     $struct:ident {
         $IsSomeMinMin:ident,
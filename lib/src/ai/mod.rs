@@ -5,8 +5,9 @@ pub mod gametree;
 pub mod test;
 pub mod stichoracle;
 pub mod cardspartition;
+pub mod neuralnetwork;
 
-use crate::ai::{handiterators::*, gametree::*};
+use crate::ai::{handiterators::*, gametree::*, neuralnetwork::{SNeuralNetwork, SNeuralNetworkFilter}};
 pub use gametree::SPerMinMaxStrategy;
 use crate::game::*;
 use crate::primitives::*;
@@ -27,12 +28,14 @@ pub enum VAIParams {
     Simulating {
         n_suggest_card_samples: usize,
     },
+    NeuralNetwork(Arc<SNeuralNetwork>), // plays outright without searching
 }
 
 pub struct SAi {
     n_rank_rules_samples: usize,
     n_suggest_card_branches: usize,
     aiparams: VAIParams,
+    oneuralnetwork_filter: Option<Arc<SNeuralNetwork>>, // if given, searching keeps the cards the network likes most instead of random ones
 }
 
 impl SAi {
@@ -41,6 +44,7 @@ impl SAi {
             n_rank_rules_samples,
             n_suggest_card_branches,
             aiparams: VAIParams::Cheating,
+            oneuralnetwork_filter: None,
         }
     }
 
@@ -51,6 +55,23 @@ impl SAi {
             aiparams: VAIParams::Simulating {
                 n_suggest_card_samples,
             },
+            oneuralnetwork_filter: None,
+        }
+    }
+
+    pub fn new_neural_network(n_rank_rules_samples: usize, neuralnetwork: Arc<SNeuralNetwork>) -> Self {
+        SAi {
+            n_rank_rules_samples,
+            n_suggest_card_branches: 1, // unused
+            aiparams: VAIParams::NeuralNetwork(neuralnetwork),
+            oneuralnetwork_filter: None,
+        }
+    }
+
+    pub fn with_neural_network_filter(self, neuralnetwork: Arc<SNeuralNetwork>) -> Self {
+        Self {
+            oneuralnetwork_filter: Some(neuralnetwork),
+            ..self
         }
     }
 
@@ -80,27 +101,150 @@ impl SAi {
         )).t_combined
     }
 
+    // Ranks the rules the hand allows to announce (None standing for no game), best first.
+    pub fn rank_announcements<'rules>(
+        &self,
+        hand: SFullHand,
+        vecrulegroup: &'rules [ruleset::SRuleGroup],
+        expensifiers: &SExpensifiers,
+    ) -> Vec<(Option<&'rules SActivelyPlayableRules>, f64)> {
+        // TODO: implement a more intelligent decision strategy
+        let mut vectplorulesf_payout_avg = ruleset::allowed_rules(vecrulegroup, hand)
+            .map(|orules| (
+                orules,
+                orules.map_or(
+                    0., // TODO how to rank None?
+                    |rules| {
+                        self.rank_rules(
+                            hand,
+                            /*epi_rank*/rules.playerindex(),
+                            &rules.clone().into(), // TODO avoid clone
+                            expensifiers,
+                        ).omaxmin.unwrap_static_some().avg().as_num::<f64>()
+                    }
+                )
+            ))
+            .collect::<Vec<_>>();
+        vectplorulesf_payout_avg.sort_by(|&(_orules_lhs, f_payout_avg_lhs), &(_orules_rhs, f_payout_avg_rhs)| {
+            assert!(!f_payout_avg_lhs.is_nan());
+            assert!(!f_payout_avg_rhs.is_nan());
+            unwrap!(f_payout_avg_lhs.partial_cmp(&f_payout_avg_rhs))
+        });
+        vectplorulesf_payout_avg.reverse(); // among equally ranked rules, prefer the last one
+        vectplorulesf_payout_avg
+    }
+
+    // Suggests doubling based on the first cards dealt.
+    pub fn suggest_doubling(&self, veccard: &[ECard]) -> bool {
+        veccard.iter()
+            .filter(|card| {
+                ESchlag::Ober==card.schlag() || ESchlag::Unter==card.schlag() || EFarbe::Herz==card.farbe()
+            })
+            .count() >= 3
+        || veccard.iter()
+                .map(|card| card.farbe())
+                .all_equal()
+        // TODO more intelligent doubling strategy
+    }
+
+    // Suggests stoss if the estimated payout for epi is large enough; also returns the estimate.
+    pub fn suggest_stoss(
+        &self,
+        epi: EPlayerIndex,
+        rules: &SRules,
+        hand: &SHand,
+        stichseq: &SStichSequence,
+        expensifiers: &SExpensifiers,
+    ) -> (bool, f64) {
+        let n_samples_per_stoss = 5; // TODO make adjustable
+        let mut vectplahandf_suspicion = forever_rand_hands(stichseq, (hand.clone(), epi), rules, &expensifiers.vecstoss)
+            .take(2*n_samples_per_stoss)
+            .map(|ahand| {
+                let f_rank_rules = rules.playerindex().map_or(0f64, |epi_active| {
+                    if epi!=epi_active {
+                        self.rank_rules(
+                            SFullHand::new(ahand[epi_active].cards(), stichseq.kurzlang()),
+                            /*epi_rank*/epi_active,
+                            rules,
+                            expensifiers,
+                        ).omaxmin.unwrap_static_some().avg().as_num::<f64>()
+                    } else {
+                        0f64
+                    }
+                });
+                (ahand, f_rank_rules)
+            })
+            .collect::<Vec<_>>();
+        vectplahandf_suspicion.sort_unstable_by(|&(ref _ahand_l, f_rank_l), &(ref _ahand_r, f_rank_r)|
+            unwrap!(f_rank_r.partial_cmp(&f_rank_l))
+        );
+        vectplahandf_suspicion.truncate(n_samples_per_stoss);
+        assert_eq!(n_samples_per_stoss, vectplahandf_suspicion.len());
+        let f_payout_avg = vectplahandf_suspicion.into_iter()
+            .map(|(mut ahand, _f_rank_rules)| {
+                explore_snapshots(
+                    (&mut ahand, &mut SStichSequence::new(stichseq.kurzlang())),
+                    rules,
+                    &SBranchingFactor::factory(1, 2),
+                    &SMinReachablePayout::new(
+                        rules,
+                        epi,
+                        expensifiers.clone(),
+                    ),
+                    &SSnapshotCacheNone::factory(), // TODO? use cache
+                    &mut SNoVisualization,
+                ).omaxmin.unwrap_static_some()[epi]
+            })
+            .sum::<isize>().as_num::<f64>()
+            / n_samples_per_stoss.as_num::<f64>();
+        (f_payout_avg > 10f64, f_payout_avg)
+    }
+
     pub fn suggest_card<SnapshotVisualizer: TSnapshotVisualizer<SMaxMinMaxSelfishMin<EnumMap<EPlayerIndex, isize>>>, Ruleset, GameAnnouncements, DetermineRules>(
         &self,
         game: &SGameGeneric<Ruleset, GameAnnouncements, DetermineRules>,
         fn_visualizer: impl Fn(usize, &EnumMap<EPlayerIndex, SHand>, Option<ECard>) -> SnapshotVisualizer + std::marker::Sync,
     ) -> ECard {
-        let rules = &game.rules;
-        let stichseq = &game.stichseq;
-        let ahand = &game.ahand;
-        let expensifiers = &game.expensifiers;
+        self.suggest_card_with_stats(game, fn_visualizer).0
+    }
+
+    // Like suggest_card, but also returns the payout stats per card if the card was determined by searching.
+    pub fn suggest_card_with_stats<SnapshotVisualizer: TSnapshotVisualizer<SMaxMinMaxSelfishMin<EnumMap<EPlayerIndex, isize>>>, Ruleset, GameAnnouncements, DetermineRules>(
+        &self,
+        game: &SGameGeneric<Ruleset, GameAnnouncements, DetermineRules>,
+        fn_visualizer: impl Fn(usize, &EnumMap<EPlayerIndex, SHand>, Option<ECard>) -> SnapshotVisualizer + std::marker::Sync,
+    ) -> (ECard, Option<SDetermineBestCardResult<SMaxMinMaxSelfishMin<SPayoutStats<()>>>>) {
+        self.suggest_card_with_stats_for_position(&game.rules, &game.stichseq, &game.ahand, &game.expensifiers, fn_visualizer)
+    }
+
+    // Like suggest_card_with_stats, but without requiring a whole game. If the AI does not cheat, only the current player's hand is used.
+    pub fn suggest_card_with_stats_for_position<SnapshotVisualizer: TSnapshotVisualizer<SMaxMinMaxSelfishMin<EnumMap<EPlayerIndex, isize>>>>(
+        &self,
+        rules: &SRules,
+        stichseq: &SStichSequence,
+        ahand: &EnumMap<EPlayerIndex, SHand>,
+        expensifiers: &SExpensifiers,
+        fn_visualizer: impl Fn(usize, &EnumMap<EPlayerIndex, SHand>, Option<ECard>) -> SnapshotVisualizer + std::marker::Sync,
+    ) -> (ECard, Option<SDetermineBestCardResult<SMaxMinMaxSelfishMin<SPayoutStats<()>>>>) {
         let epi_current = unwrap!(stichseq.current_stich().current_playerindex());
         let hand_fixed = &ahand[epi_current];
         if let Ok(card)=rules.all_allowed_cards(
             stichseq,
             hand_fixed
         ).iter().exactly_one() {
-            *card
+            (*card, None)
         } else if let Some(card) = rules.rulespecific_ai()
             .and_then(|airulespecific| airulespecific.suggest_card(hand_fixed, stichseq))
         {
-            card
+            (card, None)
         } else {
+            let on_suggest_card_samples = match &self.aiparams {
+                VAIParams::Cheating => None,
+                VAIParams::Simulating{n_suggest_card_samples} => Some(*n_suggest_card_samples),
+                VAIParams::NeuralNetwork(neuralnetwork) => {
+                    return (neuralnetwork.suggest_card(rules, stichseq, hand_fixed, epi_current), None);
+                },
+            };
             macro_rules! forward_to_determine_best_card{(
                 ($func_filter_allowed_cards: expr, $foreachsnapshot: ty,),
                 $itahand: expr,
@@ -123,42 +267,47 @@ impl SAi {
             let n_remaining_cards = stichseq.remaining_cards_per_hand()[epi_current];
             assert!(0<n_remaining_cards);
             let vecstoss = &expensifiers.vecstoss;
-            let veccard_maximum_value = unwrap!(cartesian_match!(
+            let determinebestcardresult = unwrap!(cartesian_match!(
                 forward_to_determine_best_card,
-                match (n_remaining_cards) {
-                    1..=3 => (
+                match ((n_remaining_cards, &self.oneuralnetwork_filter)) {
+                    (1..=3, _) => (
                         SNoFilter::factory(),
                         SMinReachablePayoutBase::<SPrunerNothing, STplStrategiesOnlyMaxSelfishMinMaxMin, /*TODO*/SAlphaBetaPrunerNone>,
                     ),
-                    4 => (
+                    (4, _) => (
                         SNoFilter::factory(),
                         SMinReachablePayoutBase::<SPrunerViaHint, STplStrategiesOnlyMaxSelfishMinMaxMin, /*TODO*/SAlphaBetaPrunerNone>,
                     ),
-                    _ => (
+                    (_, Some(neuralnetwork)) => (
+                        SNeuralNetworkFilter::factory(neuralnetwork.clone(), rules, self.n_suggest_card_branches),
+                        SMinReachablePayoutBase::<SPrunerViaHint, STplStrategiesOnlyMaxSelfishMinMaxMin, /*TODO*/SAlphaBetaPrunerNone>,
+                    ),
+                    (_, None) => (
                         SBranchingFactor::factory(1, self.n_suggest_card_branches+1),
                         SMinReachablePayoutBase::<SPrunerViaHint, STplStrategiesOnlyMaxSelfishMinMaxMin, /*TODO*/SAlphaBetaPrunerNone>,
                     ),
                 },
-                match ((&self.aiparams, n_remaining_cards)) {
-                    (&VAIParams::Cheating, _) => {
+                match ((on_suggest_card_samples, n_remaining_cards)) {
+                    (None, _) => {
                         std::iter::once(ahand.clone())
                     },
-                    (&VAIParams::Simulating{n_suggest_card_samples:_}, 1..=4) => {
+                    (Some(_n_suggest_card_samples), 1..=4) => {
                         all_possible_hands(stichseq, (hand_fixed.clone(), epi_current), rules, vecstoss)
                     },
-                    (&VAIParams::Simulating{n_suggest_card_samples}, _) =>{ 
+                    (Some(n_suggest_card_samples), _) =>{ 
                         forever_rand_hands(stichseq, (hand_fixed.clone(), epi_current), rules, vecstoss)
                             .take(n_suggest_card_samples)
                     },
                 },
-            )).cards_with_maximum_value(|lhs, rhs| {
+            ));
+            let card = *unwrap!(determinebestcardresult.cards_with_maximum_value(|lhs, rhs| {
                 SMaxMinMaxSelfishMin::compare_canonical( // TODO good idea?
                     lhs,
                     rhs,
                     |n_payout, ()| n_payout.cmp(&0), // TODO is this even correct?
                 )
-            }).0;
-            *unwrap!(veccard_maximum_value.first())
+            }).0.first());
+            (card, Some(determinebestcardresult))
         }
     }
 }
@@ -0,0 +1,257 @@
+use crate::ai::gametree::TFilterAllowedCards;
+use crate::primitives::*;
+use crate::rules::*;
+use crate::util::*;
+use itertools::Itertools;
+use std::{io::Read, path::Path, sync::Arc};
+
+macro_rules! card_neural_network_mapping(($macro:ident) => {
+    $macro!(
+        (EA, 1)
+        (GA, 2)
+        (HA, 3)
+        (SA, 4)
+        (EZ, 5)
+        (GZ, 6)
+        (HZ, 7)
+        (SZ, 8)
+        (EK, 9)
+        (GK, 10)
+        (HK, 11)
+        (SK, 12)
+        (EO, 13)
+        (GO, 14)
+        (HO, 15)
+        (SO, 16)
+        (EU, 17)
+        (GU, 18)
+        (HU, 19)
+        (SU, 20)
+        (E9, 21)
+        (G9, 22)
+        (H9, 23)
+        (S9, 24)
+        (E8, 25)
+        (G8, 26)
+        (H8, 27)
+        (S8, 28)
+        (E7, 29)
+        (G7, 30)
+        (H7, 31)
+        (S7, 32)
+    )
+});
+
+pub fn card_to_neural_network_input(ocard: Option<ECard>) -> usize {
+    if let Some(card) = ocard {
+        macro_rules! inner(($(($ecard:ident, $n:expr))*) => {
+            match card {
+                $(ECard::$ecard => $n,)*
+            }
+        });
+        verify_ne!(card_neural_network_mapping!(inner), 0)
+    } else {
+        0
+    }
+}
+
+pub fn neural_network_input_to_card(n: usize) -> Result<Option<ECard>, &'static str> {
+    macro_rules! inner(($(($ecard:ident, $n:expr))*) => {
+        match n {
+            0 => Ok(None),
+            $($n => Ok(Some(ECard::$ecard)),)*
+            _/*TODORUST 33..=usize::MAX*/ => Err("Unknown neural network input index"),
+        }
+    });
+    card_neural_network_mapping!(inner)
+}
+
+pub const N_NEURAL_NETWORK_CARDS: usize = 32;
+pub const N_NEURAL_NETWORK_INPUT: usize = 2 + 4 * N_NEURAL_NETWORK_CARDS;
+
+// Encodes the situation as seen by epi (who is about to play) as described for parse --training-data.
+pub fn neural_network_input(rules: &SRules, stichseq: &SStichSequence, hand: &SHand, epi: EPlayerIndex) -> [u8; N_NEURAL_NETWORK_INPUT] {
+    let card_to_column = |card| card_to_neural_network_input(Some(card)) - 1;
+    let u8_seat = |epi: EPlayerIndex| epi.to_usize().as_num::<u8>();
+    let mut an_input = [0u8; N_NEURAL_NETWORK_INPUT];
+    an_input[0] = u8_seat(epi);
+    an_input[1] = rules.playerindex().map_or(EPlayerIndex::SIZE.as_num::<u8>(), u8_seat);
+    for &card in hand.cards().iter() {
+        an_input[2 + card_to_column(card)] = 1;
+    }
+    for &card in rules.all_allowed_cards(stichseq, hand).iter() {
+        an_input[2 + N_NEURAL_NETWORK_CARDS + card_to_column(card)] = 1;
+    }
+    for (i_card, (epi_stichseq, &card_stichseq)) in stichseq.visible_cards().enumerate() {
+        an_input[2 + 2*N_NEURAL_NETWORK_CARDS + i_card] = card_to_neural_network_input(Some(card_stichseq)).as_num::<u8>();
+        an_input[2 + 3*N_NEURAL_NETWORK_CARDS + i_card] = u8_seat(epi_stichseq) + 1;
+    }
+    an_input
+}
+
+#[derive(Debug)]
+struct SDenseLayer {
+    n_in: usize,
+    vecf_weight: Vec<f32>, // row-major, one row per output
+    vecf_bias: Vec<f32>,
+}
+
+impl SDenseLayer {
+    fn apply(&self, slcf_in: &[f32], b_relu: bool) -> Vec<f32> {
+        assert_eq!(slcf_in.len(), self.n_in);
+        self.vecf_weight.chunks_exact(self.n_in).zip_eq(self.vecf_bias.iter())
+            .map(|(slcf_weight, f_bias)| {
+                let f_out = slcf_weight.iter().zip_eq(slcf_in.iter())
+                    .fold(*f_bias, |f_acc, (f_weight, f_in)| f_acc + f_weight * f_in);
+                if b_relu {f_out.max(0.)} else {f_out}
+            })
+            .collect()
+    }
+}
+
+// Multilayer perceptron mapping neural_network_input to one score per card (index card_to_neural_network_input-1).
+// Text format: Lines starting with '#' are ignored. The first other line lists the layer sizes
+// (130, hidden sizes, 32). Then, for each layer, all weights (one row of n_in values per output)
+// followed by the n_out biases, separated by whitespace. Hidden layers use ReLU, the output is linear.
+#[derive(Debug)]
+pub struct SNeuralNetwork {
+    vecdenselayer: Vec<SDenseLayer>,
+}
+
+impl SNeuralNetwork {
+    pub fn from_string(str_network: &str) -> Result<Self, Error> {
+        let mut itstr_line = str_network.lines()
+            .map(str::trim)
+            .filter(|str_line| !str_line.is_empty() && !str_line.starts_with('#'));
+        let vecn_size = itstr_line.next()
+            .ok_or_else(|| format_err!("Missing layer sizes"))?
+            .split_whitespace()
+            .map(|str_size| str_size.parse::<usize>().map_err(|err| format_err!("Invalid layer size {}: {:?}", str_size, err)))
+            .collect::<Result<Vec<_>,_>>()?;
+        if vecn_size.len() < 2 {
+            return Err(format_err!("Expected at least two layer sizes, got {:?}", vecn_size));
+        }
+        if vecn_size[0]!=N_NEURAL_NETWORK_INPUT || vecn_size[vecn_size.len()-1]!=N_NEURAL_NETWORK_CARDS {
+            return Err(format_err!("Expected layer sizes {} ... {}, got {:?}", N_NEURAL_NETWORK_INPUT, N_NEURAL_NETWORK_CARDS, vecn_size));
+        }
+        let mut itf = itstr_line
+            .flat_map(str::split_whitespace)
+            .map(|str_f| str_f.parse::<f32>().map_err(|err| format_err!("Invalid number {}: {:?}", str_f, err)));
+        let mut take_floats = |n| -> Result<Vec<f32>, Error> {
+            let vecf = itf.by_ref().take(n).collect::<Result<Vec<_>,_>>()?;
+            if vecf.len()==n {
+                Ok(vecf)
+            } else {
+                Err(format_err!("Not enough weights"))
+            }
+        };
+        let vecdenselayer = vecn_size.iter().tuple_windows()
+            .map(|(&n_in, &n_out)| Ok(SDenseLayer {
+                n_in,
+                vecf_weight: take_floats(n_in * n_out)?,
+                vecf_bias: take_floats(n_out)?,
+            }))
+            .collect::<Result<Vec<_>,Error>>()?;
+        if itf.next().is_some() {
+            return Err(format_err!("Too many weights"));
+        }
+        Ok(Self{vecdenselayer})
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Error> {
+        Self::from_string(&via_out_param_result(|str_network| std::fs::File::open(path)?.read_to_string(str_network))?.0)
+    }
+
+    pub fn evaluate(&self, an_input: &[u8; N_NEURAL_NETWORK_INPUT]) -> Vec<f32> {
+        let n_layers = self.vecdenselayer.len();
+        self.vecdenselayer.iter().enumerate().fold(
+            an_input.iter().map(|&n| f32::from(n)).collect(),
+            |vecf, (i_layer, denselayer)| denselayer.apply(&vecf, /*b_relu*/i_layer+1<n_layers),
+        )
+    }
+
+    // Allowed cards for epi, best first.
+    pub fn sorted_allowed_cards(&self, rules: &SRules, stichseq: &SStichSequence, hand: &SHand, epi: EPlayerIndex) -> Vec<(ECard, f32)> {
+        let vecf_score = self.evaluate(&neural_network_input(rules, stichseq, hand, epi));
+        rules.all_allowed_cards(stichseq, hand).iter()
+            .map(|&card| (card, vecf_score[card_to_neural_network_input(Some(card))-1]))
+            .sorted_by(|(_card_lhs, f_lhs), (_card_rhs, f_rhs)| f_rhs.total_cmp(f_lhs))
+            .collect()
+    }
+
+    pub fn suggest_card(&self, rules: &SRules, stichseq: &SStichSequence, hand: &SHand, epi: EPlayerIndex) -> ECard {
+        unwrap!(self.sorted_allowed_cards(rules, stichseq, hand, epi).into_iter().next()).0
+    }
+}
+
+// Like SBranchingFactor, but keeps the cards the network likes most instead of random ones.
+pub struct SNeuralNetworkFilter<'rules> {
+    neuralnetwork: Arc<SNeuralNetwork>,
+    rules: &'rules SRules,
+    ahand: EnumMap<EPlayerIndex, SHand>, // as of the beginning of the current stich
+    n_branches: usize,
+}
+
+impl TFilterAllowedCards for SNeuralNetworkFilter<'_> {
+    type UnregisterStich = EnumMap<EPlayerIndex, SHand>;
+    fn register_stich(&mut self, ahand: &mut EnumMap<EPlayerIndex, SHand>, _stichseq: &mut SStichSequence) -> Self::UnregisterStich {
+        std::mem::replace(&mut self.ahand, ahand.clone())
+    }
+    fn unregister_stich(&mut self, ahand: Self::UnregisterStich) {
+        self.ahand = ahand;
+    }
+    fn filter_allowed_cards(&self, stichseq: &SStichSequence, veccard: &mut SHandVector) {
+        assert!(!veccard.is_empty());
+        let epi = unwrap!(stichseq.current_stich().current_playerindex());
+        // epi has not played in the current stich yet, so self.ahand[epi] is still up to date
+        let veccardf = self.neuralnetwork.sorted_allowed_cards(self.rules, stichseq, &self.ahand[epi], epi);
+        veccard.retain(|card| veccardf.iter().take(self.n_branches).any(|&(card_keep, _f)| card_keep==*card));
+        assert!(!veccard.is_empty());
+    }
+}
+
+impl<'rules> SNeuralNetworkFilter<'rules> {
+    pub fn factory(neuralnetwork: Arc<SNeuralNetwork>, rules: &'rules SRules, n_branches: usize) -> impl Fn(&SStichSequence, &EnumMap<EPlayerIndex, SHand>)->Self + 'rules {
+        assert!(0 < n_branches);
+        move |_stichseq, ahand| Self {
+            neuralnetwork: neuralnetwork.clone(),
+            rules,
+            ahand: ahand.clone(),
+            n_branches,
+        }
+    }
+}
+
+#[test]
+fn test_neural_network() {
+    use crate::primitives::card::ECard::*;
+    for n in 0..=N_NEURAL_NETWORK_CARDS {
+        assert_eq!(card_to_neural_network_input(unwrap!(neural_network_input_to_card(n))), n);
+    }
+    assert!(neural_network_input_to_card(N_NEURAL_NETWORK_CARDS+1).is_err());
+    // single linear layer preferring cards with high index, i.e. low cards
+    let str_network = format!(
+        "# test network\n{} {}\n{}\n{}",
+        N_NEURAL_NETWORK_INPUT,
+        N_NEURAL_NETWORK_CARDS,
+        (0..N_NEURAL_NETWORK_CARDS).map(|_| vec!["0"; N_NEURAL_NETWORK_INPUT].join(" ")).join("\n"),
+        (1..=N_NEURAL_NETWORK_CARDS).join(" "),
+    );
+    let neuralnetwork = unwrap!(SNeuralNetwork::from_string(&str_network));
+    assert_eq!(neuralnetwork.evaluate(&[0; N_NEURAL_NETWORK_INPUT]), (1..=N_NEURAL_NETWORK_CARDS).map(|n| n.as_num::<f32>()).collect::<Vec<_>>());
+    assert!(SNeuralNetwork::from_string(&format!("{str_network} 0")).is_err());
+    assert!(SNeuralNetwork::from_string(&str_network.replace("130 32", "130 16 32")).is_err());
+    assert!(SNeuralNetwork::from_string("").is_err());
+    let rules = SRules::from(crate::rules::rulesramsch::SRulesRamsch::new(
+        /*n_price*/10,
+        /*odurchmarsch*/None,
+        /*ojungfrau*/None,
+    ));
+    let stichseq = SStichSequence::new(EKurzLang::Lang);
+    let hand = SHand::new_from_iter([EO, GO, HA, HZ, H9, S7, E8, G7]);
+    assert_eq!(
+        neuralnetwork.sorted_allowed_cards(&rules, &stichseq, &hand, EPlayerIndex::EPI0).into_iter().map(|(card, _f)| card).collect::<Vec<_>>(),
+        vec![S7, G7, E8, H9, GO, EO, HZ, HA],
+    );
+    assert_eq!(neuralnetwork.suggest_card(&rules, &stichseq, &hand, EPlayerIndex::EPI0), S7);
+}
@@ -2436,7 +2436,7 @@ mod tests {
 
     #[test]
     fn test_filterbystichoracle() {
-        crate::game::run::internal_run_simple_game_loop( // TODO simplify all this, and explicitly iterate over supported rules
+        unwrap!(crate::game::run::internal_run_simple_game_loop( // TODO simplify all this, and explicitly iterate over supported rules
             EPlayerIndex::map_from_fn(|_epi| Box::new(SPlayerRandom::new(
                 /*fn_check_ask_for_card*/|game: &SGameGeneric<SRuleSet, (), ()>| {
                     if game.kurzlang().cards_per_player() - if_dbg_else!({4}{5}) < game.completed_stichs().len() {
@@ -2579,6 +2579,7 @@ mod tests {
                             )
                         })
                     })
+                    .map(Ok)
                     .collect::<Vec<_>>().into_iter(); // TODO how can we avoid this?
                 if_dbg_else!(
                     {{
@@ -2589,6 +2590,7 @@ mod tests {
                 )
             },
             /*fn_print_account_balance*/|_,_| {/* no output */},
-        );
+            /*fn_after_game*/|_,_,_| None, // no replays
+        ));
     }
 }
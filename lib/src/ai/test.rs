@@ -84,7 +84,7 @@ fn test_determine_best_card() {
 
 #[test]
 fn detect_expensive_all_possible_hands() {
-    crate::game::run::run_simple_game_loop(
+    unwrap!(crate::game::run::run_simple_game_loop(
         EPlayerIndex::map_from_fn(|_epi| Box::new(SPlayerRandom::new(
             /*fn_check_ask_for_card*/|game: &SGameGeneric<SRuleSet, (), ()>| {
                 if game.kurzlang().cards_per_player() - 4 < game.completed_stichs().len() {
@@ -159,7 +159,7 @@ fn detect_expensive_all_possible_hands() {
             ",
         )),
         /*fn_print_account_balance*/|_,_| {/* no output */},
-    );
+    ));
 }
 
 // TODO (Sauspiel 964899954)
@@ -0,0 +1,1465 @@
+use crate::ai::{
+    SAi,
+    SBranchingFactor,
+    SPayoutStats,
+    VInspectionPoint,
+    determine_best_card,
+    internal_cards_and_ts,
+    gametree::{
+        compare_canonical,
+        equivalent_cards_filter,
+        EMinMaxStrategy,
+        SAlphaBetaPruner,
+        SAlphaBetaPrunerNone,
+        SFilterOnePerWinnerIndex,
+        SMinReachablePayoutBase,
+        SNoFilter,
+        SNoVisualization,
+        SPerMinMaxStrategyGeneric,
+        SPerMinMaxStrategyRawPayout,
+        SPrunerNothing,
+        SPrunerViaHint,
+        SSnapshotCacheNone,
+        STplStrategiesAll,
+        STplStrategiesOnlyMaxMin,
+        STplStrategiesOnlyMaxSelfishMin,
+        TAlphaBetaPruner,
+        TFilterAllowedCards,
+        TForEachSnapshot,
+        TPruner,
+        TSnapshotCache,
+        TTplStrategies,
+    },
+    handiterators::{all_possible_hands, forever_rand_hands, internal_all_possible_hands, internal_forever_rand_hands},
+    stichoracle::SFilterByOracle,
+};
+use crate::game::*;
+use crate::game_analysis::{
+    gamerecord::analyze_gamerecords,
+    parser::{analyze_netschafkopf, analyze_plain, analyze_sauspiel_html, analyze_sauspiel_json},
+    position::{SPosition, VPositionError},
+};
+use crate::primitives::{cardvector::parse_cards, *};
+use crate::rules::{parser::parse_rule_description_simple, ruleset::{SRuleSet, allowed_rules}, *};
+use crate::util::*;
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+// High-level entry point for programs using openschafkopf_lib. It hides the generic machinery
+// (determine_best_card, explore_snapshots, SMinReachablePayoutBase, ...) behind a few plain types:
+// * SPositionBuilder (or position_from_str) describes a game situation as a validated SGameState,
+// * SEngine suggests or evaluates cards for a game state, evaluates rules for a hand, generates card distributions
+//   and counts their properties,
+// * games_from_str reads played games.
+// Players are numbered from 0 to 3, where 0 is the player to open the first stich.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EGameFormat {
+    SauspielHtml,
+    SauspielJson,
+    Plain,
+    NetSchafkopf,
+    GameRecord,
+}
+impl fmt::Display for EGameFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", match self {
+            EGameFormat::SauspielHtml => "sauspiel.de html",
+            EGameFormat::SauspielJson => "sauspiel.de json",
+            EGameFormat::Plain => "plain text",
+            EGameFormat::NetSchafkopf => "NetSchafkopf protocol",
+            EGameFormat::GameRecord => "game records",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VApiError {
+    RulesNotParsable{str_rules: String},
+    CardsNotParsable{str_cards: String},
+    PositionNotParsable{str_position: String, str_reason: String},
+    InvalidPosition(VPositionError),
+    NoFittingDeck, // played cards and hands fit neither Kurz nor Lang
+    DeckAmbiguous, // played cards and hands fit both Kurz and Lang
+    HandGivenTwice(EPlayerIndex),
+    PositionRequired, // game finished, so there is no player to move
+    GameFinished,
+    CheckpointNotReached{n_cards_played: usize}, // fewer cards played than requested by SGameState::checkpoint
+    HandUnknown(EPlayerIndex),
+    WrongHandSize{n_cards: usize},
+    DeckMismatch{ekurzlang_hand: EKurzLang, ekurzlang_ruleset: EKurzLang},
+    CardNotInDeck{card: ECard, ekurzlang: EKurzLang},
+    CardUsedTwice(ECard),
+    NoCardDistribution, // no card distribution fits the game state
+    Cancelled,
+    GameNotParsable(Vec<(EGameFormat, /*str_reason*/String)>),
+}
+impl fmt::Display for VApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            VApiError::RulesNotParsable{str_rules} => write!(f, "Could not parse rules: {str_rules}"),
+            VApiError::CardsNotParsable{str_cards} => write!(f, "Could not parse cards: {str_cards}"),
+            VApiError::PositionNotParsable{str_position, str_reason} => write!(f, "Could not parse position {str_position}: {str_reason}"),
+            VApiError::InvalidPosition(positionerror) => write!(f, "Invalid position: {positionerror}"),
+            VApiError::NoFittingDeck => write!(f, "Played cards and hands do not fit any deck"),
+            VApiError::DeckAmbiguous => write!(f, "Played cards and hands fit both Kurz and Lang; specify kurzlang"),
+            VApiError::HandGivenTwice(epi) => write!(f, "Hand of player {epi} given twice"),
+            VApiError::PositionRequired => write!(f, "Game finished, so position must be given"),
+            VApiError::GameFinished => write!(f, "Game already finished"),
+            VApiError::CheckpointNotReached{n_cards_played} => write!(f, "Checkpoint after {n_cards_played} cards, but fewer cards played"),
+            VApiError::HandUnknown(epi) => write!(f, "Hand of player {epi} not completely known"),
+            VApiError::WrongHandSize{n_cards} => write!(f, "Hand must have a full number of cards, got {n_cards}"),
+            VApiError::DeckMismatch{ekurzlang_hand, ekurzlang_ruleset} => write!(f, "Hand is for {ekurzlang_hand:?}, but ruleset plays {ekurzlang_ruleset:?}"),
+            VApiError::CardNotInDeck{card, ekurzlang} => write!(f, "{card} not supported in {ekurzlang:?}"),
+            VApiError::CardUsedTwice(card) => write!(f, "{card} is used more than once"),
+            VApiError::NoCardDistribution => write!(f, "Could not generate card distributions fitting the game state"),
+            VApiError::Cancelled => write!(f, "Cancelled"),
+            VApiError::GameNotParsable(vectplegameformatstr_reason) => write!(f, "Could not read game as {}",
+                vectplegameformatstr_reason.iter()
+                    .map(|(egameformat, str_reason)| format!("{egameformat} ({str_reason})"))
+                    .join(", ")
+            ),
+        }
+    }
+}
+impl std::error::Error for VApiError {}
+
+// Accepts rules as plain text, e.g. "Rufspiel mit der Gras-Sau von 1" or "gras wenz von 1".
+pub fn rules_from_str(str_rules: &str) -> Result<SRules, VApiError> {
+    parse_rule_description_simple(str_rules)
+        .map_err(|_err| VApiError::RulesNotParsable{str_rules: str_rules.to_owned()})
+}
+
+// Accepts cards separated by spaces, e.g. "eo go ha h7".
+pub fn cards_from_str(str_cards: &str) -> Result<Vec<ECard>, VApiError> {
+    parse_cards::<Vec<_>>(str_cards)
+        .ok_or_else(|| VApiError::CardsNotParsable{str_cards: str_cards.to_owned()})
+}
+
+// Accepts the one-line format described in game_analysis/position.rs.
+pub fn position_from_str(str_position: &str) -> Result<SGameState, VApiError> {
+    str_position.parse()
+}
+
+// Reads games from a sauspiel.de game (html or json), or from plain text, NetSchafkopf protocols or game
+// records (possibly containing several games). Games that cannot be read completely are skipped, games
+// ended in stock are included.
+pub fn games_from_str(str_input: &str) -> Result<Vec<SGameResult<()>>, VApiError> {
+    let str_reason_html = match analyze_sauspiel_html(str_input) {
+        Ok(gameresult) => return Ok(vec![gameresult.map(|_|(), |_|(), |_|())]),
+        Err(err) => err.0,
+    };
+    let str_reason_json = match analyze_sauspiel_json(str_input, |_,_,_,_| {}) {
+        Ok(gameresult) => return Ok(vec![gameresult.map(|_|(), |_|(), |_|())]),
+        Err(err) => err.0,
+    };
+    let mut vecgameresult = Vec::new();
+    let mut collect_games = |itresgameresult: &mut dyn Iterator<Item=Result<SGameResult<()>, Error>>| {
+        let mut ostr_reason = None;
+        for resgameresult in itresgameresult {
+            match resgameresult {
+                Ok(gameresult) => vecgameresult.push(gameresult),
+                Err(err) => {
+                    ostr_reason.get_or_insert(err.0);
+                },
+            }
+        }
+        ostr_reason.unwrap_or_else(|| "No game found".to_owned())
+    };
+    let str_reason_plain = collect_games(&mut analyze_plain(str_input)
+        .map(|resgame| resgame.and_then(|game| game.finish().map_err(|_game| format_err!("Game not finished"))))
+    );
+    let str_reason_netschafkopf = match analyze_netschafkopf(str_input) {
+        Ok(vecresnetschafkopfgame) => collect_games(&mut vecresnetschafkopfgame.into_iter()
+            .map(|resnetschafkopfgame| resnetschafkopfgame.map(|netschafkopfgame| {
+                if let Some(str_discrepancy) = netschafkopfgame.payout_discrepancy() {
+                    warn!("{str_discrepancy}");
+                }
+                netschafkopfgame.gameresult.map(|_|(), |_|(), |_|())
+            }))
+        ),
+        Err(err) => err.0,
+    };
+    let str_reason_gamerecord = collect_games(&mut analyze_gamerecords(str_input)
+        .map(|resgameresult| resgameresult.map(|gameresult| gameresult.map(|_|(), |_|(), |_|())))
+    );
+    if vecgameresult.is_empty() {
+        Err(VApiError::GameNotParsable(vec![
+            (EGameFormat::SauspielHtml, str_reason_html),
+            (EGameFormat::SauspielJson, str_reason_json),
+            (EGameFormat::Plain, str_reason_plain),
+            (EGameFormat::NetSchafkopf, str_reason_netschafkopf),
+            (EGameFormat::GameRecord, str_reason_gamerecord),
+        ]))
+    } else {
+        Ok(vecgameresult)
+    }
+}
+
+// A game situation that has been checked against the rules (see SPosition::validate). Cards of some
+// players may be (partly) unknown.
+#[derive(Clone, Debug)]
+pub struct SGameState {
+    position: SPosition,
+}
+
+impl SGameState {
+    pub fn rules(&self) -> &SRules {
+        &self.position.rules
+    }
+
+    pub fn stichseq(&self) -> &SStichSequence {
+        &self.position.stichseq
+    }
+
+    pub fn expensifiers(&self) -> &SExpensifiers {
+        &self.position.expensifiers
+    }
+
+    pub fn kurzlang(&self) -> EKurzLang {
+        self.position.stichseq.kurzlang()
+    }
+
+    // Player from whose perspective the game state is seen.
+    pub fn position(&self) -> EPlayerIndex {
+        self.position.epi_position
+    }
+
+    // None if the game is finished.
+    pub fn player_to_move(&self) -> Option<EPlayerIndex> {
+        if_then_some!(!self.position.stichseq.game_finished(), unwrap!(self.position.stichseq.current_stich().current_playerindex()))
+    }
+
+    // Known remaining cards of epi.
+    pub fn known_cards(&self, epi: EPlayerIndex) -> &[ECard] {
+        self.position.ahand[epi].cards()
+    }
+
+    pub fn hand_known(&self, epi: EPlayerIndex) -> bool {
+        self.position.ahand[epi].cards().len()==self.position.stichseq.remaining_cards_per_hand()[epi]
+    }
+
+    // The same game state, seen by epi, who only knows their own cards.
+    pub fn seen_by(&self, epi: EPlayerIndex) -> Self {
+        Self {
+            position: SPosition {
+                ahand: EPlayerIndex::map_from_fn(|epi_hand| if epi_hand==epi {
+                    self.position.ahand[epi].clone()
+                } else {
+                    SHand::new_from_iter(std::iter::empty::<ECard>())
+                }),
+                epi_position: epi,
+                ..self.position.clone()
+            },
+        }
+    }
+
+    // Endless random card distributions compatible with the known cards and the stosses given.
+    pub fn sample_hands(&self) -> impl Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send + '_ {
+        forever_rand_hands(
+            &self.position.stichseq,
+            self.position.ahand.clone(),
+            &self.position.rules,
+            &self.position.expensifiers.vecstoss,
+        )
+    }
+
+    // The game state after the first n_cards_played cards, seen by the player to move then, who additionally
+    // knows the cards known now and the cards played afterwards (e.g. to review decisions made earlier).
+    pub fn checkpoint(&self, n_cards_played: usize) -> Result<Self, VApiError> {
+        let SPosition{rules, stichseq, ahand, expensifiers, epi_position: _} = &self.position;
+        if stichseq.visible_cards().count() < n_cards_played {
+            return Err(VApiError::CheckpointNotReached{n_cards_played});
+        }
+        let mut stichseq_checkpoint = SStichSequence::new(stichseq.kurzlang());
+        for (_epi, card) in stichseq.visible_cards().take(n_cards_played) {
+            stichseq_checkpoint.zugeben(*card, rules);
+        }
+        let mut ahand_checkpoint = ahand.clone();
+        for (epi, card) in stichseq.visible_cards().skip(n_cards_played) {
+            ahand_checkpoint[epi].add_card(*card);
+        }
+        let epi_position = if_then_some!(!stichseq_checkpoint.game_finished(), unwrap!(stichseq_checkpoint.current_stich().current_playerindex()))
+            .ok_or(VApiError::GameFinished)?;
+        Self::try_from(SPosition::new(rules, &stichseq_checkpoint, &ahand_checkpoint, expensifiers, epi_position))
+    }
+
+    // For use with the lower-level parts of openschafkopf_lib.
+    pub fn into_position(self) -> SPosition {
+        self.position
+    }
+}
+
+impl TryFrom<SPosition> for SGameState {
+    type Error = VApiError;
+    fn try_from(position: SPosition) -> Result<Self, Self::Error> {
+        position.validate().map_err(VApiError::InvalidPosition)?;
+        Ok(Self {
+            position,
+        })
+    }
+}
+
+impl std::str::FromStr for SGameState {
+    type Err = VApiError;
+    fn from_str(str_position: &str) -> Result<Self, Self::Err> {
+        SPosition::from_str_unvalidated(str_position)
+            .map_err(|err| VApiError::PositionNotParsable{str_position: str_position.to_owned(), str_reason: err.0})
+            .and_then(Self::try_from)
+    }
+}
+
+impl fmt::Display for SGameState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.position)
+    }
+}
+
+// Assembles a validated SGameState. Only rules and played cards are required: Unless given explicitly,
+// the deck size is inferred from the cards, and the position defaults to the player to move.
+#[derive(Clone, Debug)]
+pub struct SPositionBuilder {
+    rules: SRules,
+    veccard_played: Vec<ECard>,
+    mapepiveccard_hand: EnumMap<EPlayerIndex, Vec<ECard>>,
+    oveccard_hand_current: Option<Vec<ECard>>,
+    oekurzlang: Option<EKurzLang>,
+    oepi_position: Option<EPlayerIndex>,
+    vecstoss: Vec<SStoss>,
+    ab_doubling: [bool; EPlayerIndex::SIZE],
+    n_stock: isize,
+}
+
+impl SPositionBuilder {
+    pub fn new(rules: SRules) -> Self {
+        Self {
+            rules,
+            veccard_played: Vec::new(),
+            mapepiveccard_hand: EPlayerIndex::map_from_fn(|_epi| Vec::new()),
+            oveccard_hand_current: None,
+            oekurzlang: None,
+            oepi_position: None,
+            vecstoss: Vec::new(),
+            ab_doubling: [false; EPlayerIndex::SIZE],
+            n_stock: 0,
+        }
+    }
+
+    // Cards played so far, in order.
+    pub fn played_cards(mut self, itcard: impl IntoIterator<Item=ECard>) -> Self {
+        self.veccard_played.extend(itcard);
+        self
+    }
+
+    // Known remaining cards of epi (possibly only some of them).
+    pub fn hand(mut self, epi: EPlayerIndex, itcard: impl IntoIterator<Item=ECard>) -> Self {
+        self.mapepiveccard_hand[epi].extend(itcard);
+        self
+    }
+
+    // All remaining cards of the player to move, who need not be known in advance.
+    pub fn hand_current(mut self, itcard: impl IntoIterator<Item=ECard>) -> Self {
+        self.oveccard_hand_current = Some(itcard.into_iter().collect());
+        self
+    }
+
+    pub fn kurzlang(mut self, ekurzlang: EKurzLang) -> Self {
+        self.oekurzlang = Some(ekurzlang);
+        self
+    }
+
+    // Player from whose perspective the position is seen.
+    pub fn position(mut self, epi: EPlayerIndex) -> Self {
+        self.oepi_position = Some(epi);
+        self
+    }
+
+    // Stoss given by epi after n_cards_played cards; stosses must be given in order.
+    pub fn stoss(mut self, epi: EPlayerIndex, n_cards_played: usize) -> Self {
+        self.vecstoss.push(SStoss{epi, n_cards_played});
+        self
+    }
+
+    // One entry per player, starting with player 0.
+    pub fn doublings(mut self, ab_doubling: [bool; EPlayerIndex::SIZE]) -> Self {
+        self.ab_doubling = ab_doubling;
+        self
+    }
+
+    pub fn stock(mut self, n_stock: isize) -> Self {
+        self.n_stock = n_stock;
+        self
+    }
+
+    pub fn build(self) -> Result<SGameState, VApiError> {
+        let vecekurzlang = match self.oekurzlang {
+            Some(ekurzlang) => vec![ekurzlang],
+            None => EKurzLang::values().collect(),
+        };
+        let mut vecstichseq = vecekurzlang.into_iter()
+            .filter_map(|ekurzlang| {
+                let mut stichseq = SStichSequence::new(ekurzlang);
+                for &card in self.veccard_played.iter() {
+                    if !ekurzlang.supports_card(card) || stichseq.game_finished() {
+                        return None;
+                    }
+                    stichseq.zugeben(card, &self.rules);
+                }
+                let mapepin_remaining = stichseq.remaining_cards_per_hand();
+                if_then_some!(
+                    EPlayerIndex::values().all(|epi| self.mapepiveccard_hand[epi].len()<=mapepin_remaining[epi])
+                        && self.oveccard_hand_current.as_ref().is_none_or(|veccard_hand|
+                            !stichseq.game_finished()
+                                && mapepin_remaining[unwrap!(stichseq.current_stich().current_playerindex())]==veccard_hand.len()
+                        ),
+                    stichseq
+                )
+            })
+            .collect::<Vec<_>>();
+        let stichseq = match vecstichseq.len() {
+            0 => return Err(VApiError::NoFittingDeck),
+            1 => unwrap!(vecstichseq.pop()),
+            _ => return Err(VApiError::DeckAmbiguous),
+        };
+        let oepi_current = if_then_some!(!stichseq.game_finished(), unwrap!(stichseq.current_stich().current_playerindex()));
+        let mut mapepiveccard_hand = self.mapepiveccard_hand;
+        if let Some(veccard_hand_current) = self.oveccard_hand_current {
+            let epi_current = unwrap!(oepi_current); // checked above
+            if !mapepiveccard_hand[epi_current].is_empty() {
+                return Err(VApiError::HandGivenTwice(epi_current));
+            }
+            mapepiveccard_hand[epi_current] = veccard_hand_current;
+        }
+        SGameState::try_from(SPosition {
+            rules: self.rules,
+            stichseq,
+            ahand: mapepiveccard_hand.map(|veccard| SHand::new_from_iter(veccard.iter().copied())),
+            expensifiers: SExpensifiers::new(
+                self.n_stock,
+                SDoublings::new_full(SStaticEPI0{}, self.ab_doubling),
+                self.vecstoss,
+            ),
+            epi_position: self.oepi_position.or(oepi_current)
+                .ok_or(VApiError::PositionRequired)?,
+        })
+    }
+}
+
+// Effort SEngine::suggest may spend. Late in the game (at most 4 cards per hand), the game tree is explored
+// completely for all card distributions, regardless of the budget.
+#[derive(Clone, Copy, Debug)]
+pub struct SBudget {
+    pub n_samples: usize, // card distributions simulated (or, if all hands are known, explorations of the known one)
+    pub n_branches: usize, // cards explored per player and stich early in the game, chosen randomly (at least 1)
+    pub b_prefer_likely_hands: bool,
+}
+
+impl SBudget {
+    pub fn new(n_samples: usize, n_branches: usize) -> Self {
+        Self {
+            n_samples,
+            n_branches,
+            b_prefer_likely_hands: false,
+        }
+    }
+
+    // Instead of simulating all n_samples card distributions, keep only those in which the active player
+    // is most likely to have announced the rules (see SEngine::likely_hands). How many are kept depends
+    // on how often the rules are usually announced.
+    pub fn prefer_likely_hands(self) -> Self {
+        Self {
+            b_prefer_likely_hands: true,
+            ..self
+        }
+    }
+}
+
+// Payouts over all simulated card distributions, for one strategy (see EMinMaxStrategy).
+#[derive(Clone, Debug)]
+pub struct SPayoutSummary {
+    pub f_payout_avg: f32,
+    pub n_payout_min: isize,
+    pub n_payout_max: isize,
+    pub vectplnn_histogram: Vec<(/*n_payout*/isize, /*n_count*/usize)>, // ascending by payout
+    payoutstats: SPayoutStats<std::cmp::Ordering>,
+}
+
+impl SPayoutSummary {
+    fn new(payoutstats: &SPayoutStats<std::cmp::Ordering>) -> Self {
+        Self {
+            f_payout_avg: payoutstats.avg(),
+            n_payout_min: payoutstats.min(),
+            n_payout_max: payoutstats.max(),
+            vectplnn_histogram: payoutstats.histogram().iter()
+                .chunk_by(|((n_payout, _ord_vs_0), _n_count)| *n_payout)
+                .into_iter()
+                .map(|(n_payout, ittplpayoutn)| (n_payout, ittplpayoutn.map(|(_payout, n_count)| n_count).sum()))
+                .collect(),
+            payoutstats: payoutstats.clone(),
+        }
+    }
+
+    // For use with the lower-level parts of openschafkopf_lib. Each payout is tagged whether it means a loss,
+    // zero payout or win, which may not be obvious if payouts are points (see SSearchOptions::b_points).
+    pub fn payout_stats(&self) -> &SPayoutStats<std::cmp::Ordering> {
+        &self.payoutstats
+    }
+}
+
+// Payouts seen from the perspective of one player (see SEngine::suggest and SEngine::evaluate_cards), one
+// SPayoutSummary per strategy explored.
+#[derive(Clone, Debug)]
+pub struct SPayouts {
+    vectplemmstrategypayoutsummary: Vec<(EMinMaxStrategy, SPayoutSummary)>,
+}
+
+impl SPayouts {
+    fn new<TplStrategies: TTplStrategies>(payoutstatsperstrategy: &SPerMinMaxStrategyGeneric<SPayoutStats<std::cmp::Ordering>, TplStrategies>) -> Self {
+        Self {
+            vectplemmstrategypayoutsummary: payoutstatsperstrategy.via_accessors().into_iter()
+                .map(|(emmstrategy, payoutstats)| (emmstrategy, SPayoutSummary::new(payoutstats)))
+                .collect(),
+        }
+    }
+
+    pub fn payout_stats(&self) -> Vec<(EMinMaxStrategy, &SPayoutStats<std::cmp::Ordering>)> {
+        self.strategies()
+            .map(|(emmstrategy, payoutsummary)| (emmstrategy, payoutsummary.payout_stats()))
+            .collect()
+    }
+
+    // Greater means more favourable, primarily judged by the probability to win (see gametree::compare_canonical).
+    pub fn compare_canonical(&self, other: &Self) -> std::cmp::Ordering {
+        compare_canonical(
+            &self.payout_stats(),
+            &other.payout_stats(),
+            /*fn_loss_or_win*/|_n_payout, ord_vs_0| ord_vs_0,
+        )
+    }
+
+    pub fn strategies(&self) -> impl Iterator<Item=(EMinMaxStrategy, &SPayoutSummary)> {
+        self.vectplemmstrategypayoutsummary.iter()
+            .map(|(emmstrategy, payoutsummary)| (*emmstrategy, payoutsummary))
+    }
+
+    // Panics if emmstrategy has not been explored (see SSearchOptions::oesinglestrategy).
+    pub fn strategy(&self, emmstrategy: EMinMaxStrategy) -> &SPayoutSummary {
+        unwrap!(self.strategies().find(|(emmstrategy_summary, _payoutsummary)| *emmstrategy_summary==emmstrategy)).1
+    }
+
+    // Every other player maximizes their own minimum payout.
+    pub fn selfish(&self) -> &SPayoutSummary {
+        self.strategy(EMinMaxStrategy::MaxSelfishMin)
+    }
+
+    // All other players play against the player to move.
+    pub fn pessimistic(&self) -> &SPayoutSummary {
+        self.strategy(EMinMaxStrategy::MaxMin)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SCardEvaluation {
+    pub card: ECard,
+    pub payouts: SPayouts,
+}
+
+fn card_evaluations<'payoutstats, TplStrategies: TTplStrategies>(
+    itcardpayoutstats: impl Iterator<Item=(ECard, &'payoutstats SPerMinMaxStrategyGeneric<SPayoutStats<std::cmp::Ordering>, TplStrategies>)>,
+) -> Vec<SCardEvaluation> {
+    itcardpayoutstats
+        .map(|(card, payoutstats)| SCardEvaluation {
+            card,
+            payouts: SPayouts::new(payoutstats),
+        })
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct SSuggestion {
+    pub card: ECard,
+    pub n_ahand: usize, // card distributions simulated
+    pub veccardevaluation: Vec<SCardEvaluation>, // empty if the card was determined without searching (e.g. the only allowed card)
+    pub opayouts_combined: Option<SPayouts>, // payouts of the best card per card distribution
+}
+
+// Intermediate result of SEngine::suggest_streaming and SEngine::evaluate_cards.
+#[derive(Clone, Debug)]
+pub struct SProgress {
+    pub n_ahand: usize, // card distributions simulated so far
+    pub veccardevaluation: Vec<SCardEvaluation>,
+}
+
+// Card distributions to explore (see SEngine::hands).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VHandSelection {
+    All,
+    Sample{n_samples: usize},
+    SampleLikely{n_samples: usize, n_pool: usize}, // the n_samples most likely ones out of n_pool (see SEngine::likely_hands)
+}
+
+// Which cards SEngine::evaluate_cards explores in each node of the game tree.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VBranching {
+    All,
+    Random{n_lo: usize, n_hi: usize}, // a random number of cards in [n_lo, n_hi) (at least 1); all if there are fewer
+    Equivalent{n_until_stichseq_len: usize}, // one out of equivalent cards, up to the given number of stichs
+    Oracle, // cards leading to minimum resp. maximum points, if supported by the rules
+    OnePerWinnerIndex(Option<EPlayerIndex>), // one card per player that would win the stich so far, except for the given player
+}
+
+// Strategies SEngine::evaluate_cards explores (see EMinMaxStrategy). Exploring only one of them is faster.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ESingleStrategy {
+    MaxMin,
+    MaxSelfishMin,
+}
+
+// How SEngine::evaluate_cards explores the game tree.
+#[derive(Clone, Debug)]
+pub struct SSearchOptions {
+    pub branching: VBranching,
+    pub oesinglestrategy: Option<ESingleStrategy>, // None: all strategies
+    pub b_prune_via_hints: bool, // stop exploring as soon as the result is tentatively known (less accurate)
+    pub b_alpha_beta_pruning: bool, // only if applicable (see alpha_beta_pruning_applicable)
+    pub b_snapshot_cache: bool,
+    pub b_points: bool, // payouts are points instead of money, if supported by the rules (see TRules::points_as_payout)
+    pub n_repeat_hands: usize, // explorations per card distribution, e.g. to even out random branching
+}
+
+impl Default for SSearchOptions {
+    fn default() -> Self {
+        Self {
+            branching: VBranching::All,
+            oesinglestrategy: None,
+            b_prune_via_hints: false,
+            b_alpha_beta_pruning: false,
+            b_snapshot_cache: false,
+            b_points: false,
+            n_repeat_hands: 1,
+        }
+    }
+}
+
+impl SSearchOptions {
+    // Alpha-beta pruning requires a single strategy and, for MaxSelfishMin, support by the rules.
+    pub fn alpha_beta_pruning_applicable(&self, rules: &SRules) -> bool {
+        match self.oesinglestrategy {
+            None => false,
+            Some(ESingleStrategy::MaxMin) => true,
+            Some(ESingleStrategy::MaxSelfishMin) => self.effective_rules(rules).alpha_beta_pruner_lohi_values().is_some(),
+        }
+    }
+
+    fn effective_rules(&self, rules: &SRules) -> SRules {
+        if let Some((rules_points, _fn_payout_to_points)) = rules.points_as_payout().filter(|_tplrulesfn_points_as_payout| self.b_points) {
+            rules_points
+        } else {
+            rules.clone()
+        }
+    }
+}
+
+// Counts of SEngine::hand_stats.
+#[derive(Clone, Debug)]
+pub struct SHandStats<Group, Inspection> {
+    pub mapgroupn: HashMap<Group, usize>, // card distributions per group
+    pub vecmaptplgroupinspectionn: Vec<HashMap<(Group, Inspection), usize>>, // one entry per inspection
+}
+
+#[derive(Clone, Debug)]
+pub struct SRulesEvaluation {
+    pub orules: Option<SRules>, // None: no game
+    pub f_payout_avg: f32,
+    pub n_payout_min: isize,
+    pub n_payout_max: isize,
+}
+
+// Deck of a complete hand, checking that its cards are distinct and part of the deck.
+fn kurzlang_of_hand(slccard_hand: &[ECard]) -> Result<EKurzLang, VApiError> {
+    let ekurzlang = EKurzLang::from_cards_per_player(slccard_hand.len())
+        .ok_or(VApiError::WrongHandSize{n_cards: slccard_hand.len()})?;
+    if let Some(card) = slccard_hand.iter().find(|card| !ekurzlang.supports_card(**card)) {
+        return Err(VApiError::CardNotInDeck{card: *card, ekurzlang});
+    }
+    if let Some(card) = slccard_hand.iter().duplicates().next() {
+        return Err(VApiError::CardUsedTwice(*card));
+    }
+    Ok(ekurzlang)
+}
+
+fn make_snapshot_cache<TplStrategies: TTplStrategies>(rules: &SRules) -> impl Fn(&SRuleStateCacheFixed) -> Box<dyn TSnapshotCache<SPerMinMaxStrategyRawPayout<TplStrategies>>> + '_ {
+    move |rulestatecache| rules.snapshot_cache::<TplStrategies>(rulestatecache)
+}
+
+#[allow(clippy::extra_unused_type_parameters)]
+fn make_snapshot_cache_none<TplStrategies>(_rules: &SRules) -> impl Fn(&SRuleStateCacheFixed)->SSnapshotCacheNone {
+    SSnapshotCacheNone::factory()
+}
+
+// Searches the game tree for epi for each card distribution, returning the number of card distributions
+// explored, the evaluations of all allowed cards and the combined payouts (None if there were no card
+// distributions). fn_inspect is told about each card explored and, after each card distribution, about the
+// intermediate result.
+#[allow(clippy::too_many_arguments)]
+fn evaluate_cards_internal<
+    'stichseq,
+    'rules,
+    FilterAllowedCards: TFilterAllowedCards,
+    OFilterAllowedCards: Into<Option<FilterAllowedCards>>,
+    Pruner: TPruner+Sync,
+    TplStrategies: TTplStrategies,
+    AlphaBetaPruner: TAlphaBetaPruner+Sync,
+    SnapshotCache: TSnapshotCache<<SMinReachablePayoutBase<'rules, Pruner, TplStrategies, AlphaBetaPruner> as TForEachSnapshot>::Output>,
+    OSnapshotCache: Into<Option<SnapshotCache>>,
+>(
+    rules: &'rules SRules,
+    stichseq: &'stichseq SStichSequence,
+    expensifiers: &SExpensifiers,
+    epi: EPlayerIndex,
+    itahand: Box<dyn Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send + 'stichseq>,
+    fn_make_filter: impl Fn(&SStichSequence, &EnumMap<EPlayerIndex, SHand>)->OFilterAllowedCards + Sync,
+    fn_alphabetapruner: impl Fn(&SStichSequence, &EnumMap<EPlayerIndex, SHand>)->AlphaBetaPruner + Sync,
+    fn_snapshotcache: impl Fn(&SRuleStateCacheFixed)->OSnapshotCache + Sync,
+    fn_inspect: &(dyn Fn(&VInspectionPoint<&SProgress>, usize, &EnumMap<EPlayerIndex, SHand>) + Sync),
+    fn_payout: &(impl Fn(&SStichSequence, &EnumMap<EPlayerIndex, SHand>, isize)->(isize, std::cmp::Ordering) + Sync),
+) -> Option<(usize, Vec<SCardEvaluation>, SPayouts)> {
+    let mutexprogress = Mutex::new(SProgress {
+        n_ahand: 0,
+        veccardevaluation: Vec::new(),
+    });
+    let determinebestcardresult = determine_best_card(
+        stichseq,
+        itahand,
+        fn_make_filter,
+        /*fn_make_foreachsnapshot*/&|stichseq, ahand| SMinReachablePayoutBase::<Pruner, TplStrategies, _>::new_with_pruner(
+            rules,
+            epi,
+            expensifiers.clone(),
+            fn_alphabetapruner(stichseq, ahand),
+        ),
+        fn_snapshotcache,
+        SNoVisualization::factory(),
+        /*fn_inspect*/&|inspectionpoint, i_ahand, ahand| match inspectionpoint {
+            VInspectionPoint::Card{b_before, card} => {
+                fn_inspect(&VInspectionPoint::Card{b_before: *b_before, card: *card}, i_ahand, ahand);
+            },
+            VInspectionPoint::AfterHand(mapcardopayoutstats) => {
+                let mut progress = unwrap!(mutexprogress.lock());
+                progress.n_ahand += 1;
+                progress.veccardevaluation = card_evaluations(internal_cards_and_ts(mapcardopayoutstats));
+                fn_inspect(&VInspectionPoint::AfterHand(&*progress), i_ahand, ahand);
+            },
+        },
+        fn_payout,
+    )?;
+    Some((
+        unwrap!(mutexprogress.into_inner()).n_ahand,
+        card_evaluations(determinebestcardresult.cards_and_ts()),
+        SPayouts::new(&determinebestcardresult.t_combined),
+    ))
+}
+
+pub struct SEngine {
+    n_rank_rules_samples: usize,
+}
+
+impl SEngine {
+    pub fn new(n_rank_rules_samples: usize) -> Self {
+        Self {
+            n_rank_rules_samples,
+        }
+    }
+
+    // Suggests a card for the player to move, whose hand must be completely known. Known cards of other
+    // players are respected when simulating card distributions.
+    pub fn suggest(&self, gamestate: &SGameState, budget: SBudget) -> Result<SSuggestion, VApiError> {
+        self.suggest_streaming(gamestate, budget, &AtomicBool::new(false), /*fn_progress*/|_progress| {})
+    }
+
+    // As suggest, but reports intermediate results to fn_progress (at most every 250 ms) while searching.
+    // Returns VApiError::Cancelled as soon as b_cancel is set.
+    pub fn suggest_streaming(
+        &self,
+        gamestate: &SGameState,
+        budget: SBudget,
+        b_cancel: &AtomicBool,
+        fn_progress: impl Fn(&SProgress) + Sync,
+    ) -> Result<SSuggestion, VApiError> {
+        const DURATION_BETWEEN_PROGRESS : Duration = Duration::from_millis(250);
+        let SPosition{rules, stichseq, ahand, expensifiers, epi_position: _} = &gamestate.position;
+        let epi_current = gamestate.player_to_move().ok_or(VApiError::GameFinished)?;
+        if !gamestate.hand_known(epi_current) {
+            return Err(VApiError::HandUnknown(epi_current));
+        }
+        let hand_current = &ahand[epi_current];
+        let ocard_without_search = if let Ok(card) = rules.all_allowed_cards(stichseq, hand_current).iter().exactly_one() {
+            Some(*card)
+        } else {
+            rules.rulespecific_ai()
+                .and_then(|airulespecific| airulespecific.suggest_card(hand_current, stichseq))
+        };
+        if let Some(card) = ocard_without_search {
+            return Ok(SSuggestion {
+                card,
+                n_ahand: 0,
+                veccardevaluation: Vec::new(),
+                opayouts_combined: None,
+            });
+        }
+        let is_cancelled = || b_cancel.load(Ordering::Relaxed);
+        let b_explore_completely = hand_current.cards().len() <= 4;
+        let itahand: Box<dyn Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send + '_> = if EPlayerIndex::values().all(|epi| gamestate.hand_known(epi)) {
+            Box::new(std::iter::repeat_n(ahand.clone(), if b_explore_completely {1} else {budget.n_samples}))
+        } else if b_explore_completely {
+            Box::new(all_possible_hands(stichseq, ahand.clone(), rules, &expensifiers.vecstoss))
+        } else if let Some(f_occurence_probability) = rules.heuristic_active_occurence_probability()
+            .filter(|_f_occurence_probability| budget.b_prefer_likely_hands)
+        {
+            assert!(0. <= f_occurence_probability);
+            assert!(f_occurence_probability <= 1.);
+            Box::new(self.likely_hands(
+                gamestate.sample_hands()
+                    .take(budget.n_samples)
+                    .take_while(|_ahand| !is_cancelled()),
+                /*n_keep*/(budget.n_samples.as_num::<f64>() * f_occurence_probability).ceil().as_num::<usize>(),
+                rules,
+                stichseq,
+                expensifiers,
+            ).into_iter())
+        } else {
+            Box::new(gamestate.sample_hands().take(budget.n_samples))
+        };
+        let mutexinstant_progress = Mutex::new(Instant::now());
+        let resuggestion = self.evaluate_cards_seen_by(
+            gamestate,
+            epi_current,
+            Box::new(itahand.take_while(|_ahand| !is_cancelled())),
+            &SSearchOptions {
+                branching: if b_explore_completely {
+                    VBranching::All
+                } else {
+                    VBranching::Random{n_lo: 1, n_hi: budget.n_branches.max(1)+1}
+                },
+                ..SSearchOptions::default()
+            },
+            /*fn_inspect*/&|inspectionpoint, _i_ahand, _ahand| {
+                if let VInspectionPoint::AfterHand(progress) = inspectionpoint {
+                    let mut instant_progress = unwrap!(mutexinstant_progress.lock());
+                    if !is_cancelled() && DURATION_BETWEEN_PROGRESS <= instant_progress.elapsed() {
+                        *instant_progress = Instant::now();
+                        fn_progress(progress);
+                    }
+                }
+            },
+        );
+        if is_cancelled() {
+            return Err(VApiError::Cancelled);
+        }
+        resuggestion
+    }
+
+    // Evaluates the cards allowed for the player to move, from the perspective of the game state's position (who
+    // may be someone else than the player to move), exploring the card distributions of itahand (see SEngine::hands)
+    // as configured by searchoptions. fn_inspect is told about each card explored (along with the index of the card
+    // distribution and the card distribution) and about the intermediate result after each card distribution.
+    pub fn evaluate_cards<'gamestate>(
+        &self,
+        gamestate: &'gamestate SGameState,
+        itahand: impl Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send + 'gamestate,
+        searchoptions: &SSearchOptions,
+        fn_inspect: impl Fn(&VInspectionPoint<&SProgress>, usize, &EnumMap<EPlayerIndex, SHand>) + Sync,
+    ) -> Result<SSuggestion, VApiError> {
+        self.evaluate_cards_seen_by(gamestate, gamestate.position(), Box::new(itahand), searchoptions, &fn_inspect)
+    }
+
+    fn evaluate_cards_seen_by<'gamestate>(
+        &self,
+        gamestate: &'gamestate SGameState,
+        epi: EPlayerIndex,
+        itahand: Box<dyn Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send + 'gamestate>,
+        searchoptions: &SSearchOptions,
+        fn_inspect: &(dyn Fn(&VInspectionPoint<&SProgress>, usize, &EnumMap<EPlayerIndex, SHand>) + Sync),
+    ) -> Result<SSuggestion, VApiError> {
+        let SPosition{rules, stichseq, ahand: _, expensifiers, epi_position: _} = &gamestate.position;
+        if gamestate.player_to_move().is_none() {
+            return Err(VApiError::GameFinished);
+        }
+        let otplrulesfn_points_as_payout = rules.points_as_payout().filter(|_tplrulesfn_points_as_payout| searchoptions.b_points);
+        let rules = if let Some((rules_points, _fn_payout_to_points)) = &otplrulesfn_points_as_payout {
+            rules_points
+        } else {
+            rules
+        };
+        let fn_payout = |stichseq: &SStichSequence, ahand: &EnumMap<EPlayerIndex, SHand>, n_payout: isize| {
+            (
+                if let Some((_rules_points, fn_payout_to_points)) = &otplrulesfn_points_as_payout {
+                    fn_payout_to_points(stichseq, ahand, epi, n_payout)
+                } else {
+                    n_payout
+                },
+                n_payout.cmp(&0), // Points may not indicate loss or win: In Rufspiel, 60 points may mean either, depending on the co-player.
+            )
+        };
+        let n_repeat_hands = searchoptions.n_repeat_hands;
+        let itahand = Box::new(itahand.flat_map(move |ahand| std::iter::repeat_n(ahand, n_repeat_hands))) as Box<_>;
+        macro_rules! forward{(
+            (($($FilterAllowedCards:tt)*), $fn_make_filter:expr),
+            ($Pruner:ident),
+            ($TplStrategies:ident, $fn_alphabetapruner:expr,),
+            $fn_snapshotcache:ident,
+        ) => {{ // TODORUST generic closures
+            evaluate_cards_internal::<$($FilterAllowedCards)*, _, $Pruner, $TplStrategies, _, _, _>(
+                rules,
+                stichseq,
+                expensifiers,
+                epi,
+                itahand,
+                $fn_make_filter,
+                $fn_alphabetapruner,
+                $fn_snapshotcache::<$TplStrategies>(rules),
+                fn_inspect,
+                &fn_payout,
+            )
+        }}}
+        let (n_ahand, veccardevaluation, payouts_combined) = cartesian_match!(
+            forward,
+            match (searchoptions.branching) {
+                VBranching::All => ((_), SNoFilter::factory()),
+                VBranching::Random{n_lo, n_hi} => ((_), {
+                    let n_lo = n_lo.max(1);
+                    SBranchingFactor::factory(n_lo, n_hi.max(n_lo+1))
+                }),
+                VBranching::Equivalent{n_until_stichseq_len} => ((_), equivalent_cards_filter(
+                    n_until_stichseq_len,
+                    rules.equivalent_when_on_same_hand(),
+                )),
+                VBranching::Oracle => ((SFilterByOracle), |stichseq, ahand| {
+                    SFilterByOracle::new(rules, ahand, stichseq)
+                }),
+                VBranching::OnePerWinnerIndex(oepi_unfiltered) => ((_), |_stichseq, _ahand| {
+                    SFilterOnePerWinnerIndex::new(oepi_unfiltered, rules)
+                }),
+            },
+            match (searchoptions.b_prune_via_hints) {
+                false => (SPrunerNothing),
+                true => (SPrunerViaHint),
+            },
+            match ((searchoptions.oesinglestrategy, searchoptions.b_alpha_beta_pruning, rules.alpha_beta_pruner_lohi_values())) {
+                (None, _, _) => (
+                    STplStrategiesAll,
+                    |_stichseq, _ahand| SAlphaBetaPrunerNone,
+                ),
+                (Some(ESingleStrategy::MaxMin), false, _) => (
+                    STplStrategiesOnlyMaxMin,
+                    |_stichseq, _ahand| SAlphaBetaPrunerNone,
+                ),
+                (Some(ESingleStrategy::MaxMin), true, _) => (
+                    STplStrategiesOnlyMaxMin,
+                    (|_stichseq, _ahand| SAlphaBetaPruner::new({
+                        let mut mapepilohi = EPlayerIndex::map_from_fn(|_| ELoHi::Lo);
+                        mapepilohi[epi] = ELoHi::Hi;
+                        mapepilohi
+                    })),
+                ),
+                (Some(ESingleStrategy::MaxSelfishMin), false, _) | (Some(ESingleStrategy::MaxSelfishMin), true, None) => (
+                    STplStrategiesOnlyMaxSelfishMin,
+                    |_stichseq, _ahand| SAlphaBetaPrunerNone,
+                ),
+                (Some(ESingleStrategy::MaxSelfishMin), true, Some(fn_alpha_beta_pruner_lohi_values)) => (
+                    STplStrategiesOnlyMaxSelfishMin,
+                    (|stichseq, ahand| SAlphaBetaPruner::new({
+                        let mut mapepilohi = fn_alpha_beta_pruner_lohi_values(
+                            &SRuleStateCacheFixed::new(ahand, stichseq),
+                        );
+                        if mapepilohi[epi]==ELoHi::Lo {
+                            for lohi in mapepilohi.iter_mut() {
+                                *lohi = -*lohi;
+                            }
+                        }
+                        assert_eq!(mapepilohi[epi], ELoHi::Hi);
+                        mapepilohi
+                    })),
+                ),
+            },
+            match (searchoptions.b_snapshot_cache) { // TODO customizable depth
+                true => make_snapshot_cache,
+                false => make_snapshot_cache_none,
+            },
+        ).ok_or(VApiError::NoCardDistribution)?;
+        let card = unwrap!(veccardevaluation.iter()
+            .max_set_by(|cardevaluation_lhs, cardevaluation_rhs| cardevaluation_lhs.payouts.compare_canonical(&cardevaluation_rhs.payouts))
+            .first()
+        ).card;
+        Ok(SSuggestion {
+            card,
+            n_ahand,
+            veccardevaluation,
+            opayouts_combined: Some(payouts_combined),
+        })
+    }
+
+    // Card distributions compatible with the game state (i.e. with the known cards and the stosses given).
+    // fn_filter is told about each card distribution generated and whether it is compatible, and decides
+    // whether to keep it (e.g. to impose further constraints). Samples only count the ones kept.
+    pub fn hands<'gamestate>(
+        &self,
+        gamestate: &'gamestate SGameState,
+        handselection: VHandSelection,
+        fn_filter: impl FnMut(bool/*b_valid*/, &EnumMap<EPlayerIndex, SHand>)->bool + Send + 'gamestate,
+    ) -> Box<dyn Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send + 'gamestate> {
+        let SPosition{rules, stichseq, ahand, expensifiers, epi_position: _} = &gamestate.position;
+        match handselection {
+            VHandSelection::All => Box::new(
+                internal_all_possible_hands(stichseq, ahand.clone(), rules, &expensifiers.vecstoss, fn_filter)
+            ),
+            VHandSelection::Sample{n_samples} => Box::new(
+                internal_forever_rand_hands(stichseq, ahand.clone(), rules, &expensifiers.vecstoss, fn_filter)
+                    .take(n_samples)
+            ),
+            VHandSelection::SampleLikely{n_samples, n_pool} => Box::new(self.likely_hands(
+                internal_forever_rand_hands(stichseq, ahand.clone(), rules, &expensifiers.vecstoss, fn_filter)
+                    .take(n_pool),
+                /*n_keep*/n_samples,
+                rules,
+                stichseq,
+                expensifiers,
+            ).into_iter()),
+        }
+    }
+
+    // Counts (in parallel) how many card distributions of itahand fall into each group (see fn_group), and, per
+    // group, how often each function of slcfn_inspect yields each result.
+    pub fn hand_stats<Group: Hash+Eq+Clone+Send, Inspection: Hash+Eq+Send>(
+        &self,
+        itahand: impl Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send,
+        fn_group: impl Fn(&EnumMap<EPlayerIndex, SHand>)->Group + Sync,
+        slcfn_inspect: &[impl Fn(&EnumMap<EPlayerIndex, SHand>)->Inspection + Sync],
+    ) -> SHandStats<Group, Inspection> {
+        let handstats_empty = || SHandStats {
+            mapgroupn: HashMap::new(),
+            vecmaptplgroupinspectionn: slcfn_inspect.iter().map(|_fn_inspect| HashMap::new()).collect(),
+        };
+        itahand
+            .par_bridge() // TODO can we derive a true parallel iterator?
+            .fold(
+                handstats_empty,
+                |mut handstats, ahand| {
+                    let group = fn_group(&ahand);
+                    for (maptplgroupinspectionn, fn_inspect) in handstats.vecmaptplgroupinspectionn.iter_mut().zip_eq(slcfn_inspect) {
+                        *maptplgroupinspectionn.entry((group.clone(), fn_inspect(&ahand))).or_insert(0) += 1;
+                    }
+                    *handstats.mapgroupn.entry(group).or_insert(0) += 1;
+                    handstats
+                },
+            )
+            .reduce(
+                handstats_empty,
+                |mut handstats_lhs, handstats_rhs| {
+                    for (group, n_count) in handstats_rhs.mapgroupn {
+                        *handstats_lhs.mapgroupn.entry(group).or_insert(0) += n_count;
+                    }
+                    for (maptplgroupinspectionn_lhs, maptplgroupinspectionn_rhs) in handstats_lhs.vecmaptplgroupinspectionn.iter_mut()
+                        .zip_eq(handstats_rhs.vecmaptplgroupinspectionn)
+                    {
+                        for (tplgroupinspection, n_count) in maptplgroupinspectionn_rhs {
+                            *maptplgroupinspectionn_lhs.entry(tplgroupinspection).or_insert(0) += n_count;
+                        }
+                    }
+                    handstats_lhs
+                },
+            )
+    }
+
+    // Keeps the n_keep card distributions of itahand_pool in which the active player of rules (if any) is most
+    // likely to have announced them, i.e. in which the active player's cards promise the highest payout.
+    pub fn likely_hands(
+        &self,
+        itahand_pool: impl Iterator<Item=EnumMap<EPlayerIndex, SHand>>,
+        n_keep: usize,
+        rules: &SRules,
+        stichseq: &SStichSequence,
+        expensifiers: &SExpensifiers,
+    ) -> Vec<EnumMap<EPlayerIndex, SHand>> {
+        let Some(epi_active) = rules.playerindex() else {
+            return itahand_pool.take(n_keep).collect();
+        };
+        let ai = SAi::new_simulating(
+            self.n_rank_rules_samples,
+            /*n_suggest_card_branches*/1, // unused
+            /*n_suggest_card_samples*/0, // unused
+        );
+        itahand_pool
+            .map(|ahand| {
+                let f_payout_avg = ai.rank_rules(
+                    SFullHand::new(
+                        &stichseq.cards_from_player(&ahand[epi_active], epi_active).collect::<Vec<_>>(),
+                        stichseq.kurzlang(),
+                    ),
+                    epi_active,
+                    rules,
+                    expensifiers,
+                ).omaxselfishmin.as_ref().unwrap_static_some().avg();
+                (ahand, f_payout_avg)
+            })
+            .k_largest_by(n_keep, |tplahandf_lhs, tplahandf_rhs| unwrap!(tplahandf_lhs.1.partial_cmp(&tplahandf_rhs.1)))
+            .map(|(ahand, _f_payout_avg)| ahand)
+            .collect()
+    }
+
+    // Estimates the payout for epi if rules were played with the given (complete) hand, after the given
+    // doublings (one entry per player, starting with player 0) and with n_stock in the stock.
+    pub fn evaluate_given_rules(
+        &self,
+        slccard_hand: &[ECard],
+        epi: EPlayerIndex,
+        rules: &SRules,
+        n_stock: isize,
+        ab_doubling: [bool; EPlayerIndex::SIZE],
+    ) -> Result<SRulesEvaluation, VApiError> {
+        let ekurzlang = kurzlang_of_hand(slccard_hand)?;
+        let payoutstats = SAi::new_simulating(
+            self.n_rank_rules_samples,
+            /*n_suggest_card_branches*/1, // unused
+            /*n_suggest_card_samples*/0, // unused
+        ).rank_rules(
+            SFullHand::new(slccard_hand, ekurzlang),
+            epi,
+            rules,
+            &SExpensifiers::new(
+                n_stock,
+                SDoublings::new_full(SStaticEPI0{}, ab_doubling),
+                /*vecstoss*/Vec::new(),
+            ),
+        ).omaxselfishmin.as_ref().unwrap_static_some().clone();
+        Ok(SRulesEvaluation {
+            orules: Some(rules.clone()),
+            f_payout_avg: payoutstats.avg(),
+            n_payout_min: payoutstats.min(),
+            n_payout_max: payoutstats.max(),
+        })
+    }
+
+    // Evaluates all rules epi could announce with the given (complete) hand (see evaluate_given_rules), best first.
+    // Announcing no game (if the ruleset allows it) is rated 0, as its payout depends on the other players.
+    pub fn evaluate_rules(
+        &self,
+        slccard_hand: &[ECard],
+        epi: EPlayerIndex,
+        ruleset: &SRuleSet,
+        n_stock: isize,
+        ab_doubling: [bool; EPlayerIndex::SIZE],
+    ) -> Result<Vec<SRulesEvaluation>, VApiError> {
+        let ekurzlang = kurzlang_of_hand(slccard_hand)?;
+        if ekurzlang!=ruleset.ekurzlang {
+            return Err(VApiError::DeckMismatch{ekurzlang_hand: ekurzlang, ekurzlang_ruleset: ruleset.ekurzlang});
+        }
+        let vecorules = allowed_rules(&ruleset.avecrulegroup[epi], SFullHand::new(slccard_hand, ekurzlang))
+            .collect::<Vec<_>>();
+        let mut vecrulesevaluation = vecorules.iter()
+            .flatten()
+            .map(|rules| self.evaluate_given_rules(slccard_hand, epi, &SRules::from((*rules).clone()), n_stock, ab_doubling))
+            .collect::<Result<Vec<_>, _>>()?;
+        if vecorules.iter().any(Option::is_none) {
+            vecrulesevaluation.push(SRulesEvaluation {
+                orules: None,
+                f_payout_avg: 0.,
+                n_payout_min: 0,
+                n_payout_max: 0,
+            });
+        }
+        vecrulesevaluation.sort_by(|rulesevaluation_lhs, rulesevaluation_rhs|
+            unwrap!(rulesevaluation_rhs.f_payout_avg.partial_cmp(&rulesevaluation_lhs.f_payout_avg))
+        );
+        Ok(vecrulesevaluation)
+    }
+}
+
+#[test]
+fn test_api() {
+    use crate::primitives::card::ECard::*;
+    let rules = unwrap!(rules_from_str("Herz-Solo von 0"));
+    // infers Kurz from the size of the hand and the player to move from the cards played
+    let gamestate = unwrap!(SPositionBuilder::new(rules.clone())
+        .played_cards(unwrap!(cards_from_str("EA EK")))
+        .hand_current(unwrap!(cards_from_str("EZ GA GK SA SZ SK")))
+        .stoss(EPlayerIndex::EPI1, 0)
+        .build());
+    assert_eq!(gamestate.kurzlang(), EKurzLang::Kurz);
+    assert_eq!(gamestate.position(), EPlayerIndex::EPI2);
+    assert_eq!(gamestate.player_to_move(), Some(EPlayerIndex::EPI2));
+    assert_eq!(gamestate.known_cards(EPlayerIndex::EPI2).len(), 6);
+    assert!(gamestate.hand_known(EPlayerIndex::EPI2));
+    assert!(!gamestate.hand_known(EPlayerIndex::EPI0));
+    // round trip via the one-line format
+    let gamestate_parsed = unwrap!(position_from_str(&gamestate.to_string()));
+    assert_eq!(gamestate_parsed.to_string(), gamestate.to_string());
+    for (resgamestate_invalid, apierror) in [
+        (SPositionBuilder::new(rules.clone()).played_cards([EA, EK]).build(), VApiError::DeckAmbiguous),
+        (SPositionBuilder::new(rules.clone()).played_cards([EA, E7]).hand_current([EZ, GA, GK, SA, SZ, SK]).build(), VApiError::NoFittingDeck), // E7 not in Kurz
+        (SPositionBuilder::new(rules.clone()).played_cards([EA, EK]).hand_current([EZ]).build(), VApiError::NoFittingDeck), // too few cards
+        (SPositionBuilder::new(rules.clone()).played_cards([EA, EK]).hand_current([EZ, GA]).hand(EPlayerIndex::EPI2, [SA]).kurzlang(EKurzLang::Kurz).build(), VApiError::NoFittingDeck),
+        (SPositionBuilder::new(rules.clone()).played_cards([EA, EK]).hand(EPlayerIndex::EPI2, [SA]).hand_current([EZ, GA, GK, SZ, SK, S9]).build(), VApiError::HandGivenTwice(EPlayerIndex::EPI2)),
+        (SPositionBuilder::new(rules.clone()).played_cards([EA, EK]).hand_current([EA, GA, GK, SA, SZ, SK]).build(), VApiError::InvalidPosition(VPositionError::CardsUsedTwice(vec![EA]))),
+        (SPositionBuilder::new(rules.clone()).played_cards([EA, EK]).kurzlang(EKurzLang::Kurz).stoss(EPlayerIndex::EPI1, 3).build(), VApiError::InvalidPosition(VPositionError::StossAfterCardsPlayed{epi: EPlayerIndex::EPI1, n_cards_played: 3})),
+    ] {
+        assert_eq!(resgamestate_invalid.map(|gamestate| gamestate.to_string()), Err(apierror));
+    }
+    assert_eq!(rules_from_str("Sie").map(|_rules| ()), Err(VApiError::RulesNotParsable{str_rules: "Sie".to_owned()}));
+    assert_eq!(cards_from_str("EO X7"), Err(VApiError::CardsNotParsable{str_cards: "EO X7".to_owned()}));
+    let str_position_known = "Herz-Solo von 0; EO GO HO SO EU GU HU | GA GZ GK G9 G8 G7 S7 | SA SZ SK S9 S8 SU EK | HA HZ HK H9 H8 H7 EZ; EA E7 E8 E9; 1@0 0@0; 0000; 0; 0";
+    assert!(matches!(position_from_str(&str_position_known.replace("; 0; 0", "; 0")), Err(VApiError::PositionNotParsable{..})));
+    assert!(matches!(
+        position_from_str(&str_position_known.replace("EA E7", "EA GA").replace("GA GZ", "E7 GZ")),
+        Err(VApiError::InvalidPosition(VPositionError::CardNotAllowed{i_card: 1, epi: EPlayerIndex::EPI1, card: GA, ..})),
+    ));
+    // only the player's own cards are kept
+    let gamestate_known = unwrap!(position_from_str(str_position_known));
+    let gamestate_seen_by_1 = gamestate_known.seen_by(EPlayerIndex::EPI1);
+    assert_eq!(gamestate_seen_by_1.position(), EPlayerIndex::EPI1);
+    assert!(EPlayerIndex::values().all(|epi| gamestate_seen_by_1.hand_known(epi)==(epi==EPlayerIndex::EPI1)));
+    for ahand in gamestate_seen_by_1.sample_hands().take(10) {
+        assert_eq!(
+            ahand[EPlayerIndex::EPI1].cards().iter().collect::<std::collections::HashSet<_>>(),
+            gamestate_known.known_cards(EPlayerIndex::EPI1).iter().collect::<std::collections::HashSet<_>>(),
+        );
+    }
+}
+
+#[test]
+fn test_suggest() {
+    let engine = SEngine::new(/*n_rank_rules_samples*/5);
+    let gamestate_known = unwrap!(position_from_str("Herz-Solo von 0; EO GO HO SO EU GU HU | GA GZ GK G9 G8 G7 S7 | SA SZ SK S9 S8 SU EK | HA HZ HK H9 H8 H7 EZ; EA E7 E8 E9; 1@0 0@0; 0000; 0; 0"));
+    for gamestate in [gamestate_known.clone(), gamestate_known.seen_by(EPlayerIndex::EPI0)] {
+        for budget in [SBudget::new(/*n_samples*/3, /*n_branches*/1), SBudget::new(/*n_samples*/3, /*n_branches*/1).prefer_likely_hands()] {
+            let suggestion = unwrap!(engine.suggest(&gamestate, budget));
+            assert!(0 < suggestion.n_ahand); // fewer than n_samples if only likely hands are kept
+            assert!(suggestion.n_ahand <= 3);
+            assert!(suggestion.veccardevaluation.iter().any(|cardevaluation| cardevaluation.card==suggestion.card));
+            for cardevaluation in suggestion.veccardevaluation.iter() {
+                for (_emmstrategy, payoutsummary) in cardevaluation.payouts.strategies() {
+                    assert!(payoutsummary.n_payout_min as f32 <= payoutsummary.f_payout_avg);
+                    assert!(payoutsummary.f_payout_avg <= payoutsummary.n_payout_max as f32);
+                    assert_eq!(payoutsummary.vectplnn_histogram.iter().map(|(_n_payout, n_count)| n_count).sum::<usize>(), suggestion.n_ahand);
+                }
+                assert!(cardevaluation.payouts.pessimistic().f_payout_avg <= cardevaluation.payouts.selfish().f_payout_avg);
+            }
+        }
+    }
+    assert_eq!(
+        engine.suggest_streaming(&gamestate_known, SBudget::new(/*n_samples*/3, /*n_branches*/1), &AtomicBool::new(true), |_progress| {}).map(|suggestion| suggestion.card),
+        Err(VApiError::Cancelled),
+    );
+    assert_eq!(
+        engine.suggest(&gamestate_known.seen_by(EPlayerIndex::EPI1), SBudget::new(/*n_samples*/3, /*n_branches*/1)).map(|suggestion| suggestion.card),
+        Err(VApiError::HandUnknown(EPlayerIndex::EPI0)),
+    );
+    // late in the game, all card distributions are explored, regardless of the budget
+    let gamestate_late = unwrap!(SPositionBuilder::new(unwrap!(rules_from_str("Gras-Solo von 0")))
+        .played_cards(unwrap!(cards_from_str("eo gk g8 e7 ho hu g9 e8 go eu su h7 ea ga e9 ek ha h8 h9 hk sa s7 s8 gz")))
+        .hand_current(unwrap!(cards_from_str("g7 ez")))
+        .build());
+    assert_eq!(
+        unwrap!(engine.suggest(&gamestate_late, SBudget::new(/*n_samples*/1, /*n_branches*/1))).n_ahand,
+        all_possible_hands(gamestate_late.stichseq(), gamestate_late.position.ahand.clone(), gamestate_late.rules(), /*slcstoss*/&[]).count(),
+    );
+    let mut positionbuilder_finished = SPositionBuilder::new(unwrap!(rules_from_str("Gras-Solo von 0")))
+        .played_cards(unwrap!(cards_from_str("eo gk g8 e7 ho hu g9 e8 go eu su h7 ea ga e9 ek ha h8 h9 hk sa s7 s8 gz g7 so gu sz s9 sk hz ez")));
+    assert_eq!(positionbuilder_finished.clone().build().map(|gamestate| gamestate.to_string()), Err(VApiError::PositionRequired));
+    positionbuilder_finished = positionbuilder_finished.position(EPlayerIndex::EPI0);
+    assert_eq!(
+        engine.suggest(&unwrap!(positionbuilder_finished.build()), SBudget::new(/*n_samples*/1, /*n_branches*/1)).map(|suggestion| suggestion.card),
+        Err(VApiError::GameFinished),
+    );
+}
+
+#[test]
+fn test_evaluate_rules() {
+    use crate::primitives::card::ECard::*;
+    let engine = SEngine::new(/*n_rank_rules_samples*/5);
+    let rules = unwrap!(rules_from_str("Herz-Solo von 0"));
+    let slccard_hand = [EO, GO, HO, SO, EU, GU, HU, SU];
+    let rulesevaluation = unwrap!(engine.evaluate_given_rules(&slccard_hand, EPlayerIndex::EPI0, &rules, /*n_stock*/0, [false; EPlayerIndex::SIZE]));
+    assert!(rulesevaluation.orules.is_some());
+    assert!(rulesevaluation.n_payout_min as f32 <= rulesevaluation.f_payout_avg);
+    assert!(rulesevaluation.f_payout_avg <= rulesevaluation.n_payout_max as f32);
+    // with all trumpf, the Solo is won, and every doubling doubles the payout
+    let rulesevaluation_doubled = unwrap!(engine.evaluate_given_rules(&slccard_hand, EPlayerIndex::EPI0, &rules, /*n_stock*/0, [true, true, false, false]));
+    assert_eq!(rulesevaluation_doubled.n_payout_min, 4*rulesevaluation.n_payout_min);
+    for (slccard_hand_invalid, apierror) in [
+        (&[EO, GO, HO][..], VApiError::WrongHandSize{n_cards: 3}),
+        (&[EO, GO, HO, SO, EU, GU, HU, SU, E7, E8, E9, EK, EZ, EA][..], VApiError::WrongHandSize{n_cards: 14}),
+        (&[EO, GO, HO, SO, EU, E7][..], VApiError::CardNotInDeck{card: E7, ekurzlang: EKurzLang::Kurz}),
+        (&[EO, GO, HO, SO, EU, EO][..], VApiError::CardUsedTwice(EO)),
+    ] {
+        assert_eq!(
+            engine.evaluate_given_rules(slccard_hand_invalid, EPlayerIndex::EPI0, &rules, /*n_stock*/0, [false; EPlayerIndex::SIZE]).map(|rulesevaluation| rulesevaluation.f_payout_avg),
+            Err(apierror),
+        );
+    }
+    let ruleset = unwrap!(SRuleSet::from_string(
+        r"
+        base-price=10
+        solo-price=50
+        lauf-min=3
+        [rufspiel]
+        [solo]
+        ",
+    ));
+    let vecrulesevaluation = unwrap!(engine.evaluate_rules(&slccard_hand, EPlayerIndex::EPI0, &ruleset, /*n_stock*/0, [false; EPlayerIndex::SIZE]));
+    assert!(vecrulesevaluation.iter().any(|rulesevaluation| rulesevaluation.orules.is_none()));
+    assert!(vecrulesevaluation.iter().any(|rulesevaluation| rulesevaluation.orules.is_some()));
+    assert!(vecrulesevaluation.iter().tuple_windows().all(|(rulesevaluation_lhs, rulesevaluation_rhs)| rulesevaluation_rhs.f_payout_avg <= rulesevaluation_lhs.f_payout_avg));
+    assert_eq!(
+        engine.evaluate_rules(&[EO, GO, HO, SO, EU, GU], EPlayerIndex::EPI0, &ruleset, /*n_stock*/0, [false; EPlayerIndex::SIZE]).map(|vecrulesevaluation| vecrulesevaluation.len()),
+        Err(VApiError::DeckMismatch{ekurzlang_hand: EKurzLang::Kurz, ekurzlang_ruleset: EKurzLang::Lang}),
+    );
+}
+
+#[test]
+fn test_likely_hands() {
+    let engine = SEngine::new(/*n_rank_rules_samples*/5);
+    let gamestate = unwrap!(position_from_str("Rufspiel mit der Gras-Sau von 1; EO GO HO SO EU GU HU SU | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __; ; ; 0000; 0; 0"));
+    let vecahand = engine.likely_hands(gamestate.sample_hands().take(20), /*n_keep*/5, gamestate.rules(), gamestate.stichseq(), gamestate.expensifiers());
+    assert_eq!(vecahand.len(), 5);
+    for ahand in vecahand.iter() {
+        assert_eq!(
+            ahand[EPlayerIndex::EPI0].cards().iter().collect::<std::collections::HashSet<_>>(),
+            gamestate.known_cards(EPlayerIndex::EPI0).iter().collect::<std::collections::HashSet<_>>(),
+        );
+    }
+}
+
+#[test]
+fn test_evaluate_cards() {
+    let engine = SEngine::new(/*n_rank_rules_samples*/5);
+    let gamestate_known = unwrap!(SPositionBuilder::new(unwrap!(rules_from_str("Gras-Solo von 0")))
+        .played_cards(unwrap!(cards_from_str("eo gk g8 e7 ho hu g9 e8 go eu su h7 ea ga e9 ek ha h8 h9 hk sa s7 s8 gz")))
+        .hand_current(unwrap!(cards_from_str("g7 ez")))
+        .build());
+    let epi_current = unwrap!(gamestate_known.player_to_move());
+    // the player to move is evaluated from the perspective of someone else, who does not know their cards
+    let gamestate = gamestate_known.seen_by(epi_current.wrapping_add(1));
+    let vecahand_all = engine.hands(&gamestate, VHandSelection::All, /*fn_filter*/|b_valid, _ahand| b_valid).collect::<Vec<_>>();
+    assert_eq!(
+        vecahand_all.len(),
+        all_possible_hands(gamestate.stichseq(), gamestate.position.ahand.clone(), gamestate.rules(), /*slcstoss*/&[]).count(),
+    );
+    let vecahand_ez = engine.hands(&gamestate, VHandSelection::All, /*fn_filter*/|b_valid, ahand| b_valid && ahand[epi_current].cards().contains(&ECard::EZ))
+        .collect::<Vec<_>>();
+    assert!(vecahand_ez.iter().all(|ahand| ahand[epi_current].cards().contains(&ECard::EZ)));
+    assert!(vecahand_ez.len() < vecahand_all.len());
+    assert_eq!(engine.hands(&gamestate, VHandSelection::Sample{n_samples: 3}, /*fn_filter*/|b_valid, _ahand| b_valid).count(), 3);
+    for searchoptions in [
+        SSearchOptions::default(),
+        SSearchOptions {
+            branching: VBranching::Random{n_lo: 1, n_hi: 2},
+            oesinglestrategy: Some(ESingleStrategy::MaxMin),
+            b_alpha_beta_pruning: true,
+            n_repeat_hands: 2,
+            ..SSearchOptions::default()
+        },
+        SSearchOptions {
+            branching: VBranching::Equivalent{n_until_stichseq_len: 8},
+            oesinglestrategy: Some(ESingleStrategy::MaxSelfishMin),
+            b_prune_via_hints: true,
+            b_snapshot_cache: true,
+            b_points: true,
+            ..SSearchOptions::default()
+        },
+    ] {
+        let mutexn_after_hand = Mutex::new(0);
+        let suggestion = unwrap!(engine.evaluate_cards(
+            &gamestate,
+            vecahand_ez.iter().cloned(),
+            &searchoptions,
+            /*fn_inspect*/|inspectionpoint, _i_ahand, _ahand| {
+                if let VInspectionPoint::AfterHand(progress) = inspectionpoint {
+                    let mut n_after_hand = unwrap!(mutexn_after_hand.lock());
+                    *n_after_hand += 1;
+                    assert_eq!(progress.n_ahand, *n_after_hand);
+                }
+            },
+        ));
+        assert_eq!(suggestion.n_ahand, vecahand_ez.len() * searchoptions.n_repeat_hands);
+        assert_eq!(unwrap!(mutexn_after_hand.into_inner()), suggestion.n_ahand);
+        assert!(suggestion.veccardevaluation.iter().any(|cardevaluation| cardevaluation.card==suggestion.card));
+        for cardevaluation in suggestion.veccardevaluation.iter() {
+            assert_eq!(
+                cardevaluation.payouts.strategies().map(|(emmstrategy, _payoutsummary)| emmstrategy).collect::<Vec<_>>(),
+                match searchoptions.oesinglestrategy {
+                    None => vec![EMinMaxStrategy::MinMin, EMinMaxStrategy::MaxMin, EMinMaxStrategy::MaxSelfishMin, EMinMaxStrategy::MaxSelfishMax, EMinMaxStrategy::Max],
+                    Some(ESingleStrategy::MaxMin) => vec![EMinMaxStrategy::MaxMin],
+                    Some(ESingleStrategy::MaxSelfishMin) => vec![EMinMaxStrategy::MaxSelfishMin],
+                },
+            );
+            for (_emmstrategy, payoutsummary) in cardevaluation.payouts.strategies() {
+                assert_eq!(payoutsummary.vectplnn_histogram.iter().map(|(_n_payout, n_count)| n_count).sum::<usize>(), suggestion.n_ahand);
+            }
+        }
+        assert!(suggestion.veccardevaluation.iter().all(|cardevaluation|
+            cardevaluation.payouts.compare_canonical(&unwrap!(suggestion.veccardevaluation.iter().find(|cardevaluation_best| cardevaluation_best.card==suggestion.card)).payouts)
+                !=std::cmp::Ordering::Greater
+        ));
+    }
+    assert_eq!(
+        engine.evaluate_cards(&gamestate, std::iter::empty(), &SSearchOptions::default(), /*fn_inspect*/|_inspectionpoint, _i_ahand, _ahand| {}).map(|suggestion| suggestion.card),
+        Err(VApiError::NoCardDistribution),
+    );
+    let handstats = engine.hand_stats(
+        vecahand_all.iter().cloned(),
+        /*fn_group*/|ahand| ahand[epi_current].cards().contains(&ECard::EZ),
+        &[|ahand: &EnumMap<EPlayerIndex, SHand>| ahand[epi_current].cards().len()],
+    );
+    assert_eq!(handstats.mapgroupn.get(&true), Some(&vecahand_ez.len()));
+    assert_eq!(handstats.mapgroupn.values().sum::<usize>(), vecahand_all.len());
+    assert_eq!(
+        handstats.vecmaptplgroupinspectionn,
+        vec![handstats.mapgroupn.iter().map(|(b_ez, n_count)| ((*b_ez, /*n_cards*/2), *n_count)).collect::<HashMap<_, _>>()],
+    );
+}
+
+#[test]
+fn test_checkpoint() {
+    let gamestate = unwrap!(SPositionBuilder::new(unwrap!(rules_from_str("Gras-Solo von 0")))
+        .played_cards(unwrap!(cards_from_str("eo gk g8 e7 ho hu g9 e8 go eu su h7 ea ga e9 ek ha h8 h9 hk sa s7 s8 gz")))
+        .hand_current(unwrap!(cards_from_str("g7 ez")))
+        .build());
+    let gamestate_checkpoint = unwrap!(gamestate.checkpoint(6));
+    assert_eq!(gamestate_checkpoint.stichseq().visible_cards().count(), 6);
+    assert_eq!(gamestate_checkpoint.player_to_move(), Some(gamestate_checkpoint.position()));
+    // the player then to move knows the cards they played afterwards
+    let epi_checkpoint = gamestate_checkpoint.position();
+    assert_eq!(
+        gamestate_checkpoint.known_cards(epi_checkpoint).len(),
+        gamestate.known_cards(epi_checkpoint).len() + gamestate.stichseq().visible_cards().skip(6).filter(|(epi, _card)| *epi==epi_checkpoint).count(),
+    );
+    assert_eq!(unwrap!(gamestate.checkpoint(24)).to_string(), gamestate.to_string());
+    assert_eq!(gamestate.checkpoint(25).map(|gamestate| gamestate.to_string()), Err(VApiError::CheckpointNotReached{n_cards_played: 25}));
+}
+
+#[test]
+fn test_games_from_str() {
+    let str_game = "Rufspiel mit der Gras-Sau von 3: so h7 go eo ho hz hk eu gu h9 su g8 g9 ga gk e9 ea ek ez e7 g7 ha s7 gz sa s9 h8 sz e8 sk hu s8";
+    assert_eq!(unwrap!(games_from_str(str_game)).len(), 1);
+    assert_eq!(unwrap!(games_from_str(&format!("{str_game}\n# comment\n{str_game}"))).len(), 2);
+    match games_from_str("no game") {
+        Err(VApiError::GameNotParsable(vectplegameformatstr_reason)) => {
+            assert_eq!(
+                vectplegameformatstr_reason.iter().map(|(egameformat, _str_reason)| *egameformat).collect::<Vec<_>>(),
+                vec![EGameFormat::SauspielHtml, EGameFormat::SauspielJson, EGameFormat::Plain, EGameFormat::NetSchafkopf, EGameFormat::GameRecord],
+            );
+        },
+        resvecgameresult => panic!("Unexpected result: {:?}", resvecgameresult.map(|vecgameresult| vecgameresult.len())),
+    }
+}
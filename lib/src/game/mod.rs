@@ -41,7 +41,7 @@ impl SExpensifiersNoStoss {
         }
     }
 
-    fn into_with_stoss(self) -> SExpensifiers {
+    pub fn into_with_stoss(self) -> SExpensifiers {
         SExpensifiers::new(
             self.n_stock,
             self.doublings,
@@ -50,7 +50,7 @@ impl SExpensifiersNoStoss {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SDealCards {
     aveccard : EnumMap<EPlayerIndex, /*not yet a "hand"*/SHandVector>,
     expensifiers: SExpensifiersNoStoss,
@@ -86,19 +86,33 @@ pub fn first_hand_for(slccard: &[ECard], ekurzlang: EKurzLang) -> &[ECard] {
 impl SDealCards {
     pub fn new(ruleset: SRuleSet, n_stock: isize) -> SDealCards {
         let ekurzlang = ruleset.ekurzlang;
-        SDealCards {
-            aveccard : {
+        Self::new_with_cards(
+            ruleset,
+            n_stock,
+            {
                 let mut veccard = ECard::values(ekurzlang).collect::<Vec<_>>();
                 assert_eq!(veccard.len(), EPlayerIndex::SIZE*ekurzlang.cards_per_player());
                 EPlayerIndex::map_from_fn(move |_epi|
                     random_hand(ekurzlang.cards_per_player(), &mut veccard)
                 )
             },
+        )
+    }
+
+    // Deals the given cards, e.g. to replay a game.
+    pub fn new_with_cards(ruleset: SRuleSet, n_stock: isize, aveccard: EnumMap<EPlayerIndex, SHandVector>) -> SDealCards {
+        assert!(aveccard.iter().all(|veccard| veccard.len()==ruleset.ekurzlang.cards_per_player()));
+        SDealCards {
+            aveccard,
             expensifiers: SExpensifiersNoStoss::new(n_stock),
             ruleset,
         }
     }
 
+    pub fn cards(&self) -> &EnumMap<EPlayerIndex, SHandVector> {
+        &self.aveccard
+    }
+
     pub fn first_hand_for(&self, epi: EPlayerIndex) -> &[ECard] {
         first_hand_for(&self.aveccard[epi], self.ruleset.ekurzlang)
     }
@@ -222,7 +236,7 @@ impl SGamePreparations {
     }
 }
 
-#[derive(new, Debug)]
+#[derive(new, Debug, Clone)]
 pub struct SDetermineRules {
     pub aveccard : EnumMap<EPlayerIndex, SHandVector>,
     pub expensifiers : SExpensifiersNoStoss,
@@ -492,6 +506,10 @@ impl<Ruleset, GameAnnouncement, DetermineRules> SGameGeneric<Ruleset, GameAnnoun
         }
     }
 
+    pub fn ruleset(&self) -> &Ruleset {
+        &self.ruleset
+    }
+
     forward_to_field!(self.stichseq,
         pub fn current_playable_stich(&self) -> &SStich;
         pub fn completed_stichs(&self) -> &[SStich];
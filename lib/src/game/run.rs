@@ -2,84 +2,229 @@ use super::*;
 use crate::player::*;
 use std::sync::mpsc;
 
-pub struct SAtTable {
-    pub player: Box<dyn TPlayer>,
+pub struct SAtTable<Player=Box<dyn TPlayer>> {
+    pub player: Player,
     pub n_money: isize,
 }
 
-fn communicate_via_channel<T: std::fmt::Debug>(f: impl FnOnce(mpsc::Sender<T>)) -> T {
+pub enum VCardOrUndo {
+    Card(ECard),
+    Undo(SGameGeneric<SRuleSet, (), ()>), // the game as it was before the card that is taken back
+}
+
+// Players taking part in internal_run_simple_game_loop. Some of them may take back cards.
+pub trait TGameLoopPlayer {
+    fn player(&self) -> &dyn TPlayer;
+
+    fn ask_for_card_or_undo(&self, game: &SGameGeneric<SRuleSet, (), ()>) -> Result<VCardOrUndo, Error> {
+        try_communicate_via_channel(|txcard| {
+            self.player().ask_for_card(game, txcard);
+        }).map(VCardOrUndo::Card)
+    }
+
+    // Called for all players if some player took back a card so that n_cards_played cards remain.
+    fn forget_decisions_after(&self, _n_cards_played: usize) {
+    }
+}
+
+impl TGameLoopPlayer for Box<dyn TPlayer> {
+    fn player(&self) -> &dyn TPlayer {
+        self.as_ref()
+    }
+}
+
+pub fn communicate_via_channel<T: std::fmt::Debug>(f: impl FnOnce(mpsc::Sender<T>)) -> T {
+    unwrap!(try_communicate_via_channel(f))
+}
+
+// Fails if the player gives up (e.g. because its input is closed), i.e. drops the sender without answering.
+pub fn try_communicate_via_channel<T>(f: impl FnOnce(mpsc::Sender<T>)) -> Result<T, Error> {
     let (txt, rxt) = mpsc::channel::<T>();
     f(txt);
-    unwrap!(rxt.recv())
+    rxt.recv().map_err(|_| format_err!("Player gave up without answering."))
 }
 
-pub fn internal_run_simple_game_loop<ItStockOrGame: Iterator<Item=VStockOrT<SGameResult<SRuleSet>, SGameGeneric<SRuleSet, (), ()>>>>(
-    aplayer: EnumMap<EPlayerIndex, Box<dyn TPlayer>>,
+fn notify_players<'player>(fn_player: &impl Fn(EPlayerIndex)->&'player dyn TPlayer, gameevent: VGameEvent) {
+    for epi in EPlayerIndex::values() {
+        fn_player(epi).notify(&gameevent);
+    }
+}
+
+pub fn ask_for_doublings<'player>(dealcards: &mut SDealCards, fn_player: impl Fn(EPlayerIndex)->&'player dyn TPlayer) -> Result<(), Error> {
+    while let Some(epi) = dealcards.which_player_can_do_something() {
+        let b_doubling = try_communicate_via_channel(|txb_doubling| {
+            fn_player(epi).ask_for_doubling(
+                dealcards.first_hand_for(epi),
+                txb_doubling
+            );
+        })?;
+        notify_players(&fn_player, VGameEvent::Doubling{epi, b_doubling});
+        unwrap!(dealcards.announce_doubling(epi, b_doubling));
+    }
+    Ok(())
+}
+
+pub fn determine_rules<'player>(
+    mut gamepreparations: SGamePreparations,
+    fn_player: impl Fn(EPlayerIndex)->&'player dyn TPlayer,
+) -> Result<VStockOrT<SGameResult<SRuleSet>, SGameGeneric<SRuleSet, (), ()>>, Error> {
+    while let Some(epi) = gamepreparations.which_player_can_do_something() {
+        info!("Asking player {epi} for game");
+        let orules = try_communicate_via_channel(|txorules| {
+            fn_player(epi).ask_for_game(
+                epi,
+                gamepreparations.fullhand(epi),
+                &gamepreparations.gameannouncements,
+                &gamepreparations.ruleset.avecrulegroup[epi],
+                &gamepreparations.expensifiers.clone().into_with_stoss(),
+                None,
+                txorules
+            );
+        })?.cloned();
+        notify_players(&fn_player, VGameEvent::Announcement{epi, orules: orules.as_ref()});
+        unwrap!(gamepreparations.announce_game(epi, orules));
+    }
+    info!("Asked players if they want to play. Determining rules");
+    Ok(match unwrap!(gamepreparations.finish()) {
+        VGamePreparationsFinish::DetermineRules(mut determinerules) => {
+            while let Some((epi, vecrulegroup_steigered))=determinerules.which_player_can_do_something() {
+                if let Some(rules) = try_communicate_via_channel(|txorules| {
+                    fn_player(epi).ask_for_game(
+                        epi,
+                        determinerules.fullhand(epi),
+                        /*gameannouncements*/&SPlayersInRound::new(SStaticEPI0{}),
+                        &vecrulegroup_steigered,
+                        &determinerules.expensifiers.clone().into_with_stoss(),
+                        Some(determinerules.currently_offered_prio()),
+                        txorules
+                    );
+                })?.cloned() {
+                    notify_players(&fn_player, VGameEvent::Announcement{epi, orules: Some(&rules)});
+                    unwrap!(determinerules.announce_game(epi, rules));
+                } else {
+                    notify_players(&fn_player, VGameEvent::Announcement{epi, orules: None});
+                    unwrap!(determinerules.resign(epi));
+                }
+            }
+            let game = unwrap!(determinerules.finish());
+            notify_players(&fn_player, VGameEvent::Rules{rules: &game.rules});
+            VStockOrT::OrT(game)
+        },
+        VGamePreparationsFinish::DirectGame(game) => {
+            notify_players(&fn_player, VGameEvent::Rules{rules: &game.rules});
+            VStockOrT::OrT(game)
+        },
+        VGamePreparationsFinish::Stock(gameresult) => {
+            VStockOrT::Stock(gameresult)
+        }
+    })
+}
+
+// Returns the first of the given players that wants to give stoss.
+pub fn ask_for_stoss<'player>(
+    game: &SGameGeneric<SRuleSet, (), ()>,
+    slcepi_stoss: &[EPlayerIndex],
+    fn_player: impl Fn(EPlayerIndex)->&'player dyn TPlayer,
+) -> Result<Option<EPlayerIndex>, Error> {
+    for &epi in slcepi_stoss {
+        if try_communicate_via_channel(|txb_stoss| {
+            fn_player(epi).ask_for_stoss(
+                epi,
+                &game.rules,
+                &game.ahand[epi],
+                &game.stichseq,
+                &game.expensifiers,
+                txb_stoss,
+            );
+        })? {
+            notify_players(&fn_player, VGameEvent::Stoss{epi});
+            return Ok(Some(epi));
+        }
+    }
+    Ok(None)
+}
+
+// Plays n_games games. After each game, fn_after_game may request a replay of the same cards, where
+// the players at the given seats swap places. Replays do not count towards the players' money.
+// Fails if a player gives up.
+pub fn internal_run_simple_game_loop<Player: TGameLoopPlayer, ItStockOrGame: Iterator<Item=Result<VStockOrT<SGameResult<SRuleSet>, SGameGeneric<SRuleSet, (), ()>>, Error>>>(
+    aplayer: EnumMap<EPlayerIndex, Player>,
     n_games: usize,
     ruleset: SRuleSet,
-    fn_gamepreparations_to_stockorgame: impl Fn(SGamePreparations, &EnumMap<EPlayerIndex, SAtTable>)->ItStockOrGame,
-    fn_print_account_balance: impl Fn(&EnumMap<EPlayerIndex, isize>, isize),
-) -> ([SAtTable; EPlayerIndex::SIZE], isize) {
+    fn_gamepreparations_to_stockorgame: impl Fn(SGamePreparations, &EnumMap<EPlayerIndex, SAtTable<Player>>)->ItStockOrGame,
+    fn_print_account_balance: impl Fn(&EnumMap<EPlayerIndex, SAtTable<Player>>, isize),
+    mut fn_after_game: impl FnMut(&SGameResult<SRuleSet>, &EnumMap<EPlayerIndex, SHandVector>, &EnumMap<EPlayerIndex, SAtTable<Player>>)->Option<(EPlayerIndex, EPlayerIndex)>,
+) -> Result<([SAtTable<Player>; EPlayerIndex::SIZE], isize), Error> {
     let mut aattable = aplayer.map_into(|player| SAtTable{player, n_money:0});
     let mut n_stock = 0;
     for _i_game in 0..n_games {
-        let mut dealcards = SDealCards::new(ruleset.clone(), n_stock);
-        while let Some(epi) = dealcards.which_player_can_do_something() {
-            unwrap!(dealcards.announce_doubling(
-                epi,
-                /*b_doubling*/communicate_via_channel(|txb_doubling| {
-                    aattable[epi].player.ask_for_doubling(
-                        dealcards.first_hand_for(epi),
-                        txb_doubling
-                    );
-                })
-            ));
-        }
-        for stockorgame in fn_gamepreparations_to_stockorgame(unwrap!(dealcards.finish()), &aattable) {
-            let gameresult = match stockorgame {
-                VStockOrT::OrT(mut game) => {
-                    while let Some(gameaction)=game.which_player_can_do_something() {
-                        if !gameaction.1.is_empty()
-                            && let Some(epi_stoss) = gameaction.1.iter()
-                                .find(|epi| {
-                                    communicate_via_channel(|txb_stoss| {
-                                        aattable[**epi].player.ask_for_stoss(
-                                            **epi,
-                                            &game.rules,
-                                            &game.ahand[**epi],
-                                            &game.stichseq,
-                                            &game.expensifiers,
-                                            txb_stoss,
-                                        );
-                                    })
-                                })
-                        {
-                                unwrap!(game.stoss(*epi_stoss));
+        let mut oaveccard_replay : Option<EnumMap<EPlayerIndex, SHandVector>> = None;
+        let mut vectplepiepi_swapped = Vec::new(); // to restore the seats after replays
+        loop {
+            let b_replay = oaveccard_replay.is_some();
+            let mut dealcards = match oaveccard_replay.take() {
+                None => SDealCards::new(ruleset.clone(), n_stock),
+                Some(aveccard) => SDealCards::new_with_cards(ruleset.clone(), n_stock, aveccard),
+            };
+            let aveccard = dealcards.cards().clone();
+            for epi in EPlayerIndex::values() {
+                aattable[epi].player.player().notify(&VGameEvent::Seat{epi});
+            }
+            ask_for_doublings(&mut dealcards, |epi| aattable[epi].player.player())?;
+            let mut otplepiepi_replay = None;
+            for stockorgame in fn_gamepreparations_to_stockorgame(unwrap!(dealcards.finish()), &aattable) {
+                let gameresult = match stockorgame? {
+                    VStockOrT::OrT(mut game) => {
+                        while let Some(gameaction)=game.which_player_can_do_something() {
+                            if let Some(epi_stoss) = ask_for_stoss(&game, &gameaction.1, |epi| aattable[epi].player.player())? {
+                                unwrap!(game.stoss(epi_stoss));
                                 continue;
+                            }
+                            match aattable[gameaction.0].player.ask_for_card_or_undo(&game)? {
+                                VCardOrUndo::Card(card) => {
+                                    notify_players(&|epi| aattable[epi].player.player(), VGameEvent::Card{epi: gameaction.0, card});
+                                    unwrap!(game.zugeben(card, gameaction.0));
+                                },
+                                VCardOrUndo::Undo(game_before_card) => {
+                                    for attable in aattable.iter() {
+                                        attable.player.forget_decisions_after(game_before_card.stichseq.count_played_cards());
+                                    }
+                                    game = game_before_card;
+                                },
+                            }
                         }
-                        unwrap!(game.zugeben(
-                            communicate_via_channel(|txcard| {
-                                aattable[gameaction.0].player.ask_for_card(
-                                    &game,
-                                    txcard,
-                                );
-                            }),
-                            gameaction.0
-                        ));
-                    }
-                    unwrap!(game.finish())
-                },
-                VStockOrT::Stock(gameresult) => gameresult,
-            };
-            gameresult.apply_payout(&mut n_stock, |epi, n_payout| {
-                aattable[epi].n_money += n_payout;
-            });
-            assert_eq!(n_stock + aattable.iter().map(|attable| attable.n_money).sum::<isize>(), 0);
-            fn_print_account_balance(&aattable.map(|attable| attable.n_money), n_stock);
+                        unwrap!(game.finish())
+                    },
+                    VStockOrT::Stock(gameresult) => gameresult,
+                };
+                notify_players(&|epi| aattable[epi].player.player(), VGameEvent::GameResult{an_payout: &gameresult.an_payout});
+                if !b_replay { // replays are for training only
+                    gameresult.apply_payout(&mut n_stock, |epi, n_payout| {
+                        aattable[epi].n_money += n_payout;
+                    });
+                    assert_eq!(n_stock + aattable.iter().map(|attable| attable.n_money).sum::<isize>(), 0);
+                    notify_players(&|epi| aattable[epi].player.player(), VGameEvent::Balance{
+                        an_money: &aattable.map(|attable| attable.n_money),
+                        n_stock,
+                    });
+                    fn_print_account_balance(&aattable, n_stock);
+                }
+                otplepiepi_replay = fn_after_game(&gameresult, &aveccard, &aattable);
+            }
+            if let Some((epi_a, epi_b)) = otplepiepi_replay {
+                aattable.as_raw_mut().swap(epi_a.to_usize(), epi_b.to_usize());
+                vectplepiepi_swapped.push((epi_a, epi_b));
+                oaveccard_replay = Some(aveccard);
+            } else {
+                break;
+            }
+        }
+        for (epi_a, epi_b) in vectplepiepi_swapped.into_iter().rev() {
+            aattable.as_raw_mut().swap(epi_a.to_usize(), epi_b.to_usize());
         }
         aattable.as_raw_mut().rotate_left(1);
     }
-    (aattable.into_raw(), n_stock)
+    Ok((aattable.into_raw(), n_stock))
 }
 
 pub fn run_simple_game_loop(
@@ -87,60 +232,16 @@ pub fn run_simple_game_loop(
     n_games: usize,
     ruleset: SRuleSet,
     fn_print_account_balance: impl Fn(&EnumMap<EPlayerIndex, isize>, isize),
-) -> ([SAtTable; EPlayerIndex::SIZE], isize) {
+) -> Result<([SAtTable; EPlayerIndex::SIZE], isize), Error> {
     internal_run_simple_game_loop(
         aplayer,
         n_games,
         ruleset,
-        /*fn_gamepreparations_to_stockorgame*/|mut gamepreparations, aattable| {
-            while let Some(epi) = gamepreparations.which_player_can_do_something() {
-                info!("Asking player {epi} for game");
-                unwrap!(gamepreparations.announce_game(
-                    epi,
-                    communicate_via_channel(|txorules| {
-                        aattable[epi].player.ask_for_game(
-                            epi,
-                            gamepreparations.fullhand(epi),
-                            &gamepreparations.gameannouncements,
-                            &gamepreparations.ruleset.avecrulegroup[epi],
-                            &gamepreparations.expensifiers.clone().into_with_stoss(),
-                            None,
-                            txorules
-                        );
-                    }).cloned()
-                ));
-            }
-            info!("Asked players if they want to play. Determining rules");
-            std::iter::once(match unwrap!(gamepreparations.finish()) {
-                VGamePreparationsFinish::DetermineRules(mut determinerules) => {
-                    while let Some((epi, vecrulegroup_steigered))=determinerules.which_player_can_do_something() {
-                        if let Some(rules) = communicate_via_channel(|txorules| {
-                            aattable[epi].player.ask_for_game(
-                                epi,
-                                determinerules.fullhand(epi),
-                                /*gameannouncements*/&SPlayersInRound::new(SStaticEPI0{}),
-                                &vecrulegroup_steigered,
-                                &determinerules.expensifiers.clone().into_with_stoss(),
-                                Some(determinerules.currently_offered_prio()),
-                                txorules
-                            );
-                        }).cloned() {
-                            unwrap!(determinerules.announce_game(epi, rules));
-                        } else {
-                            unwrap!(determinerules.resign(epi));
-                        }
-                    }
-                    VStockOrT::OrT(unwrap!(determinerules.finish()))
-                },
-                VGamePreparationsFinish::DirectGame(game) => {
-                    VStockOrT::OrT(game)
-                },
-                VGamePreparationsFinish::Stock(gameresult) => {
-                    VStockOrT::Stock(gameresult)
-                }
-            })
+        /*fn_gamepreparations_to_stockorgame*/|gamepreparations, aattable| {
+            std::iter::once(determine_rules(gamepreparations, move |epi| aattable[epi].player.as_ref()))
         },
-        fn_print_account_balance,
+        /*fn_print_account_balance*/|aattable, n_stock| fn_print_account_balance(&aattable.map(|attable| attable.n_money), n_stock),
+        /*fn_after_game*/|_gameresult, _aveccard, _aattable| None, // no replays
     )
 }
 
@@ -238,20 +339,21 @@ fn test_game_loop() {
             })
             .sample(&mut rng, 2)
     {
-        run_simple_game_loop(
+        unwrap!(run_simple_game_loop(
             EPlayerIndex::map_from_fn(|epi| -> Box<dyn TPlayer> {
-                Box::new(SPlayerComputer{ai: {
-                    if epi<EPlayerIndex::EPI2 {
+                Box::new(SPlayerComputer{
+                    ai: if epi<EPlayerIndex::EPI2 {
                         ai::SAi::new_cheating(/*n_rank_rules_samples*/1, /*n_suggest_card_branches*/2)
                     } else {
                         ai::SAi::new_simulating(/*n_rank_rules_samples*/1, /*n_suggest_card_branches*/1, /*n_suggest_card_samples*/1)
-                    }
-                }})
+                    },
+                    str_name: format!("SPlayerComputer {epi}"),
+                })
             }),
             /*n_games*/4,
             ruleset,
             /*fn_print_account_balance*/|_,_| {/* no output */},
-        );
+        ));
     }
 }
 
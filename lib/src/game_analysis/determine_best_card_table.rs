@@ -1,6 +1,6 @@
 use crate::ai::{
-    gametree::{TTplStrategies, SPerMinMaxStrategyGeneric},
-    SDetermineBestCardResult, SPayoutStats,
+    gametree::{compare_canonical, EMinMaxStrategy},
+    SPayoutStats,
 };
 use crate::primitives::*;
 use itertools::*;
@@ -12,9 +12,9 @@ pub const N_COLUMNS : usize = 4;
 
 // crude formatting: treat all numbers as f32, and convert structured input to a plain number table
 #[derive(PartialEq, Clone)]
-pub struct SOutputLine<T, TplStrategies: TTplStrategies> {
+pub struct SOutputLine<T> {
     pub vect: Vec<T>,
-    pub perminmaxstrategyatplstrf: SPerMinMaxStrategyGeneric<[(String, f32); N_COLUMNS], TplStrategies>,
+    pub vectplemmstrategyatplstrf: Vec<(EMinMaxStrategy, [(String, f32); N_COLUMNS])>,
 }
 
 #[derive(Clone, /*TODO really needed for array construction?*/Copy)]
@@ -31,54 +31,61 @@ impl PartialEq for EGrouping {
     }
 }
 
-pub struct SPayoutStatsTable<T, TplStrategies: TTplStrategies> {
-    vecoutputline: Vec<SOutputLine<T, TplStrategies>>,
-    perminmaxstrategyaformatinfo: SPerMinMaxStrategyGeneric<[SFormatInfo; N_COLUMNS], TplStrategies>,
+// All lines contain the same strategies in the same order.
+pub struct SPayoutStatsTable<T> {
+    vecoutputline: Vec<SOutputLine<T>>,
+    vectplemmstrategyaformatinfo: Vec<(EMinMaxStrategy, [SFormatInfo; N_COLUMNS])>,
 }
-impl<T, TplStrategies: TTplStrategies> SPayoutStatsTable<T, TplStrategies> {
+impl<T> SPayoutStatsTable<T> {
     // TODO? would an accessor macro be helpful?
-    pub fn output_lines(&self) -> &Vec<SOutputLine<T, TplStrategies>> {
+    pub fn output_lines(&self) -> &Vec<SOutputLine<T>> {
         &self.vecoutputline
     }
-    pub fn into_output_lines(self) -> Vec<SOutputLine<T, TplStrategies>> {
+    pub fn into_output_lines(self) -> Vec<SOutputLine<T>> {
         self.vecoutputline
     }
-    pub fn format_infos(&self) -> &SPerMinMaxStrategyGeneric<[SFormatInfo; N_COLUMNS], TplStrategies> {
-        &self.perminmaxstrategyaformatinfo
+    pub fn format_infos(&self) -> &Vec<(EMinMaxStrategy, [SFormatInfo; N_COLUMNS])> {
+        &self.vectplemmstrategyaformatinfo
     }
 }
 
 pub fn internal_table<
-    TplStrategies: TTplStrategies,
     T,
     PayoutStatsPayload: Copy+Ord+std::fmt::Debug+'static, // TODO why is 'static needed?
-    PayoutStatsPerStrategy: Borrow<SPerMinMaxStrategyGeneric<SPayoutStats<PayoutStatsPayload>, TplStrategies>>,
+    PayoutStats: Borrow<SPayoutStats<PayoutStatsPayload>>,
 >(
-    mut vectpayoutstatsperstrategy: Vec<(T, PayoutStatsPerStrategy)>,
+    mut vecvectplemmstrategypayoutstats: Vec<(T, Vec<(EMinMaxStrategy, PayoutStats)>)>,
     b_group: bool,
     fn_loss_or_win: &dyn Fn(isize, PayoutStatsPayload) -> std::cmp::Ordering,
-) -> SPayoutStatsTable<T, TplStrategies> {
-    vectpayoutstatsperstrategy.sort_unstable_by(|(_t_lhs, minmax_lhs), (_t_rhs, minmax_rhs)| {
-        minmax_lhs.borrow().compare_canonical(minmax_rhs.borrow(), fn_loss_or_win)
+) -> SPayoutStatsTable<T> {
+    vecvectplemmstrategypayoutstats.sort_unstable_by(|(_t_lhs, vectplemmstrategypayoutstats_lhs), (_t_rhs, vectplemmstrategypayoutstats_rhs)| {
+        compare_canonical(vectplemmstrategypayoutstats_lhs, vectplemmstrategypayoutstats_rhs, fn_loss_or_win)
     });
-    vectpayoutstatsperstrategy.reverse(); // descending
-    let mut vecoutputline : Vec<SOutputLine<_,_>> = Vec::new();
-    let mut perminmaxstrategyaformatinfo = SPerMinMaxStrategyGeneric::<_,_>::new([
-        SFormatInfo {
-            f_min: f32::MAX,
-            f_max: f32::MIN,
-            n_width: 0,
-        };
-        N_COLUMNS
-    ]);
-    let b_exists_count_of_zero_payout_positive = vectpayoutstatsperstrategy.iter().any(|(_, perminmaxstrategypaystats)| {
-        perminmaxstrategypaystats.borrow().via_accessors().iter().any(|(_emmstrategy, paystats)|
-            0!=paystats.counts(fn_loss_or_win)[std::cmp::Ordering::Equal]
+    vecvectplemmstrategypayoutstats.reverse(); // descending
+    let mut vecoutputline : Vec<SOutputLine<_>> = Vec::new();
+    let mut vectplemmstrategyaformatinfo = vecvectplemmstrategypayoutstats.first()
+        .map(|(_t, vectplemmstrategypayoutstats)| vectplemmstrategypayoutstats.iter()
+            .map(|(emmstrategy, _payoutstats)| (
+                *emmstrategy,
+                [
+                    SFormatInfo {
+                        f_min: f32::MAX,
+                        f_max: f32::MIN,
+                        n_width: 0,
+                    };
+                    N_COLUMNS
+                ],
+            ))
+            .collect::<Vec<_>>()
+        )
+        .unwrap_or_default();
+    let b_exists_count_of_zero_payout_positive = vecvectplemmstrategypayoutstats.iter().any(|(_, vectplemmstrategypayoutstats)| {
+        vectplemmstrategypayoutstats.iter().any(|(_emmstrategy, paystats)|
+            0!=paystats.borrow().counts(fn_loss_or_win)[std::cmp::Ordering::Equal]
         )
     });
-    for ((perminmaxstrategyatplstrf, _grouping), grptpltmapemmstrategyatplstrf) in vectpayoutstatsperstrategy.into_iter()
-        .map(|(t, minmax)| {
-            let minmax = minmax.borrow();
+    for ((vectplemmstrategyatplstrf, _grouping), grptpltmapemmstrategyatplstrf) in vecvectplemmstrategypayoutstats.into_iter()
+        .map(|(t, vectplemmstrategypayoutstats)| {
             let column_counts = |paystats: &SPayoutStats<PayoutStatsPayload>| {
                 let mapordn_count = paystats.counts(fn_loss_or_win);
                 let f_percentage_not_lost = (mapordn_count[std::cmp::Ordering::Equal]+mapordn_count[std::cmp::Ordering::Greater])
@@ -113,17 +120,25 @@ pub fn internal_table<
             };
             (
                 t,
-                minmax.map(|payoutstats| [
-                    column_min_or_max(payoutstats.min()),
-                    column_average(payoutstats),
-                    column_min_or_max(payoutstats.max()),
-                    column_counts(payoutstats),
-                ]),
+                vectplemmstrategypayoutstats.iter()
+                    .map(|(emmstrategy, payoutstats)| {
+                        let payoutstats = payoutstats.borrow();
+                        (
+                            *emmstrategy,
+                            [
+                                column_min_or_max(payoutstats.min()),
+                                column_average(payoutstats),
+                                column_min_or_max(payoutstats.max()),
+                                column_counts(payoutstats),
+                            ],
+                        )
+                    })
+                    .collect::<Vec<_>>(),
             )
         })
-        .chunk_by(|(_t, perminmaxstrategyatplstrf)| {
+        .chunk_by(|(_t, vectplemmstrategyatplstrf)| {
             (
-                perminmaxstrategyatplstrf.clone(),
+                vectplemmstrategyatplstrf.clone(),
                 if b_group {
                     EGrouping::Group
                 } else {
@@ -133,7 +148,11 @@ pub fn internal_table<
         })
         .into_iter()
     {
-        perminmaxstrategyaformatinfo.modify_with_other(&perminmaxstrategyatplstrf, |aformatinfo, atplstrf| {
+        for ((emmstrategy_aformatinfo, aformatinfo), (emmstrategy_atplstrf, atplstrf)) in itertools::zip_eq(
+            vectplemmstrategyaformatinfo.iter_mut(),
+            vectplemmstrategyatplstrf.iter(),
+        ) {
+            assert_eq!(emmstrategy_aformatinfo, emmstrategy_atplstrf);
             for ((str_val, f_val), formatinfo) in itertools::zip_eq(
                 atplstrf.iter(),
                 aformatinfo.iter_mut(),
@@ -142,30 +161,31 @@ pub fn internal_table<
                 assign_lt_partial_ord(&mut formatinfo.f_min, *f_val);
                 assign_gt_partial_ord(&mut formatinfo.f_max, *f_val);
             }
-        });
+        }
         vecoutputline.push(SOutputLine{
             vect: grptpltmapemmstrategyatplstrf.into_iter()
                 .map(|(t, _atplstrf)| t)
                 .collect(),
-            perminmaxstrategyatplstrf,
+            vectplemmstrategyatplstrf,
         });
     }
     SPayoutStatsTable{
         vecoutputline,
-        perminmaxstrategyaformatinfo,
+        vectplemmstrategyaformatinfo,
     }
 }
 
+// Groups cards with equal payout stats.
 pub fn table<
-    TplStrategies: TTplStrategies,
     PayoutStatsPayload: Copy+Ord+std::fmt::Debug+'static,
+    PayoutStats: Borrow<SPayoutStats<PayoutStatsPayload>>,
 >(
-    determinebestcardresult: &SDetermineBestCardResult<SPerMinMaxStrategyGeneric<SPayoutStats<PayoutStatsPayload>, TplStrategies>>,
+    itcardvectplemmstrategypayoutstats: impl Iterator<Item=(ECard, Vec<(EMinMaxStrategy, PayoutStats)>)>,
     rules: &SRules,
     fn_loss_or_win: &dyn Fn(isize, PayoutStatsPayload) -> std::cmp::Ordering,
-) -> SPayoutStatsTable<ECard, TplStrategies> {
+) -> SPayoutStatsTable<ECard> {
     let mut payoutstatstable = internal_table(
-        determinebestcardresult.cards_and_ts().collect(),
+        itcardvectplemmstrategypayoutstats.collect(),
         /*b_group*/true,
         fn_loss_or_win,
     );
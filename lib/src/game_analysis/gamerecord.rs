@@ -0,0 +1,454 @@
+use crate::game::*;
+use crate::game_analysis::parser::*;
+use crate::primitives::*;
+use crate::rules::{
+    SDoublings,
+    SStoss,
+    SStossParams,
+    ruleset::{SRuleSet, VStockOrT},
+    parser::{parse_rule_description, rule_description},
+};
+use crate::util::*;
+use serde::{Serialize, Deserialize};
+use itertools::Itertools;
+
+// Increment whenever SGameRecord changes incompatibly.
+pub const N_VERSION_GAMERECORD: usize = 3;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SGameRecordRules {
+    pub str_rules: String, // understood by parse_rule_description, players given by index
+    pub n_tarif_extra: isize,
+    pub n_tarif_ruf: isize,
+    pub n_tarif_solo: isize,
+    pub n_stoss_max: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SGameRecordStoss {
+    pub epi: EPlayerIndex,
+    pub n_cards_played: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum VGameRecordRuleset {
+    Sauspiel(SSauspielRuleset),
+    NetSchafkopf {
+        str_name: String, // given in the protocol's first line
+        str_toml: String, // ruleset reconstructed from the protocol, understood by SRuleSet::from_string
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SGameRecordTable {
+    pub str_source: String,
+    pub oruleset: Option<VGameRecordRuleset>,
+    pub omapepistr_player: Option<[String; EPlayerIndex::SIZE]>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SGameRecordGame {
+    pub rules: SGameRecordRules,
+    pub mapepiogameannouncement: [Option<SSauspielGameAnnouncement>; EPlayerIndex::SIZE], // None if unknown
+    pub vecdeterminerulesstep: Vec<SSauspielDetermineRulesStep>, // empty if unknown
+    pub vecstoss: Vec<SGameRecordStoss>,
+    pub veccard: Vec<ECard>, // in the order they were played
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SGameRecord {
+    pub n_version: usize,
+    pub table: SGameRecordTable,
+    pub mapepiveccard: [Vec<ECard>; EPlayerIndex::SIZE], // in the order they were dealt, empty if unknown
+    pub n_stock: isize,
+    pub vecb_doubling: Vec<bool>, // starting at EPI0
+    pub an_payout: [isize; EPlayerIndex::SIZE],
+    pub ogame: Option<SGameRecordGame>, // None if the game was not played (Stock)
+}
+
+pub type SGameResultRecorded = SGameResultGeneric<SGameRecordTable, Option<SSauspielGameAnnouncement>, Vec<SSauspielDetermineRulesStep>>;
+
+impl SGameRecord {
+    pub fn new<Ruleset, GameAnnouncement, DetermineRules>(
+        gameresult: &SGameResultGeneric<Ruleset, GameAnnouncement, DetermineRules>,
+        odeal_stock: Option<&SDeal>, // cards and doublings if gameresult is Stock and they are known
+        table: SGameRecordTable,
+        (n_tarif_extra, n_tarif_ruf, n_tarif_solo): (isize, isize, isize),
+        n_stoss_max: usize,
+        fn_announcement: impl Fn(&GameAnnouncement)->Option<SSauspielGameAnnouncement>,
+        fn_determinerules: impl FnOnce(&DetermineRules)->Vec<SSauspielDetermineRulesStep>,
+    ) -> Self {
+        fn vecb_doubling(doublings: &SDoublings) -> Vec<bool> {
+            doublings.iter().map(|(_epi, &b_doubling)| b_doubling).collect()
+        }
+        let (mapepiveccard, n_stock, vecb_doubling, ogame) = match &gameresult.stockorgame {
+            VStockOrT::Stock(_ruleset) => (
+                EPlayerIndex::map_from_fn(|epi| odeal_stock.map_or(Vec::new(), |deal| deal.aveccard[epi].iter().copied().collect())),
+                /*n_stock*/0,
+                odeal_stock.map_or(Vec::new(), |deal| vecb_doubling(&deal.doublings)),
+                /*ogame*/None,
+            ),
+            VStockOrT::OrT(game) => (
+                game.aveccard.map(|veccard| veccard.iter().copied().collect()),
+                game.expensifiers.n_stock,
+                vecb_doubling(&game.expensifiers.doublings),
+                Some(SGameRecordGame {
+                    rules: SGameRecordRules {
+                        str_rules: rule_description(&game.rules),
+                        n_tarif_extra,
+                        n_tarif_ruf,
+                        n_tarif_solo,
+                        n_stoss_max,
+                    },
+                    mapepiogameannouncement: game.mapepigameannouncement.map(&fn_announcement).into_raw(),
+                    vecdeterminerulesstep: fn_determinerules(&game.determinerules),
+                    vecstoss: game.expensifiers.vecstoss.iter()
+                        .map(|stoss| SGameRecordStoss{epi: stoss.epi, n_cards_played: stoss.n_cards_played})
+                        .collect(),
+                    veccard: game.stichseq.visible_cards().map(|(_epi, card)| *card).collect(),
+                }),
+            ),
+        };
+        Self {
+            n_version: N_VERSION_GAMERECORD,
+            table,
+            mapepiveccard: mapepiveccard.into_raw(),
+            n_stock,
+            vecb_doubling,
+            an_payout: gameresult.an_payout.into_raw(),
+            ogame,
+        }
+    }
+
+    // Dealt cards and doublings, None if the cards are unknown.
+    pub fn deal(&self) -> Result<Option<(EKurzLang, SDeal)>, SStringifiedError> {
+        if self.mapepiveccard.iter().all(|veccard| veccard.is_empty()) {
+            return Ok(None);
+        }
+        if self.vecb_doubling.len() > EPlayerIndex::SIZE {
+            return Err(format_err!("Too many doublings: {:?}", self.vecb_doubling));
+        }
+        let mut doublings = SDoublings::new(SStaticEPI0{});
+        for &b_doubling in self.vecb_doubling.iter() {
+            doublings.push(b_doubling);
+        }
+        let ekurzlang = self.mapepiveccard.iter()
+            .map(|veccard| EKurzLang::from_cards_per_player(veccard.len()))
+            .all_equal_value()
+            .map_err(|e| format_err!("Not all players have the same number of cards: {:?}", e))?
+            .ok_or_else(|| format_err!("Could not determine ekurzlang"))?;
+        let aveccard = EPlayerIndex::map_from_raw(unwrap!(
+            self.mapepiveccard.iter()
+                .map(|veccard| SHandVector::try_from(veccard.as_slice()))
+                .collect::<Result<Vec<_>, _>>()?
+                .try_into()
+                .ok()
+        ));
+        Ok(Some((ekurzlang, SDeal{aveccard, doublings})))
+    }
+
+    // Replays the recorded game and checks that it yields the recorded payout.
+    pub fn gameresult(&self) -> Result<SGameResultRecorded, SStringifiedError> {
+        if self.n_version!=N_VERSION_GAMERECORD {
+            return Err(format_err!("Unsupported game record version {} (expected {}).", self.n_version, N_VERSION_GAMERECORD));
+        }
+        let an_payout = EPlayerIndex::map_from_raw(self.an_payout);
+        let odeal = self.deal()?;
+        let Some(recordgame) = &self.ogame else {
+            return Ok(SGameResultGeneric {
+                an_payout,
+                stockorgame: VStockOrT::Stock(self.table.clone()),
+            });
+        };
+        let (ekurzlang, SDeal{aveccard, doublings}) = odeal
+            .ok_or_else(|| format_err!("Game record does not contain the dealt cards."))?;
+        let rules = parse_rule_description(
+            &recordgame.rules.str_rules,
+            (recordgame.rules.n_tarif_extra, recordgame.rules.n_tarif_ruf, recordgame.rules.n_tarif_solo),
+            SStossParams::new(recordgame.rules.n_stoss_max),
+            /*fn_player_to_epi*/|str_epi| str_epi.parse::<EPlayerIndex>().map_err(|err| format_err!("{}", err)),
+        )?;
+        // parse_rule_description does not know NetSchafkopf's Laufende and Ramsch rules.
+        let rules = match &self.table.oruleset {
+            Some(VGameRecordRuleset::NetSchafkopf{str_name, str_toml}) => SRuleSet::from_string(str_toml)?
+                .rules_like(&rules)
+                .ok_or_else(|| format_err!("{} not part of ruleset {}", recordgame.rules.str_rules, str_name))?,
+            Some(VGameRecordRuleset::Sauspiel(_)) | None => rules,
+        };
+        let stichseq = SStichSequence::new_from_cards(ekurzlang, recordgame.veccard.iter().copied(), &rules)
+            .map_err(|SDuplicateCard(card)| format_err!("Duplicate card: {}", card))?;
+        let gameresult = SGameGeneric::new_with(
+            aveccard,
+            SExpensifiersNoStoss::new_with_doublings(self.n_stock, doublings),
+            rules,
+            self.table.clone(),
+            EPlayerIndex::map_from_raw(recordgame.mapepiogameannouncement.clone()),
+            recordgame.vecdeterminerulesstep.clone(),
+        )
+            .play_cards_and_stoss(
+                recordgame.vecstoss.iter().map(|stoss| SStoss{epi: stoss.epi, n_cards_played: stoss.n_cards_played}),
+                stichseq.visible_cards(),
+                /*fn_before_zugeben*/|_,_,_,_| {},
+            )?
+            .finish()
+            .map_err(|_game| format_err!("Game record does not contain a finished game."))?;
+        if gameresult.an_payout!=an_payout {
+            return Err(format_err!("Recorded payout {:?} differs from computed payout {:?}.", an_payout, gameresult.an_payout));
+        }
+        Ok(gameresult)
+    }
+}
+
+fn table_plain(str_source: &str) -> SGameRecordTable {
+    SGameRecordTable {
+        str_source: str_source.to_string(),
+        oruleset: None,
+        omapepistr_player: None,
+    }
+}
+
+// Tarif and stoss limit assumed by parse_rule_description_simple.
+const TPLN_TARIF_DEFAULT: (isize, isize, isize) = (/*n_tarif_extra*/10, /*n_tarif_ruf*/20, /*n_tarif_solo*/50);
+const N_STOSS_MAX_DEFAULT: usize = 4;
+
+pub fn gamerecord_from_sauspiel_html(str_html: &str) -> Result<SGameRecord, SStringifiedError> {
+    let (gameresult, mapepistr_username, odeal_stock) = internal_analyze_sauspiel_html(
+        select::document::Document::from(str_html),
+        /*fn_gameannouncement*/|_epi, gameannouncement, _node| gameannouncement.clone(),
+        /*fn_determinerules_step*/|determinerulesstep, _node| determinerulesstep.clone(),
+        /*fn_before_play_card*/|_,_,_,_| (),
+    )?;
+    let ruleset = match &gameresult.stockorgame {
+        VStockOrT::Stock(ruleset) => ruleset,
+        VStockOrT::OrT(game) => game.ruleset(),
+    };
+    Ok(SGameRecord::new(
+        &gameresult,
+        odeal_stock.as_ref(),
+        SGameRecordTable {
+            str_source: "sauspiel-html".into(),
+            oruleset: Some(VGameRecordRuleset::Sauspiel(ruleset.clone())),
+            omapepistr_player: Some(mapepistr_username.into_raw()),
+        },
+        ruleset.tarif(),
+        N_STOSS_MAX_DEFAULT,
+        /*fn_announcement*/|gameannouncement| Some(gameannouncement.clone()),
+        /*fn_determinerules*/|vecdeterminerulesstep| vecdeterminerulesstep.clone(),
+    ))
+}
+
+pub fn gamerecord_from_sauspiel_json(str_json: &str) -> Result<SGameRecord, SStringifiedError> {
+    let gameresult = analyze_sauspiel_json(str_json, /*fn_before_zugeben*/|_,_,_,_| {})?;
+    let ruleset = match &gameresult.stockorgame {
+        VStockOrT::Stock(ruleset) => ruleset,
+        VStockOrT::OrT(game) => game.ruleset(),
+    };
+    Ok(SGameRecord::new(
+        &gameresult,
+        /*odeal_stock*/None,
+        SGameRecordTable {
+            str_source: "sauspiel-json".into(),
+            oruleset: Some(VGameRecordRuleset::Sauspiel(ruleset.clone())),
+            omapepistr_player: None,
+        },
+        ruleset.tarif(),
+        N_STOSS_MAX_DEFAULT,
+        /*fn_announcement*/|ogameannouncement| ogameannouncement.clone(),
+        /*fn_determinerules*/|vecdeterminerulesstep| vecdeterminerulesstep.clone(),
+    ))
+}
+
+pub fn gamerecords_from_netschafkopf(str_lines: &str) -> Result<Vec<Result<SGameRecord, SStringifiedError>>, SStringifiedError> {
+    Ok(analyze_netschafkopf(str_lines)?
+        .into_iter()
+        .map(|resnetschafkopfgame| resnetschafkopfgame.map(|netschafkopfgame| {
+            let gameresult = &netschafkopfgame.gameresult;
+            let ruleset = match &gameresult.stockorgame {
+                VStockOrT::Stock(ruleset) => ruleset,
+                VStockOrT::OrT(game) => game.ruleset(),
+            };
+            SGameRecord::new(
+                gameresult,
+                /*odeal_stock: NetSchafkopf does not reveal all cards*/None,
+                SGameRecordTable {
+                    str_source: "netschafkopf".into(),
+                    oruleset: Some(VGameRecordRuleset::NetSchafkopf {
+                        str_name: ruleset.name().to_string(),
+                        str_toml: ruleset.toml().to_string(),
+                    }),
+                    omapepistr_player: None,
+                },
+                ruleset.tarif(),
+                N_STOSS_MAX_NETSCHAFKOPF,
+                /*fn_announcement*/|()| None,
+                /*fn_determinerules*/|()| Vec::new(),
+            )
+        }))
+        .collect())
+}
+
+pub fn gamerecords_from_plain(str_lines: &str) -> impl Iterator<Item=Result<SGameRecord, SStringifiedError>> + '_ {
+    analyze_plain(str_lines).map(|resgame| {
+        let gameresult = resgame?.finish().map_err(|_game| format_err!("Could not game.finish"))?;
+        Ok(SGameRecord::new(
+            &gameresult,
+            /*odeal_stock*/None,
+            table_plain("plain"),
+            TPLN_TARIF_DEFAULT,
+            N_STOSS_MAX_DEFAULT,
+            /*fn_announcement*/|()| None,
+            /*fn_determinerules*/|()| Vec::new(),
+        ))
+    })
+}
+
+// Tries all importers, yielding every game that could be read from str_input.
+pub fn gamerecords_from_str(str_input: &str) -> Vec<SGameRecord> {
+    if let Ok(gamerecord) = gamerecord_from_sauspiel_html(str_input)
+        .or_else(|_err| gamerecord_from_sauspiel_json(str_input))
+    {
+        vec![gamerecord]
+    } else {
+        gamerecords_from_plain(str_input)
+            .chain(gamerecords_from_netschafkopf(str_input).into_iter().flatten())
+            .filter_map(Result::ok)
+            .collect()
+    }
+}
+
+// Reads one JSON-serialized SGameRecord per line.
+pub fn analyze_gamerecords(str_lines: &str) -> impl Iterator<Item=Result<SGameResultRecorded, SStringifiedError>> + '_ {
+    str_lines
+        .lines()
+        .filter(|str_line| str_line.trim_start().starts_with('{'))
+        .map(|str_line| serde_json::from_str::<SGameRecord>(str_line)?.gameresult())
+}
+
+#[cfg(test)]
+fn assert_gamerecords_roundtrip(itresgamerecord: impl IntoIterator<Item=Result<SGameRecord, SStringifiedError>>) {
+    let mut b_found = false;
+    for gamerecord in itresgamerecord.into_iter().map(|resgamerecord| unwrap!(resgamerecord)) {
+        b_found = true;
+        let str_json = unwrap!(serde_json::to_string(&gamerecord));
+        let gamerecord_parsed = unwrap!(serde_json::from_str::<SGameRecord>(&str_json));
+        assert_eq!(gamerecord, gamerecord_parsed);
+        let gameresult = unwrap!(gamerecord_parsed.gameresult());
+        let gamerecord_rewritten = match &gamerecord.ogame {
+            None => SGameRecord::new(
+                &gameresult,
+                unwrap!(gamerecord.deal()).map(|(_ekurzlang, deal)| deal).as_ref(),
+                gamerecord.table.clone(),
+                TPLN_TARIF_DEFAULT,
+                N_STOSS_MAX_DEFAULT,
+                /*fn_announcement*/|ogameannouncement| ogameannouncement.clone(),
+                /*fn_determinerules*/|vecdeterminerulesstep| vecdeterminerulesstep.clone(),
+            ),
+            Some(recordgame) => {
+                let VStockOrT::OrT(game) = &gameresult.stockorgame else {
+                    panic!("Game record yielded Stock instead of game.");
+                };
+                assert_eq!(rule_description(&game.rules), recordgame.rules.str_rules);
+                SGameRecord::new(
+                    &gameresult,
+                    /*odeal_stock*/None,
+                    gamerecord.table.clone(),
+                    (recordgame.rules.n_tarif_extra, recordgame.rules.n_tarif_ruf, recordgame.rules.n_tarif_solo),
+                    recordgame.rules.n_stoss_max,
+                    /*fn_announcement*/|ogameannouncement| ogameannouncement.clone(),
+                    /*fn_determinerules*/|vecdeterminerulesstep| vecdeterminerulesstep.clone(),
+                )
+            },
+        };
+        assert_eq!(gamerecord, gamerecord_rewritten);
+        assert_eq!(
+            unwrap!(unwrap!(analyze_gamerecords(&str_json).exactly_one().ok())).an_payout,
+            gameresult.an_payout,
+        );
+    }
+    assert!(b_found);
+}
+
+#[test]
+fn test_gamerecord_roundtrip() {
+    for str_plain in [
+        include_str!("../../../examples/example_games.txt"),
+        include_str!("../../../examples/wiesegger_joseph_schafkopf_buch/uebung_3_5.txt"),
+        include_str!("../../../examples/wiesegger_joseph_schafkopf_buch/uebung_3_31.txt"),
+        include_str!("../../../examples/wiesegger_joseph_schafkopf_buch/uebung_4_14.txt"),
+        include_str!("../../../examples/wiesegger_joseph_schafkopf_buch/uebung_6_2.txt"),
+    ] {
+        assert_gamerecords_roundtrip(gamerecords_from_plain(str_plain));
+    }
+    for str_netschafkopf in [
+        include_bytes!("Schafkopfprotokoll_vom_14.12.22.txt").as_slice(),
+        include_bytes!("Schafkopfprotokoll_vom_16.05.20.txt"),
+        include_bytes!("Schafkopfprotokoll_vom_20.03.23.txt"),
+        include_bytes!("Schafkopfprotokoll_vom_04.08.24.txt"),
+    ].map(String::from_utf8_lossy) {
+        assert_gamerecords_roundtrip(unwrap!(gamerecords_from_netschafkopf(&str_netschafkopf)));
+    }
+}
+
+#[test]
+fn test_gamerecord_sauspiel_json() {
+    let (_rules, stichseq) = stichseq_sauspiel_for_test();
+    let str_json = sauspiel_json_for_test(/*b_doubling_stoss*/true, /*str_base_rate*/"");
+    let gamerecord = unwrap!(gamerecord_from_sauspiel_json(&str_json));
+    let recordgame = unwrap!(gamerecord.ogame.as_ref());
+    assert_eq!(recordgame.veccard, stichseq.visible_cards().map(|(_epi, card)| *card).collect::<Vec<_>>());
+    assert_eq!(gamerecord.vecb_doubling, vec![false, true, false, false]);
+    assert_eq!(recordgame.vecstoss.len(), 1);
+    assert_eq!(recordgame.vecdeterminerulesstep.len(), 2); // "hätt a Sauspiel", "spielt auf die Blaue"
+    assert!(recordgame.mapepiogameannouncement[EPlayerIndex::EPI3.to_usize()].is_some());
+    assert_eq!(
+        EPlayerIndex::map_from_raw(gamerecord.an_payout),
+        unwrap!(analyze_sauspiel_json(&str_json, /*fn_before_zugeben*/|_,_,_,_| {})).an_payout,
+    );
+    assert_gamerecords_roundtrip([Ok(gamerecord)]);
+}
+
+#[test]
+fn test_gamerecord_sauspiel_tarif() {
+    let str_json = sauspiel_json_for_test(/*b_doubling_stoss*/true, /*str_base_rate*/"P 5 / 10 / 25");
+    let gamerecord = unwrap!(gamerecord_from_sauspiel_json(&str_json));
+    let recordgame = unwrap!(gamerecord.ogame.as_ref());
+    assert_eq!(
+        (recordgame.rules.n_tarif_extra, recordgame.rules.n_tarif_ruf, recordgame.rules.n_tarif_solo),
+        (5, 10, 25),
+    );
+    assert_eq!(
+        EPlayerIndex::map_from_raw(gamerecord.an_payout).map(|n_payout| n_payout*2),
+        unwrap!(gamerecord_from_sauspiel_json(&sauspiel_json_for_test(/*b_doubling_stoss*/true, /*str_base_rate*/""))).an_payout,
+    );
+    assert_gamerecords_roundtrip([Ok(gamerecord.clone())]);
+    // The recorded payout is checked against the recorded prices.
+    let mut gamerecord_wrong_tarif = gamerecord;
+    unwrap!(gamerecord_wrong_tarif.ogame.as_mut()).rules.n_tarif_ruf = 20;
+    assert!(gamerecord_wrong_tarif.gameresult().is_err());
+}
+
+#[test]
+fn test_gamerecord_sauspiel_html() {
+    let str_html = sauspiel_html_for_test();
+    let gamerecord = unwrap!(gamerecord_from_sauspiel_html(&str_html));
+    let recordgame = unwrap!(gamerecord.ogame.as_ref());
+    assert_eq!(
+        (recordgame.rules.n_tarif_extra, recordgame.rules.n_tarif_ruf, recordgame.rules.n_tarif_solo),
+        (10, 20, 50),
+    );
+    assert_eq!(unwrap!(gamerecord.table.omapepistr_player.as_ref())[0], "Spieler0");
+    assert_eq!(gamerecord.vecb_doubling, vec![false, true, false, false]);
+    assert_eq!(recordgame.vecstoss.len(), 1);
+    assert_eq!(
+        EPlayerIndex::map_from_raw(gamerecord.an_payout),
+        unwrap!(analyze_sauspiel_html(&str_html)).an_payout,
+    );
+    assert_gamerecords_roundtrip([Ok(gamerecord)]);
+    // Prices other than the default ones survive the round trip.
+    let gamerecord_cheap = unwrap!(gamerecord_from_sauspiel_html(&str_html.replace("P 10 / 20 / 50", "P 5 / 10 / 25")));
+    assert_eq!(
+        EPlayerIndex::map_from_raw(gamerecord_cheap.an_payout).map(|n_payout| n_payout*2),
+        unwrap!(analyze_sauspiel_html(&str_html)).an_payout,
+    );
+    assert_gamerecords_roundtrip([Ok(gamerecord_cheap)]);
+}
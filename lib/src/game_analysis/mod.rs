@@ -10,7 +10,9 @@ use std::{
 };
 
 pub mod determine_best_card_table;
+pub mod gamerecord;
 pub mod parser;
+pub mod position;
 
 
 pub fn generate_html_auxiliary_files(path_out_dir: &std::path::Path) -> Result<(), std::io::Error> {
@@ -47,8 +49,9 @@ pub fn html_payout_table<'a, 'b, HtmlAttributeOrChildCard: html_generator::Attri
         );
         otplstrf_first.into_iter()
     }
-    let vecoutputline_cheating = determine_best_card_table::table::</*TODO type annotations needed?*/TplStrategies, _>(
-        determinebestcardresult,
+    let vecoutputline_cheating = determine_best_card_table::table(
+        determinebestcardresult.cards_and_ts()
+            .map(|(card, payoutstatsperstrategy)| (card, payoutstatsperstrategy.via_accessors())),
         rules,
         /*fn_loss_or_win*/&|n_payout, ()| n_payout.cmp(&0),
     ).into_output_lines();
@@ -74,7 +77,7 @@ pub fn html_payout_table<'a, 'b, HtmlAttributeOrChildCard: html_generator::Attri
                 colspan(format!("{}",
                     unwrap!(vecoutputline_cheating.iter()
                         .map(|outputline|
-                            outputline.perminmaxstrategyatplstrf.via_accessors().into_iter().flat_map(|(_emmstrategy, atplstrf)| condensed_cheating_columns(atplstrf)).count()
+                            outputline.vectplemmstrategyatplstrf.iter().flat_map(|(_emmstrategy, atplstrf)| condensed_cheating_columns(atplstrf)).count()
                         )
                         .all_equal_value()),
                 )),
@@ -84,7 +87,7 @@ pub fn html_payout_table<'a, 'b, HtmlAttributeOrChildCard: html_generator::Attri
         )
     }));
     table((
-        html_iter(vecoutputline_cheating.into_iter().map(move |SOutputLine{vect, perminmaxstrategyatplstrf}| {
+        html_iter(vecoutputline_cheating.into_iter().map(move |SOutputLine{vect, vectplemmstrategyatplstrf}| {
             tr((
                 td((
                     attributes::style("padding: 5px;"),
@@ -93,10 +96,8 @@ pub fn html_payout_table<'a, 'b, HtmlAttributeOrChildCard: html_generator::Attri
                     )),
                 )),
                 {
-                    html_iter(perminmaxstrategyatplstrf.via_accessors().into_iter()
-                        .map(|(_emmstrategy, atplstrf)| atplstrf.to_owned())
-                        .collect::<Vec<_>>() // TODO required?
-                        .into_iter()
+                    html_iter(vectplemmstrategyatplstrf.into_iter()
+                        .map(|(_emmstrategy, atplstrf)| atplstrf)
                         .flat_map(|atplstrf| {
                             // TODO simplify to one item per emmstrategy
                             condensed_cheating_columns(&atplstrf)
@@ -113,12 +114,17 @@ pub fn html_payout_table<'a, 'b, HtmlAttributeOrChildCard: html_generator::Attri
     ))
 }
 
+// Button copying a suggest-card command for the current position; empty if the game is already over.
 pub fn html_copy_button(
     rules: &SRules,
     ahand: &EnumMap<EPlayerIndex, SHand>,
     stichseq: &SStichSequence,
+    expensifiers: &SExpensifiers,
     str_openschafkopf_executable: &str,
 ) -> String { // TODO use html_generator
+    let Some(epi_current) = stichseq.current_stich().current_playerindex() else {
+        return String::new();
+    };
     format!(r###"<button onclick='
         (function /*copyToClipboard*/(str, btn) {{
             navigator.clipboard.writeText(str).then(
@@ -131,13 +137,19 @@ pub fn html_copy_button(
             );
         }})("{}", this)
         '>&#128203</button>"###,
-        format!("{str_openschafkopf_executable} suggest-card --rules \"{str_rules}\" --played-cards \"{str_cards_on_table}\" --hand \"{str_hand_all}\" --hand \"{str_hand_single}\"",
-            str_rules=SDisplayRules::new(rules, /*b_include_playerindex*/true),
-            str_cards_on_table=stichseq.visible_stichs().iter()
-                .filter_map(|stich| if_then_some!(!stich.is_empty(), stich.iter().map(|(_epi, card)| *card).join(" ")))
-                .join("  "),
-            str_hand_all=display_card_slices(ahand, rules, "  "),
-            str_hand_single=SDisplayCardSlice::new(ahand[unwrap!(stichseq.current_stich().current_playerindex())].cards().to_vec(), rules),
+        format!("{str_openschafkopf_executable} suggest-card --position-string \"{position_all}\" --position-string \"{position_single}\"",
+            position_all=position::SPosition::new(rules, stichseq, ahand, expensifiers, epi_current),
+            position_single=position::SPosition::new(
+                rules,
+                stichseq,
+                &EPlayerIndex::map_from_fn(|epi| if epi==epi_current {
+                    ahand[epi].clone()
+                } else {
+                    SHand::new_from_iter(std::iter::empty::<ECard>())
+                }),
+                expensifiers,
+                epi_current,
+            ),
         ).replace('\"', "\\\""),
     )
 }
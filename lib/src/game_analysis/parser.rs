@@ -1,17 +1,19 @@
 use crate::game_analysis::*;
 use crate::rules::{
+    SDoublings,
+    SStoss,
     SStossParams,
-    ruleset::{TRuleSet, VStockOrT},
+    ruleset::{SRuleSet, TRuleSet, VStockOrT},
     parser::parse_rule_description,
 };
 use crate::primitives::cardvector::*;
 use itertools::Itertools;
 use combine::{char::*, *};
 use std::fmt::Debug;
-use serde::Deserialize;
+use serde::{Serialize, Deserialize};
 use serde_repr::Deserialize_repr;
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct SSauspielAllowedRules {
     // Sauspiel, Solo, Wenz: implicitly allowed
     b_farbwenz: bool,
@@ -19,13 +21,14 @@ pub struct SSauspielAllowedRules {
     b_ramsch: bool,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum VSauspielAllowedRules {
     Turnier(String),
     AllowedRules(SSauspielAllowedRules),
+    Unknown, // Sauspiel JSON does not tell
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct SSauspielRuleset { // TODO can we represent this as a plain SRuleSet?
     ekurzlang: EKurzLang,
     #[allow(dead_code)] // TODO
@@ -35,14 +38,81 @@ pub struct SSauspielRuleset { // TODO can we represent this as a plain SRuleSet?
     n_tarif_solo: isize,
 }
 
+impl SSauspielRuleset {
+    pub fn tarif(&self) -> (/*n_tarif_extra*/isize, /*n_tarif_ruf*/isize, /*n_tarif_solo*/isize) {
+        (self.n_tarif_extra, self.n_tarif_ruf, self.n_tarif_solo)
+    }
+}
+
 impl TRuleSet for SSauspielRuleset {
     fn kurzlang(&self) -> EKurzLang {
         self.ekurzlang
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SGameAnnouncementAnonymous{} // braces so that serde does not write Some(SGameAnnouncementAnonymous) as null
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SSauspielGameAnnouncement {
+    pub ogameannouncement: Option<SGameAnnouncementAnonymous>, // None if player "sagt weiter."
+    pub b_timeout: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ESauspielGameType {
+    Sauspiel,
+    Solo,
+    Wenz,
+    Farbwenz,
+    Geier,
+    Sie,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum VSauspielDetermineRules {
+    Game{esauspielgametype: ESauspielGameType, b_tout: bool}, // e.g. "hätt an Wenz-Tout"
+    Rufsau(EFarbe), // e.g. "spielt auf die Alte"
+    Trumpf(EFarbe), // e.g. "spielt Eichel"
+    Vortritt, // "lässt den Vortritt."
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SSauspielDetermineRulesStep {
+    pub epi: EPlayerIndex,
+    pub determinerules: VSauspielDetermineRules,
+    pub b_timeout: bool,
+}
+
+impl SSauspielDetermineRulesStep {
+    // What this step reveals about trumpf: Schlag (left) and/or Farbe (right).
+    pub fn trumpf(&self) -> Option<itertools::EitherOrBoth<&'static [ESchlag], Option<EFarbe>>> {
+        const SLCSCHLAG_OBER_UNTER: &[ESchlag] = &[ESchlag::Ober, ESchlag::Unter];
+        const SLCSCHLAG_OBER: &[ESchlag] = &[ESchlag::Ober];
+        const SLCSCHLAG_UNTER: &[ESchlag] = &[ESchlag::Unter];
+        use itertools::EitherOrBoth as EOB;
+        match self.determinerules {
+            VSauspielDetermineRules::Game{esauspielgametype, b_tout:_} => Some(match esauspielgametype {
+                ESauspielGameType::Sauspiel => EOB::Both(SLCSCHLAG_OBER_UNTER, Some(EFarbe::Herz)),
+                ESauspielGameType::Solo => EOB::Left(SLCSCHLAG_OBER_UNTER),
+                ESauspielGameType::Wenz => EOB::Both(SLCSCHLAG_UNTER, None),
+                ESauspielGameType::Farbwenz => EOB::Left(SLCSCHLAG_UNTER),
+                ESauspielGameType::Geier => EOB::Both(SLCSCHLAG_OBER, None),
+                ESauspielGameType::Sie => EOB::Both(SLCSCHLAG_OBER_UNTER, None),
+            }),
+            VSauspielDetermineRules::Rufsau(_efarbe) => Some(EOB::Both(SLCSCHLAG_OBER_UNTER, Some(EFarbe::Herz))),
+            VSauspielDetermineRules::Trumpf(efarbe) => Some(EOB::Right(Some(efarbe))),
+            VSauspielDetermineRules::Vortritt => None,
+        }
+    }
+}
+
+// Dealt cards and doublings, which SGameResultGeneric does not store for Stock.
 #[derive(Debug, Clone)]
-pub struct SGameAnnouncementAnonymous;
+pub struct SDeal {
+    pub aveccard: EnumMap<EPlayerIndex, SHandVector>,
+    pub doublings: SDoublings,
+}
 
 fn iter_to_arr<T>(it: impl IntoIterator<Item=T>) -> Result<[T; EPlayerIndex::SIZE], SStringifiedError> {
     it.into_iter()
@@ -90,6 +160,7 @@ pub enum VSauspielHtmlData {
     Element,
     Text(String),
     Comment,
+    Unsupported, // e.g. processing instructions
 }
 
 impl<'node> TSauspielHtmlNode<'node> for select::node::Node<'node> {
@@ -129,13 +200,49 @@ impl<'node> TSauspielHtmlNode<'node> for select::node::Node<'node> {
     }
 }
 
-pub fn analyze_sauspiel_html(str_html: &str) -> Result<SGameResultGeneric<SSauspielRuleset, /*GameAnnouncement*/(), Vec<()>>, SStringifiedError> {
+// Parses a tarif as shown on Sauspiel, e.g. "P 10 / 20 / 50" or "€ 0,10 / 0,50".
+fn parse_sauspiel_tarif(str_tarif: &str) -> Result<(/*n_tarif_extra*/isize, /*n_tarif_ruf*/isize, /*n_tarif_solo*/isize), SStringifiedError> {
+    let parser_digits = many1::<String,_>(digit())
+        .map(|str_digits| str_digits.parse::<isize>());
+    macro_rules! parser_tarif(($parser_currency: expr, $parser_digits: expr) => {
+        $parser_currency.with((
+            $parser_digits.clone(),
+            string(" / ").with($parser_digits.clone()),
+            optional(string(" / ").with($parser_digits.clone())),
+        )).map(|(resn_1, resn_2, oresn_3)| -> Result<_, SStringifiedError> {
+            Ok(if let Some(resn_3)=oresn_3 {
+                (resn_1?, resn_2?, resn_3?)
+            } else {
+                let n_2 = resn_2?;
+                (resn_1?, n_2, n_2)
+            })
+        })
+    });
+    parse_trimmed(
+        str_tarif,
+        choice!(
+            parser_tarif!(string("P "), parser_digits),
+            parser_tarif!(
+                choice!(string("€ "), string("$ ")), // Note: I could not find a game from Vereinsheim, but I suspect they use $
+                (parser_digits.clone(), char(','), count_min_max::<String,_>(2, 2, digit()))
+                    .map(|(resn_before_comma, _str_comma, str_2_digits_after_comma)| -> Result<_, SStringifiedError> {
+                        let n_before_comma : isize = resn_before_comma?;
+                        let n_after_comma : isize = str_2_digits_after_comma.parse::<isize>()?;
+                        Ok(n_before_comma * 100 + n_after_comma)
+                    })
+            )
+        )
+    )
+        .map_err(|err| format_err!("Failed to parse tarif: {:?}", err))? // unpack result of combine::parse call
+}
+
+pub fn analyze_sauspiel_html(str_html: &str) -> Result<SGameResultGeneric<SSauspielRuleset, SSauspielGameAnnouncement, Vec<SSauspielDetermineRulesStep>>, SStringifiedError> {
     internal_analyze_sauspiel_html(
         Document::from(str_html),
-        /*fn_gameannouncement*/|_,_,_| (),
-        /*fn_determinerules_step*/|_,_,_| (),
+        /*fn_gameannouncement*/|_epi, gameannouncement, _node| gameannouncement.clone(),
+        /*fn_determinerules_step*/|determinerulesstep, _node| determinerulesstep.clone(),
         /*fn_before_play_card*/|_,_,_,_| (),
-    ).map(|(gameresult, _mapepistr_username)| gameresult)
+    ).map(|(gameresult, _mapepistr_username, _odeal_stock)| gameresult)
 }
 
 pub fn internal_analyze_sauspiel_html<Document: TSauspielHtmlDocument, GameAnnouncement, FnGameAnnouncement, DetermineRulesStep, FnDetermineRulesStep, FnBeforePlayCard>(
@@ -147,16 +254,13 @@ pub fn internal_analyze_sauspiel_html<Document: TSauspielHtmlDocument, GameAnnou
         (
             SGameResultGeneric<SSauspielRuleset, GameAnnouncement, Vec<DetermineRulesStep>>,
             EnumMap<EPlayerIndex, String>,
+            Option<SDeal>, // Some if the game was thrown in
         ),
         SStringifiedError
     >
     where
-        for <'card> FnGameAnnouncement: FnMut(EPlayerIndex, &Option<SGameAnnouncementAnonymous>, Document::HtmlNode<'card>)->GameAnnouncement,
-        for <'card> FnDetermineRulesStep: FnMut(
-            EPlayerIndex,
-            &Option<itertools::EitherOrBoth<&'static [ESchlag], Option<EFarbe>>>,
-            Document::HtmlNode<'card>,
-        )->DetermineRulesStep,
+        for <'card> FnGameAnnouncement: FnMut(EPlayerIndex, &SSauspielGameAnnouncement, Document::HtmlNode<'card>)->GameAnnouncement,
+        for <'card> FnDetermineRulesStep: FnMut(&SSauspielDetermineRulesStep, Document::HtmlNode<'card>)->DetermineRulesStep,
         for <'card> FnBeforePlayCard: FnMut(&SGameGeneric<SSauspielRuleset, GameAnnouncement, Vec<DetermineRulesStep>>, ECard, EPlayerIndex, Document::HtmlNode<'card>),
 {
     // TODO acknowledge timeouts
@@ -232,42 +336,7 @@ pub fn internal_analyze_sauspiel_html<Document: TSauspielHtmlDocument, GameAnnou
         .map_err(|e| format_err!("Not all players have the same number of cards: {:?}", e))?
         .ok_or(format_err!("Could not determine ekurzlang"))?;
     let ruleset = if let Ok(node_tarif) = scrape_from_key_figure_table("Tarif") {
-        let (n_tarif_extra, n_tarif_ruf, n_tarif_solo) = {
-            let str_tarif = node_tarif.inner_html();
-            let parser_digits = many1::<String,_>(digit())
-                .map(|str_digits| str_digits.parse::<isize>());
-            macro_rules! parser_tarif(($parser_currency: expr, $parser_digits: expr) => {
-                $parser_currency.with((
-                    $parser_digits.clone(),
-                    string(" / ").with($parser_digits.clone()),
-                    optional(string(" / ").with($parser_digits.clone())),
-                )).map(|(resn_1, resn_2, oresn_3)| -> Result<_, SStringifiedError> {
-                    Ok(if let Some(resn_3)=oresn_3 {
-                        (resn_1?, resn_2?, resn_3?)
-                    } else {
-                        let n_2 = resn_2?;
-                        (resn_1?, n_2, n_2)
-                    })
-                })
-            });
-            parse_trimmed(
-                &str_tarif,
-                choice!(
-                    parser_tarif!(string("P "), parser_digits),
-                    parser_tarif!(
-                        choice!(string("€ "), string("$ ")), // Note: I could not find a game from Vereinsheim, but I suspect they use $
-                        (parser_digits.clone(), char(','), count_min_max::<String,_>(2, 2, digit()))
-                            .map(|(resn_before_comma, _str_comma, str_2_digits_after_comma)| -> Result<_, SStringifiedError> {
-                                let n_before_comma : isize = resn_before_comma?;
-                                let n_after_comma : isize = str_2_digits_after_comma.parse::<isize>()?;
-                                Ok(n_before_comma * 100 + n_after_comma)
-                            })
-                    )
-                )
-            )
-                .map_err(|err| format_err!("Failed to parse tarif: {:?}", err))? // unpack result of combine::parse call
-                ? // unpack parsed result
-        };
+        let (n_tarif_extra, n_tarif_ruf, n_tarif_solo) = parse_sauspiel_tarif(&node_tarif.inner_html())?;
         SSauspielRuleset{
             ekurzlang,
             n_tarif_extra,
@@ -275,9 +344,11 @@ pub fn internal_analyze_sauspiel_html<Document: TSauspielHtmlDocument, GameAnnou
             n_tarif_solo,
             allowedrules: VSauspielAllowedRules::AllowedRules(scrape_from_key_figure_table("Sonderregeln")?
                 .children()
-                .filter(|node|
-                    !matches!(node.data(), VSauspielHtmlData::Text(str_text) if str_text.trim().is_empty() || str_text.trim()!="-")
-                )
+                .filter(|node| match node.data() {
+                    VSauspielHtmlData::Text(str_text) => !str_text.trim().is_empty() && str_text.trim()!="-",
+                    VSauspielHtmlData::Comment => false,
+                    VSauspielHtmlData::Element | VSauspielHtmlData::Unsupported => true,
+                })
                 .try_fold(
                     SSauspielAllowedRules{
                         b_farbwenz: false,
@@ -388,21 +459,25 @@ pub fn internal_analyze_sauspiel_html<Document: TSauspielHtmlDocument, GameAnnou
                     )
                         .with(choice!(
                             (string("sagt weiter."), optional((newline(), spaces(), string("(timeout)"))))
-                                .map(|_| None),
+                                .map(|(_str_weiter, otimeout)| SSauspielGameAnnouncement{
+                                    ogameannouncement: None,
+                                    b_timeout: otimeout.is_some(),
+                                }),
                             (string("dad gern."))
-                                .map(|_| Some(SGameAnnouncementAnonymous))
+                                .map(|_| SSauspielGameAnnouncement{
+                                    ogameannouncement: Some(SGameAnnouncementAnonymous{}),
+                                    b_timeout: false,
+                                })
                         ))
                 ).map_err(|err| format_err!("Failed to parse game announcement 1: {:1}", err))
-                .map(|ogameannouncement| fn_gameannouncement(epi, &ogameannouncement, node_gameannouncement))
+                .map(|gameannouncement| fn_gameannouncement(epi, &gameannouncement, node_gameannouncement))
             })
             .collect::<Result<Vec<_>, _>>()?
     ).map(EPlayerIndex::map_from_raw)?;
     let vecvectplepistr_determinerules = itnode_gameannouncement
         .map(|node_gameannouncement| {
-            const SLCSCHLAG_OBER_UNTER: &[ESchlag] = &[ESchlag::Ober, ESchlag::Unter];
-            const SLCSCHLAG_OBER: &[ESchlag] = &[ESchlag::Ober];
-            const SLCSCHLAG_UNTER: &[ESchlag] = &[ESchlag::Unter];
-            use itertools::EitherOrBoth as EOB;
+            use VSauspielDetermineRules::*;
+            use ESauspielGameType::*;
             parse_trimmed(
                 node_gameannouncement.inner_html().trim(), // TODO move newlines to parser
                 choice(EPlayerIndex::map_from_fn(
@@ -410,33 +485,38 @@ pub fn internal_analyze_sauspiel_html<Document: TSauspielHtmlDocument, GameAnnou
                         username_parser(epi),
                         choice(
                             [
-                                (" h\u{00E4}tt a Sauspiel", Some(EOB::Both(SLCSCHLAG_OBER_UNTER, Some(EFarbe::Herz)))),
-                                (" h\u{00E4}tt a Solo-Tout", Some(EOB::Left(SLCSCHLAG_OBER_UNTER))),
-                                (" h\u{00E4}tt a Solo", Some(EOB::Left(SLCSCHLAG_OBER_UNTER))),
-                                (" h\u{00E4}tt an Wenz-Tout", Some(EOB::Both(SLCSCHLAG_UNTER, None))),
-                                (" h\u{00E4}tt an Wenz", Some(EOB::Both(SLCSCHLAG_UNTER, None))),
-                                (" h\u{00E4}tt an Farbwenz-Tout", Some(EOB::Left(SLCSCHLAG_UNTER))),
-                                (" h\u{00E4}tt an Farbwenz", Some(EOB::Left(SLCSCHLAG_UNTER))),
-                                (" h\u{00E4}tt an Geier-Tout", Some(EOB::Both(SLCSCHLAG_OBER, None))),
-                                (" h\u{00E4}tt an Geier", Some(EOB::Both(SLCSCHLAG_OBER, None))),
-                                (" spielt auf die Alte", Some(EOB::Both(SLCSCHLAG_OBER_UNTER, Some(EFarbe::Herz)))),
-                                (" spielt auf die Blaue", Some(EOB::Both(SLCSCHLAG_OBER_UNTER, Some(EFarbe::Herz)))),
-                                (" spielt auf die Hundsgfickte", Some(EOB::Both(SLCSCHLAG_OBER_UNTER, Some(EFarbe::Herz)))),
-                                (" spielt Eichel", Some(EOB::Right(Some(EFarbe::Eichel)))),
-                                (" spielt Gras", Some(EOB::Right(Some(EFarbe::Gras)))),
-                                (" spielt Herz", Some(EOB::Right(Some(EFarbe::Herz)))),
-                                (" spielt Schelle", Some(EOB::Right(Some(EFarbe::Schelln)))),
-                                (" l\u{00E4}sst den Vortritt.", None),
-                            ].map(|(str_determinerules, oeobslcschlagoefarbe_trumpf): (_, Option<_>)| {
+                                (" h\u{00E4}tt a Sauspiel", Game{esauspielgametype: Sauspiel, b_tout: false}),
+                                (" h\u{00E4}tt a Solo-Tout", Game{esauspielgametype: Solo, b_tout: true}),
+                                (" h\u{00E4}tt a Sie", Game{esauspielgametype: Sie, b_tout: false}),
+                                (" h\u{00E4}tt a Solo", Game{esauspielgametype: Solo, b_tout: false}),
+                                (" h\u{00E4}tt an Wenz-Tout", Game{esauspielgametype: Wenz, b_tout: true}),
+                                (" h\u{00E4}tt an Wenz", Game{esauspielgametype: Wenz, b_tout: false}),
+                                (" h\u{00E4}tt an Farbwenz-Tout", Game{esauspielgametype: Farbwenz, b_tout: true}),
+                                (" h\u{00E4}tt an Farbwenz", Game{esauspielgametype: Farbwenz, b_tout: false}),
+                                (" h\u{00E4}tt an Geier-Tout", Game{esauspielgametype: Geier, b_tout: true}),
+                                (" h\u{00E4}tt an Geier", Game{esauspielgametype: Geier, b_tout: false}),
+                                (" spielt auf die Alte", Rufsau(EFarbe::Eichel)),
+                                (" spielt auf die Blaue", Rufsau(EFarbe::Gras)),
+                                (" spielt auf die Hundsgfickte", Rufsau(EFarbe::Schelln)),
+                                (" spielt Eichel", Trumpf(EFarbe::Eichel)),
+                                (" spielt Gras", Trumpf(EFarbe::Gras)),
+                                (" spielt Herz", Trumpf(EFarbe::Herz)),
+                                (" spielt Schelle", Trumpf(EFarbe::Schelln)),
+                                (" l\u{00E4}sst den Vortritt.", Vortritt),
+                            ].map(|(str_determinerules, determinerules)| {
                                 attempt(string(str_determinerules))
-                                    .map(move |_| oeobslcschlagoefarbe_trumpf.clone()/*TODO clone needed?*/)
+                                    .map(move |_| determinerules.clone()/*TODO clone needed?*/)
                             })
-                        )
-                            .skip(optional(string(" (timeout)"))),
+                        ),
+                        optional(string(" (timeout)"))
+                            .map(|ostr_timeout| ostr_timeout.is_some()),
                     ))
                 ).into_raw()),
             ).map_err(|err| format_err!("Failed to parse game announcement 2: {:?}", err))
-            .map(|(epi, oeobslcschlagoefarbe_trumpf)| fn_determinerules_step(epi, &oeobslcschlagoefarbe_trumpf, node_gameannouncement))
+            .map(|(epi, determinerules, b_timeout)| fn_determinerules_step(
+                &SSauspielDetermineRulesStep{epi, determinerules, b_timeout},
+                node_gameannouncement,
+            ))
         })
         .collect::<Result<Vec<_>, _>>()?;
     if let Some(rules) = orules {
@@ -448,6 +528,7 @@ pub fn internal_analyze_sauspiel_html<Document: TSauspielHtmlDocument, GameAnnou
             mapepigameannouncement,
             vecvectplepistr_determinerules,
         );
+        // The HTML only tells who gave Kontra and Retour, not when, so we assume it was before the first card.
         for epi in get_doublings_stoss("Kontra und Retour")? {
             verify_is_unit!(game.stoss(epi)?);
         }
@@ -463,22 +544,28 @@ pub fn internal_analyze_sauspiel_html<Document: TSauspielHtmlDocument, GameAnnou
                 verify_is_unit!(game.zugeben(card, epi_zugeben)?);
             }
         }
-        game.finish().map_err(|_game| format_err!("Could not game.finish"))
+        game.finish()
+            .map_err(|_game| format_err!("Could not game.finish"))
+            .map(|gameresult| (gameresult, mapepistr_username, /*odeal_stock*/None))
     } else {
         // TODO assert that there are actually no stichs in doc
-        Ok(SGameResultGeneric {
-            an_payout: EPlayerIndex::map_from_fn(|_epi| /*Sauspiel does not know stock*/0),
-            stockorgame: VStockOrT::Stock(ruleset),
-        })
-    }.map(|gameresult| (gameresult, mapepistr_username))
+        Ok((
+            SGameResultGeneric {
+                an_payout: EPlayerIndex::map_from_fn(|_epi| /*Sauspiel does not know stock*/0),
+                stockorgame: VStockOrT::Stock(ruleset),
+            },
+            mapepistr_username,
+            Some(SDeal{aveccard, doublings}),
+        ))
+    }
 }
 
 plain_enum_mod!(modesauspielposition, derive(Deserialize_repr,), map_derive(), ESauspielPosition {_0, _1, _2, _3,});
 
 pub fn analyze_sauspiel_json(
     str_json: &str,
-    fn_before_zugeben: impl FnMut(&SGameGeneric<EKurzLang, (), ()>, /*i_stich*/usize, EPlayerIndex, ECard),
-) -> Result<SGameResultGeneric</*Ruleset*/EKurzLang, (), ()>, SStringifiedError> {
+    fn_before_zugeben: impl FnMut(&SGameGeneric<SSauspielRuleset, Option<SSauspielGameAnnouncement>, Vec<SSauspielDetermineRulesStep>>, /*i_stich*/usize, EPlayerIndex, ECard),
+) -> Result<SGameResultGeneric<SSauspielRuleset, /*GameAnnouncement: None if unknown*/Option<SSauspielGameAnnouncement>, Vec<SSauspielDetermineRulesStep>>, SStringifiedError> {
     #[derive(Deserialize, Debug)]
     #[serde(tag = "type")]
     #[allow(non_camel_case_types, non_snake_case)] // to match Sauspiel JSON
@@ -551,9 +638,12 @@ pub fn analyze_sauspiel_json(
     #[derive(Debug)]
     struct SMissing;
     let mut resstr_rules_no_playerindex = Err(SMissing);
+    let mut resstr_base_rate = Err(SMissing);
     let mut resoposition_active = Err(SMissing);
     let mut resoefarbe = Err(SMissing);
     let mut vectplpositioncard_played = Vec::new();
+    let mut vecposition_doubling = Vec::new();
+    let mut vectplpositionn_stoss = Vec::new(); // remembers how many cards were played before stoss
     let mut vecerr = Vec::new();
 
     for jsonval_sauspieljsonevent in serde_json::from_str::<Vec<serde_json::Value>>(str_json)? {
@@ -567,8 +657,6 @@ pub fn analyze_sauspiel_json(
                 | VSauspielJSONEvent::gameStarted{..}
                 | VSauspielJSONEvent::playersGotCards{..} // TODO? derive EKurzLang and aveccard from this
                 | VSauspielJSONEvent::youGotCards{..} // TODO? derive EKurzLang from this
-                | VSauspielJSONEvent::hasKnocked{..} // TODO collect doublings
-                | VSauspielJSONEvent::hasContra{..} // TODO collect stoss
                 | VSauspielJSONEvent::wonTheTrick{..} // TODO? consistency checks
             )
             => {
@@ -577,11 +665,18 @@ pub fn analyze_sauspiel_json(
                 resoefarbe = Ok(suit);
                 resoposition_active = Ok(position);
             },
+            Ok(VSauspielJSONEvent::hasKnocked{position}) => {
+                vecposition_doubling.push(position);
+            },
+            Ok(VSauspielJSONEvent::hasContra{position}) => {
+                vectplpositionn_stoss.push((position, vectplpositioncard_played.len()));
+            },
             Ok(VSauspielJSONEvent::playedACard{position, cardID}) => {
                 vectplpositioncard_played.push((position, cardID));
             },
-            Ok(VSauspielJSONEvent::gameResult{gameType, ..}) => {
+            Ok(VSauspielJSONEvent::gameResult{gameType, baseRate, ..}) => {
                 resstr_rules_no_playerindex = Ok(gameType);
+                resstr_base_rate = Ok(baseRate);
                 // TODO? consistency check gameresult
             },
         }
@@ -593,18 +688,74 @@ pub fn analyze_sauspiel_json(
         let position_to_epi = move |position: ESauspielPosition| {
             unwrap!(EPlayerIndex::checked_from_usize(position.wrapped_difference(position_corresponding_to_epi0).0.to_usize()))
         };
-        let rules = crate::rules::parser::parse_rule_description_simple(&{
-            // TODO? good to go through parse_rule_description_simple?
-            let mut str_rules = "".to_string();
-            if let Some(efarbe) = resoefarbe.map_err(|err| format_err!("oefarbe not found: {:?}", err))? {
-                str_rules += &format!("{efarbe} ");
-            }
-            str_rules += &resstr_rules_no_playerindex.map_err(|err| format_err!("str_rules_no_playerindex not found: {:?}", err))?;
-            if let Some(position_active)=resoposition_active.map_err(|err| format_err!("oepi_active not found: {:?}", err))? {
-                str_rules += &format!(" von {}", position_to_epi(position_active));
+        let oefarbe = resoefarbe.map_err(|err| format_err!("oefarbe not found: {:?}", err))?;
+        let str_rules_no_playerindex = resstr_rules_no_playerindex.map_err(|err| format_err!("str_rules_no_playerindex not found: {:?}", err))?;
+        let oepi_active = resoposition_active.map_err(|err| format_err!("oepi_active not found: {:?}", err))?
+            .map(position_to_epi);
+        // We expect baseRate in the format of the "Tarif" shown on game pages and fall back to the tarif
+        // assumed by parse_rule_description_simple if it is empty.
+        let str_base_rate = resstr_base_rate.map_err(|err| format_err!("str_base_rate not found: {:?}", err))?;
+        let (n_tarif_extra, n_tarif_ruf, n_tarif_solo) = if str_base_rate.trim().is_empty() {
+            (/*n_tarif_extra*/10, /*n_tarif_ruf*/20, /*n_tarif_solo*/50)
+        } else {
+            parse_sauspiel_tarif(&str_base_rate)?
+        };
+        let rules = parse_rule_description(
+            &{
+                let mut str_rules = "".to_string();
+                if let Some(efarbe) = oefarbe {
+                    str_rules += &format!("{efarbe} ");
+                }
+                str_rules += &str_rules_no_playerindex;
+                if let Some(epi_active)=oepi_active {
+                    str_rules += &format!(" von {epi_active}");
+                }
+                str_rules
+            },
+            (n_tarif_extra, n_tarif_ruf, n_tarif_solo),
+            SStossParams::new(/*n_stoss_max*/4), // TODO? is this correct
+            /*fn_player_to_epi*/|str_epi| EPlayerIndex::checked_from_usize(str_epi.parse()?)
+                .ok_or_else(|| format_err!("Cannot convert {} to EPlayerIndex.", str_epi)),
+        )?;
+        // The JSON only tells who plays which game, so we know neither how the others bid nor whether someone was outbid.
+        let vecdeterminerulesstep = if let Some(epi_active)=oepi_active {
+            let str_rules_lowercase = str_rules_no_playerindex.to_lowercase();
+            let esauspielgametype = [
+                ("farbwenz", ESauspielGameType::Farbwenz),
+                ("wenz", if oefarbe.is_some() {ESauspielGameType::Farbwenz} else {ESauspielGameType::Wenz}),
+                ("geier", ESauspielGameType::Geier),
+                ("sie", ESauspielGameType::Sie),
+                ("solo", ESauspielGameType::Solo),
+                ("sauspiel", ESauspielGameType::Sauspiel),
+                ("rufspiel", ESauspielGameType::Sauspiel),
+            ].into_iter()
+                .find(|(str_gametype, _esauspielgametype)| str_rules_lowercase.contains(str_gametype))
+                .map(|(_str_gametype, esauspielgametype)| esauspielgametype)
+                .ok_or_else(|| format_err!("Unknown game type: {}", str_rules_no_playerindex))?;
+            let determinerulesstep = |determinerules| SSauspielDetermineRulesStep{
+                epi: epi_active,
+                determinerules,
+                b_timeout: false,
+            };
+            let mut vecdeterminerulesstep = vec![determinerulesstep(VSauspielDetermineRules::Game{
+                esauspielgametype,
+                b_tout: str_rules_lowercase.contains("tout"),
+            })];
+            if let Some(efarbe) = oefarbe {
+                vecdeterminerulesstep.push(determinerulesstep(match esauspielgametype {
+                    ESauspielGameType::Sauspiel => VSauspielDetermineRules::Rufsau(efarbe),
+                    ESauspielGameType::Solo
+                    | ESauspielGameType::Wenz
+                    | ESauspielGameType::Farbwenz
+                    | ESauspielGameType::Geier
+                    | ESauspielGameType::Sie
+                        => VSauspielDetermineRules::Trumpf(efarbe),
+                }));
             }
-            str_rules
-        })?;
+            vecdeterminerulesstep
+        } else {
+            Vec::new()
+        };
         let ekurzlang = EKurzLang::values()
             .find(|ekurzlang| ekurzlang.cards_per_player()*EPlayerIndex::SIZE==vectplpositioncard_played.len())
             .ok_or(format_err!("Could not determine ekurzlang"))?;
@@ -613,21 +764,45 @@ pub fn analyze_sauspiel_json(
             vectplpositioncard_played.iter().map(|tplpositioncard| tplpositioncard.1),
             &rules
         ).map_err(|SDuplicateCard(card)| format_err!("Duplicate card: {}", card))?;
-        SGameGeneric::</*Ruleset*/EKurzLang, (), ()>::new_finished_with_ruleset(
+        let game = SGameGeneric::new_with(
+            EPlayerIndex::map_from_fn(|epi| stichseq.completed_cards_by(epi).collect()),
+            SExpensifiersNoStoss::new_with_doublings(
+                /*n_stock: Sauspiel does not support Stock*/0,
+                SDoublings::new_full(
+                    SStaticEPI0{},
+                    EPlayerIndex::map_from_fn(|epi|
+                        vecposition_doubling.iter().any(|&position| position_to_epi(position)==epi)
+                    ).into_raw(),
+                ),
+            ),
             rules,
-            SExpensifiers::new_no_stock_doublings_stoss(), // TODO
-            SStichSequenceGameFinished::new(&stichseq),
-            ekurzlang,
+            SSauspielRuleset{
+                ekurzlang,
+                allowedrules: VSauspielAllowedRules::Unknown,
+                n_tarif_extra,
+                n_tarif_ruf,
+                n_tarif_solo,
+            },
+            EPlayerIndex::map_from_fn(|epi| if_then_some!(Some(epi)==oepi_active, SSauspielGameAnnouncement{
+                ogameannouncement: Some(SGameAnnouncementAnonymous{}),
+                b_timeout: false,
+            })),
+            vecdeterminerulesstep,
+        ).play_cards_and_stoss(
+            vectplpositionn_stoss.iter()
+                .map(|&(position, n_cards_played)| SStoss{epi: position_to_epi(position), n_cards_played}),
+            stichseq.visible_cards(),
             fn_before_zugeben,
-        ).and_then(|game| game.finish()
+        )?;
+        game.finish()
             .map_err(|err| format_err!("Could not finish game: {:?}", err))
-        )
     }().map_err(|err| format_err!("{}: {:?}", err, vecerr))
 }
 
 pub fn analyze_plain(str_lines: &str) -> impl Iterator<Item=Result<SGame, SStringifiedError>> + std::fmt::Debug + '_ {
     str_lines
         .lines()
+        .filter(|str_plain| !str_plain.trim().is_empty() && !str_plain.starts_with('#')) // allow comments
         .map(|str_plain| {
             let [str_rules, str_cards] = str_plain
                 .split(':')
@@ -654,18 +829,251 @@ pub fn analyze_plain(str_lines: &str) -> impl Iterator<Item=Result<SGame, SStrin
         })
 }
 
-pub fn analyze_netschafkopf(str_lines: &str) -> Result<Vec<Result<SGameResult</*Ruleset*/String>, SStringifiedError>>, SStringifiedError> {
+#[derive(Debug, Clone)]
+pub struct SNetSchafkopfRuleset {
+    str_name: String, // as given in the protocol's first line, e.g. "PM-Regeln"
+    n_tarif_extra: isize,
+    n_tarif_ruf: isize,
+    n_tarif_solo: isize,
+    str_toml: String, // understood by SRuleSet::from_string
+    ruleset: SRuleSet,
+}
+
+// NetSchafkopf allows "spritzen" (i.e. stoss) up to this many times.
+pub const N_STOSS_MAX_NETSCHAFKOPF: usize = 4;
+
+// Finds the number preceding (b_number_first) or following slcstr_context in slcstr_word.
+fn netschafkopf_number(slcstr_word: &[&str], slcstr_context: &[&str], b_number_first: bool) -> Option<isize> {
+    slcstr_word.windows(slcstr_context.len()+1).find_map(|slcstr_window| {
+        let (str_number, slcstr_window_context) = if b_number_first {
+            unwrap!(slcstr_window.split_first())
+        } else {
+            unwrap!(slcstr_window.split_last())
+        };
+        if_then_some!(slcstr_window_context==slcstr_context, str_number.parse().ok()).flatten()
+    })
+}
+
+// Section of SRuleSet's TOML governing str_game as NetSchafkopf names it, e.g. "Mit der Eichel-Ass", "Grün-Wenz" or "Wenz Tout".
+fn netschafkopf_rule_section(str_game: &str) -> Option<&'static str> {
+    let b_farbe = str_game.contains('-');
+    if str_game.starts_with("Mit der ") {
+        Some("rufspiel")
+    } else if str_game.contains("Solo") {
+        Some("solo")
+    } else if str_game.contains("Wenz") {
+        Some(if b_farbe {"farbwenz"} else {"wenz"})
+    } else if str_game.contains("Geier") {
+        Some(if b_farbe {"farbgeier"} else {"geier"})
+    } else {
+        None
+    }
+}
+
+impl SNetSchafkopfRuleset {
+    // NetSchafkopf does not write out its ruleset, so we reconstruct it from how it explains the prices of the individual games.
+    // Prices of games that do not occur in the protocol cannot be known, but do not matter for the protocol's payouts either.
+    fn new(str_name: String, str_lines: &str) -> Result<Self, SStringifiedError> {
+        let vecstr_word = str_lines.split_whitespace().collect::<Vec<_>>();
+        // e.g. "Das Rufspiel kostet 10 Punkte. Mit Schneider 20 Punkte."
+        let price = |str_game: &str| netschafkopf_number(&vecstr_word, &[str_game, "kostet"], /*b_number_first*/false);
+        let price_extra = |str_game: &str| vecstr_word.windows(7)
+            .find_map(|slcstr_window| match slcstr_window {
+                [str_game_window, "kostet", str_price, _str_punkte, "Mit", "Schneider", str_price_schneider] if *str_game_window==str_game => {
+                    Some(str_price_schneider.parse::<isize>().ok()? - str_price.parse::<isize>().ok()?)
+                },
+                _ => None,
+            });
+        let (n_tarif_extra_default, n_tarif_ruf_default, n_tarif_solo_default) = (10, 20, 50);
+        let n_tarif_ruf = price("Rufspiel").unwrap_or(n_tarif_ruf_default);
+        let n_tarif_solo = price("Einzelspiel").unwrap_or(n_tarif_solo_default);
+        let n_tarif_extra_ruf = price_extra("Rufspiel").or_else(|| price_extra("Einzelspiel")).unwrap_or(n_tarif_extra_default);
+        let n_tarif_extra_solo = price_extra("Einzelspiel").unwrap_or(n_tarif_extra_ruf);
+        let ekurzlang = str_lines.lines()
+            .find_map(|str_line| str_line.split_once(" hat: "))
+            .and_then(|(_str_player, str_cards)| EKurzLang::from_cards_per_player(str_cards.split_whitespace().count()))
+            .ok_or_else(|| format_err!("Could not determine ekurzlang"))?;
+        // NetSchafkopf only mentions Laufende if they are paid (e.g. "Mit 3 Laufenden 50 Punkte."),
+        // so we take the fewest Laufende mentioned per game and assume that games without mention do not pay them.
+        let mut mapstrn_lauf_min = std::collections::HashMap::<&str, usize>::new();
+        for str_game in str_lines.split("\nGeber: ").skip(1) {
+            let vecstr_word_game = str_game.split_whitespace().collect::<Vec<_>>();
+            let on_laufende = vecstr_word_game.windows(3).find_map(|slcstr_window| match slcstr_window {
+                ["Mit"|"Ohne", str_laufende, "Laufenden"|"Laufende"] => str_laufende.parse::<usize>().ok(),
+                _ => None,
+            });
+            if let Some(n_laufende) = on_laufende {
+                let str_section = str_game.lines()
+                    .find_map(|str_line| str_line.split_once(" spielt ").filter(|(_str_player, str_game)| !str_game.starts_with("aus:")))
+                    .and_then(|(_str_player, str_game)| netschafkopf_rule_section(str_game))
+                    .ok_or_else(|| format_err!("Laufende mentioned for unknown game: {}", str_game))?;
+                let n_lauf_min = mapstrn_lauf_min.entry(str_section).or_insert(n_laufende);
+                *n_lauf_min = (*n_lauf_min).min(n_laufende);
+            }
+        }
+        let lauf_min = |str_section: &str| mapstrn_lauf_min.get(str_section).copied()
+            .unwrap_or(/*more than there are cards, i.e. never paid*/EPlayerIndex::SIZE * ekurzlang.cards_per_player() + 1);
+        let str_ramsch = price("Ramsch")
+            .map_or("".to_string(), |n_price_ramsch| format!(
+                "[ramsch]
+                price={n_price_ramsch}
+                {str_jungfrau}
+                ",
+                // e.g. "Mit 1 mal Jungfrau 80 Punkte."; our protocols contain no Durchmarsch, so we do not know how NetSchafkopf handles it.
+                str_jungfrau=if netschafkopf_number(&vecstr_word, &["mal", "Jungfrau"], /*b_number_first*/true).is_some() {
+                    "jungfrau=\"DoubleAll\""
+                } else {
+                    ""
+                },
+            ));
+        let str_doubling = if vecstr_word.contains(&"aufgedoppelt") {
+            "[doubling]
+            stock=\"no\"
+            "
+        } else {
+            ""
+        };
+        let str_toml = format!(
+            "base-price={n_tarif_ruf}
+            solo-price={n_tarif_solo}
+            deck=\"{str_deck}\"
+            [rufspiel]
+            extra={n_tarif_extra_ruf}
+            lauf-min={n_lauf_min_rufspiel}
+            [solo]
+            extra={n_tarif_extra_solo}
+            lauf-min={n_lauf_min_solo}
+            [wenz]
+            extra={n_tarif_extra_solo}
+            lauf-min={n_lauf_min_wenz}
+            [farbwenz]
+            extra={n_tarif_extra_solo}
+            lauf-min={n_lauf_min_farbwenz}
+            [geier]
+            extra={n_tarif_extra_solo}
+            lauf-min={n_lauf_min_geier}
+            [farbgeier]
+            extra={n_tarif_extra_solo}
+            lauf-min={n_lauf_min_farbgeier}
+            [stoss]
+            max={N_STOSS_MAX_NETSCHAFKOPF}
+            {str_doubling}
+            {str_ramsch}",
+            str_deck=match ekurzlang {
+                EKurzLang::Kurz => "kurz",
+                EKurzLang::Lang => "lang",
+            },
+            n_lauf_min_rufspiel=lauf_min("rufspiel"),
+            n_lauf_min_solo=lauf_min("solo"),
+            n_lauf_min_wenz=lauf_min("wenz"),
+            n_lauf_min_farbwenz=lauf_min("farbwenz"),
+            n_lauf_min_geier=lauf_min("geier"),
+            n_lauf_min_farbgeier=lauf_min("farbgeier"),
+        );
+        let ruleset = SRuleSet::from_string(&str_toml)?;
+        Ok(Self {
+            str_name,
+            n_tarif_extra: n_tarif_extra_ruf,
+            n_tarif_ruf,
+            n_tarif_solo,
+            str_toml,
+            ruleset,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.str_name
+    }
+
+    pub fn tarif(&self) -> (/*n_tarif_extra*/isize, /*n_tarif_ruf*/isize, /*n_tarif_solo*/isize) {
+        (self.n_tarif_extra, self.n_tarif_ruf, self.n_tarif_solo)
+    }
+
+    pub fn ruleset(&self) -> &SRuleSet {
+        &self.ruleset
+    }
+
+    pub fn toml(&self) -> &str {
+        &self.str_toml
+    }
+}
+
+impl TRuleSet for SNetSchafkopfRuleset {
+    fn kurzlang(&self) -> EKurzLang {
+        self.ruleset.kurzlang()
+    }
+}
+
+#[derive(Debug)]
+pub struct SNetSchafkopfGame {
+    pub n_game: usize, // as numbered by NetSchafkopf
+    pub gameresult: SGameResult<SNetSchafkopfRuleset>,
+    pub an_payout_netschafkopf: EnumMap<EPlayerIndex, isize>,
+    pub n_pay_into_stock_netschafkopf: isize,
+}
+
+impl SNetSchafkopfGame {
+    pub fn payout_discrepancy(&self) -> Option<String> {
+        let n_pay_into_stock = -self.gameresult.an_payout.iter().sum::<isize>();
+        if_then_some!(
+            self.gameresult.an_payout!=self.an_payout_netschafkopf || n_pay_into_stock!=self.n_pay_into_stock_netschafkopf,
+            format!("Game {}: NetSchafkopf pays {:?} (Stock: {}), we pay {:?} (Stock: {})",
+                self.n_game,
+                self.an_payout_netschafkopf,
+                self.n_pay_into_stock_netschafkopf,
+                self.gameresult.an_payout,
+                n_pay_into_stock,
+            )
+        )
+    }
+}
+
+pub fn analyze_netschafkopf(str_lines: &str) -> Result<Vec<Result<SNetSchafkopfGame, SStringifiedError>>, SStringifiedError> {
     let mut itstr_line = str_lines.lines();
-    let ruleset = itstr_line.next().ok_or_else(|| format_err!("First line should contain rules"))?.to_string();
+    let ruleset = SNetSchafkopfRuleset::new(
+        itstr_line.next().ok_or_else(|| format_err!("First line should contain rules"))?.to_string(),
+        str_lines,
+    )?;
     itstr_line.next()
         .filter(|str_gespielt_von| str_gespielt_von.starts_with("gespielt von")) // TODO be more precise?
         .ok_or_else(|| format_err!("Expected 'gespielt von'."))?;
+    // NetSchafkopf lists accumulated balances after each game, so we remember them to compute each game's payout.
+    let mut mapstrn_balance = std::collections::HashMap::<String, isize>::new();
+    let mut n_stock = 0;
     Ok(itstr_line
         .chunk_by(|str_line| str_line.trim().is_empty())
         .into_iter()
         .filter(|(b_is_empty, _grpstr_line)| !b_is_empty)
         .map(|(_b_is_empty, grpstr_line)| -> Result<_, _> {
-            let mut grpstr_line = grpstr_line.peekable();
+            let mut vecstr_line = grpstr_line.collect::<Vec<_>>();
+            let (n_game, mapstrn_payout, n_pay_into_stock_netschafkopf) = {
+                let (Some(str_row), Some(str_header)) = (vecstr_line.pop(), vecstr_line.pop()) else {
+                    return Err(format_err!("Expected payout table"));
+                };
+                let (vecstr_player, vecn_row) = match (
+                    str_header.split_whitespace().collect::<Vec<_>>().as_slice(),
+                    str_row.split_whitespace().map(|str_n| str_n.parse::<isize>()).collect::<Result<Vec<_>, _>>(),
+                ) {
+                    (["Spiel", "Preis", vecstr_player@.., "Stock"], Ok(vecn_row)) if vecstr_player.len()==EPlayerIndex::SIZE && vecn_row.len()==EPlayerIndex::SIZE+3 => {
+                        (vecstr_player.to_vec(), vecn_row)
+                    },
+                    _ => return Err(format_err!("Could not parse payout table: {} / {}", str_header, str_row)),
+                };
+                let n_stock_before = n_stock;
+                n_stock = vecn_row[EPlayerIndex::SIZE+2];
+                (
+                    vecn_row[0].as_num::<usize>(),
+                    vecstr_player.iter().zip_eq(&vecn_row[2..EPlayerIndex::SIZE+2])
+                        .map(|(str_player, n_balance)| (
+                            str_player.to_string(),
+                            n_balance - mapstrn_balance.insert(str_player.to_string(), *n_balance).unwrap_or(0),
+                        ))
+                        .collect::<std::collections::HashMap<_,_>>(),
+                    n_stock - n_stock_before,
+                )
+            };
+            let n_stock_before = n_stock - n_pay_into_stock_netschafkopf;
+            let mut grpstr_line = vecstr_line.into_iter().peekable();
             grpstr_line.next()
                 .filter(|str_geber| str_geber.starts_with("Geber: ")) // TODO be more precise?
                 .ok_or_else(|| format_err!("Expected 'Geber: '"))?;
@@ -705,7 +1113,17 @@ pub fn analyze_netschafkopf(str_lines: &str) -> Result<Vec<Result<SGameResult</*
                 }
             }
             let ekurzlang = unwrap!(oekurzlang);
+            if ekurzlang!=ruleset.kurzlang() {
+                return Err(format_err!("Game uses {:?}, ruleset uses {:?}", ekurzlang, ruleset.kurzlang()));
+            }
             let mapepistr_player = EPlayerIndex::map_from_raw(unwrap!(iter_to_arr(vecstr_player_name)));
+            let an_payout_netschafkopf = EPlayerIndex::map_from_raw(iter_to_arr(
+                mapepistr_player.iter()
+                    .map(|str_player| mapstrn_payout.get(str_player).copied()
+                        .ok_or_else(|| format_err!("player {} not part of payout table {:?}", str_player, mapstrn_payout))
+                    )
+                    .collect::<Result<Vec<_>, _>>()?
+            )?);
             let player_to_epi = |str_player: &str| {
                 EPlayerIndex::values()
                     .find(|epi| mapepistr_player[*epi]==str_player)
@@ -715,18 +1133,31 @@ pub fn analyze_netschafkopf(str_lines: &str) -> Result<Vec<Result<SGameResult</*
                 tokens2(|l,r|l==r, mapepistr_player[epi].chars()) // TODO? can we use combine::char::string?
                     .map(move |mut str_player| verify_eq!(epi, unwrap!(player_to_epi(&str_player.join("")))))
             };
-            if Some(&"Es wurde zusammengeworfen.")==grpstr_line.peek() {
-                Ok(SGameResultGeneric {
-                    an_payout: EPlayerIndex::map_from_fn(|_epi| 0), // TODO could there be stock?
+            let gameresult = if Some(&"Es wurde zusammengeworfen.")==grpstr_line.peek() {
+                grpstr_line.next();
+                let vecstr_word = grpstr_line.flat_map(|str_line| str_line.split_whitespace()).collect::<Vec<_>>();
+                let an_payout = match vecstr_word.as_slice() {
+                    [] => EPlayerIndex::map_from_fn(|_epi| 0),
+                    // "Der Erste und der Letzte zahlen an die beiden anderen Spieler 50 Punkte, weil sie nicht gespielt haben."
+                    ["Der", "Erste", "und", "der", "Letzte", "zahlen", "an", "die", "beiden", "anderen", "Spieler", str_price, ..] => {
+                        let n_price = str_price.parse::<isize>()
+                            .map_err(|err| format_err!("Could not parse price {}: {:?}", str_price, err))?;
+                        EPlayerIndex::map_from_fn(|epi| n_price.neg_if(epi==EPlayerIndex::EPI0 || epi==EPlayerIndex::EPI3))
+                    },
+                    _ => return Err(format_err!("Unknown payout for thrown-in game: {}", vecstr_word.join(" "))),
+                };
+                SGameResultGeneric {
+                    an_payout,
                     stockorgame: VStockOrT::Stock(ruleset.clone()),
-                })
+                }
             } else {
-                let rules = parse_rule_description(
-                    grpstr_line.next().ok_or_else(|| format_err!("Expected rules"))?,
-                    (/*n_tarif_extra*/10, /*n_tarif_ruf*/20, /*n_tarif_solo*/50), // TODO? make adjustable
-                    SStossParams::new(/*n_stoss_max*/4), // TODO? support
+                let str_rules = grpstr_line.next().ok_or_else(|| format_err!("Expected rules"))?;
+                let rules = ruleset.ruleset().rules_like(&parse_rule_description(
+                    str_rules,
+                    ruleset.tarif(),
+                    SStossParams::new(N_STOSS_MAX_NETSCHAFKOPF),
                     /*fn_player_to_epi*/player_to_epi,
-                )?;
+                )?).ok_or_else(|| format_err!("{} not part of ruleset {}", str_rules, ruleset.name()))?;
                 let mut stichseq = SStichSequence::new(ekurzlang);
                 for _i_stich in 0..ekurzlang.cards_per_player() {
                     let (_epi, veccard) = parse_trimmed(
@@ -742,17 +1173,44 @@ pub fn analyze_netschafkopf(str_lines: &str) -> Result<Vec<Result<SGameResult</*
                         stichseq.zugeben(card, &rules);
                     }
                 }
-                SGameGeneric::</*Ruleset*/String, /*GameAnnouncement*/(), /*DetermineRules*/()>::new_finished_with_ruleset(
+                // Remaining lines describe the outcome and how the price was computed, e.g. "2 mal aufgedoppelt 40 Punkte."
+                let vecstr_word = grpstr_line.flat_map(|str_line| str_line.split_whitespace()).collect::<Vec<_>>();
+                let n_doubling = netschafkopf_number(&vecstr_word, &["mal", "aufgedoppelt"], /*b_number_first*/true).unwrap_or(0);
+                let n_stoss = netschafkopf_number(&vecstr_word, &["mal", "gespritzt"], /*b_number_first*/true).unwrap_or(0);
+                // NetSchafkopf does not tell who doubled or gave stoss, but the payout only depends on how often it happened.
+                // (The rules only let the parties take turns giving stoss, so any permitted player yields the same payout.)
+                let doublings = SDoublings::new_full(
+                    SStaticEPI0{},
+                    EPlayerIndex::map_from_fn(|epi| epi.to_usize().as_num::<isize>() < n_doubling).into_raw(),
+                );
+                let mut game = SGameGeneric::</*Ruleset*/SNetSchafkopfRuleset, /*GameAnnouncement*/(), /*DetermineRules*/()>::new_with_ruleset(
+                    EPlayerIndex::map_from_fn(|epi|
+                        stichseq.completed_cards_by(epi).collect()
+                    ),
+                    SExpensifiersNoStoss::new_with_doublings(n_stock_before, doublings),
                     rules,
-                    SExpensifiers::new_no_stock_doublings_stoss(), // TODO? support
-                    SStichSequenceGameFinished::new(&stichseq),
                     ruleset.clone(),
+                );
+                for _i_stoss in 0..n_stoss {
+                    let epi_stoss = game.which_player_can_do_something()
+                        .and_then(|(_epi_card, vecepi_stoss)| vecepi_stoss.first().copied())
+                        .ok_or_else(|| format_err!("Could not find player for stoss"))?;
+                    game.stoss(epi_stoss)?;
+                }
+                game.play_cards_and_stoss(
+                    /*itstoss*/std::iter::empty::<SStoss>(),
+                    stichseq.visible_cards(),
                     /*fn_before_zugeben*/|_,_,_,_| {},
-                )
-                    .and_then(|game| game.finish()
-                        .map_err(|err| format_err!("Could not finish game: {:?}", err))
-                    )
-            }
+                )?
+                    .finish()
+                    .map_err(|err| format_err!("Could not finish game: {:?}", err))?
+            };
+            Ok(SNetSchafkopfGame {
+                n_game,
+                gameresult,
+                an_payout_netschafkopf,
+                n_pay_into_stock_netschafkopf,
+            })
         })
         // TODO is the following needed?
         .collect::<Vec<_>>()
@@ -761,15 +1219,30 @@ pub fn analyze_netschafkopf(str_lines: &str) -> Result<Vec<Result<SGameResult</*
 
 #[test]
 fn test_parse_netschafkopf() {
-    fn test_internal(slcu8_netschafkopf: &[u8]) {
-        for resgame in unwrap!(analyze_netschafkopf(&String::from_utf8_lossy(slcu8_netschafkopf))) {
-            unwrap!(resgame);
+    // slcpairnan_payout lists some games together with their expected payouts (in the order the players are listed after "Geber").
+    fn test_internal(slcu8_netschafkopf: &[u8], slcpairnan_payout: &[(usize, [isize; EPlayerIndex::SIZE])]) {
+        let vecnetschafkopfgame = unwrap!(analyze_netschafkopf(&String::from_utf8_lossy(slcu8_netschafkopf)))
+            .into_iter()
+            .map(|resnetschafkopfgame| unwrap!(resnetschafkopfgame))
+            .collect::<Vec<_>>();
+        for netschafkopfgame in vecnetschafkopfgame.iter() {
+            assert_eq!(netschafkopfgame.payout_discrepancy(), None, "Game {}", netschafkopfgame.n_game);
+        }
+        for (n_game, an_payout) in slcpairnan_payout {
+            let netschafkopfgame = unwrap!(vecnetschafkopfgame.iter().find(|netschafkopfgame| netschafkopfgame.n_game==*n_game));
+            assert_eq!(netschafkopfgame.gameresult.an_payout.into_raw(), *an_payout, "Game {}", n_game);
         }
     }
-    test_internal(include_bytes!("Schafkopfprotokoll_vom_14.12.22.txt"));
-    test_internal(include_bytes!("Schafkopfprotokoll_vom_16.05.20.txt"));
-    test_internal(include_bytes!("Schafkopfprotokoll_vom_20.03.23.txt"));
-    test_internal(include_bytes!("Schafkopfprotokoll_vom_04.08.24.txt"));
+    test_internal(include_bytes!("Schafkopfprotokoll_vom_14.12.22.txt"), &[
+        (1, [-20, 20, -20, 20]), // Rufspiel, 1 doubling
+        (3, [480, -160, -160, -160]), // Laufende in Farbwenz
+    ]);
+    test_internal(include_bytes!("Schafkopfprotokoll_vom_16.05.20.txt"), &[
+        (19, [-50, 50, 50, -50]), // "Der Erste und der Letzte zahlen"
+        (21, [-50, 50, 50, -50]),
+    ]);
+    test_internal(include_bytes!("Schafkopfprotokoll_vom_20.03.23.txt"), &[]); // Laufende not paid
+    test_internal(include_bytes!("Schafkopfprotokoll_vom_04.08.24.txt"), &[]);
 }
 
 #[test]
@@ -779,4 +1252,166 @@ fn test_analyze_plain() {
     }
     internal_test("Rufspiel Blaue von 3: so h7 go eo ho hz hk eu gu h9 su g8 g9 ga gk e9 ea ek ez e7 g7 ha s7 gz sa s9 h8 sz e8 sk hu s8");
     internal_test("Schelln-Wenz von 2: ea ek e7 ez gz g7 ga go eu e9 so s9 gu h7 sa hu su h8 e8 sz s8 ha eo g9 s7 h9 hk g8 sk hz ho gk");
+    internal_test("# 0: A (+20), 1: B (-20), 2: C (+20), 3: D (-20), Stock: 0\n\nRufspiel mit der Gras-Sau von 3: so h7 go eo ho hz hk eu gu h9 su g8 g9 ga gk e9 ea ek ez e7 g7 ha s7 gz sa s9 h8 sz e8 sk hu s8");
+}
+
+// A Rufspiel as it could have been played on Sauspiel.
+#[cfg(test)]
+pub(crate) fn stichseq_sauspiel_for_test() -> (SRules, SStichSequence) {
+    let rules = unwrap!(crate::rules::parser::parse_rule_description_simple("Rufspiel Blaue von 3"));
+    let stichseq = unwrap!(SStichSequence::new_from_cards(
+        EKurzLang::Lang,
+        unwrap!(parse_cards::<Vec<_>>("so h7 go eo ho hz hk eu gu h9 su g8 g9 ga gk e9 ea ek ez e7 g7 ha s7 gz sa s9 h8 sz e8 sk hu s8")).into_iter(),
+        &rules,
+    ).ok());
+    (rules, stichseq)
+}
+
+// stichseq_sauspiel_for_test as Sauspiel JSON, optionally with Klopfen and Kontra by player 1.
+#[cfg(test)]
+pub(crate) fn sauspiel_json_for_test(b_doubling_stoss: bool, str_base_rate: &str) -> String {
+    let (_rules, stichseq) = stichseq_sauspiel_for_test();
+    let n_doubling_stoss = if b_doubling_stoss {1} else {0};
+    format!(
+        r#"[{}{{"type":"playsTheGame","suit":"G","gameType":1,"position":3,"announcement":0}},{},{{"type":"gameResult","won":true,"points":61,"amount":{},"balanceType":0,"gameType":"Sauspiel","gameRate":"","baseRate":"{}","runners":0,"result":0,"knockings":{},"contras":{},"announcement":0}}]"#,
+        if b_doubling_stoss {
+            r#"{"type":"hasKnocked","position":1},{"type":"hasContra","position":1},"#
+        } else {
+            ""
+        },
+        stichseq.visible_cards()
+            .map(|(epi, card)| format!(r#"{{"type":"playedACard","position":{},"cardID":"{}"}}"#, epi.to_usize(), card))
+            .join(","),
+        /*amount*/if b_doubling_stoss {80} else {20},
+        str_base_rate,
+        n_doubling_stoss,
+        n_doubling_stoss,
+    )
+}
+
+// stichseq_sauspiel_for_test as Sauspiel game page (as far as internal_analyze_sauspiel_html relies on it),
+// with Klopfen and Kontra by player 1 and a timeout of player 2.
+#[cfg(test)]
+pub(crate) fn sauspiel_html_for_test() -> String {
+    let (_rules, stichseq) = stichseq_sauspiel_for_test();
+    let mapepistr_username = EPlayerIndex::map_from_fn(|epi| format!("Spieler{epi}"));
+    fn html_cards<'card>(itcard: impl Iterator<Item=&'card ECard>) -> String {
+        itcard.map(|card| format!(r#"<span class="card-image by g0 {card}"></span>"#)).join("")
+    }
+    format!(
+        "<html><body>
+        <div class=\"game-participants\">{str_participants}</div>
+        <div><span class=\"title-supertext\">Spiel</span><h1>Sauspiel auf die Blaue von {str_username_active}</h1></div>
+        <table>
+            <tr><th>Tarif</th><td>P 10 / 20 / 50</td></tr>
+            <tr><th>Sonderregeln</th><td> <img class=\"rules__rule\" alt=\"Farbwenz\" title=\"Farbwenz\"> <!-- comments are ignored --> </td></tr>
+            <tr><th>Klopfer</th><td><a href=\"#\">{str_username_doubling}</a></td></tr>
+            <tr><th>Kontra und Retour</th><td><a href=\"#\">{str_username_doubling}</a></td></tr>
+        </table>
+        {str_hands}
+        <div><div><h4>Spielermittlung</h4></div><div class=\"card-rows\">
+            <div class=\"card-row\">{str_username_0}
+                sagt weiter.</div>
+            <div class=\"card-row\">{str_username_1}
+                sagt weiter.</div>
+            <div class=\"card-row\">{str_username_2}
+                sagt weiter.
+                (timeout)</div>
+            <div class=\"card-row\">{str_username_active}
+                dad gern.</div>
+            <div class=\"card-row\">{str_username_active} h\u{00E4}tt a Sauspiel</div>
+            <div class=\"card-row\">{str_username_active} spielt auf die Blaue</div>
+        </div></div>
+        {str_stichs}
+        </body></html>",
+        str_participants=mapepistr_username.iter()
+            .map(|str_username| format!(r#"<a data-username="{str_username}">{str_username}</a>"#))
+            .join(""),
+        str_username_active=mapepistr_username[EPlayerIndex::EPI3],
+        str_username_doubling=mapepistr_username[EPlayerIndex::EPI1],
+        str_username_0=mapepistr_username[EPlayerIndex::EPI0],
+        str_username_1=mapepistr_username[EPlayerIndex::EPI1],
+        str_username_2=mapepistr_username[EPlayerIndex::EPI2],
+        str_hands=EPlayerIndex::values()
+            .map(|epi| format!(
+                "<div><div><span>Karten von:</span> <a>{}</a></div>{}</div>",
+                mapepistr_username[epi],
+                html_cards(stichseq.completed_cards_by(epi).collect::<Vec<_>>().iter()),
+            ))
+            .join(""),
+        str_stichs=stichseq.completed_stichs().iter()
+            .map(|stich| format!(
+                "<div><div><span>Stich von</span> <a>{}</a></div>{}</div>",
+                mapepistr_username[stich.first_playerindex()],
+                html_cards(stich.iter().map(|(_epi, card)| card)),
+            ))
+            .join(""),
+    )
+}
+
+#[test]
+fn test_analyze_sauspiel_json() {
+    let analyze_with_expensifiers = |b_doubling_stoss| {
+        unwrap!(analyze_sauspiel_json(&sauspiel_json_for_test(b_doubling_stoss, /*str_base_rate*/""), /*fn_before_zugeben*/|_,_,_,_| {}))
+    };
+    let gameresult_plain = analyze_with_expensifiers(/*b_doubling_stoss*/false);
+    let gameresult_expensive = analyze_with_expensifiers(/*b_doubling_stoss*/true);
+    let VStockOrT::OrT(game) = &gameresult_expensive.stockorgame else {
+        panic!("Expected game, got Stock.");
+    };
+    assert_eq!(game.expensifiers.doublings.iter().filter(|&(_epi, &b_doubling)| b_doubling).count(), 1);
+    assert_eq!(game.expensifiers.vecstoss.len(), 1);
+    assert_eq!(gameresult_expensive.an_payout, gameresult_plain.an_payout.map(|n_payout| n_payout*4));
+    assert_eq!(game.ruleset().tarif(), (10, 20, 50));
+    let gameresult_cheap = unwrap!(analyze_sauspiel_json(
+        &sauspiel_json_for_test(/*b_doubling_stoss*/false, /*str_base_rate*/"P 5 / 10 / 25"),
+        /*fn_before_zugeben*/|_,_,_,_| {},
+    ));
+    assert_eq!(gameresult_cheap.an_payout.map(|n_payout| n_payout*2), gameresult_plain.an_payout);
+    assert!(analyze_sauspiel_json(&sauspiel_json_for_test(/*b_doubling_stoss*/false, /*str_base_rate*/"10 Cent"), /*fn_before_zugeben*/|_,_,_,_| {}).is_err());
+    assert_eq!(
+        game.determinerules,
+        vec![
+            SSauspielDetermineRulesStep{
+                epi: EPlayerIndex::EPI3,
+                determinerules: VSauspielDetermineRules::Game{esauspielgametype: ESauspielGameType::Sauspiel, b_tout: false},
+                b_timeout: false,
+            },
+            SSauspielDetermineRulesStep{
+                epi: EPlayerIndex::EPI3,
+                determinerules: VSauspielDetermineRules::Rufsau(EFarbe::Gras),
+                b_timeout: false,
+            },
+        ],
+    );
+    assert_eq!(
+        game.mapepigameannouncement.iter().map(Option::is_some).collect::<Vec<_>>(),
+        vec![false, false, false, true],
+    );
+}
+
+#[test]
+fn test_analyze_sauspiel_html() {
+    let (_rules, stichseq) = stichseq_sauspiel_for_test();
+    let gameresult_html = unwrap!(analyze_sauspiel_html(&sauspiel_html_for_test()));
+    let VStockOrT::OrT(game_html) = &gameresult_html.stockorgame else {
+        panic!("Expected game, got Stock.");
+    };
+    assert_eq!(game_html.stichseq.visible_cards().collect::<Vec<_>>(), stichseq.visible_cards().collect::<Vec<_>>());
+    assert_eq!(game_html.ruleset().tarif(), (10, 20, 50));
+    assert_eq!(
+        game_html.mapepigameannouncement.iter().map(|gameannouncement| (gameannouncement.ogameannouncement.is_some(), gameannouncement.b_timeout)).collect::<Vec<_>>(),
+        vec![(false, false), (false, false), (false, true), (true, false)],
+    );
+    assert_eq!(game_html.expensifiers.vecstoss.iter().map(|stoss| (stoss.epi, stoss.n_cards_played)).collect::<Vec<_>>(), vec![(EPlayerIndex::EPI1, 0)]);
+    // The same game given as Sauspiel JSON yields the same payout and rules determination.
+    let gameresult_json = unwrap!(analyze_sauspiel_json(
+        &sauspiel_json_for_test(/*b_doubling_stoss*/true, /*str_base_rate*/""),
+        /*fn_before_zugeben*/|_,_,_,_| {},
+    ));
+    let VStockOrT::OrT(game_json) = &gameresult_json.stockorgame else {
+        panic!("Expected game, got Stock.");
+    };
+    assert_eq!(gameresult_html.an_payout, gameresult_json.an_payout);
+    assert_eq!(game_html.determinerules, game_json.determinerules);
 }
@@ -0,0 +1,368 @@
+use crate::primitives::*;
+use crate::rules::{parser::parse_rule_description_simple, *};
+use crate::util::*;
+use itertools::Itertools;
+use std::fmt;
+
+// A complete description of a game situation, as a single line of seven or eight fields separated by ';':
+//  rules; hands; played cards; stoss; doublings; stock; position[; prices]
+// * rules as accepted by parse_rule_description_simple, e.g. "Rufspiel mit der Gras-Sau von 1"
+// * the remaining cards of players 0 to 3, separated by '|', "__" denoting an unknown card
+// * the cards played so far, in order (stichs may be separated by '|')
+// * stoss as "epi@n" (given by epi after n cards have been played), separated by spaces
+// * doublings as one digit (0 or 1) per player, starting with player 0
+// * the amount in the stock
+// * the player from whose perspective the position is seen
+// * optionally, prices as "key=value" separated by spaces, keys being price, extra, lauf-min and
+//   stoss-max; missing prices default to those of parse_rule_description_simple
+// Example: "Herz-Solo von 0; EO GO HO SO EU GU HU SU | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __; ; 1@0; 0000; 0; 0"
+#[derive(Clone, Debug)]
+pub struct SPosition {
+    pub rules: SRules,
+    pub stichseq: SStichSequence,
+    pub ahand: EnumMap<EPlayerIndex, SHand>, // known cards; missing cards are unknown
+    pub expensifiers: SExpensifiers,
+    pub epi_position: EPlayerIndex,
+}
+
+impl SPosition {
+    pub fn new(rules: &SRules, stichseq: &SStichSequence, ahand: &EnumMap<EPlayerIndex, SHand>, expensifiers: &SExpensifiers, epi_position: EPlayerIndex) -> Self {
+        let n_cards_played = stichseq.visible_cards().count();
+        Self {
+            rules: rules.clone(),
+            stichseq: stichseq.clone(),
+            ahand: ahand.clone(),
+            expensifiers: SExpensifiers::new(
+                expensifiers.n_stock,
+                expensifiers.doublings.clone(),
+                expensifiers.vecstoss.iter()
+                    .filter(|stoss| stoss.n_cards_played <= n_cards_played)
+                    .cloned()
+                    .collect(),
+            ),
+            epi_position,
+        }
+    }
+
+    // Known cards and placeholders for unknown cards of all players, in order.
+    pub fn cards_with_holes(&self) -> Vec<Option<ECard>> {
+        let mapepin_remaining = self.stichseq.remaining_cards_per_hand();
+        EPlayerIndex::values()
+            .flat_map(|epi| {
+                let veccard = self.ahand[epi].cards();
+                veccard.iter().copied().map(Some)
+                    .chain(std::iter::repeat_n(None, mapepin_remaining[epi] - veccard.len()))
+            })
+            .collect()
+    }
+
+    // Checks the position against the rules. Cards played and stosses given can only be checked
+    // for players whose cards are completely known.
+    pub fn validate(&self) -> Result<(), VPositionError> {
+        let ekurzlang = self.stichseq.kurzlang();
+        let mapepin_remaining = self.stichseq.remaining_cards_per_hand();
+        for epi in EPlayerIndex::values() {
+            let veccard_hand = self.ahand[epi].cards();
+            if mapepin_remaining[epi] < veccard_hand.len() {
+                return Err(VPositionError::TooManyCards{epi, n_cards_remaining: mapepin_remaining[epi], veccard_hand: veccard_hand.to_vec()});
+            }
+            if let Some(card) = veccard_hand.iter().find(|card| !ekurzlang.supports_card(**card)) {
+                return Err(VPositionError::CardNotInDeck{card: *card, epi, ekurzlang});
+            }
+        }
+        let veccard_duplicate = self.stichseq.visible_cards().map(|(_epi, card)| *card)
+            .chain(self.ahand.iter().flat_map(|hand| hand.cards().iter().copied()))
+            .duplicates()
+            .collect::<Vec<_>>();
+        if !veccard_duplicate.is_empty() {
+            return Err(VPositionError::CardsUsedTwice(veccard_duplicate));
+        }
+        let mapepib_known = EPlayerIndex::map_from_fn(|epi| self.ahand[epi].cards().len()==mapepin_remaining[epi]);
+        let mut ahand = EPlayerIndex::map_from_fn(|epi| SHand::new_from_iter(self.stichseq.cards_from_player(&self.ahand[epi], epi)));
+        let mut stichseq = SStichSequence::new(ekurzlang);
+        let vecstoss = &self.expensifiers.vecstoss;
+        if let Some(stoss) = vecstoss.iter().find(|stoss| self.stichseq.visible_cards().count() < stoss.n_cards_played) {
+            return Err(VPositionError::StossAfterCardsPlayed{epi: stoss.epi, n_cards_played: stoss.n_cards_played});
+        }
+        if let Some((stoss_lhs, stoss_rhs)) = vecstoss.iter().tuple_windows().find(|(stoss_lhs, stoss_rhs)| stoss_rhs.n_cards_played < stoss_lhs.n_cards_played) {
+            return Err(VPositionError::StossOutOfOrder{
+                tplepin_before: (stoss_lhs.epi, stoss_lhs.n_cards_played),
+                tplepin_after: (stoss_rhs.epi, stoss_rhs.n_cards_played),
+            });
+        }
+        // stoss_allowed relies on previous stosses being valid, so we stop checking at the first one we cannot check
+        let mut ob_check_stoss_from = Some(0);
+        let mut check_stosses = |stichseq: &SStichSequence, ahand: &EnumMap<EPlayerIndex, SHand>| -> Result<(), VPositionError> {
+            let n_cards_played = stichseq.visible_cards().count();
+            while let Some(i_stoss) = ob_check_stoss_from.filter(|&i_stoss|
+                vecstoss.get(i_stoss).is_some_and(|stoss| stoss.n_cards_played==n_cards_played)
+            ) {
+                let stoss = &vecstoss[i_stoss];
+                if !mapepib_known[stoss.epi] {
+                    ob_check_stoss_from = None;
+                } else if self.rules.stoss_allowed(stichseq, &ahand[stoss.epi], stoss.epi, &vecstoss[..i_stoss]) {
+                    ob_check_stoss_from = Some(i_stoss + 1);
+                } else {
+                    return Err(VPositionError::StossNotAllowed{epi: stoss.epi, n_cards_played: stoss.n_cards_played, i_stoss});
+                }
+            }
+            Ok(())
+        };
+        for (i_card, (epi, &card)) in self.stichseq.visible_cards().enumerate() {
+            check_stosses(&stichseq, &ahand)?;
+            if mapepib_known[epi] && !self.rules.card_is_allowed(&stichseq, &ahand[epi], card) {
+                let mut veccard_allowed = self.rules.all_allowed_cards(&stichseq, &ahand[epi]).to_vec();
+                self.rules.sort_cards(&mut veccard_allowed);
+                return Err(VPositionError::CardNotAllowed{i_card, epi, card, veccard_allowed});
+            }
+            ahand[epi].play_card(card);
+            stichseq.zugeben(card, &self.rules);
+        }
+        check_stosses(&stichseq, &ahand)
+    }
+}
+
+// Reasons for SPosition::validate to reject a position. Stosses are identified by the player giving
+// them and the number of cards played before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VPositionError {
+    TooManyCards{epi: EPlayerIndex, n_cards_remaining: usize, veccard_hand: Vec<ECard>},
+    CardNotInDeck{card: ECard, epi: EPlayerIndex, ekurzlang: EKurzLang},
+    CardsUsedTwice(Vec<ECard>),
+    StossAfterCardsPlayed{epi: EPlayerIndex, n_cards_played: usize}, // refers to cards not yet played
+    StossOutOfOrder{tplepin_before: (EPlayerIndex, usize), tplepin_after: (EPlayerIndex, usize)},
+    StossNotAllowed{epi: EPlayerIndex, n_cards_played: usize, i_stoss: usize},
+    CardNotAllowed{i_card: usize, epi: EPlayerIndex, card: ECard, veccard_allowed: Vec<ECard>},
+}
+
+impl fmt::Display for VPositionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VPositionError::TooManyCards{epi, n_cards_remaining, veccard_hand} => write!(f, "Player {} has {} cards left, but {} are given: {}", epi, n_cards_remaining, veccard_hand.len(), veccard_hand.iter().join(" ")),
+            VPositionError::CardNotInDeck{card, epi, ekurzlang} => write!(f, "{card} (hand of player {epi}) not supported in {ekurzlang:?}"),
+            VPositionError::CardsUsedTwice(veccard) => write!(f, "Cards are used more than once: {}", veccard.iter().join(", ")),
+            VPositionError::StossAfterCardsPlayed{epi, n_cards_played} => write!(f, "Stoss {epi}@{n_cards_played} refers to cards not yet played"),
+            VPositionError::StossOutOfOrder{tplepin_before: (epi_before, n_before), tplepin_after: (epi_after, n_after)} => write!(f, "Stoss {epi_before}@{n_before} given before stoss {epi_after}@{n_after}"),
+            VPositionError::StossNotAllowed{epi, n_cards_played, i_stoss} => write!(f, "Stoss {}@{} (stoss number {}) not allowed", epi, n_cards_played, i_stoss + 1),
+            VPositionError::CardNotAllowed{i_card, epi, card, veccard_allowed} => write!(f, "Played card number {} ({} by player {}) not allowed; allowed: {}", i_card + 1, card, epi, veccard_allowed.iter().join(" ")),
+        }
+    }
+}
+impl std::error::Error for VPositionError {}
+
+impl SPosition {
+    // Parses the description without validating the position against the rules (see validate).
+    pub(crate) fn from_str_unvalidated(str_position: &str) -> Result<Self, Error> {
+        let mut itstr_field = str_position.split(';').map(str::trim);
+        let (str_rules, str_ahand, str_played, str_stoss, str_doublings, str_stock, str_epi) = itstr_field
+            .next_tuple()
+            .ok_or_else(|| format_err!("Expected 7 or 8 fields separated by ';': {}", str_position))?;
+        let ostr_prices = itstr_field.next();
+        if itstr_field.next().is_some() {
+            return Err(format_err!("Expected 7 or 8 fields separated by ';': {}", str_position));
+        }
+        let mut rules = parse_rule_description_simple(str_rules)?;
+        if let Some(str_prices) = ostr_prices {
+            fn parse_price<T: std::str::FromStr>(ot_applicable: Option<T>, str_price: &str, str_value: &str, str_rules: &str) -> Result<Option<T>, Error>
+                where T::Err: fmt::Display,
+            {
+                if ot_applicable.is_none() {
+                    return Err(format_err!("{} not applicable to {}", str_price, str_rules));
+                }
+                str_value.parse().map(Some).map_err(|err| format_err!("Could not parse price {}: {}", str_price, err))
+            }
+            let prices_applicable = rules.prices();
+            let mut prices = SPrices::default();
+            for str_price in str_prices.split_whitespace() {
+                let (str_key, str_value) = str_price.split_once('=')
+                    .ok_or_else(|| format_err!("Expected price as key=value: {}", str_price))?;
+                match str_key {
+                    "price" => prices.on_price = parse_price(prices_applicable.on_price, str_price, str_value, str_rules)?,
+                    "extra" => prices.on_extra = parse_price(prices_applicable.on_extra, str_price, str_value, str_rules)?,
+                    "lauf-min" => prices.on_lauf_min = parse_price(prices_applicable.on_lauf_min, str_price, str_value, str_rules)?,
+                    "stoss-max" => prices.on_stoss_max = parse_price(prices_applicable.on_stoss_max, str_price, str_value, str_rules)?,
+                    _ => return Err(format_err!("Unknown price {}", str_price)),
+                }
+            }
+            rules.set_prices(&prices);
+        }
+        let vecvecocard_hand = str_ahand.split('|')
+            .map(str::trim)
+            .map(|str_hand| cardvector::parse_optional_cards::<Vec<_>>(str_hand)
+                .ok_or_else(|| format_err!("Could not parse hand: {}", str_hand))
+            )
+            .collect::<Result<Vec<_>,_>>()?;
+        let n_hands = vecvecocard_hand.len();
+        let vecvecocard_hand = EPlayerIndex::map_from_raw(vecvecocard_hand.into_iter()
+            .collect_array()
+            .ok_or_else(|| format_err!("Expected {} hands, got {}", EPlayerIndex::SIZE, n_hands))?);
+        let veccard_played = cardvector::parse_cards::<Vec<_>>(str_played.replace('|', " ").trim())
+            .ok_or_else(|| format_err!("Could not parse played cards: {}", str_played))?;
+        if let Some(card) = veccard_played.iter().chain(vecvecocard_hand.iter().flatten().flatten()).duplicates().next() {
+            return Err(format_err!("{} is used more than once", card));
+        }
+        let n_cards_total = veccard_played.len() + vecvecocard_hand.iter().map(Vec::len).sum::<usize>();
+        let ekurzlang = EKurzLang::values()
+            .find(|ekurzlang| ekurzlang.cards_per_player()*EPlayerIndex::SIZE==n_cards_total)
+            .ok_or_else(|| format_err!("Played cards and hands contain {} cards in total", n_cards_total))?;
+        let mut stichseq = SStichSequence::new(ekurzlang);
+        for &card in veccard_played.iter() {
+            if !ekurzlang.supports_card(card) {
+                return Err(format_err!("{} not supported in {:?}", card, ekurzlang));
+            }
+            stichseq.zugeben(card, &rules);
+        }
+        let mapepin_remaining = stichseq.remaining_cards_per_hand();
+        for epi in EPlayerIndex::values() {
+            if vecvecocard_hand[epi].len()!=mapepin_remaining[epi] {
+                return Err(format_err!("Player {} must have {} cards, got {}", epi, mapepin_remaining[epi], vecvecocard_hand[epi].len()));
+            }
+        }
+        let vecstoss = str_stoss.split_whitespace()
+            .map(|str_stoss| {
+                let (str_epi, str_n_cards_played) = str_stoss.split_once('@').unwrap_or((str_stoss, "0"));
+                Ok(SStoss {
+                    epi: str_epi.parse().map_err(|err| format_err!("Could not parse stoss {}: {}", str_stoss, err))?,
+                    n_cards_played: str_n_cards_played.parse().map_err(|err| format_err!("Could not parse stoss {}: {:?}", str_stoss, err))?,
+                })
+            })
+            .collect::<Result<Vec<_>,Error>>()?;
+        if EPlayerIndex::SIZE < str_doublings.len() {
+            return Err(format_err!("Too many doublings: {}", str_doublings));
+        }
+        let mut doublings = SDoublings::new(SStaticEPI0{});
+        for chr_doubling in str_doublings.chars() {
+            doublings.push(match chr_doubling {
+                '0' => false,
+                '1' => true,
+                _ => return Err(format_err!("Doublings must consist of 0 and 1: {}", str_doublings)),
+            });
+        }
+        Ok(Self {
+            ahand: vecvecocard_hand.map(|vecocard| SHand::new_from_iter(vecocard.iter().flatten())),
+            rules,
+            stichseq,
+            expensifiers: SExpensifiers::new(
+                str_stock.parse().map_err(|err| format_err!("Could not parse stock {}: {:?}", str_stock, err))?,
+                doublings,
+                vecstoss,
+            ),
+            epi_position: str_epi.parse().map_err(|err| format_err!("Could not parse position {}: {}", str_epi, err))?,
+        })
+    }
+}
+
+impl std::str::FromStr for SPosition {
+    type Err = Error;
+    fn from_str(str_position: &str) -> Result<Self, Self::Err> {
+        let position = Self::from_str_unvalidated(str_position)?;
+        position.validate().map_err(|err| format_err!("{}", err))?;
+        Ok(position)
+    }
+}
+
+impl fmt::Display for SPosition {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mapepin_remaining = self.stichseq.remaining_cards_per_hand();
+        let displayrules = SDisplayRules::new(&self.rules, /*b_include_playerindex*/true);
+        write!(fmt, "{}; {}; {}; {}; {}; {}; {}",
+            displayrules,
+            EPlayerIndex::values()
+                .map(|epi| {
+                    let mut veccard = self.ahand[epi].cards().to_vec();
+                    self.rules.sort_cards(&mut veccard);
+                    veccard.iter().map(ECard::to_string)
+                        .chain(std::iter::repeat_n("__".to_owned(), mapepin_remaining[epi] - veccard.len()))
+                        .join(" ")
+                })
+                .join(" | "),
+            self.stichseq.visible_stichs().iter()
+                .filter(|stich| !stich.is_empty())
+                .map(|stich| stich.iter().map(|(_epi, card)| card).join(" "))
+                .join(" | "),
+            self.expensifiers.vecstoss.iter()
+                .map(|stoss| format!("{}@{}", stoss.epi, stoss.n_cards_played))
+                .join(" "),
+            self.expensifiers.doublings.iter()
+                .map(|(_epi, &b_doubling)| if b_doubling {'1'} else {'0'})
+                .join(""),
+            self.expensifiers.n_stock,
+            self.epi_position,
+        )?;
+        // only write prices differing from those obtained when parsing the rules
+        fn price_to_string<T: PartialEq + fmt::Display>(str_key: &str, ot: Option<T>, ot_parsed: Option<T>) -> Option<String> {
+            if_then_some!(ot!=ot_parsed, ot.map(|t| format!("{str_key}={t}"))).flatten()
+        }
+        let prices = self.rules.prices();
+        let prices_parsed = parse_rule_description_simple(&displayrules.to_string())
+            .map(|rules| rules.prices())
+            .unwrap_or_default();
+        let vecstr_price = [
+            price_to_string("price", prices.on_price, prices_parsed.on_price),
+            price_to_string("extra", prices.on_extra, prices_parsed.on_extra),
+            price_to_string("lauf-min", prices.on_lauf_min, prices_parsed.on_lauf_min),
+            price_to_string("stoss-max", prices.on_stoss_max, prices_parsed.on_stoss_max),
+        ].into_iter().flatten().collect::<Vec<_>>();
+        if !vecstr_price.is_empty() {
+            write!(fmt, "; {}", vecstr_price.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_position() {
+    use crate::primitives::card::ECard::*;
+    for str_position in [
+        "Herz-Solo von 0; EO GO HO SO EU GU HU SU | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __; ; 1@0; 0000; 0; 0",
+        "Rufspiel mit der Eichel-Sau von 1; GO __ __ __ __ __ __ | __ __ __ __ __ __ __ | __ __ __ __ __ __ __ | __ __ __ __ __ __ __; HO HU HA H7; 2@0 3@0; 0100; 20; 2",
+        "Wenz von 3; HA __ __ __ __ | __ __ __ __ | HU __ __ __ | __ __ __ __ __; EK EZ E9 EO | GA GK; ; 0000; 0; 2",
+        "Wenz von 3; HA __ __ __ __ | __ __ __ __ | HU __ __ __ | __ __ __ __ __; EK EZ E9 EO | GA GK; ; 0000; 0; 2; price=20 extra=5 lauf-min=3 stoss-max=2",
+    ] {
+        let position = unwrap!(str_position.parse::<SPosition>());
+        assert_eq!(position.to_string(), str_position);
+    }
+    let str_position_wenz = "Wenz von 3; HA __ __ __ __ | __ __ __ __ | HU __ __ __ | __ __ __ __ __; EK EZ E9 EO GA GK; 1@0; 0000; 0; 2";
+    let position = unwrap!(str_position_wenz.parse::<SPosition>());
+    assert_eq!(position.stichseq.kurzlang(), EKurzLang::Kurz);
+    assert_eq!(position.stichseq.completed_stichs().len(), 1);
+    assert_eq!(position.cards_with_holes().len(), 18);
+    assert_eq!(position.cards_with_holes().iter().flatten().collect::<Vec<_>>(), vec![&HA, &HU]);
+    for str_position_invalid in [
+        str_position_wenz.replace("; 2", ""), // missing field
+        str_position_wenz.replace(" | __ __ __ __ __;", ";"), // missing hand
+        str_position_wenz.replace("HU", "HA"), // duplicate card
+        str_position_wenz.replace("HA __ __ __ __", "HA __ __ __"), // wrong number of cards
+        str_position_wenz.replace("HA", "H8"), // card not in Kurz
+        str_position_wenz.replace("1@0", "1@7"), // stoss in the future
+        str_position_wenz.replace("0000", "00000"), // too many doublings
+        str_position_wenz.replace("; 2", "; 4"), // invalid position
+        format!("{str_position_wenz}; price=20; 0"), // too many fields
+        format!("{str_position_wenz}; price"), // price without value
+        format!("{str_position_wenz}; price=x"), // invalid price
+        format!("{str_position_wenz}; preis=20"), // unknown price
+        format!("{}; lauf-min=2", str_position_wenz.replace("Wenz", "Bettel")), // Bettel knows no Laufende
+    ] {
+        assert!(str_position_invalid.parse::<SPosition>().is_err(), "{}", str_position_invalid);
+    }
+    let str_position_known = "Herz-Solo von 0; EO GO HO SO EU GU HU | GA GZ GK G9 G8 G7 S7 | SA SZ SK S9 S8 SU EK | HA HZ HK H9 H8 H7 EZ; EA E7 E8 E9; 1@0 0@0; 0000; 0; 0";
+    unwrap!(str_position_known.parse::<SPosition>());
+    let position = unwrap!(format!("{str_position_known}; price=20 stoss-max=3").parse::<SPosition>());
+    assert_eq!(
+        position.rules.prices(),
+        SPrices{on_price: Some(20), on_extra: Some(10), on_lauf_min: Some(3), on_stoss_max: Some(3)},
+    );
+    for str_position_invalid in [
+        format!("{str_position_known}; stoss-max=1"), // too many stosses
+
+        str_position_known.replace("EA E7", "EA GA").replace("GA GZ", "E7 GZ"), // not following suit
+        str_position_known.replace("1@0 0@0", "0@0"), // solo player must not give first stoss
+        str_position_known.replace("1@0 0@0", "1@0 2@0"), // same party must not give consecutive stosses
+        str_position_known.replace("1@0 0@0", "1@1"), // stoss too late
+        str_position_known.replace("1@0 0@0", "1@2 0@1"), // stoss out of order
+    ] {
+        assert!(str_position_invalid.parse::<SPosition>().is_err(), "{}", str_position_invalid);
+    }
+    // cards of player 1 unknown, so their moves cannot be checked
+    unwrap!(str_position_known.replace("EA E7", "EA GA").replace("GA GZ GK G9 G8 G7 S7", "__ __ __ __ __ __ __").parse::<SPosition>());
+}
@@ -11,6 +11,7 @@
 #[macro_use]
 pub(crate) mod util;
 pub mod ai;
+pub mod api;
 pub mod game;
 pub mod game_analysis;
 pub mod player;
@@ -1,13 +1,46 @@
 pub mod playercomputer;
-#[cfg(test)]
 pub mod playerrandom;
+pub mod playertext;
 
 use crate::game::*;
 use crate::primitives::*;
 use crate::rules::{ruleset::*, *};
 
+use plain_enum::EnumMap;
 use std::sync::mpsc;
 
+// Actions during a game, reported to all players.
+pub enum VGameEvent<'event> {
+    Seat { // sent only to the player sitting at epi, before the cards are dealt
+        epi: EPlayerIndex,
+    },
+    Doubling {
+        epi: EPlayerIndex,
+        b_doubling: bool,
+    },
+    Announcement {
+        epi: EPlayerIndex,
+        orules: Option<&'event SActivelyPlayableRules>,
+    },
+    Rules {
+        rules: &'event SRules,
+    },
+    Stoss {
+        epi: EPlayerIndex,
+    },
+    Card {
+        epi: EPlayerIndex,
+        card: ECard,
+    },
+    GameResult {
+        an_payout: &'event EnumMap<EPlayerIndex, isize>,
+    },
+    Balance { // money of the players at the table (not sent after replays)
+        an_money: &'event EnumMap<EPlayerIndex, isize>,
+        n_stock: isize,
+    },
+}
+
 pub trait TPlayer {
     fn ask_for_doubling(
         &self,
@@ -39,4 +72,8 @@ pub trait TPlayer {
     );
 
     fn name(&self) -> &str;
+
+    fn notify(&self, _gameevent: &VGameEvent) {
+        // most players only look at the state they are given when asked
+    }
 }
@@ -1,20 +1,14 @@
-use crate::ai::{
-    handiterators::forever_rand_hands,
-    gametree::{
-        explore_snapshots, SMinReachablePayout, SNoVisualization, SSnapshotCacheNone,
-    },
-    *,
-};
+use crate::ai::{gametree::SNoVisualization, *};
 use crate::game::*;
 use crate::player::*;
 use crate::primitives::*;
 use crate::rules::{ruleset::*, *};
 use crate::util::*;
 use std::sync::mpsc;
-use itertools::Itertools;
 
 pub struct SPlayerComputer {
     pub ai : SAi,
+    pub str_name: String,
 }
 
 impl TPlayer for SPlayerComputer {
@@ -23,16 +17,7 @@ impl TPlayer for SPlayerComputer {
         veccard: &[ECard],
         txb_doubling: mpsc::Sender<bool>,
     ) {
-        txb_doubling.send(
-            veccard.iter()
-                .filter(|card| {
-                    ESchlag::Ober==card.schlag() || ESchlag::Unter==card.schlag() || EFarbe::Herz==card.farbe()
-                })
-                .count() >= 3
-            || veccard.iter()
-                    .map(|card| card.farbe())
-                    .all_equal()
-        ).ok(); // TODO more intelligent doubling strategy
+        txb_doubling.send(self.ai.suggest_doubling(veccard)).ok();
     }
 
     fn ask_for_card(&self, game: &SGameGeneric<SRuleSet, (), ()>, txcard: mpsc::Sender<ECard>) {
@@ -49,28 +34,7 @@ impl TPlayer for SPlayerComputer {
         _otplepiprio: Option<(EPlayerIndex, VGameAnnouncementPriority)>,
         txorules: mpsc::Sender<Option<&'rules SActivelyPlayableRules>>
     ) {
-        // TODO: implement a more intelligent decision strategy
-        unwrap!(txorules.send(unwrap!(allowed_rules(vecrulegroup, hand)
-            .map(|orules| (
-                orules,
-                orules.map_or(
-                    0., // TODO how to rank None?
-                    |rules| {
-                        self.ai.rank_rules(
-                            hand,
-                            /*epi_rank*/rules.playerindex(),
-                            &rules.clone().into(), // TODO avoid clone
-                            expensifiers,
-                        ).omaxmin.unwrap_static_some().avg().as_num::<f64>()
-                    }
-                )
-            ))
-            .max_by(|&(_orules_lhs, f_payout_avg_lhs), &(_orules_rhs, f_payout_avg_rhs)| {
-                assert!(!f_payout_avg_lhs.is_nan());
-                assert!(!f_payout_avg_rhs.is_nan());
-                unwrap!(f_payout_avg_lhs.partial_cmp(&f_payout_avg_rhs))
-            })
-        ).0));
+        unwrap!(txorules.send(unwrap!(self.ai.rank_announcements(hand, vecrulegroup, expensifiers).first()).0));
     }
 
     fn ask_for_stoss(
@@ -82,53 +46,10 @@ impl TPlayer for SPlayerComputer {
         expensifiers: &SExpensifiers,
         txb: mpsc::Sender<bool>,
     ) {
-        let n_samples_per_stoss = 5; // TODO move to ai, make adjustable
-        let mut vectplahandf_suspicion = forever_rand_hands(stichseq, (hand.clone(), epi), rules, &expensifiers.vecstoss)
-            .take(2*n_samples_per_stoss)
-            .map(|ahand| {
-                let f_rank_rules = rules.playerindex().map_or(0f64, |epi_active| {
-                    if epi!=epi_active {
-                        self.ai.rank_rules(
-                            SFullHand::new(ahand[epi_active].cards(), stichseq.kurzlang()),
-                            /*epi_rank*/epi_active,
-                            rules,
-                            expensifiers,
-                        ).omaxmin.unwrap_static_some().avg().as_num::<f64>()
-                    } else {
-                        0f64
-                    }
-                });
-                (ahand, f_rank_rules)
-            })
-            .collect::<Vec<_>>();
-        vectplahandf_suspicion.sort_unstable_by(|&(ref _ahand_l, f_rank_l), &(ref _ahand_r, f_rank_r)|
-            unwrap!(f_rank_r.partial_cmp(&f_rank_l))
-        );
-        vectplahandf_suspicion.truncate(n_samples_per_stoss);
-        assert_eq!(n_samples_per_stoss, vectplahandf_suspicion.len());
-        unwrap!(txb.send(
-            vectplahandf_suspicion.into_iter()
-                .map(|(mut ahand, _f_rank_rules)| {
-                    explore_snapshots(
-                        (&mut ahand, &mut SStichSequence::new(stichseq.kurzlang())),
-                        rules,
-                        &SBranchingFactor::factory(1, 2),
-                        &SMinReachablePayout::new(
-                            rules,
-                            epi,
-                            expensifiers.clone(),
-                        ),
-                        &SSnapshotCacheNone::factory(), // TODO? use cache
-                        &mut SNoVisualization,
-                    ).omaxmin.unwrap_static_some()[epi]
-                })
-                .sum::<isize>().as_num::<f64>()
-                / n_samples_per_stoss.as_num::<f64>()
-                > 10f64
-        ))
+        unwrap!(txb.send(self.ai.suggest_stoss(epi, rules, hand, stichseq, expensifiers).0))
     }
 
     fn name(&self) -> &str {
-        "SPlayerComputer" // TODO
+        &self.str_name
     }
 }
@@ -0,0 +1,258 @@
+use crate::game::*;
+use crate::player::*;
+use crate::primitives::{cardvector::parse_cards, *};
+use crate::rules::{ruleset::*, *};
+use crate::util::*;
+use itertools::Itertools;
+use std::{
+    cell::RefCell,
+    io::{BufRead, Write},
+    sync::mpsc,
+};
+
+// Player communicating via a line-based text protocol (similar to UCI in chess), so that
+// scripts, external GUIs or screen readers can take part in a game.
+//
+// Each line written by SPlayerText starts with a keyword. Players are denoted by their index (0 to 3),
+// cards are written like "EO" (Eichel-Ober) or "S7" (Schelln-7).
+//
+// Events (sent for all players' actions, including the own ones):
+//   seat <epi>
+//     the player's own index, sent before the cards of each game are dealt
+//   doubling <epi> yes|no
+//   announcement <epi> <rules>|Nothing
+//   rules <rules>
+//   stoss <epi>
+//   card <epi> <card>
+//   result <payout of player 0> <payout of player 1> <payout of player 2> <payout of player 3>
+//   balance <money of player 0> <money of player 1> <money of player 2> <money of player 3> <stock>
+//
+// Questions (each preceded by "hand <cards>" with the cards of the asked player):
+//   ask doubling
+//     answer "yes" or "no"
+//   ask game <epi>
+//     preceded by "option <i> <rules>|Nothing" for each allowed option (and "offer <epi> <priority>" if
+//     another player has to be outbid, where <priority> is one of "rufspiel", "solo <i>",
+//     "solo-steigern <points to win> <step>", "tout <i>", "sie", and <i> orders games of the same kind);
+//     answer the option's index or its rules
+//   ask stoss <epi>
+//     answer "yes" or "no"
+//   ask card <epi> <allowed cards>
+//     answer a card
+//
+// Invalid answers are rejected with "error <message>", after which the question is repeated.
+// If the input is closed, the player gives up with "error <message>", which ends the game loop.
+pub struct SPlayerText<Input, Output> {
+    str_name: String,
+    input: RefCell<Input>,
+    output: RefCell<Output>,
+}
+
+fn parse_yes_no(str_answer: &str) -> Result<bool, String> {
+    match str_answer {
+        "yes" => Ok(true),
+        "no" => Ok(false),
+        _ => Err(format!("Expected yes or no, got {str_answer}")),
+    }
+}
+
+fn parse_card(str_answer: &str) -> Result<ECard, String> {
+    parse_cards::<Vec<_>>(str_answer)
+        .and_then(|veccard| veccard.into_iter().exactly_one().ok())
+        .ok_or_else(|| format!("Expected a single card, got {str_answer}"))
+}
+
+fn orules_to_string(orules: Option<&SActivelyPlayableRules>) -> String {
+    match orules {
+        None => "Nothing".to_string(),
+        Some(rules) => SDisplayRules::new(rules, /*b_include_playerindex*/false).to_string(),
+    }
+}
+
+fn gameannouncementpriority_to_string(gameannouncementprio: &VGameAnnouncementPriority) -> String {
+    match gameannouncementprio {
+        VGameAnnouncementPriority::RufspielLike => "rufspiel".to_string(),
+        VGameAnnouncementPriority::SoloLike(VGameAnnouncementPrioritySoloLike::SoloSimple(i_prio)) => format!("solo {i_prio}"),
+        VGameAnnouncementPriority::SoloLike(VGameAnnouncementPrioritySoloLike::SoloSteigern{n_points_to_win, n_step}) => {
+            format!("solo-steigern {n_points_to_win} {n_step}")
+        },
+        VGameAnnouncementPriority::SoloTout(i_prio) => format!("tout {i_prio}"),
+        VGameAnnouncementPriority::SoloSie => "sie".to_string(),
+    }
+}
+
+impl<Input: BufRead, Output: Write> SPlayerText<Input, Output> {
+    pub fn new(str_name: String, input: Input, output: Output) -> Self {
+        Self {
+            str_name,
+            input: RefCell::new(input),
+            output: RefCell::new(output),
+        }
+    }
+
+    fn write_line(&self, str_line: &str) {
+        let mut output = self.output.borrow_mut();
+        unwrap!(writeln!(output, "{str_line}"));
+        unwrap!(output.flush());
+    }
+
+    fn write_hand(&self, slccard: &[ECard]) {
+        self.write_line(&format!("hand {}", slccard.iter().join(" ")));
+    }
+
+    // Repeats str_question until fn_parse accepts the answer. Fails if the input is closed.
+    fn ask<T>(&self, str_question: &str, fn_parse: impl Fn(&str)->Result<T, String>) -> Result<T, Error> {
+        loop {
+            self.write_line(str_question);
+            let mut str_answer = String::new();
+            if 0==self.input.borrow_mut().read_line(&mut str_answer)? {
+                return Err(format_err!("Input closed while waiting for answer"));
+            }
+            match fn_parse(str_answer.trim()) {
+                Ok(t) => return Ok(t),
+                Err(str_error) => self.write_line(&format!("error {str_error}")),
+            }
+        }
+    }
+
+    // Gives up (i.e. drops tx without sending) if there is no answer.
+    fn send_answer<T>(&self, tx: mpsc::Sender<T>, rest: Result<T, Error>) {
+        match rest {
+            Ok(t) => unwrap!(tx.send(t)),
+            Err(err) => self.write_line(&format!("error {err}")),
+        }
+    }
+}
+
+impl<Input: BufRead, Output: Write> TPlayer for SPlayerText<Input, Output> {
+    fn ask_for_doubling(
+        &self,
+        veccard: &[ECard],
+        txb_doubling: mpsc::Sender<bool>,
+    ) {
+        self.write_hand(veccard);
+        self.send_answer(txb_doubling, self.ask("ask doubling", parse_yes_no));
+    }
+
+    fn ask_for_card(&self, game: &SGameGeneric<SRuleSet, (), ()>, txcard: mpsc::Sender<ECard>) {
+        let epi = unwrap!(game.which_player_can_do_something()).0;
+        let sort_cards = |mut veccard: SHandVector| {
+            game.rules.sort_cards(&mut veccard);
+            veccard
+        };
+        self.write_hand(&sort_cards(game.ahand[epi].cards().clone()));
+        let veccard_allowed = sort_cards(game.rules.all_allowed_cards(&game.stichseq, &game.ahand[epi]));
+        self.send_answer(txcard, self.ask(
+            &format!("ask card {} {}", epi, veccard_allowed.iter().join(" ")),
+            |str_answer| {
+                let card = parse_card(str_answer)?;
+                if veccard_allowed.contains(&card) {
+                    Ok(card)
+                } else {
+                    Err(format!("{card} not allowed"))
+                }
+            },
+        ));
+    }
+
+    fn ask_for_game<'rules>(
+        &self,
+        epi: EPlayerIndex,
+        hand: SFullHand,
+        _gameannouncements: &SGameAnnouncements, // already sent as events
+        vecrulegroup: &'rules [SRuleGroup],
+        _expensifiers: &SExpensifiers, // already sent as events
+        otplepiprio: Option<(EPlayerIndex, VGameAnnouncementPriority)>,
+        txorules: mpsc::Sender<Option<&'rules SActivelyPlayableRules>>
+    ) {
+        self.write_hand(hand.get());
+        let vecorules = allowed_rules(vecrulegroup, hand).collect::<Vec<_>>();
+        for (i_orules, orules) in vecorules.iter().enumerate() {
+            self.write_line(&format!("option {} {}", i_orules, orules_to_string(*orules)));
+        }
+        if let Some((epi_offer, gameannouncementprio)) = otplepiprio {
+            self.write_line(&format!("offer {} {}", epi_offer, gameannouncementpriority_to_string(&gameannouncementprio)));
+        }
+        self.send_answer(txorules, self.ask(
+            &format!("ask game {epi}"),
+            |str_answer| {
+                str_answer.parse::<usize>().ok()
+                    .and_then(|i_orules| vecorules.get(i_orules))
+                    .or_else(|| vecorules.iter().find(|orules| orules_to_string(**orules)==str_answer))
+                    .copied()
+                    .ok_or_else(|| format!("Unknown option {str_answer}"))
+            },
+        ));
+    }
+
+    fn ask_for_stoss(
+        &self,
+        epi: EPlayerIndex,
+        rules: &SRules,
+        hand: &SHand,
+        _stichseq: &SStichSequence,
+        _expensifiers: &SExpensifiers,
+        txb: mpsc::Sender<bool>,
+    ) {
+        let mut veccard = hand.cards().clone();
+        rules.sort_cards(&mut veccard);
+        self.write_hand(&veccard);
+        self.send_answer(txb, self.ask(&format!("ask stoss {epi}"), parse_yes_no));
+    }
+
+    fn name(&self) -> &str {
+        &self.str_name
+    }
+
+    fn notify(&self, gameevent: &VGameEvent) {
+        self.write_line(&match gameevent {
+            VGameEvent::Seat{epi} => format!("seat {epi}"),
+            VGameEvent::Doubling{epi, b_doubling} => format!("doubling {} {}", epi, if *b_doubling {"yes"} else {"no"}),
+            VGameEvent::Announcement{epi, orules} => format!("announcement {} {}", epi, orules_to_string(*orules)),
+            VGameEvent::Rules{rules} => format!("rules {}", SDisplayRules::new(*rules, /*b_include_playerindex*/true)),
+            VGameEvent::Stoss{epi} => format!("stoss {epi}"),
+            VGameEvent::Card{epi, card} => format!("card {epi} {card}"),
+            VGameEvent::GameResult{an_payout} => format!("result {}", an_payout.iter().join(" ")),
+            VGameEvent::Balance{an_money, n_stock} => format!("balance {} {}", an_money.iter().join(" "), n_stock),
+        });
+    }
+}
+
+#[test]
+fn test_playertext_repeats_question_on_invalid_answer() {
+    let playertext = SPlayerText::new("You".to_string(), "maybe\nyes\n".as_bytes(), Vec::new());
+    assert!(crate::game::run::communicate_via_channel(|txb_doubling| {
+        playertext.ask_for_doubling(&[ECard::new(EFarbe::Eichel, ESchlag::Ober)], txb_doubling);
+    }));
+    assert_eq!(
+        unwrap!(String::from_utf8(playertext.output.into_inner())),
+        "hand EO\nask doubling\nerror Expected yes or no, got maybe\nask doubling\n",
+    );
+}
+
+#[test]
+fn test_playertext_gives_up_on_closed_input() {
+    let playertext = SPlayerText::new("You".to_string(), "maybe\n".as_bytes(), Vec::new());
+    assert!(crate::game::run::try_communicate_via_channel(|txb_doubling| {
+        playertext.ask_for_doubling(&[ECard::new(EFarbe::Eichel, ESchlag::Ober)], txb_doubling);
+    }).is_err());
+    assert_eq!(
+        unwrap!(String::from_utf8(playertext.output.into_inner())),
+        "hand EO\nask doubling\nerror Expected yes or no, got maybe\nask doubling\nerror Input closed while waiting for answer\n",
+    );
+}
+
+#[test]
+fn test_playertext_events() {
+    let playertext = SPlayerText::new("You".to_string(), "".as_bytes(), Vec::new());
+    playertext.notify(&VGameEvent::Seat{epi: EPlayerIndex::EPI1});
+    playertext.notify(&VGameEvent::Balance{an_money: &EPlayerIndex::map_from_raw([10, -20, 30, -40]), n_stock: 20});
+    assert_eq!(
+        unwrap!(String::from_utf8(playertext.output.into_inner())),
+        "seat 1\nbalance 10 -20 30 -40 20\n",
+    );
+    assert_eq!(
+        gameannouncementpriority_to_string(&VGameAnnouncementPriority::SoloLike(VGameAnnouncementPrioritySoloLike::SoloSteigern{n_points_to_win: 76, n_step: 10})),
+        "solo-steigern 76 10",
+    );
+}
@@ -2,7 +2,7 @@ use crate::util::*;
 use std::{
     fmt,
 };
-use serde::{Serializer};
+use serde::{Serializer, Serialize, Deserialize};
 
 plain_enum_mod!(modefarbe, EFarbe {
     Eichel,
@@ -22,6 +22,20 @@ impl fmt::Display for EFarbe {
     }
 }
 
+impl serde::Serialize for EFarbe {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+    {
+        serializer.serialize_str(match self { // understood by farbe_parser
+            EFarbe::Eichel => "E",
+            EFarbe::Gras => "G",
+            EFarbe::Herz => "H",
+            EFarbe::Schelln => "S",
+        })
+    }
+}
+
 impl<'de> serde::Deserialize<'de> for EFarbe {
     fn deserialize<D>(deserializer: D) -> Result<EFarbe, D::Error>
         where
@@ -51,7 +65,7 @@ impl fmt::Display for ESchlag {
     }
 }
 
-plain_enum_mod!(modekurzlang, EKurzLang {
+plain_enum_mod!(modekurzlang, derive(Serialize, Deserialize,), map_derive(), EKurzLang {
     Kurz,
     Lang,
 });
@@ -129,6 +143,16 @@ fn test_serialization() {
         H7 H8 H9 HZ HU HO HK HA
         S7 S8 S9 SZ SU SO SK SA
     );
+    for (efarbe, str_farbe) in [
+        (EFarbe::Eichel, "E"),
+        (EFarbe::Gras, "G"),
+        (EFarbe::Herz, "H"),
+        (EFarbe::Schelln, "S"),
+    ] {
+        serde_test::assert_tokens(&efarbe, &[
+            serde_test::Token::Str(str_farbe),
+        ]);
+    }
 }
 
 impl fmt::Debug for ECard {
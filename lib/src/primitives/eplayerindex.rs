@@ -1,9 +1,9 @@
 use crate::util::*;
 use arrayvec::{self, ArrayVec};
 use std::{fmt, slice, str::FromStr};
-use serde_repr::Serialize_repr;
+use serde_repr::{Serialize_repr, Deserialize_repr};
 
-plain_enum_mod!(modepi, derive(Serialize_repr, Hash,), map_derive(), EPlayerIndex {
+plain_enum_mod!(modepi, derive(Serialize_repr, Deserialize_repr, Hash,), map_derive(), EPlayerIndex {
     EPI0, EPI1, EPI2, EPI3,
 });
 define_static_value!(pub SStaticEPI0, EPlayerIndex, EPlayerIndex::EPI0);
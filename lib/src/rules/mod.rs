@@ -22,6 +22,7 @@ use crate::ai::gametree::{TSnapshotCache, TTplStrategies, SPerMinMaxStrategyRawP
 use crate::primitives::*;
 use crate::rules::card_points::points_stich;
 use trumpfdecider::{STrumpfDecider, SLaufendeCount};
+use payoutdecider::ESchneiderSchwarz;
 use crate::util::*;
 use std::{
     borrow::Borrow,
@@ -89,6 +90,20 @@ impl SStossParams {
         stichseq.no_card_played() // TODORULES Adjustable latest time of stoss
             && slcstoss.len() < self.n_stoss_max
     }
+    fn set_prices(&mut self, prices: &SPrices) {
+        if let Some(n_stoss_max) = prices.on_stoss_max {
+            self.n_stoss_max = n_stoss_max;
+        }
+    }
+}
+
+// Prices and limits not covered by the rules' description, None if not applicable to the rules
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SPrices {
+    pub on_price: Option<isize>,
+    pub on_extra: Option<isize>, // Schneider, Schwarz and each Laufender
+    pub on_lauf_min: Option<usize>,
+    pub on_stoss_max: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
@@ -389,6 +404,9 @@ pub trait TRules : Sync + fmt::Debug + Send + Clone {
         self.trumpfdecider().compare_cards(card_fst, card_snd)
     }
     fn count_laufende(&self, ekurzlang: EKurzLang, fn_who_has_card: impl Fn(ECard)->EPlayerIndex) -> Option<SLaufendeCount>;
+    fn schneiderschwarz(&self, _stichseq: SStichSequenceGameFinished, _rulestatecache: &SRuleStateCache) -> Option<ESchneiderSchwarz> {
+        None // only relevant for rules paying Schneider/Schwarz
+    }
 
 
     fn can_be_played(&self, _hand: SFullHand) -> bool {
@@ -397,6 +415,9 @@ pub trait TRules : Sync + fmt::Debug + Send + Clone {
 
     fn stoss_allowed(&self, stichseq: &SStichSequence, hand: &SHand, epi: EPlayerIndex, vecstoss: &[SStoss]) -> bool;
 
+    fn prices(&self) -> SPrices;
+    fn set_prices(&mut self, prices: &SPrices); // ignores prices not applicable to the rules
+
     fn payout(&self, stichseq: SStichSequenceGameFinished, expensifiers: &SExpensifiers, rulestatecache: &SRuleStateCache, if_dbg_else!({b_test_points_as_payout}{_}): dbg_parameter!(bool)) -> EnumMap<EPlayerIndex, isize> {
         let an_payout = self.payout_no_invariant(
             stichseq,
@@ -693,6 +714,17 @@ pub enum SActivelyPlayableRules {
     BettelStichzwang(SRulesBettel<SBettelAllAllowedCardsWithinStichStichzwang>),
 }
 
+impl SRules {
+    // The card whose owner is the active player's partner, if any.
+    pub fn rufsau(&self) -> Option<ECard> {
+        match self {
+            Self::ActivelyPlayable(SActivelyPlayableRules::Rufspiel(rules)) => Some(rules.rufsau()),
+            Self::ActivelyPlayable(SActivelyPlayableRules::RufspielPointsAsPayout(rules)) => Some(rules.rufsau()),
+            Self::ActivelyPlayable(_) | Self::Ramsch(_) => None,
+        }
+    }
+}
+
 impl TRulesPlayerIndex for SActivelyPlayableRules {
     type PlayerIndex = EPlayerIndex;
     fn playerindex(&self) -> Self::PlayerIndex {
@@ -828,7 +860,7 @@ fn test_snapshotcache() {
             determine_best_card,
         },
     };
-    crate::game::run::internal_run_simple_game_loop( // TODO simplify all this, and explicitly iterate over supported rules
+    unwrap!(crate::game::run::internal_run_simple_game_loop( // TODO simplify all this, and explicitly iterate over supported rules
         EPlayerIndex::map_from_fn(|_epi| Box::new(SPlayerRandom::new(
             /*fn_check_ask_for_card*/|game_in: &SGameGeneric<SRuleSet, (), ()>| {
                 let internal_test = |game: &SGameGeneric<SRuleSet, (), ()>| {
@@ -907,6 +939,7 @@ fn test_snapshotcache() {
                         )
                     })
                 })
+                .map(Ok)
                 .collect::<Vec<_>>().into_iter(); // TODO how can we avoid this?
             if_dbg_else!(
                 {{
@@ -917,5 +950,6 @@ fn test_snapshotcache() {
             )
         },
         /*fn_print_account_balance*/|_,_| {/* no output */},
-    );
+        /*fn_after_game*/|_,_,_| None, // no replays
+    ));
 }
@@ -69,7 +69,13 @@ pub fn parse_rule_description(
                 stossparams.clone(),
             ).into())
         }}
-        if str_rules_contains(&["tout"]) {
+        if str_rules_contains(&["sie"]) {
+            if oefarbe.is_some() {
+                Err(format_err!("Sie incompatible with efarbe"))
+            } else {
+                make_sololike_internal!(SPayoutDeciderSie)
+            }
+        } else if str_rules_contains(&["tout"]) {
             make_sololike_internal!(SPayoutDeciderTout)
         } else {
             make_sololike_internal!(SPayoutDeciderPointBased)
@@ -101,7 +107,7 @@ pub fn parse_rule_description(
                 }
             }
         }),
-        (&["solo", "sticht"], make_sololike(ESoloLike::Solo)),
+        (&["solo", "sticht", "sie"], make_sololike(ESoloLike::Solo)),
         (&["wenz"], make_sololike(ESoloLike::Wenz)),
         (&["geier"], make_sololike(ESoloLike::Geier)),
         (&["bettel normal"], {
@@ -147,6 +153,20 @@ pub fn parse_rule_description(
     }
 }
 
+// Describes rules such that parse_rule_description (with player indices as numbers) understands them.
+pub fn rule_description(rules: &SRules) -> String {
+    // SDisplayRules does not distinguish Bettel variants, but parse_rule_description needs to.
+    let ostr_bettel = match rules {
+        SRules::ActivelyPlayable(SActivelyPlayableRules::BettelNormal(_)) => Some("Bettel normal"),
+        SRules::ActivelyPlayable(SActivelyPlayableRules::BettelStichzwang(_)) => Some("Bettel stich"),
+        _ => None,
+    };
+    match (ostr_bettel, rules.playerindex()) {
+        (Some(str_bettel), Some(epi)) => format!("{str_bettel} von {epi}"),
+        _ => SDisplayRules::new(rules, /*b_include_playerindex*/true).to_string(),
+    }
+}
+
 pub fn parse_rule_description_simple(str_rules: &str) -> Result<SRules, Error> {
     parse_rule_description(
         str_rules,
@@ -158,3 +178,18 @@ pub fn parse_rule_description_simple(str_rules: &str) -> Result<SRules, Error> {
             .ok_or_else(|| format_err!("Cannot convert {} to EPlayerIndex.", str_epi)),
     )
 }
+
+#[test]
+fn test_parse_rule_description_sie() {
+    let rules = unwrap!(parse_rule_description_simple("Sie von 2"));
+    assert!(matches!(rules, SRules::ActivelyPlayable(SActivelyPlayableRules::SoloLikeSie(_))));
+    assert_eq!(rules.playerindex(), Some(EPlayerIndex::EPI2));
+    let str_rules = rule_description(&rules);
+    assert_eq!(str_rules, "Solo-Sie von 2");
+    assert!(matches!(
+        unwrap!(parse_rule_description_simple(&str_rules)),
+        SRules::ActivelyPlayable(SActivelyPlayableRules::SoloLikeSie(_)),
+    ));
+    assert!(parse_rule_description_simple("Herz-Sie von 2").is_err());
+    assert!(parse_rule_description_simple("Sie").is_err());
+}
@@ -2,6 +2,7 @@ use crate::primitives::*;
 use crate::rules::{trumpfdecider::STrumpfDecider, *};
 use crate::util::*;
 use crate::ai::gametree::{TTplStrategies, SPerMinMaxStrategyGeneric};
+use serde::Serialize;
 
 #[derive(Clone, new, Debug)]
 pub struct SLaufendeParams {
@@ -16,6 +17,29 @@ pub struct SPayoutDeciderParams {
     pub laufendeparams : SLaufendeParams,
 }
 
+impl SPayoutDeciderParams {
+    pub fn prices(&self) -> SPrices {
+        SPrices {
+            on_price: Some(self.n_payout_base),
+            on_extra: Some(self.n_payout_schneider_schwarz),
+            on_lauf_min: Some(self.laufendeparams.n_lauf_lbound),
+            on_stoss_max: None,
+        }
+    }
+    pub fn set_prices(&mut self, prices: &SPrices) {
+        if let Some(n_price) = prices.on_price {
+            self.n_payout_base = n_price;
+        }
+        if let Some(n_extra) = prices.on_extra {
+            self.n_payout_schneider_schwarz = n_extra;
+            self.laufendeparams.n_payout_per_lauf = n_extra;
+        }
+        if let Some(n_lauf_min) = prices.on_lauf_min {
+            self.laufendeparams.n_lauf_lbound = n_lauf_min;
+        }
+    }
+}
+
 
 pub trait TPointsToWin : Sync + Send + 'static + Clone + fmt::Debug {
     fn points_to_win(&self) -> isize;
@@ -36,6 +60,44 @@ pub struct SPayoutDeciderPointBased<PointsToWin> {
     pub pointstowin: PointsToWin,
 }
 
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ESchneiderSchwarz {
+    Nichts,
+    Schneider,
+    Schwarz,
+}
+
+impl<PointsToWin: TPointsToWin> SPayoutDeciderPointBased<PointsToWin> {
+    pub fn schneiderschwarz(
+        &self,
+        if_dbg_else!({rules}{_}): dbg_parameter!(&impl TRules),
+        rulestatecache: &SRuleStateCache,
+        stichseq: SStichSequenceGameFinished,
+        playerparties: &impl TPlayerParties,
+    ) -> ESchneiderSchwarz {
+        let SPointStichCount {
+            n_point: n_points_primary_party,
+            n_stich: n_stichs_primary_party,
+        } = pointstichcount_for_party(/*b_primary*/true, &rulestatecache.changing, playerparties);
+        let b_primary_party_wins = n_points_primary_party >= self.pointstowin.points_to_win();
+        if debug_verify_eq!(
+            if b_primary_party_wins {
+                n_stichs_primary_party
+            } else {
+                stichseq.get().kurzlang().cards_per_player()-n_stichs_primary_party
+            }==stichseq.get().kurzlang().cards_per_player(),
+            stichseq.get().completed_stichs_winner_index(rules)
+                .all(|(_stich, epi_winner)| b_primary_party_wins==playerparties.is_primary_party(epi_winner))
+        ) {
+            ESchneiderSchwarz::Schwarz
+        } else if (b_primary_party_wins && n_points_primary_party>90) || (!b_primary_party_wins && n_points_primary_party<=30) {
+            ESchneiderSchwarz::Schneider
+        } else {
+            ESchneiderSchwarz::Nichts
+        }
+    }
+}
+
 // TODO this should probably be a method on the pointbased TPayoutDecider impementations
 pub fn equivalent_when_on_same_hand_point_based(slccard_ordered: &[ECard]) -> Vec<Vec<ECard>> {
     slccard_ordered.iter()
@@ -114,29 +176,14 @@ impl<
         stichseq: SStichSequenceGameFinished,
         playerparties: &impl TPlayerParties,
     ) -> EnumMap<EPlayerIndex, isize> {
-        let SPointStichCount {
-            n_point: n_points_primary_party,
-            n_stich: n_stichs_primary_party,
-        } = pointstichcount_for_party(/*b_primary*/true, &rulestatecache.changing, playerparties);
-        let b_primary_party_wins = n_points_primary_party >= self.pointstowin.points_to_win();
+        let b_primary_party_wins = pointstichcount_for_party(/*b_primary*/true, &rulestatecache.changing, playerparties).n_point
+            >= self.pointstowin.points_to_win();
         internal_payout(
             (self.payoutparams.n_payout_base
-            + { 
-                if debug_verify_eq!(
-                    if b_primary_party_wins {
-                        n_stichs_primary_party
-                    } else {
-                        stichseq.get().kurzlang().cards_per_player()-n_stichs_primary_party
-                    }==stichseq.get().kurzlang().cards_per_player(),
-                    stichseq.get().completed_stichs_winner_index(rules)
-                        .all(|(_stich, epi_winner)| b_primary_party_wins==playerparties.is_primary_party(epi_winner))
-                ) {
-                    2*self.payoutparams.n_payout_schneider_schwarz // schwarz
-                } else if (b_primary_party_wins && n_points_primary_party>90) || (!b_primary_party_wins && n_points_primary_party<=30) {
-                    self.payoutparams.n_payout_schneider_schwarz // schneider
-                } else {
-                    0 // "nothing", i.e. neither schneider nor schwarz
-                }
+            + match self.schneiderschwarz(dbg_argument!(rules), rulestatecache, stichseq, playerparties) {
+                ESchneiderSchwarz::Schwarz => 2*self.payoutparams.n_payout_schneider_schwarz,
+                ESchneiderSchwarz::Schneider => self.payoutparams.n_payout_schneider_schwarz,
+                ESchneiderSchwarz::Nichts => 0,
             }
             + self.payoutparams.laufendeparams.payout_laufende(trumpfdecider, &rulestatecache.fixed, stichseq.get().kurzlang(), playerparties)).neg_if(!b_primary_party_wins),
             playerparties,
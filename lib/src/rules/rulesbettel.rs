@@ -121,6 +121,22 @@ impl<BettelAllAllowedCardsWithinStich: TBettelAllAllowedCardsWithinStich> TRules
 
     impl_single_play!();
 
+    fn prices(&self) -> SPrices {
+        SPrices {
+            on_price: Some(self.n_payout_base),
+            on_extra: None,
+            on_lauf_min: None,
+            on_stoss_max: Some(self.stossparams.n_stoss_max),
+        }
+    }
+
+    fn set_prices(&mut self, prices: &SPrices) {
+        if let Some(n_price) = prices.on_price {
+            self.n_payout_base = n_price;
+        }
+        self.stossparams.set_prices(prices);
+    }
+
     fn payout_no_invariant(&self, if_dbg_else!({stichseq}{_stichseq}): SStichSequenceGameFinished, expensifiers: &SExpensifiers, rulestatecache: &SRuleStateCache) -> EnumMap<EPlayerIndex, isize> {
         let playerparties13 = SPlayerParties13::new(self.epi);
         internal_payout(
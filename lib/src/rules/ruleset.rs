@@ -342,5 +342,19 @@ impl SRuleSet {
         // TODO? ruleset creation wizard
         Self::from_string(&via_out_param_result(|str_toml| File::open(path)?.read_to_string(str_toml))?.0)
     }
+
+    // The rules of this ruleset that are described like rules (e.g. rules from parse_rule_description), carrying this ruleset's prices.
+    pub fn rules_like(&self, rules: &SRules) -> Option<SRules> {
+        match rules {
+            SRules::Ramsch(_) => if_then_some!(let VStockOrT::OrT(rulesramsch)=&self.stockorramsch, rulesramsch.clone().into()),
+            SRules::ActivelyPlayable(_) => {
+                let str_rules = crate::rules::parser::rule_description(rules);
+                self.avecrulegroup[rules.playerindex()?].iter()
+                    .flat_map(|rulegroup| rulegroup.vecorules.iter().flatten())
+                    .map(|rules_ruleset| SRules::from(rules_ruleset.clone()))
+                    .find(|rules_ruleset| crate::rules::parser::rule_description(rules_ruleset)==str_rules)
+            },
+        }
+    }
 }
 
@@ -63,6 +63,21 @@ impl TRules for SRulesRamsch {
         false
     }
 
+    fn prices(&self) -> SPrices {
+        SPrices {
+            on_price: Some(self.n_price),
+            on_extra: None,
+            on_lauf_min: None,
+            on_stoss_max: None,
+        }
+    }
+
+    fn set_prices(&mut self, prices: &SPrices) {
+        if let Some(n_price) = prices.on_price {
+            self.n_price = n_price;
+        }
+    }
+
     fn payout_no_invariant(&self, stichseq: SStichSequenceGameFinished, expensifiers: &SExpensifiers, rulestatecache: &SRuleStateCache) -> EnumMap<EPlayerIndex, isize> {
         let mapepipointstichcount = &rulestatecache.changing.mapepipointstichcount;
         let points_for_player = |epi| mapepipointstichcount[epi].n_point;
@@ -24,6 +24,20 @@ pub trait TRufspielPayout: Clone + Sync + fmt::Debug + Send + 'static {
         rules: &SRulesRufspielGeneric<Self>,
         rulestatecachefixed: &SRuleStateCacheFixed,
     ) -> Box<dyn TSnapshotCache<SPerMinMaxStrategyRawPayout<TplStrategies>>>;
+    fn schneiderschwarz(
+        &self,
+        _rules: &SRulesRufspielGeneric<Self>,
+        _stichseq: SStichSequenceGameFinished,
+        _rulestatecache: &SRuleStateCache,
+    ) -> Option<ESchneiderSchwarz> {
+        None
+    }
+    fn payoutparams_mut(&mut self) -> Option<&mut SPayoutDeciderParams> {
+        None
+    }
+    fn payoutparams(&self) -> Option<&SPayoutDeciderParams> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -32,6 +46,12 @@ pub struct SRufspielPayout {
 }
 
 impl TRufspielPayout for SRufspielPayout {
+    fn payoutparams_mut(&mut self) -> Option<&mut SPayoutDeciderParams> {
+        Some(&mut self.payoutdecider.payoutparams)
+    }
+    fn payoutparams(&self) -> Option<&SPayoutDeciderParams> {
+        Some(&self.payoutdecider.payoutparams)
+    }
     fn payout(&self, rules: &SRulesRufspielGeneric<Self>, stichseq: SStichSequenceGameFinished, expensifiers: &SExpensifiers, rulestatecache: &SRuleStateCache) -> EnumMap<EPlayerIndex, isize> {
         let playerparties = rules.playerparties(&rulestatecache.fixed);
         let an_payout_no_stock = self.payoutdecider.payout(
@@ -78,6 +98,14 @@ impl TRufspielPayout for SRufspielPayout {
     fn snapshot_cache<TplStrategies: TTplStrategies>(&self, rules: &SRulesRufspielGeneric<Self>, rulestatecachefixed: &SRuleStateCacheFixed) -> Box<dyn TSnapshotCache<SPerMinMaxStrategyRawPayout<TplStrategies>>> {
         super::snapshot_cache_point_based::<TplStrategies,_>(rules.playerparties(rulestatecachefixed))
     }
+    fn schneiderschwarz(&self, rules: &SRulesRufspielGeneric<Self>, stichseq: SStichSequenceGameFinished, rulestatecache: &SRuleStateCache) -> Option<ESchneiderSchwarz> {
+        Some(self.payoutdecider.schneiderschwarz(
+            dbg_argument!(rules),
+            rulestatecache,
+            stichseq,
+            &rules.playerparties(&rulestatecache.fixed),
+        ))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -179,6 +207,10 @@ impl<RufspielPayout: TRufspielPayout> TRules for SRulesRufspielGeneric<RufspielP
         ))
     }
 
+    fn schneiderschwarz(&self, stichseq: SStichSequenceGameFinished, rulestatecache: &SRuleStateCache) -> Option<ESchneiderSchwarz> {
+        self.rufspielpayout.schneiderschwarz(self, stichseq, rulestatecache)
+    }
+
     fn can_be_played(&self, hand: SFullHand) -> bool {
         let it = || {hand.get().iter().filter(|&card| self.is_ruffarbe(*card))};
         it().all(|card| card.schlag()!=ESchlag::Ass)
@@ -194,6 +226,20 @@ impl<RufspielPayout: TRufspielPayout> TRules for SRulesRufspielGeneric<RufspielP
         }
     }
 
+    fn prices(&self) -> SPrices {
+        SPrices {
+            on_stoss_max: Some(self.stossparams.n_stoss_max),
+            ..self.rufspielpayout.payoutparams().map(SPayoutDeciderParams::prices).unwrap_or_default()
+        }
+    }
+
+    fn set_prices(&mut self, prices: &SPrices) {
+        if let Some(payoutparams) = self.rufspielpayout.payoutparams_mut() {
+            payoutparams.set_prices(prices);
+        }
+        self.stossparams.set_prices(prices);
+    }
+
     fn payout_no_invariant(&self, stichseq: SStichSequenceGameFinished, expensifiers: &SExpensifiers, rulestatecache: &SRuleStateCache) -> EnumMap<EPlayerIndex, isize> {
         self.rufspielpayout.payout(
             self,
@@ -23,6 +23,17 @@ pub trait TPayoutDeciderSoloLike : Sync + 'static + Clone + fmt::Debug + Send {
     }
 
     fn snapshot_cache<TplStrategies: TTplStrategies>(&self, rules: &SRulesSoloLike<Self>) -> Box<dyn TSnapshotCache<SPerMinMaxStrategyRawPayout<TplStrategies>>>;
+
+    fn schneiderschwarz(&self, _rules: &SRulesSoloLike<Self>, _rulestatecache: &SRuleStateCache, _stichseq: SStichSequenceGameFinished) -> Option<ESchneiderSchwarz> {
+        None
+    }
+
+    fn payoutparams_mut(&mut self) -> Option<&mut SPayoutDeciderParams> {
+        None
+    }
+    fn payoutparams(&self) -> Option<&SPayoutDeciderParams> {
+        None
+    }
 }
 
 pub trait TPayoutDeciderSoloLikeDefault : TPayoutDeciderSoloLike {
@@ -44,6 +55,13 @@ impl TPayoutDeciderSoloLikeDefault for SPayoutDeciderTout {
         )
     }
 }
+impl TPayoutDeciderSoloLikeDefault for SPayoutDeciderSie {
+    fn default_payoutdecider(n_payout_base: isize, n_payout_schneider_schwarz: isize, laufendeparams: SLaufendeParams) -> Self {
+        Self::new(
+            SPayoutDeciderParams::new(n_payout_base, n_payout_schneider_schwarz, laufendeparams),
+        )
+    }
+}
 
 
 impl TPointsToWin for VGameAnnouncementPrioritySoloLike {
@@ -56,6 +74,13 @@ impl TPointsToWin for VGameAnnouncementPrioritySoloLike {
 }
 
 impl TPayoutDeciderSoloLike for SPayoutDeciderPointBased<VGameAnnouncementPrioritySoloLike> {
+    fn payoutparams_mut(&mut self) -> Option<&mut SPayoutDeciderParams> {
+        Some(&mut self.payoutparams)
+    }
+    fn payoutparams(&self) -> Option<&SPayoutDeciderParams> {
+        Some(&self.payoutparams)
+    }
+
     fn priority(&self) -> VGameAnnouncementPriority {
         VGameAnnouncementPriority::SoloLike(self.pointstowin.clone())
     }
@@ -111,6 +136,15 @@ impl TPayoutDeciderSoloLike for SPayoutDeciderPointBased<VGameAnnouncementPriori
         ))
     }
 
+    fn schneiderschwarz(&self, rules: &SRulesSoloLike<Self>, rulestatecache: &SRuleStateCache, stichseq: SStichSequenceGameFinished) -> Option<ESchneiderSchwarz> {
+        Some(SPayoutDeciderPointBased::schneiderschwarz(self,
+            dbg_argument!(rules),
+            rulestatecache,
+            stichseq,
+            &SPlayerParties13::new(rules.epi),
+        ))
+    }
+
     fn equivalent_when_on_same_hand(slccard_ordered: &[ECard]) -> Vec<Vec<ECard>> {
         equivalent_when_on_same_hand_point_based(slccard_ordered)
     }
@@ -233,6 +267,13 @@ pub struct SPayoutDeciderTout {
 }
 
 impl TPayoutDeciderSoloLike for SPayoutDeciderTout {
+    fn payoutparams_mut(&mut self) -> Option<&mut SPayoutDeciderParams> {
+        Some(&mut self.payoutparams)
+    }
+    fn payoutparams(&self) -> Option<&SPayoutDeciderParams> {
+        Some(&self.payoutparams)
+    }
+
     fn priority(&self) -> VGameAnnouncementPriority {
         VGameAnnouncementPriority::SoloTout(self.i_prio)
     }
@@ -299,6 +340,13 @@ fn cards_valid_for_sie<ItCard: Clone+Iterator<Item=ECard>>(
 }
 
 impl TPayoutDeciderSoloLike for SPayoutDeciderSie {
+    fn payoutparams_mut(&mut self) -> Option<&mut SPayoutDeciderParams> {
+        Some(&mut self.payoutparams)
+    }
+    fn payoutparams(&self) -> Option<&SPayoutDeciderParams> {
+        Some(&self.payoutparams)
+    }
+
     fn priority(&self) -> VGameAnnouncementPriority {
         VGameAnnouncementPriority::SoloSie
     }
@@ -400,6 +448,20 @@ impl<PayoutDecider: TPayoutDeciderSoloLike> TRules for SRulesSoloLike<PayoutDeci
     }
     impl_single_play!();
 
+    fn prices(&self) -> SPrices {
+        SPrices {
+            on_stoss_max: Some(self.stossparams.n_stoss_max),
+            ..self.payoutdecider.payoutparams().map(SPayoutDeciderParams::prices).unwrap_or_default()
+        }
+    }
+
+    fn set_prices(&mut self, prices: &SPrices) {
+        if let Some(payoutparams) = self.payoutdecider.payoutparams_mut() {
+            payoutparams.set_prices(prices);
+        }
+        self.stossparams.set_prices(prices);
+    }
+
     fn payout_no_invariant(&self, stichseq: SStichSequenceGameFinished, expensifiers: &SExpensifiers, rulestatecache: &SRuleStateCache) -> EnumMap<EPlayerIndex, isize> {
         self.payoutdecider.payout(
             self,
@@ -409,6 +471,10 @@ impl<PayoutDecider: TPayoutDeciderSoloLike> TRules for SRulesSoloLike<PayoutDeci
         )
     }
 
+    fn schneiderschwarz(&self, stichseq: SStichSequenceGameFinished, rulestatecache: &SRuleStateCache) -> Option<ESchneiderSchwarz> {
+        self.payoutdecider.schneiderschwarz(self, rulestatecache, stichseq)
+    }
+
     fn payouthints(&self, tplahandstichseq: (&EnumMap<EPlayerIndex, SHand>, &SStichSequence), expensifiers: &SExpensifiers, rulestatecache: &SRuleStateCache) -> EnumMap<EPlayerIndex, SInterval<Option<isize>>> {
         self.payoutdecider.payouthints(
             self,
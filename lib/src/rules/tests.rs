@@ -2047,6 +2047,48 @@ fn test_rulesbettel() {
     );
 }
 
+#[test]
+fn test_rulessie() {
+    use EPlayerIndex::*;
+    let rulessie = sololike(EPlayerIndex::EPI0, /*oefarbe*/None, ESoloLike::Solo, SPayoutDeciderSie::default_payoutdecider(/*n_payout_base*/50, /*n_payout_schneider_schwarz*/10, SLaufendeParams::new(10, 2)), SStossParams::new(/*n_stoss_max*/4));
+    test_rules_manual(
+        "0 wins Sie",
+        rulessie.clone(),
+        vec![],
+        vec![],
+        /*n_stock*/0,
+        &[
+            (EPI0, [EO,EA,GA,SA]),
+            (EPI0, [GO,EZ,GZ,SZ]),
+            (EPI0, [HO,EK,GK,SK]),
+            (EPI0, [SO,E9,G9,S9]),
+            (EPI0, [EU,E8,G8,S8]),
+            (EPI0, [GU,E7,G7,S7]),
+            (EPI0, [HU,HA,HK,H8]),
+            (EPI0, [SU,HZ,H9,H7]),
+        ],
+        ([1560, -520, -520, -520], 0),
+    );
+    test_rules_manual(
+        "0 looses Sie despite winning all stichs",
+        rulessie,
+        vec![],
+        vec![],
+        /*n_stock*/0,
+        &[
+            (EPI0, [EO,SU,GA,SA]),
+            (EPI0, [GO,EA,GZ,SZ]),
+            (EPI0, [HO,EZ,GK,SK]),
+            (EPI0, [SO,EK,G9,S9]),
+            (EPI0, [EU,E9,G8,S8]),
+            (EPI0, [GU,E8,G7,S7]),
+            (EPI0, [HU,E7,HK,H8]),
+            (EPI0, [HA,HZ,H9,H7]),
+        ],
+        ([-1560, 520, 520, 520], 0),
+    );
+}
+
 #[test]
 fn test_laufende() {
     let rulesrufspiel = rulesrufspiel_new_test(
@@ -2143,3 +2185,39 @@ fn test_stock() {
         );
     }
 }
+
+#[test]
+fn test_schneiderschwarz() {
+    use EPlayerIndex::*;
+    let schneiderschwarz = |rules: &SRules, slctplepiacard_stich: &[(EPlayerIndex, [ECard; EPlayerIndex::SIZE])]| {
+        let stichseq = unwrap!(SStichSequence::new_from_cards(
+            EKurzLang::Lang,
+            slctplepiacard_stich.iter().flat_map(|(_epi, acard)| acard.iter().copied()),
+            rules,
+        ).ok());
+        let stichseq = SStichSequenceGameFinished::new(&stichseq);
+        rules.schneiderschwarz(
+            stichseq,
+            &SRuleStateCache::new_from_gamefinishedstiche(stichseq, dbg_argument!(rules)),
+        )
+    };
+    // EPI0 wins with exactly 90 points, so the opponents' 30 points are not Schneider.
+    let rules_wenz = SRules::from(sololike(EPI0, None, ESoloLike::Wenz, SPayoutDeciderPointBased::default_payoutdecider(/*n_payout_base*/50, /*n_payout_schneider_schwarz*/10, SLaufendeParams::new(10, 2)), SStossParams::new(/*n_stoss_max*/4)));
+    let slctplepiacard_stich_wenz = [(EPI0, [EU,H7,E7,G7]),(EPI0, [GU,H8,E8,G8]),(EPI0, [HU,H9,E9,G9]),(EPI0, [SU,HO,EO,GO]),(EPI0, [SA,SO,S8,S7]),(EPI0, [SZ,S9,EZ,GZ]),(EPI0, [SK,HZ,EK,GA]),(EPI0, [HK,HA,EA,GK]),];
+    test_rules_manual(
+        "0 wins Wenz with 90 points",
+        rules_wenz.clone(),
+        vec![],
+        vec![],
+        /*n_stock*/0,
+        &slctplepiacard_stich_wenz,
+        ([270, -90, -90, -90], 0),
+    );
+    assert_eq!(schneiderschwarz(&rules_wenz, &slctplepiacard_stich_wenz), Some(ESchneiderSchwarz::Nichts));
+    // The opponents take only the last stich, so EPI0 does not win Schwarz, but Schneider.
+    let slctplepiacard_stich_wenz_schneider = [(EPI0, [EU,H7,EA,GA]),(EPI0, [GU,H8,E8,G8]),(EPI0, [HU,H9,E9,G9]),(EPI0, [SU,HO,EO,GO]),(EPI0, [SA,SO,S8,S7]),(EPI0, [SZ,S9,EZ,GZ]),(EPI0, [SK,HZ,EK,GK]),(EPI0, [HK,HA,E7,G7]),];
+    assert_eq!(schneiderschwarz(&rules_wenz, &slctplepiacard_stich_wenz_schneider), Some(ESchneiderSchwarz::Schneider));
+    // Bettel does not know Schneider/Schwarz.
+    let rules_bettel = SRules::from(SActivelyPlayableRules::from(SRulesBettel::<SBettelAllAllowedCardsWithinStichNormal>::new(EPI0, /*i_prio*/0, /*n_payout_base*/10, SStossParams::new(/*n_stoss_max*/4))));
+    assert_eq!(schneiderschwarz(&rules_bettel, &slctplepiacard_stich_wenz), None);
+}
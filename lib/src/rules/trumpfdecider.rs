@@ -110,6 +110,7 @@ impl STrumpfDecider {
     }
 }
 
+#[derive(Debug)]
 pub struct SLaufendeCount {
     pub n_laufende: usize,
     pub b_primary_party: bool,
@@ -42,13 +42,54 @@ class SDisplayedStichs {
     stichcurrent: SDisplayedStichCurrent;
     ostichprev: null | SDisplayedStichPrev;
 }
+class SDisplayedGameReveal {
+    readonly mapepivecstr_card: Array<Array<string>>;
+    readonly mapepin_point: Array<number>;
+    readonly mapepin_stich: Array<number>;
+    readonly omapepib_primary: null | Array<boolean>;
+    readonly oeschneiderschwarz: null | string;
+    readonly otplnb_laufende: null | [number, boolean];
+    readonly n_stoss: number;
+    readonly n_doubling: number;
+    readonly n_stoss_doubling_factor: number;
+    readonly mapepin_payout: Array<number>;
+}
 class SSiteState {
-    readonly vectplstrstr_caption_message_zugeben: Array<[string, string]>;
+    readonly vectplstrstr_caption_message_zugeben: Array<[string, null | string]>; // null for cards that must not be clicked
     readonly msg: string | Ask_;
     readonly odisplayedstichs: null | SDisplayedStichs;
     readonly mapepistr: Array<string>;
     readonly otplepistr_rules: null | [EPlayerIndex, string]
     readonly oepi_timeout: null | EPlayerIndex;
+    readonly omapepivecstr_card: null | Array<Array<string>>; // all hands, only for trainer view
+    readonly ogamereveal: null | SDisplayedGameReveal;
+}
+
+class STableInfo {
+    readonly str_table_name: string;
+    readonly str_ruleset: string;
+    readonly obotstrength: null | string;
+    readonly vecstr_player: Array<string>;
+    readonly n_players_inactive: number;
+    readonly n_spectators: number;
+    readonly n_stock: number;
+}
+class SLobbyState {
+    readonly vecstr_ruleset: Array<string>;
+    readonly vectableinfo: Array<STableInfo>;
+    readonly ostr_error: null | string;
+}
+
+class SSessionInfo {
+    readonly str_session_token: string;
+    readonly str_player_id: string;
+}
+
+function isLobbyState(state: SSiteState | SLobbyState | SSessionInfo) : state is SLobbyState {
+    return (state as SLobbyState).vectableinfo !== undefined;
+}
+function isSessionInfo(state: SSiteState | SLobbyState | SSessionInfo) : state is SSessionInfo {
+    return (state as SSessionInfo).str_session_token !== undefined;
 }
 
 class SLocalState {
@@ -113,15 +154,54 @@ function arrays_are_equal<T>(vec_lhs: T[], vec_rhs: T[]) : boolean { // TODO? Ge
 
 let localstate = new SLocalState();
 let str_player_name = prompt("Name:");
-let ws = new WebSocket("ws://localhost:8080");
-ws.onopen = function(event) {
-    ws.send(JSON.stringify({"PlayerLogin": {"str_player_name": str_player_name}}));
-};
-ws.onmessage = function(msg) {
-    let sitestate = dbg(JSON.parse(msg.data) as SSiteState); // assume that server sends valid SSiteState // TODO? assert/check
+let str_table_name = new URLSearchParams(window.location.search).get("table") || "default";
+let ostr_spectate = new URLSearchParams(window.location.search).get("spectate"); // seat index or "all"
+let ws = connect();
+
+function connect() : WebSocket {
+    let ws_new = new WebSocket("ws://localhost:8080");
+    ws_new.onopen = function(event) {
+        ws_new.send(JSON.stringify({"PlayerLogin": {
+            "str_player_name": str_player_name,
+            "ostr_session_token": sessionStorage.getItem("str_session_token"),
+            "ostr_player_id": localStorage.getItem("str_player_id"), // survives sessions, identifies the balance
+        }}));
+    };
+    ws_new.onmessage = on_message;
+    ws_new.onclose = function(event) {
+        // try to get our seat back
+        setTimeout(function () { ws = connect(); }, 1000);
+    };
+    return ws_new;
+}
+
+function on_message(msg: MessageEvent) {
+    let state = dbg(JSON.parse(msg.data) as SSiteState | SLobbyState | SSessionInfo); // assume that server sends valid data // TODO? assert/check
+    if (isSessionInfo(state)) {
+        sessionStorage.setItem("str_session_token", state.str_session_token);
+        localStorage.setItem("str_player_id", state.str_player_id);
+        return;
+    }
+    if (isLobbyState(state)) {
+        if (state.ostr_error) {
+            alert(state.ostr_error); // TODO proper lobby UI
+        } else {
+            // Not seated (anymore), so join the requested table.
+            if (ostr_spectate===null) {
+                ws.send(JSON.stringify({"JoinTable": {"str_table_name": str_table_name}}));
+            } else {
+                ws.send(JSON.stringify({"Spectate": {
+                    "str_table_name": str_table_name,
+                    "spectatorview": ostr_spectate==="all" ? "AllHands" : {"Seat": Number(ostr_spectate)},
+                }}));
+            }
+        }
+        return;
+    }
+    let sitestate = state;
     {
         let div_hand_new = new_div_with_id("hand");
-        let veccard_hand = sitestate.vectplstrstr_caption_message_zugeben.map((tplstrstr: [String, String]) => {
+        let veccard_hand = sitestate.vectplstrstr_caption_message_zugeben.map((tplstrstr: [string, null | string]) => {
             tplstrstr[0]
         });
         if (!arrays_are_equal(veccard_hand, localstate.veccard_hand)) {
@@ -129,10 +209,13 @@ ws.onmessage = function(msg) {
             for (let tplstrstr of sitestate.vectplstrstr_caption_message_zugeben) {
                 let div_card = new_div_card_in_hand(tplstrstr[0]);
                 div_hand_new.appendChild(div_card);
-                (<HTMLElement>div_card).onclick = function () {
-                    // TODO if (!player is active) { check } else
-                    ws.send(JSON.stringify({"GamePhaseAction": dbg(tplstrstr[1])}));
-                };
+                let ogamephaseaction = tplstrstr[1];
+                if (ogamephaseaction!==null) {
+                    (<HTMLElement>div_card).onclick = function () {
+                        // TODO if (!player is active) { check } else
+                        ws.send(JSON.stringify({"GamePhaseAction": dbg(ogamephaseaction)}));
+                    };
+                }
             }
             replace_div_with(unwrap(document.getElementById("hand")), div_hand_new);
         }
@@ -203,6 +286,9 @@ ws.onmessage = function(msg) {
             if (dbg(sitestate.otplepistr_rules) && i_epi==sitestate.otplepistr_rules[0]) {
                 div_player.textContent += ": " + sitestate.otplepistr_rules[1];
             }
+            if (sitestate.omapepivecstr_card) {
+                div_player.textContent += " [" + sitestate.omapepivecstr_card[i_epi].join(" ") + "]";
+            }
             if (sitestate.oepi_timeout===i_epi) {
                 div_player.className = "playerpanel_active";
             } else {
@@ -210,6 +296,34 @@ ws.onmessage = function(msg) {
             }
         }
     }
-    {
+    if (sitestate.ogamereveal) {
+        let gamereveal = sitestate.ogamereveal;
+        let div_askpanel_reveal = new_div_with_id("askpanel");
+        let append_paragraph = function (str_text: string) {
+            let paragraph = document.createElement("p");
+            paragraph.appendChild(document.createTextNode(str_text));
+            div_askpanel_reveal.appendChild(paragraph);
+        };
+        let oask_reveal = getAsk(sitestate.msg);
+        if (oask_reveal) {
+            append_paragraph(oask_reveal.str_question);
+        }
+        for (let i_epi = 0; i_epi<4; i_epi++) {
+            append_paragraph(
+                sitestate.mapepistr[i_epi]
+                + (gamereveal.omapepib_primary && gamereveal.omapepib_primary[i_epi] ? " (Spieler)" : "")
+                + ": " + gamereveal.mapepivecstr_card[i_epi].join(" ")
+                + " - " + gamereveal.mapepin_point[i_epi] + " Augen, " + gamereveal.mapepin_stich[i_epi] + " Stiche"
+                + " - " + gamereveal.mapepin_payout[i_epi]
+            );
+        }
+        if (gamereveal.oeschneiderschwarz) {
+            append_paragraph(gamereveal.oeschneiderschwarz);
+        }
+        if (gamereveal.otplnb_laufende && gamereveal.otplnb_laufende[0]>0) {
+            append_paragraph(gamereveal.otplnb_laufende[0] + " Laufende (" + (gamereveal.otplnb_laufende[1] ? "Spieler" : "Gegner") + ")");
+        }
+        append_paragraph("Stoss: " + gamereveal.n_stoss + ", Doppelt: " + gamereveal.n_doubling + ", Faktor: " + gamereveal.n_stoss_doubling_factor);
+        replace_div_with(unwrap(document.getElementById("askpanel")), div_askpanel_reveal);
     }
-};
+}
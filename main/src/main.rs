@@ -30,6 +30,7 @@ fn main() -> Result<(), SStringifiedError> {
     subcommands!(
         // play
         (cli, "cli")
+        (text, "text")
         // analyze
         (analyze, "analyze")
         (suggest_card, "suggest-card")
@@ -37,6 +38,7 @@ fn main() -> Result<(), SStringifiedError> {
         // misc
         (parse, "parse")
         (webext, "webext")
+        (engine, "engine")
     );
     Ok(())
 }
@@ -1,6 +1,7 @@
 use openschafkopf_lib::{
     primitives::*,
-    game_analysis::{*, parser::*},
+    api::games_from_str,
+    game_analysis::{*, position::SPosition},
     game::*,
     ai::{handiterators::*, gametree::*, *},
     rules::{TRules, SDisplayRules, ruleset::VStockOrT},
@@ -10,7 +11,7 @@ use std::{
     borrow::Cow,
     io::Write,
     time::Instant,
-    sync::{Arc, atomic::{AtomicUsize, Ordering}, Mutex},
+    sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}, Mutex},
 };
 use std::fmt::Write as _;
 use rayon::prelude::*;
@@ -46,50 +47,28 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
                 None => Cow::Borrowed("stdin"), // hope that path is not "stdin"
             };
             println!("Opened {str_path}");
-            let mut b_found = false;
-            let mut push_game = |str_description: String, resgameresult: Result<_, _>| {
-                b_found = b_found || resgameresult.is_ok();
-                vecgamewithdesc.push(SGameWithDesc{
-                    str_description,
-                    resgameresult,
-                });
-            };
-            if let resgameresult@Ok(_) = analyze_sauspiel_html(&str_input)
-                .map(|game| game.map(|_|(), |_|(), |_|()))
-                .or_else(|_err| analyze_sauspiel_json(&str_input, |_,_,_,_| {})
-                    .map(|game| game.map(|_|(), |_|(), |_|()))
-                )
-            {
-                push_game(
-                    str_path.clone().into_owned(),
-                    resgameresult
-                )
-            } else {
-                let mut b_found_plain = false;
-                for (i, resgame) in analyze_plain(&str_input)
-                    .chain(analyze_netschafkopf(&str_input).into_iter().flatten()
-                        .map(|resgameresult| resgameresult.and_then(|gameresult| {
-                            match gameresult.stockorgame {
-                                VStockOrT::Stock(_ruleset) => Err(format_err!("Nothing to analyze.")),
-                                VStockOrT::OrT(game) => Ok(game.map(|_|(), |_|(), |_|(), |rules| rules)),
-                            }
-                        }))
-                    )
-                    .filter(|res| res.is_ok())
-                    .enumerate()
-                {
-                    b_found_plain = true;
-                    push_game(
-                        format!("{str_path}_{i}"),
-                        resgame.and_then(|game| game.finish().map_err(|_game| format_err!("Could not game.finish")))
-                    )
-                }
-                if !b_found_plain {
-                    push_game(str_path.clone().into_owned(), Err(format_err!("Nothing found in {}: Trying to continue.", str_path)));
-                }
-            }
-            if !b_found {
-                eprintln!("Nothing found in {str_path}: Trying to continue.");
+            match games_from_str(&str_input) {
+                Ok(vecgameresult) => {
+                    let b_single = vecgameresult.len()==1;
+                    for (i, gameresult) in vecgameresult.into_iter().enumerate() {
+                        vecgamewithdesc.push(SGameWithDesc{
+                            str_description: if b_single {
+                                str_path.clone().into_owned()
+                            } else {
+                                format!("{str_path}_{i}")
+                            },
+                            resgameresult: Ok(gameresult),
+                        });
+                    }
+                },
+                Err(err) => {
+                    info!("{str_path}: {err}");
+                    eprintln!("Nothing found in {str_path}: Trying to continue.");
+                    vecgamewithdesc.push(SGameWithDesc{
+                        str_description: str_path.clone().into_owned(),
+                        resgameresult: Err(format_err!("Nothing found in {}: Trying to continue.", str_path)),
+                    });
+                },
             }
         },
     )?;
@@ -113,10 +92,11 @@ struct SGameWithDesc {
     pub resgameresult: Result<SGameResult</*Ruleset*/()>, SStringifiedError>,
 }
 
-fn analyze_game(
+pub(super) fn analyze_game(
     game_in: SGame,
     n_max_remaining_cards: usize,
     b_simulate_all_hands: bool,
+    b_cancel: &AtomicBool, // skips the remaining analysis once set
 ) -> SGameAnalysis {
     let mut vecanalysispercard = Vec::new();
     let an_payout = unwrap!(game_in.clone().finish()).an_payout;
@@ -132,7 +112,7 @@ fn analyze_game(
         &game_in.expensifiers.vecstoss,
         game_in.stichseq.visible_cards(),
         /*fn_before_zugeben*/|game, i_stich, epi_zugeben, card_played| {
-            if game.stichseq.remaining_cards_per_hand()[epi_zugeben] <= n_max_remaining_cards {
+            if !b_cancel.load(Ordering::Relaxed) && game.stichseq.remaining_cards_per_hand()[epi_zugeben] <= n_max_remaining_cards {
                 let stichseq = &game.stichseq;
                 let fwd_to_determine_best_card = |epi, itahand| {
                     unwrap!(determine_best_card(
@@ -277,6 +257,7 @@ fn analyze_games(path_analysis: &std::path::Path, fn_link: impl Fn(&str)->String
                         game,
                         n_max_remaining_cards,
                         b_simulate_all_hands,
+                        /*b_cancel*/&AtomicBool::new(false),
                     );
                     let duration = instant_analysis_begin.elapsed();
                     let path = write_html(path, &gameanalysis.generate_analysis_html(
@@ -357,7 +338,7 @@ pub struct SAnalysisCardAndPayout {
 pub struct SAnalysisPerCard {
     pub stichseq: SStichSequence, // TODO this is space-inefficient
     ahand: EnumMap<EPlayerIndex, SHand>, // TODO this is space-inefficient
-    card_played: ECard,
+    pub card_played: ECard,
     determinebestcardresult_cheating: SDetermineBestCardResult<SPerMinMaxStrategy<SPayoutStats<()>>>,
     pub oanalysisimpr: Option<SAnalysisImprovement>,
 }
@@ -432,9 +413,9 @@ impl SGameAnalysis {
                     )),
                     ul(html_iter(self.vecanalysispercard.iter()
                         .filter_map(|analysispercard| analysispercard.oanalysisimpr.as_ref().map(|analysisimpr|
-                            (analysisimpr, &analysispercard.stichseq)
+                            (analysisimpr, &analysispercard.stichseq, &analysispercard.ahand)
                         ))
-                        .map(|(analysisimpr, stichseq)| {
+                        .map(|(analysisimpr, stichseq, ahand)| {
                             let (str_stich_caption, epi) = stich_caption(stichseq);
                             li((
                                 format!(
@@ -465,6 +446,7 @@ impl SGameAnalysis {
                                         n_payout_real = mapepin_payout[epi],
                                     )))
                                 }),
+                                ul(li(("Position: ", code(SPosition::new(&game.rules, stichseq, ahand, &game.expensifiers, epi).to_string())))),
                             ))
                         })
                     )),
@@ -517,6 +499,7 @@ impl SGameAnalysis {
                                 &game.rules,
                                 &analysispercard.ahand,
                                 &analysispercard.stichseq,
+                                &game.expensifiers,
                                 str_openschafkopf_executable,
                             ),
                         )
@@ -2,37 +2,219 @@ mod skui;
 mod playerhuman;
 
 use openschafkopf_lib::{
-    game::run::run_simple_game_loop,
-    player::{*, playercomputer::*},
+    game::{*, run::*},
+    player::{*, playercomputer::*, playerrandom::*},
     primitives::*,
+    rules::ruleset::*,
 };
 use openschafkopf_util::*;
+use itertools::Itertools;
 use plain_enum::PlainEnum;
+use std::rc::Rc;
 
 pub fn subcommand(str_subcommand: &'static str) -> clap::Command<'static> {
     clap::Command::new(str_subcommand)
         .about("Play in command line")
+        .long_about("Play in command line. Press '?' for a hint by the AI, 'u' to take back your last card. After each game, your decisions are reviewed and you can replay the same cards from any seat.")
         .arg(openschafkopf_shared_args::ruleset_arg())
-        .arg(openschafkopf_shared_args::ai_arg())
+        .args(openschafkopf_shared_args::ai_args())
         .arg(openschafkopf_shared_args::clap_arg("numgames", "4")
             .help("Number of games to play")
         )
+        .arg(clap::Arg::new("no-card-review")
+            .long("no-card-review")
+            .help("Do not let the AI review the cards you played")
+            .long_help("Do not let the AI review the cards you played after each game. Reviewing each card takes as long as asking for a hint, so this saves time with slow AIs.")
+        )
+        .arg(clap::Arg::new("seat")
+            .long("seat")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("Player for the next seat, given as [NAME=]KIND")
+            .long_help("Player for the next seat (starting at seat 0), given as [NAME=]KIND, where KIND is one of human, random, cheating, simulating, neural-network. Either no or exactly 4 seats must be given. If several humans take part, they share the screen, and hands are hidden when handing over to the next human. By default, one human plays against three instances of --ai.")
+        )
 }
 
-pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
-    let _tui = skui::STuiGuard::init_ui();
-    let (mut aattable, n_stock) = run_simple_game_loop(
-        /*aplayer*/EPlayerIndex::map_from_fn(|epi| -> Box<dyn TPlayer> {
-            if EPlayerIndex::EPI1==epi {
-                Box::new(playerhuman::SPlayerHuman{ai : super::ai(clapmatches)})
+type SPlayerRandomCli = SPlayerRandom<fn(&SGameGeneric<SRuleSet, (), ()>)>;
+
+enum VCliPlayer {
+    Human(playerhuman::SPlayerHuman),
+    Computer(SPlayerComputer),
+    Random {
+        str_name: String,
+        playerrandom: SPlayerRandomCli,
+    },
+}
+
+impl VCliPlayer {
+    fn name(&self) -> &str {
+        match self {
+            VCliPlayer::Random{str_name, playerrandom:_} => str_name,
+            VCliPlayer::Human(_) | VCliPlayer::Computer(_) => self.player().name(),
+        }
+    }
+}
+
+fn players(clapmatches: &clap::ArgMatches) -> Result<Vec<VCliPlayer>, SStringifiedError> {
+    let vectplstrstr_name_kind = if let Some(itstr_seat) = clapmatches.values_of("seat") {
+        let vectplstrstr_name_kind = itstr_seat
+            .enumerate()
+            .map(|(i_seat, str_seat)| {
+                let (str_name, str_kind) = str_seat.split_once('=')
+                    .map(|(str_name, str_kind)| (str_name.trim().to_string(), str_kind.trim()))
+                    .unwrap_or_else(|| (format!("{} {}", str_seat.trim(), i_seat), str_seat.trim()));
+                match str_kind {
+                    "human"|"random"|"cheating"|"simulating"|"neural-network" => Ok((str_name, str_kind.to_string())),
+                    _ => Err(format_err!("Unknown player kind: {}", str_kind)),
+                }
+            })
+            .collect::<Result<Vec<_>,_>>()?;
+        if vectplstrstr_name_kind.len()!=EPlayerIndex::SIZE {
+            return Err(format_err!("Exactly {} seats required, but {} given.", EPlayerIndex::SIZE, vectplstrstr_name_kind.len()));
+        }
+        if !vectplstrstr_name_kind.iter().map(|(str_name, _str_kind)| str_name).all_unique() {
+            return Err(format_err!("Player names must be unique."));
+        }
+        vectplstrstr_name_kind
+    } else {
+        let str_ai = unwrap!(clapmatches.value_of("ai"));
+        EPlayerIndex::values()
+            .map(|epi| if epi==EPlayerIndex::EPI1 {
+                ("You".to_string(), "human".to_string())
             } else {
-                Box::new(SPlayerComputer{ai: super::ai(clapmatches)})
+                (format!("{str_ai} {epi}"), str_ai.to_string())
+            })
+            .collect()
+    };
+    let ohotseat = if_then_some!(
+        1<vectplstrstr_name_kind.iter().filter(|(_str_name, str_kind)| str_kind=="human").count(),
+        Rc::new(playerhuman::SHotSeat::default())
+    );
+    let aifactory = super::SAiFactory::new(clapmatches)?;
+    vectplstrstr_name_kind.into_iter()
+        .map(|(str_name, str_kind)| Ok(match str_kind.as_str() {
+            "human" => VCliPlayer::Human(playerhuman::SPlayerHuman::new(
+                aifactory.ai()?,
+                str_name,
+                ohotseat.clone(),
+                /*b_review_cards*/!clapmatches.is_present("no-card-review"),
+            )),
+            "random" => VCliPlayer::Random{
+                str_name,
+                playerrandom: SPlayerRandomCli::new(/*fn_check_ask_for_card*/|_game| {/* no checks */}),
+            },
+            str_ai => VCliPlayer::Computer(SPlayerComputer{ai: aifactory.ai_from_str(str_ai)?, str_name}),
+        }))
+        .collect()
+}
+
+impl TGameLoopPlayer for VCliPlayer {
+    fn player(&self) -> &dyn TPlayer {
+        match self {
+            VCliPlayer::Human(playerhuman) => playerhuman,
+            VCliPlayer::Computer(playercomputer) => playercomputer,
+            VCliPlayer::Random{str_name:_, playerrandom} => playerrandom,
+        }
+    }
+
+    fn ask_for_card_or_undo(&self, game: &SGameGeneric<SRuleSet, (), ()>) -> Result<VCardOrUndo, Error> {
+        if let VCliPlayer::Human(playerhuman) = self {
+            loop {
+                if let Some(card) = playerhuman.ask_for_card_or_undo(game) {
+                    return Ok(VCardOrUndo::Card(card));
+                } else if let Some(game_before_card) = playerhuman.undo_card() {
+                    return Ok(VCardOrUndo::Undo(game_before_card));
+                } // else nothing to take back
             }
-        }),
+        } else {
+            try_communicate_via_channel(|txcard| {
+                self.player().ask_for_card(game, txcard);
+            }).map(VCardOrUndo::Card)
+        }
+    }
+
+    fn forget_decisions_after(&self, n_cards_played: usize) {
+        if let VCliPlayer::Human(playerhuman) = self {
+            playerhuman.forget_decisions_after(n_cards_played);
+        }
+    }
+}
+
+pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
+    let mut itplayer = players(clapmatches)?.into_iter();
+    let _tui = skui::STuiGuard::init_ui();
+    let (mut aattable, n_stock) = internal_run_simple_game_loop(
+        /*aplayer*/EPlayerIndex::map_from_fn(|_epi| unwrap!(itplayer.next())),
         /*n_games*/unwrap!(clapmatches.value_of("numgames")).parse::<usize>().unwrap_or(4),
         openschafkopf_shared_args::get_ruleset(clapmatches)?,
-        /*fn_print_account_balance*/skui::print_account_balance,
-    );
+        /*fn_gamepreparations_to_stockorgame*/|gamepreparations, aattable| {
+            std::iter::once(determine_rules(gamepreparations, move |epi| aattable[epi].player.player()))
+        },
+        /*fn_print_account_balance*/|aattable, n_stock| skui::print_account_balance(
+            &aattable.map(|attable| attable.n_money),
+            &EPlayerIndex::map_from_fn(|epi| aattable[epi].player.name()),
+            n_stock,
+        ),
+        /*fn_after_game*/|gameresult, aveccard, aattable| {
+            let vecstr_hand = EPlayerIndex::values()
+                .map(|epi| {
+                    let mut veccard = aveccard[epi].to_vec();
+                    if let VStockOrT::OrT(game) = &gameresult.stockorgame {
+                        game.rules.sort_cards(&mut veccard);
+                    }
+                    format!("{} ({}): {} ({})",
+                        aattable[epi].player.name(),
+                        epi,
+                        veccard.iter().join(" "),
+                        gameresult.an_payout[epi],
+                    )
+                })
+                .collect::<Vec<_>>();
+            let vecepi_human = EPlayerIndex::values()
+                .filter(|epi| matches!(aattable[*epi].player, VCliPlayer::Human(_)))
+                .collect::<Vec<_>>();
+            if vecepi_human.is_empty() {
+                return None; // nobody to review or to replay
+            }
+            for &epi_human in vecepi_human.iter() {
+                if let VCliPlayer::Human(playerhuman) = &aattable[epi_human].player {
+                    playerhuman.activate();
+                    skui::print_review(&["Reviewing your decisions...".to_string()]);
+                    skui::print_review(&vecstr_hand.iter().cloned()
+                        .chain(std::iter::once("".to_string()))
+                        .chain(playerhuman.review_decisions())
+                        .collect::<Vec<_>>()
+                    );
+                    if playerhuman.ohotseat.is_some() {
+                        skui::wait_for_key();
+                    }
+                }
+            }
+            let vectplstrotplepiepi_replay = std::iter::once(("Next game".to_string(), None))
+                .chain(if let &[epi_human] = vecepi_human.as_slice() {
+                    EPlayerIndex::values()
+                        .map(|epi_replay| (format!("Replay these cards as player {epi_replay}"), Some((epi_human, epi_replay))))
+                        .collect::<Vec<_>>()
+                } else {
+                    vec![("Replay these cards".to_string(), Some((EPlayerIndex::EPI0, EPlayerIndex::EPI0)))] // everybody keeps their seat
+                })
+                .collect::<Vec<_>>();
+            skui::ask_for_alternative(
+                &vectplstrotplepiepi_replay,
+                &skui::choose_alternative_from_list_key_bindings(),
+                |_| true, // all alternatives allowed
+                |ncwin, i_tplstrotplepiepi_chosen, _osuggestion: &Option<()>| {
+                    for (i_tplstrotplepiepi, (str_replay, _otplepiepi)) in vectplstrotplepiepi_replay.iter().enumerate() {
+                        skui::wprintln(ncwin, &format!("{} {}",
+                            if i_tplstrotplepiepi==i_tplstrotplepiepi_chosen {"*"} else {" "},
+                            str_replay,
+                        ));
+                    }
+                },
+                || None, // no suggestions
+            ).1
+        },
+    )?;
     aattable.sort_unstable_by_key(|attable| attable.n_money);
     println!("Results:");
     for attable in aattable.iter() {
@@ -41,4 +223,3 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
     println!("Stock: {n_stock}");
     Ok(())
 }
-
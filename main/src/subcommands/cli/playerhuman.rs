@@ -6,12 +6,92 @@ use openschafkopf_lib::{
     rules::{ruleset::*, *},
 };
 use openschafkopf_util::*;
-use std::sync::mpsc;
+use std::{cell::RefCell, rc::Rc, sync::mpsc};
 
 use super::skui;
 
+// Decisions taken by the human player, remembered to review them after the game.
+pub enum VDecision {
+    Doubling {
+        veccard: Vec<ECard>,
+        b_doubling: bool,
+    },
+    Announcement {
+        veccard: Vec<ECard>,
+        vecrulegroup: Vec<SRuleGroup>,
+        expensifiers: SExpensifiers,
+        str_rules: String,
+    },
+    Stoss {
+        epi: EPlayerIndex,
+        rules: SRules,
+        hand: SHand,
+        stichseq: SStichSequence,
+        expensifiers: SExpensifiers,
+        b_stoss: bool,
+    },
+    Card {
+        game: SGameGeneric<SRuleSet, (), ()>, // state before the card was played
+        card: ECard,
+    },
+}
+
+// Shared by all human players sitting in front of the same screen.
+#[derive(Default)]
+pub struct SHotSeat {
+    ostr_name_active: RefCell<Option<String>>,
+}
+
+impl SHotSeat {
+    fn activate(&self, str_name: &str) {
+        let mut ostr_name_active = self.ostr_name_active.borrow_mut();
+        if ostr_name_active.as_deref()!=Some(str_name) {
+            skui::hand_over(str_name);
+            *ostr_name_active = Some(str_name.to_string());
+        }
+    }
+}
+
 pub struct SPlayerHuman {
     pub ai : SAi,
+    pub str_name: String,
+    pub vecdecision: RefCell<Vec<VDecision>>,
+    pub ohotseat: Option<Rc<SHotSeat>>,
+    b_review_cards: bool, // reviewing a card is as expensive as a hint
+}
+
+fn orules_to_string(orules: Option<&SActivelyPlayableRules>) -> String {
+    match orules {
+        None => "Nothing".to_string(),
+        Some(rules) => SDisplayRules::new(rules, /*b_include_playerindex*/false).to_string(),
+    }
+}
+
+fn gameannouncementpriority_to_string(gameannouncementprio: &VGameAnnouncementPriority) -> String {
+    match gameannouncementprio {
+        VGameAnnouncementPriority::RufspielLike => "Rufspiel".to_string(),
+        VGameAnnouncementPriority::SoloLike(VGameAnnouncementPrioritySoloLike::SoloSimple(_)) => "Solo".to_string(),
+        VGameAnnouncementPriority::SoloLike(VGameAnnouncementPrioritySoloLike::SoloSteigern{n_points_to_win, n_step:_}) => {
+            format!("Solo, winning with {n_points_to_win} points")
+        },
+        VGameAnnouncementPriority::SoloTout(_) => "Tout".to_string(),
+        VGameAnnouncementPriority::SoloSie => "Sie".to_string(),
+    }
+}
+
+fn payoutstats_to_string(permmstrategy: &SMaxMinMaxSelfishMin<SPayoutStats<()>>) -> String {
+    format!("{:.2} (maxmin), {:.2} (maxselfishmin)",
+        permmstrategy.omaxmin.as_ref().unwrap_static_some().avg(),
+        permmstrategy.omaxselfishmin.as_ref().unwrap_static_some().avg(),
+    )
+}
+
+fn b_stoss_to_string(b_stoss: bool) -> &'static str {
+    if b_stoss {"Give"} else {"No"}
+}
+
+fn b_doubling_to_string(b_doubling: bool) -> &'static str {
+    if b_doubling {"Doubling"} else {"No Doubling"}
 }
 
 fn choose_ruleset_or_rules<'t, T>(
@@ -20,17 +100,22 @@ fn choose_ruleset_or_rules<'t, T>(
     fn_format: impl Fn(&T)->String,
     fn_choose: impl Fn(usize)->Option<&'t SActivelyPlayableRules>,
     otplepiprio: &Option<(EPlayerIndex, VGameAnnouncementPriority)>,
+    fn_suggest: impl Fn()->Option<Vec<(String, f64)>>,
 ) -> &'t T {
     skui::ask_for_alternative(
         vect,
         &skui::choose_alternative_from_list_key_bindings(),
         |_ot| {true},
-        |ncwin, i_ot_chosen, _ot_suggest| {
+        |ncwin, i_ot_chosen, ovectplstrf_suggest| {
             let orules = fn_choose(i_ot_chosen);
-            assert!(_ot_suggest.is_none());
-            skui::wprintln(ncwin, &format!("Your cards: {}. What do you want to play?", SDisplayCardSlice::new(hand.cards().clone(), &orules.cloned())));
-            if let Some(ref tplepiprio) = *otplepiprio {
-                skui::wprintln(ncwin, &format!("{} offers {:?}", tplepiprio.0, tplepiprio.1)); // TODO improve output here
+            skui::wprintln(ncwin, &format!("Your cards: {}. What do you want to play? (?: hint)", SDisplayCardSlice::new(hand.cards().clone(), &orules.cloned())));
+            if let Some((epi, gameannouncementprio)) = otplepiprio {
+                skui::wprintln(ncwin, &format!("{} offers {}", epi, gameannouncementpriority_to_string(gameannouncementprio)));
+            }
+            if let Some(vectplstrf_suggest) = ovectplstrf_suggest {
+                for (str_rules, f_payout_avg) in vectplstrf_suggest.iter().take(5) {
+                    skui::wprintln(ncwin, &format!("AI: {str_rules}: {f_payout_avg:.2}"));
+                }
             }
             for (i_t, t) in vect.iter().enumerate() {
                 skui::wprintln(ncwin, &format!("{} {} ({})",
@@ -45,68 +130,195 @@ fn choose_ruleset_or_rules<'t, T>(
             }
             skui::print_hand(&veccard, None);
         },
-        || {None}
+        fn_suggest,
     )
 }
 
+impl SPlayerHuman {
+    pub fn new(ai: SAi, str_name: String, ohotseat: Option<Rc<SHotSeat>>, b_review_cards: bool) -> Self {
+        Self {
+            ai,
+            str_name,
+            vecdecision: RefCell::new(Vec::new()),
+            ohotseat,
+            b_review_cards,
+        }
+    }
+
+    // Ensures that no other human player is looking at the screen.
+    pub fn activate(&self) {
+        if let Some(hotseat) = &self.ohotseat {
+            hotseat.activate(&self.str_name);
+        }
+    }
+
+    // Returns None if the user wants to take back the previous card.
+    pub fn ask_for_card_or_undo(&self, game: &SGameGeneric<SRuleSet, (), ()>) -> Option<ECard> {
+        self.internal_ask_for_card(game, /*b_undo_allowed*/true)
+    }
+
+    fn internal_ask_for_card(&self, game: &SGameGeneric<SRuleSet, (), ()>, b_undo_allowed: bool) -> Option<ECard> {
+        self.activate();
+        skui::print_stichseq(unwrap!(game.current_playable_stich().current_playerindex()), &game.stichseq);
+        let epi = unwrap!(game.which_player_can_do_something()).0;
+        let veccard = {
+            let mut veccard = game.ahand[epi].cards().clone();
+            game.rules.sort_cards(&mut veccard);
+            veccard
+        };
+        let veccard_allowed = game.rules.all_allowed_cards(&game.stichseq, &SHand::new_from_vec(veccard.clone()));
+        let ocard = skui::ask_for_alternative_or_undo(
+            &veccard,
+            &skui::choose_card_from_hand_key_bindings(),
+            b_undo_allowed,
+            |card| {veccard_allowed.iter().any(|card_allowed| card_allowed==card)},
+            |ncwin, i_card_chosen, osuggestion| {
+                skui::wprintln(ncwin, if b_undo_allowed {"?: hint, u: take back your last card"} else {"?: hint"});
+                if let Some((card, odeterminebestcardresult)) = osuggestion {
+                    skui::wprintln(ncwin, &format!("AI: {card}"));
+                    if let Some(determinebestcardresult) = odeterminebestcardresult {
+                        for (card, permmstrategy) in determinebestcardresult.cards_and_ts() {
+                            skui::wprintln(ncwin, &format!("  {}: {}", card, payoutstats_to_string(permmstrategy)));
+                        }
+                    }
+                }
+                skui::print_hand(&veccard, Some(i_card_chosen));
+                skui::print_game_info(&game.rules, &game.expensifiers);
+            },
+            || {
+                Some(self.ai.suggest_card_with_stats(
+                    game,
+                    visualizer_factory(
+                        std::path::Path::new("gametree").to_path_buf(),
+                        &game.rules,
+                        epi,
+                    ),
+                ))
+            }
+        ).copied();
+        if let Some(card) = ocard {
+            self.vecdecision.borrow_mut().push(VDecision::Card{game: game.clone(), card});
+        }
+        ocard
+    }
+
+    // Forgets all decisions since the last card played by the human player and returns the game as it was before that card.
+    pub fn undo_card(&self) -> Option<SGameGeneric<SRuleSet, (), ()>> {
+        let mut vecdecision = self.vecdecision.borrow_mut();
+        let i_decision_card = vecdecision.iter().rposition(|decision| matches!(decision, VDecision::Card{..}))?;
+        match vecdecision.drain(i_decision_card..).next() {
+            Some(VDecision::Card{game, card:_}) => Some(game),
+            _ => panic!("Expected card decision"),
+        }
+    }
+
+    // Forgets all decisions that were taken after n_cards_played cards (needed if another player took back a card).
+    pub fn forget_decisions_after(&self, n_cards_played: usize) {
+        self.vecdecision.borrow_mut().retain(|decision| match decision {
+            VDecision::Doubling{..} | VDecision::Announcement{..} => true,
+            VDecision::Stoss{stichseq, ..} => stichseq.count_played_cards()<=n_cards_played,
+            VDecision::Card{game, card:_} => game.stichseq.count_played_cards()<n_cards_played,
+        });
+    }
+
+    // Compares each decision of the finished game with the decision the engine would have taken.
+    pub fn review_decisions(&self) -> Vec<String> {
+        self.vecdecision.take().into_iter()
+            .map(|decision| match decision {
+                VDecision::Doubling{veccard, b_doubling} => {
+                    format!("Doubling: {}. AI (heuristic): {}",
+                        b_doubling_to_string(b_doubling),
+                        b_doubling_to_string(self.ai.suggest_doubling(&veccard)),
+                    )
+                },
+                VDecision::Announcement{veccard, vecrulegroup, expensifiers, str_rules} => {
+                    let vectplstrf_ranked = self.ai.rank_announcements(
+                        SFullHand::new(&veccard, unwrap!(EKurzLang::from_cards_per_player(veccard.len()))),
+                        &vecrulegroup,
+                        &expensifiers,
+                    ).into_iter()
+                        .map(|(orules, f_payout_avg)| (orules_to_string(orules), f_payout_avg))
+                        .collect::<Vec<_>>();
+                    let (str_rules_best, f_payout_avg_best) = unwrap!(vectplstrf_ranked.first());
+                    format!("Game: {} ({}). AI: {} ({:.2})",
+                        str_rules,
+                        vectplstrf_ranked.iter()
+                            .find(|(str_rules_ranked, _f_payout_avg)| *str_rules_ranked==str_rules)
+                            .map_or("?".to_string(), |(_str_rules, f_payout_avg)| format!("{f_payout_avg:.2}")),
+                        str_rules_best,
+                        f_payout_avg_best,
+                    )
+                },
+                VDecision::Stoss{epi, rules, hand, stichseq, expensifiers, b_stoss} => {
+                    let (b_stoss_suggest, f_payout_avg) = self.ai.suggest_stoss(epi, &rules, &hand, &stichseq, &expensifiers);
+                    format!("Stoss after {} cards: {}. AI: {} ({:.2})",
+                        stichseq.count_played_cards(),
+                        b_stoss_to_string(b_stoss),
+                        b_stoss_to_string(b_stoss_suggest),
+                        f_payout_avg,
+                    )
+                },
+                VDecision::Card{game, card} if !self.b_review_cards => {
+                    format!("Stich {}: {}", game.stichseq.completed_stichs().len() + 1, card)
+                },
+                VDecision::Card{game, card} => {
+                    let (card_suggest, odeterminebestcardresult) = self.ai.suggest_card_with_stats(&game, SNoVisualization::factory());
+                    let payoutstats_of = |card| odeterminebestcardresult.as_ref()
+                        .and_then(|determinebestcardresult| determinebestcardresult.cards_and_ts()
+                            .find(|(card_determined, _permmstrategy)| *card_determined==card)
+                            .map(|(_card, permmstrategy)| format!(" ({})", payoutstats_to_string(permmstrategy)))
+                        )
+                        .unwrap_or_default();
+                    format!("Stich {}: {}{}. {}",
+                        game.stichseq.completed_stichs().len() + 1,
+                        card,
+                        payoutstats_of(card),
+                        if card==card_suggest {
+                            "AI agrees".to_string()
+                        } else {
+                            format!("AI: {}{}", card_suggest, payoutstats_of(card_suggest))
+                        },
+                    )
+                },
+            })
+            .collect()
+    }
+}
+
 impl TPlayer for SPlayerHuman {
     fn ask_for_doubling(
         &self,
         veccard: &[ECard],
         txb_doubling: mpsc::Sender<bool>,
     ) {
+        self.activate();
         let ab_doubling = [false, true];
-        unwrap!(txb_doubling.send(*skui::ask_for_alternative(
+        let b_doubling = *skui::ask_for_alternative(
             &ab_doubling,
             &skui::choose_alternative_from_list_key_bindings(),
             |_| true, // all alternatives allowed
             |ncwin, i_b_doubling_chosen, ob_doubling_suggest| {
-                assert!(ob_doubling_suggest.is_none());
                 // TODO show who else already doubled
                 skui::print_hand(veccard, None);
+                if let Some(b_doubling_suggest) = ob_doubling_suggest {
+                    skui::wprintln(ncwin, &format!("AI (heuristic): {}", b_doubling_to_string(*b_doubling_suggest)));
+                }
                 for (i_b_doubling, b_doubling) in ab_doubling.iter().enumerate() {
                     skui::wprintln(ncwin, &format!("{} {}",
                         if i_b_doubling==i_b_doubling_chosen {"*"} else {" "},
-                        if *b_doubling {"Doubling"} else {"No Doubling"},
+                        b_doubling_to_string(*b_doubling),
                     ));
                 }
             },
-            || None, // TODO implement suggestions
-        )))
+            || Some(self.ai.suggest_doubling(veccard)),
+        );
+        self.vecdecision.borrow_mut().push(VDecision::Doubling{veccard: veccard.to_vec(), b_doubling});
+        unwrap!(txb_doubling.send(b_doubling))
     }
 
     fn ask_for_card(&self, game: &SGameGeneric<SRuleSet, (), ()>, txcard: mpsc::Sender<ECard>) {
-        skui::print_stichseq(unwrap!(game.current_playable_stich().current_playerindex()), &game.stichseq);
-        let epi = unwrap!(game.which_player_can_do_something()).0;
-        let veccard = {
-            let mut veccard = game.ahand[epi].cards().clone();
-            game.rules.sort_cards(&mut veccard);
-            veccard
-        };
-        let veccard_allowed = game.rules.all_allowed_cards(&game.stichseq, &SHand::new_from_vec(veccard.clone()));
         if txcard.send(
-            *skui::ask_for_alternative(
-                &veccard,
-                &skui::choose_card_from_hand_key_bindings(),
-                |card| {veccard_allowed.iter().any(|card_allowed| card_allowed==card)},
-                |ncwin, i_card_chosen, ocard_suggest| {
-                    if let Some(card) = *ocard_suggest {
-                        skui::wprintln(ncwin, &format!("AI: {card}"));
-                    }
-                    skui::print_hand(&veccard, Some(i_card_chosen));
-                    skui::print_game_info(&game.rules, &game.expensifiers);
-                },
-                || {
-                    Some(self.ai.suggest_card(
-                        game,
-                        visualizer_factory(
-                            std::path::Path::new("gametree").to_path_buf(),
-                            &game.rules,
-                            epi,
-                        ),
-                    ))
-                }
-            )
+            unwrap!(self.internal_ask_for_card(game, /*b_undo_allowed*/false))
         ).is_err() {
             unimplemented!() // we possibly want to be able to deal with "blocked" plays (timeout etc.)
         }
@@ -118,12 +330,18 @@ impl TPlayer for SPlayerHuman {
         hand: SFullHand,
         gameannouncements : &SGameAnnouncements,
         vecrulegroup: &'rules [SRuleGroup],
-        _expensifiers: &SExpensifiers,
+        expensifiers: &SExpensifiers,
         otplepiprio: Option<(EPlayerIndex, VGameAnnouncementPriority)>,
         txorules: mpsc::Sender<Option<&'rules SActivelyPlayableRules>>,
     ) {
+        self.activate();
         skui::print_game_announcements(epi, gameannouncements);
-        let vecrulegroup : Vec<&SRuleGroup> = vecrulegroup.iter()
+        let suggest_announcement = || Some(
+            self.ai.rank_announcements(hand, vecrulegroup, expensifiers).into_iter()
+                .map(|(orules, f_payout_avg)| (orules_to_string(orules), f_payout_avg))
+                .collect::<Vec<_>>()
+        );
+        let vecrulegroup_allowed : Vec<&SRuleGroup> = vecrulegroup.iter()
             .filter(|rulegroup| 0 < rulegroup.allowed_rules(hand).count())
             .collect();
         loop {
@@ -131,10 +349,11 @@ impl TPlayer for SPlayerHuman {
                 .chain(
                     choose_ruleset_or_rules(
                         &SHand::new_from_iter(hand.get()),
-                        &vecrulegroup,
+                        &vecrulegroup_allowed,
                         |rulegroup| rulegroup.str_name.clone(),
-                        |i_rulegroup_chosen| vecrulegroup[i_rulegroup_chosen].vecorules[0].as_ref(),
+                        |i_rulegroup_chosen| vecrulegroup_allowed[i_rulegroup_chosen].vecorules[0].as_ref(),
                         &otplepiprio,
+                        suggest_announcement,
                     )
                         .allowed_rules(hand)
                         .map(Some)
@@ -145,12 +364,18 @@ impl TPlayer for SPlayerHuman {
                 &vecoorules,
                 |oorules| match *oorules {
                     None => "Back".to_string(),
-                    Some(None) => "Nothing".to_string(),
-                    Some(Some(rules)) => SDisplayRules::new(rules, /*b_include_playerindex*/false).to_string()
+                    Some(orules) => orules_to_string(orules),
                 },
                 |i_oorules_chosen| vecoorules[i_oorules_chosen].and_then(|orules| orules),
                 &otplepiprio,
+                suggest_announcement,
             ) {
+                self.vecdecision.borrow_mut().push(VDecision::Announcement{
+                    veccard: hand.get().to_vec(),
+                    vecrulegroup: vecrulegroup.to_vec(),
+                    expensifiers: expensifiers.clone(),
+                    str_rules: orules_to_string(orules),
+                });
                 unwrap!(txorules.send(orules));
                 return;
             }
@@ -159,30 +384,33 @@ impl TPlayer for SPlayerHuman {
 
     fn ask_for_stoss(
         &self,
-        _epi: EPlayerIndex,
+        epi: EPlayerIndex,
         rules: &SRules,
         hand: &SHand,
-        _stichseq: &SStichSequence,
+        stichseq: &SStichSequence,
         expensifiers: &SExpensifiers,
         txb: mpsc::Sender<bool>,
     ) {
+        self.activate();
         let ab_stoss = [false, true];
-        unwrap!(txb.send(*skui::ask_for_alternative(
+        let b_stoss = *skui::ask_for_alternative(
             &ab_stoss,
             &skui::choose_alternative_from_list_key_bindings(),
             |_| true, // all alternatives allowed
-            |ncwin, i_b_stoss_chosen, ob_stoss_suggest| {
-                assert!(ob_stoss_suggest.is_none());
+            |ncwin, i_b_stoss_chosen, otplbf_stoss_suggest| {
                 skui::print_game_info(rules, expensifiers);
                 {
                     let mut veccard = hand.cards().clone();
                     rules.sort_cards(veccard.as_mut_slice());
                     skui::print_hand(&veccard, None);
                 }
+                if let Some((b_stoss_suggest, f_payout_avg)) = otplbf_stoss_suggest {
+                    skui::wprintln(ncwin, &format!("AI: {} ({:.2})", b_stoss_to_string(*b_stoss_suggest), f_payout_avg));
+                }
                 for (i_b_stoss, b_stoss) in ab_stoss.iter().enumerate() {
                     skui::wprintln(ncwin, &format!("{} {} {}",
                         if i_b_stoss==i_b_stoss_chosen {"*"} else {" "},
-                        if *b_stoss {"Give"} else {"No"},
+                        b_stoss_to_string(*b_stoss),
                         { match expensifiers.vecstoss.len() {
                             0 => "Kontra".to_string(),
                             1 => "Re".to_string(),
@@ -193,11 +421,20 @@ impl TPlayer for SPlayerHuman {
                     ));
                 }
             },
-            || None, // TODO implement suggestions
-        )))
+            || Some(self.ai.suggest_stoss(epi, rules, hand, stichseq, expensifiers)),
+        );
+        self.vecdecision.borrow_mut().push(VDecision::Stoss{
+            epi,
+            rules: rules.clone(),
+            hand: hand.clone(),
+            stichseq: stichseq.clone(),
+            expensifiers: expensifiers.clone(),
+            b_stoss,
+        });
+        unwrap!(txb.send(b_stoss))
     }
 
     fn name(&self) -> &str {
-        "SPlayerHuman" // TODO
+        &self.str_name
     }
 }
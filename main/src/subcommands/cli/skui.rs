@@ -88,6 +88,7 @@ enum VSkUiWindow {
     PlayerInfo (ERelativePlayerPosition),
     GameInfo,
     AccountBalance,
+    Review,
 }
 
 fn do_in_window<RetVal>(skuiwin: &VSkUiWindow, fn_do: impl FnOnce(ncurses::WINDOW)->RetVal) -> RetVal {
@@ -124,6 +125,7 @@ fn do_in_window<RetVal>(skuiwin: &VSkUiWindow, fn_do: impl FnOnce(ncurses::WINDO
         VSkUiWindow::Interaction => {create_fullwidth_window(17, n_height-3)},
         VSkUiWindow::GameInfo => {create_fullwidth_window(n_height-3, n_height-2)}
         VSkUiWindow::AccountBalance => {create_fullwidth_window(n_height-2, n_height-1)}
+        VSkUiWindow::Review => {create_fullwidth_window(0, 17)}, // replaces stich and hand after the game
     };
     ncurses::werase(ncwin);
     let retval = fn_do(ncwin);
@@ -200,16 +202,41 @@ pub fn print_game_info(rules: &SRules, expensifiers: &SExpensifiers) {
     })
 }
 
-pub fn account_balance_string(an: &EnumMap<EPlayerIndex, isize>, n_stock: isize) -> String {
+pub fn account_balance_string(an: &EnumMap<EPlayerIndex, isize>, astr_name: &EnumMap<EPlayerIndex, &str>, n_stock: isize) -> String {
     EPlayerIndex::values()
-        .map(|epi| format!("{}: {} | ", epi, an[epi]))
+        .map(|epi| format!("{} ({}): {} | ", astr_name[epi], epi, an[epi]))
         .join("")
         + type_inference!(&str, &format!("Stock: {n_stock}"))
 }
 
-pub fn print_account_balance(an: &EnumMap<EPlayerIndex, isize>, n_stock: isize) {
+pub fn print_account_balance(an: &EnumMap<EPlayerIndex, isize>, astr_name: &EnumMap<EPlayerIndex, &str>, n_stock: isize) {
     do_in_window(&VSkUiWindow::AccountBalance, |ncwin| {
-        wprint(ncwin, &account_balance_string(an, n_stock));
+        wprint(ncwin, &account_balance_string(an, astr_name, n_stock));
+    })
+}
+
+pub fn wait_for_key() {
+    do_in_window(&VSkUiWindow::Interaction, |ncwin| {
+        wprintln(ncwin, "Press any key to continue.");
+        ncurses::getch();
+    })
+}
+
+// Clears the whole screen so that the next player does not see the previous player's hand.
+pub fn hand_over(str_name: &str) {
+    ncurses::clear();
+    ncurses::mvaddstr(0, 0, &format!("Please hand over to {str_name} and press any key."));
+    ncurses::refresh();
+    ncurses::getch();
+    ncurses::clear();
+    ncurses::refresh();
+}
+
+pub fn print_review(slcstr_line: &[String]) {
+    do_in_window(&VSkUiWindow::Review, |ncwin| {
+        for str_line in slcstr_line {
+            wprintln(ncwin, str_line);
+        }
     })
 }
 
@@ -218,6 +245,7 @@ pub struct SAskForAlternativeKeyBindings {
     key_next : i32,
     key_choose : i32,
     key_suggest : i32,
+    key_undo : i32,
 }
 
 pub fn choose_card_from_hand_key_bindings() -> SAskForAlternativeKeyBindings {
@@ -226,6 +254,7 @@ pub fn choose_card_from_hand_key_bindings() -> SAskForAlternativeKeyBindings {
         key_next : ncurses::KEY_RIGHT,
         key_choose : ncurses::KEY_UP,
         key_suggest : '?' as i32,
+        key_undo : 'u' as i32,
     }
 }
 
@@ -235,24 +264,37 @@ pub fn choose_alternative_from_list_key_bindings() -> SAskForAlternativeKeyBindi
         key_next : ncurses::KEY_DOWN,
         key_choose : ncurses::KEY_RIGHT,
         key_suggest : '?' as i32,
+        key_undo : 'u' as i32,
     }
 }
 
-pub fn ask_for_alternative<'vect, T>(
+pub fn ask_for_alternative<'vect, T, Suggestion>(
     vect: &'vect [T],
     askforalternativekeybindings: &SAskForAlternativeKeyBindings,
     fn_filter: impl Fn(&T)->bool,
-    fn_callback: impl Fn(ncurses::WINDOW, usize, &Option<T>),
-    fn_suggest: impl Fn()->Option<T>
+    fn_callback: impl Fn(ncurses::WINDOW, usize, &Option<Suggestion>),
+    fn_suggest: impl Fn()->Option<Suggestion>
 ) -> &'vect T {
+    unwrap!(ask_for_alternative_or_undo(vect, askforalternativekeybindings, /*b_undo_allowed*/false, fn_filter, fn_callback, fn_suggest))
+}
+
+// Returns None if undo is allowed and the user wants to undo.
+pub fn ask_for_alternative_or_undo<'vect, T, Suggestion>(
+    vect: &'vect [T],
+    askforalternativekeybindings: &SAskForAlternativeKeyBindings,
+    b_undo_allowed: bool,
+    fn_filter: impl Fn(&T)->bool,
+    fn_callback: impl Fn(ncurses::WINDOW, usize, &Option<Suggestion>),
+    fn_suggest: impl Fn()->Option<Suggestion>
+) -> Option<&'vect T> {
     do_in_window(&VSkUiWindow::Interaction, |ncwin| {
-        let mut ot_suggest = None;
+        let mut osuggestion = None;
         let vect = vect.iter().enumerate().filter(|&(_i_t, t)| fn_filter(t)).collect::<Vec<_>>();
         assert!(!vect.is_empty());
         let mut i_alternative = 0; // initially, point to 0th alternative
-        fn_callback(ncwin, vect[i_alternative].0, &ot_suggest);
+        fn_callback(ncwin, vect[i_alternative].0, &osuggestion);
         ncurses::refresh();
-        if 1<vect.len() {
+        if 1<vect.len() || b_undo_allowed { // undo must be possible even if there is no choice
             let mut ch = askforalternativekeybindings.key_prev;
             while ch!=askforalternativekeybindings.key_choose {
                 if ch==askforalternativekeybindings.key_prev {
@@ -265,16 +307,19 @@ pub fn ask_for_alternative<'vect, T>(
                         i_alternative += 1
                     }
                 } else if ch==askforalternativekeybindings.key_suggest {
-                    ot_suggest = fn_suggest();
+                    osuggestion = fn_suggest();
+                } else if b_undo_allowed && ch==askforalternativekeybindings.key_undo {
+                    ncurses::werase(ncwin);
+                    return None;
                 }
                 ncurses::werase(ncwin);
-                fn_callback(ncwin, vect[i_alternative].0, &ot_suggest);
+                fn_callback(ncwin, vect[i_alternative].0, &osuggestion);
                 ncurses::refresh();
                 ch = ncurses::getch();
             }
         }
         ncurses::werase(ncwin);
-        vect[i_alternative].1
+        Some(vect[i_alternative].1)
     })
 }
 
@@ -1,8 +1,6 @@
 use openschafkopf_lib::{
-    ai::{
-        SAi,
-        handiterators::*,
-    },
+    api::{SEngine, SGameState, VApiError, VHandSelection, rules_from_str},
+    game_analysis::position::SPosition,
     primitives::*,
     rules::{
         ruleset::VStockOrT,
@@ -13,7 +11,6 @@ use openschafkopf_lib::{
         TRules,
         TRulesPlayerIndex,
         SDisplayRules,
-        parser::parse_rule_description_simple,
     },
 };
 use openschafkopf_util::*;
@@ -28,6 +25,13 @@ enum VChooseItAhand {
     Sample(/*n_samples*/usize, /*on_pool*/Option<usize>),
 }
 
+// Checkpoint (see --played-cards) for which fn_with_args is invoked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SCheckpoint {
+    pub i_checkpoint: usize,
+    pub n_checkpoints: usize,
+}
+
 #[derive(Clone)]
 enum VUserSuppliedPosition {
 	CurrentPlayer,
@@ -67,7 +71,7 @@ pub fn subcommand_given_game(str_subcommand: &'static str, str_about: &'static s
         .arg(clap::Arg::new("hand")
             .long("hand")
             .takes_value(true)
-            .required(true)
+            .required_unless_present_any(["position_string", "position_file"])
             .multiple_occurrences(true)
             .help("The cards on someone's hand")
             .long_help("The cards on the current player's hand (simply separated by spaces, such as \"eo go ho so eu gu hu su\" for a Sie), or the hands of all players. Specifying all player's hands works by first listing cards of player 0, then player 1, then player 2, then player 3 (Example: \"ea ez  ga gz  ha hz  sa sz\" means player 0 has Eichel-Ass and Eichel-Zehn, player 1 has Gras-Ass and Gras-Zehn, and so forth). You can use underscore to leave \"holes\" in other players' hands (Example: \"ea __  ga __  ha __  sa __\" means player 0 has Eichel-Ass and another unknown card, player 1 has Gras-Ass and unknown card, and so forth).")
@@ -76,7 +80,7 @@ pub fn subcommand_given_game(str_subcommand: &'static str, str_about: &'static s
             .long("played-cards")
             .takes_value(true)
             .help("Cards played so far")
-            .long_help("Cards played so far in the order they have been played. The software matches the cards to the respective player.")
+            .long_help("Cards played so far in the order they have been played. The software matches the cards to the respective player. A | marks a checkpoint: suggest-card then evaluates the game state at each checkpoint (and at the end) for the respective current player, simulating hands separately for each checkpoint. Example: \"ea | ez ek e9  sa sz | sk s9\".")
         )
         .arg(clap::Arg::new("stoss")
             .long("stoss")
@@ -84,6 +88,21 @@ pub fn subcommand_given_game(str_subcommand: &'static str, str_about: &'static s
             .help("Stosses given")
             .long_help("Stosses given so far. Enumerate the respective player indices one after another, separated by a space.")
         )
+        .arg(clap::Arg::new("position_string")
+            .long("position-string")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .conflicts_with_all(&["rules", "hand", "cards_on_table", "stoss", "position", "position_file"])
+            .help("Complete description of the position")
+            .long_help("Complete description of the position, replacing --rules, --hand, --played-cards, --stoss and --position. It consists of seven or eight fields separated by semicolons: rules; hands of players 0 to 3 separated by | (__ for unknown cards); cards played so far; stoss as player@number of cards played before; doublings as one digit (0 or 1) per player; stock; player whose position is considered; optionally prices as key=value separated by spaces (keys price, extra, lauf-min and stoss-max). Example: \"Herz-Solo von 0; EO GO HO SO EU GU HU SU | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __; ; 1@0; 0000; 0; 0\". Missing prices default to 10/20/50, lauf-min 3 (2 for Wenz and Geier), at most 4 stosses. analyze prints such a description for each finding. Multiple occurrences may only differ in the hands.")
+        )
+        .arg(clap::Arg::new("position_file")
+            .long("position-file")
+            .takes_value(true)
+            .conflicts_with_all(&["rules", "hand", "cards_on_table", "stoss", "position"])
+            .help("File containing position descriptions")
+            .long_help("File containing one position description (see --position-string) per line. Empty lines and lines starting with # are ignored.")
+        )
         .arg(clap::Arg::new("simulate_hands")
             .long("simulate-hands")
             .takes_value(true)
@@ -102,6 +121,13 @@ pub fn subcommand_given_game(str_subcommand: &'static str, str_about: &'static s
             .help("Constrain simulated hands")
             .long_help("Constrain simulated hands so that certain criteria are fulfilled. Example: \"4<ctx.trumpf(0) && ctx.ea(1)\" only considers card distributions where player 0 has more than 4 Trumpf and player 1 has Eichel-Ass. (Players are numbere from 0 to 3, where 0 is the player to open the first stich (1, 2, 3 follow accordingly).)") // TODO improve docs
         )
+        .arg(clap::Arg::new("constrain_hands_file")
+            .long("constrain-hands-file")
+            .takes_value(true)
+            .multiple_occurrences(true)
+            .help("File containing named hand constraints")
+            .long_help("Rhai file containing a library of named constraints (see --constrain-hands). Each public function taking exactly one parameter (ctx) is used as a separate constraint, named after the function. Private functions can be used as helpers.")
+        )
 }
 
 pub fn with_common_args<FnWithArgs>(
@@ -109,14 +135,12 @@ pub fn with_common_args<FnWithArgs>(
     mut fn_with_args: FnWithArgs,
 ) -> Result<(), Error>
     where
-        for<'rules> FnWithArgs: FnMut(
-            Box<dyn Iterator<Item=EnumMap<EPlayerIndex, SHand>>+Send+'rules>,
-            &'rules SRules,
-            &SStichSequence,
-            &EnumMap<EPlayerIndex, SHand>, // TODO? Good idea? Could this simply given by itahand?
-            EPlayerIndex/*epi_position*/,
-            &SExpensifiers,
+        for<'gamestate> FnWithArgs: FnMut(
+            &SEngine,
+            &'gamestate SGameState,
+            Box<dyn Iterator<Item=EnumMap<EPlayerIndex, SHand>>+Send+'gamestate>,
             bool/*b_verbose*/,
+            SCheckpoint,
         ) -> Result<(), Error>,
 {
     let iteratehands = if_then_some!(let Some(str_itahand)=clapmatches.value_of("simulate_hands"),
@@ -137,69 +161,134 @@ pub fn with_common_args<FnWithArgs>(
     ).unwrap_or_else(|| {
         VChooseItAhand::All
     });
-    let vecotplconstraintstr = clapmatches.values_of("constrain_hands")
-        .map(|values_constrain_hands| -> Result<Vec<(SConstraint, &str)>, _> {
-            values_constrain_hands
-                .map(|str_constrain_hands|
-                    str_constrain_hands.parse::<SConstraint>()
-                        .map_err(|err| format_err!("Cannot parse hand constraints: {:?}", err))
-                        .map(|constraint| (
-                            constraint,
-                            str_constrain_hands,
-                        )),
-                )
-                .collect::<Result<Vec<_>,_>>()
-        })
-        .transpose()?
-        .map(|vectplconstraintstr: Vec<(SConstraint, &str)>| -> Vec<Option<(SConstraint, &str)>> {
-            vectplconstraintstr.into_iter().map(Some).collect()
-        })
-        .unwrap_or_else(|| vec!(None));
+    let mut vectplconstraintstr = clapmatches.values_of("constrain_hands")
+        .into_iter()
+        .flatten()
+        .map(|str_constrain_hands|
+            str_constrain_hands.parse::<SConstraint>()
+                .map_err(|err| format_err!("Cannot parse hand constraints: {:?}", err))
+                .map(|constraint| (
+                    constraint,
+                    str_constrain_hands.to_owned(),
+                )),
+        )
+        .collect::<Result<Vec<(SConstraint, String)>,_>>()?;
+    for str_path in clapmatches.values_of("constrain_hands_file").into_iter().flatten() {
+        vectplconstraintstr.extend(
+            SConstraint::vec_from_file(str_path)?
+                .into_iter()
+                .map(|constraint| {
+                    let str_constraint = constraint.to_string();
+                    (constraint, str_constraint)
+                })
+        );
+    }
+    let vecotplconstraintstr = if vectplconstraintstr.is_empty() {
+        vec!(None)
+    } else {
+        vectplconstraintstr.into_iter().map(Some).collect::<Vec<Option<(SConstraint, String)>>>()
+    };
     assert!(!vecotplconstraintstr.is_empty());
     assert!(vecotplconstraintstr.iter().map(Option::is_some).all_equal());
     let b_verbose = clapmatches.is_present("verbose");
-    let veccard_stichseq = match clapmatches.value_of("cards_on_table") { // TODO allow multiple stichseq (in particular something like "ea | ez ek e9  sa sz | sk s9" so that the user can query intermittent game states).
-        None => Vec::new(),
-        Some(str_cards_on_table) => cardvector::parse_cards(str_cards_on_table)
-            .ok_or_else(||format_err!("Could not parse played cards"))?,
-    };
-    let vectplvecocardstr_ahand = unwrap!(clapmatches.values_of("hand"))
-        .map(|str_ahand| 
-            cardvector::parse_optional_cards::<Vec<_>>(str_ahand)
-                .ok_or_else(||format_err!("Could not parse hand: {}", str_ahand))
-                .map(|vecocard| (vecocard, str_ahand))
+    let engine = SEngine::new(/*n_rank_rules_samples*/100);
+    let mut vecposition = clapmatches.values_of("position_string")
+        .into_iter()
+        .flatten()
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    if let Some(str_path) = clapmatches.value_of("position_file") {
+        vecposition.extend(
+            std::fs::read_to_string(str_path)?
+                .lines()
+                .map(str::trim)
+                .filter(|str_line| !str_line.is_empty() && !str_line.starts_with('#'))
+                .map(str::to_owned)
+        );
+    }
+    let vecposition = vecposition.iter()
+        .map(|str_position| str_position.parse::<SPosition>()
+            .map_err(|err| format_err!("Could not parse position {}: {}", str_position, err))
+        )
+        .collect::<Result<Vec<_>,_>>()?;
+    let (veccard_stichseq, vecn_cards_played_checkpoint, vectplvecocardstr_ahand, expensifiers, ovecrules, usersuppliedposition) = if let Some(position) = vecposition.first() {
+        let position_without_hands = |position: &SPosition| SPosition {
+            ahand: EPlayerIndex::map_from_fn(|_epi| SHand::new_from_iter(std::iter::empty::<ECard>())),
+            ..position.clone()
+        }.to_string();
+        let str_position_without_hands = position_without_hands(position);
+        if let Some(position_other) = vecposition.iter().find(|position_other| position_without_hands(position_other)!=str_position_without_hands) {
+            return Err(format_err!("Positions differ in more than the hands: {} vs. {}", position, position_other));
+        }
+        (
+            position.stichseq.visible_cards().map(|(_epi, card)| *card).collect(),
+            vec![position.stichseq.visible_cards().count()],
+            vecposition.iter()
+                .map(|position| (position.cards_with_holes(), position.to_string()))
+                .collect(),
+            position.expensifiers.clone(),
+            Some(vec![position.rules.clone()]),
+            VUserSuppliedPosition::Concrete(position.epi_position),
+        )
+    } else {
+        let mut veccard_stichseq = Vec::new();
+        let mut vecn_cards_played_checkpoint = Vec::new();
+        for str_cards_on_table in clapmatches.value_of("cards_on_table").unwrap_or("").split('|') {
+            veccard_stichseq.extend(cardvector::parse_cards::<Vec<_>>(str_cards_on_table.trim())
+                .ok_or_else(||format_err!("Could not parse played cards"))?);
+            vecn_cards_played_checkpoint.push(veccard_stichseq.len());
+        }
+        vecn_cards_played_checkpoint.dedup();
+        let vectplvecocardstr_ahand = unwrap!(clapmatches.values_of("hand"))
+            .map(|str_ahand| 
+                cardvector::parse_optional_cards::<Vec<_>>(str_ahand)
+                    .ok_or_else(||format_err!("Could not parse hand: {}", str_ahand))
+                    .map(|vecocard| (vecocard, str_ahand.to_owned()))
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+        let vecstoss = match clapmatches.value_of("stoss")
+            .map(|str_stoss| {
+                if str_stoss.trim().is_empty() {
+                    Ok(Vec::new())
+                } else {
+                    str_stoss
+                        .split(' ')
+                        .filter(|str_epi| !str_epi.is_empty())
+                        .map(|str_epi| str_epi.parse::<EPlayerIndex>()
+                            .map(|epi| SStoss {
+                                epi,
+                                n_cards_played: 0, // use --position-string for other timings
+                            })
+                        )
+                        .collect::<Result<Vec<_>, _>>()
+                }
+            })
+        {
+            Some(Ok(vecstoss)) => vecstoss,
+            None => Vec::new(),
+            Some(Err(e)) => return Err(format_err!("Could not parse stoss: {}", e)),
+        };
+        let expensifiers = SExpensifiers::new(
+            /*n_stock*/0, // use --position-string for other values
+            /*doublings*/SDoublings::new_full( // use --position-string for other values
+                SStaticEPI0{},
+                [false; EPlayerIndex::SIZE],
+            ),
+            vecstoss,
+        );
+        let ovecrules = clapmatches.values_of("rules")
+            .map(|values| values.map(rules_from_str).collect::<Result<Vec<_>,_>>())
+            .transpose()
+            .map_err(|err| format_err!("{}", err))?;
+        (
+            veccard_stichseq,
+            vecn_cards_played_checkpoint,
+            vectplvecocardstr_ahand,
+            expensifiers,
+            ovecrules,
+            unwrap!(clapmatches.get_one::<VUserSuppliedPosition>("position")).clone(),
         )
-        .collect::<Result<Vec<_>, _>>()?;
-    let vecstoss = match clapmatches.value_of("stoss")
-        .map(|str_stoss| {
-            if str_stoss.trim().is_empty() {
-                Ok(Vec::new())
-            } else {
-                str_stoss
-                    .split(' ')
-                    .filter(|str_epi| !str_epi.is_empty())
-                    .map(|str_epi| str_epi.parse::<EPlayerIndex>()
-                        .map(|epi| SStoss {
-                            epi,
-                            n_cards_played: 0, // TODO? make adjustable
-                        })
-                    )
-                    .collect::<Result<Vec<_>, _>>()
-            }
-        })
-    {
-        Some(Ok(vecstoss)) => vecstoss,
-        None => Vec::new(),
-        Some(Err(e)) => return Err(format_err!("Could not parse stoss: {}", e)),
     };
-    let expensifiers = SExpensifiers::new(
-        /*n_stock*/0, // TODO? make adjustable
-        /*doublings*/SDoublings::new_full( // TODO? make adjustable
-            SStaticEPI0{},
-            [false; EPlayerIndex::SIZE],
-        ),
-        vecstoss,
-    );
     for ((vecocard_hand, str_ahand), otplconstraintstr) in itertools::iproduct!(
         vectplvecocardstr_ahand.iter(),
         vecotplconstraintstr.iter() // TODO itertools support trailing comma
@@ -211,47 +300,38 @@ pub fn with_common_args<FnWithArgs>(
         if !veccard_duplicate.is_empty() {
             return Err(format_err!("Cards are used more than once: {}", veccard_duplicate.iter().join(", ")));
         }
-        let (itrules, b_single_rules) = match clapmatches.values_of("rules")
-            .map(|values| values.map(parse_rule_description_simple))
-            .into_iter()
-            .flatten()
-            .collect::<Result<Vec<_>,_>>()
-        {
-            Ok(vecrules) => {
-                if vecrules.is_empty() {
-                    let ruleset = openschafkopf_shared_args::get_ruleset(clapmatches)?;
-                    (
-                        Box::new(ruleset
-                            .avecrulegroup.into_raw().into_iter()
-                            .flat_map(|vecrulegroup|
-                                vecrulegroup.into_iter().flat_map(|rulegroup| {
-                                    rulegroup.vecorules.into_iter()
-                                        .filter_map(|orules|
-                                            orules.as_ref().map(|rules|
-                                                SRules::from(rules.clone())
-                                            )
+        let (itrules, b_single_rules) = match ovecrules.clone() {
+            None => {
+                let ruleset = openschafkopf_shared_args::get_ruleset(clapmatches)?;
+                (
+                    Box::new(ruleset
+                        .avecrulegroup.into_raw().into_iter()
+                        .flat_map(|vecrulegroup|
+                            vecrulegroup.into_iter().flat_map(|rulegroup| {
+                                rulegroup.vecorules.into_iter()
+                                    .filter_map(|orules|
+                                        orules.as_ref().map(|rules|
+                                            SRules::from(rules.clone())
                                         )
-                                })
-                            )
-                            .chain(match ruleset.stockorramsch {
-                                VStockOrT::Stock(_) => None,
-                                VStockOrT::OrT(rules) => Some(rules.into())
+                                    )
                             })
-                        ) as Box<dyn Iterator<Item=SRules>>,
-                        /*b_single_rules*/false,
-                    )
-                } else {
-                    let b_single_rules = vecrules.len()==1;
-                    (Box::new(vecrules.into_iter()) as Box<dyn Iterator<Item=SRules>>, b_single_rules)
-                }
+                        )
+                        .chain(match ruleset.stockorramsch {
+                            VStockOrT::Stock(_) => None,
+                            VStockOrT::OrT(rules) => Some(rules.into())
+                        })
+                    ) as Box<dyn Iterator<Item=SRules>>,
+                    /*b_single_rules*/false,
+                )
             },
-            Err(err) => {
-                return Err(format_err!("Could not parse rules: {}", err));
+            Some(vecrules) => {
+                let b_single_rules = vecrules.len()==1;
+                (Box::new(vecrules.into_iter()) as Box<dyn Iterator<Item=SRules>>, b_single_rules)
             },
         };
         for rules in itrules {
             let rules = &rules;
-			let oepi_position_concrete = match unwrap!(clapmatches.get_one("position")) {
+			let oepi_position_concrete = match &usersuppliedposition {
 				VUserSuppliedPosition::CurrentPlayer => None, // To be determined with stichseq
 				VUserSuppliedPosition::Concrete(epi_position_concrete) => {
 					Some(*epi_position_concrete)
@@ -263,26 +343,27 @@ pub fn with_common_args<FnWithArgs>(
 					)
 				},
 			};
-            let (stichseq, ahand_with_holes, epi_position) = EKurzLang::values()
-                .filter_map(|ekurzlang| {
+            let (vectplstichseqahandepi, vecstr_err) : (Vec<_>, Vec<_>) = EKurzLang::values()
+                .map(|ekurzlang| {
                     let mut stichseq = SStichSequence::new(ekurzlang);
-                    for &card in veccard_stichseq.iter() {
-                        if !ekurzlang.supports_card(card)
-                            || stichseq.game_finished()
-                        {
-                            return None; // TODO? distinguish error
+                    for (i_card, &card) in veccard_stichseq.iter().enumerate() {
+                        if !ekurzlang.supports_card(card) {
+                            return Err(format!("{:?}: Played card number {} ({}) not supported", ekurzlang, i_card+1, card));
+                        }
+                        if stichseq.game_finished() {
+                            return Err(format!("{:?}: Played card number {} ({}) after game has finished", ekurzlang, i_card+1, card));
                         }
                         stichseq.zugeben(card, rules);
                     }
                     let epi_position = oepi_position_concrete.unwrap_or_else(||
 						unwrap!(stichseq.current_stich().current_playerindex())
 					);
+                    let n_cards_total = stichseq.kurzlang().cards_per_player()*EPlayerIndex::SIZE;
                     if_then_some!(
                         stichseq.remaining_cards_per_hand()[epi_position]==vecocard_hand.len(),
                         (SHand::new_from_iter(vecocard_hand.iter().flatten()), epi_position)
                             .to_ahand()
                     ).or_else(|| {
-                        let n_cards_total = stichseq.kurzlang().cards_per_player()*EPlayerIndex::SIZE;
                         if_then_some!(stichseq.visible_cards().count()+vecocard_hand.len()==n_cards_total, {
                             let mut i_card_lo = 0;
                             EPlayerIndex::map_from_raw(stichseq.remaining_cards_per_hand().as_raw().map(|n_remaining| {
@@ -297,14 +378,48 @@ pub fn with_common_args<FnWithArgs>(
                             }))
                         })
                     })
-                    .map(|ahand| (stichseq, ahand, epi_position))
+                    .map(|ahand| (stichseq.clone(), ahand, epi_position))
+                    .ok_or_else(|| format!("{:?}: Hand must contain either {} cards (player {}) or {} cards (all players), got {}",
+                        ekurzlang,
+                        stichseq.remaining_cards_per_hand()[epi_position],
+                        epi_position,
+                        n_cards_total - stichseq.visible_cards().count(),
+                        vecocard_hand.len(),
+                    ))
                 })
+                .partition_result();
+            let (stichseq, ahand_with_holes, epi_position) = vectplstichseqahandepi.into_iter()
                 .exactly_one()
-                .map_err(|err| format_err!("Could not determine ekurzlang: {}", err))?;
+                .map_err(|_| if vecstr_err.len()==EKurzLang::SIZE {
+                    format_err!("Could not determine ekurzlang: {}", vecstr_err.join("; "))
+                } else {
+                    format_err!("Could not determine ekurzlang: Played cards and hand fit several decks")
+                })?;
 			assert!(
 				oepi_position_concrete.is_none() || oepi_position_concrete==Some(epi_position)
 			);
-            // TODO check that everything is ok (no duplicate cards, cards are allowed, current stich not full, etc.)
+            let gamestate = match SGameState::try_from(SPosition::new(rules, &stichseq, &ahand_with_holes, &expensifiers, epi_position)) {
+                Ok(gamestate) => gamestate,
+                Err(VApiError::InvalidPosition(err)) => {
+                    if b_single_rules {
+                        return Err(format_err!("Invalid position for {}: {}", SDisplayRules::new(rules, /*b_include_playerindex*/true), err));
+                    } else {
+                        if b_verbose {
+                            println!("Invalid position for {}: {}", SDisplayRules::new(rules, /*b_include_playerindex*/true), err);
+                        }
+                        continue;
+                    }
+                },
+                Err(err) => return Err(format_err!("{}", err)),
+            };
+            if let Some((constraint, str_constraint)) = otplconstraintstr {
+                let mapepin_remaining = stichseq.remaining_cards_per_hand();
+                if EPlayerIndex::values().all(|epi| ahand_with_holes[epi].cards().len()==mapepin_remaining[epi])
+                    && !constraint.eval(&stichseq, &ahand_with_holes, rules.clone(), &expensifiers)
+                {
+                    return Err(format_err!("Hands {} do not fulfill constraint {}", display_card_slices(&ahand_with_holes, rules, " | "), str_constraint));
+                }
+            }
             if let Some(epi_active) = rules.playerindex() {
                 let veccard_hand_active = stichseq.cards_from_player(&ahand_with_holes[epi_active], epi_active)
                     .collect::<Vec<_>>();
@@ -323,127 +438,191 @@ pub fn with_common_args<FnWithArgs>(
                     // let hand iterators try to generate valid hands.
                 }
             }
-            let mapepin_cards_per_hand = stichseq.remaining_cards_per_hand();
-            for epi in EPlayerIndex::values() {
-                assert!(ahand_with_holes[epi].cards().len() <= mapepin_cards_per_hand[epi]);
+            if b_verbose || !b_single_rules {
+                println!("Rules: {}", SDisplayRules::new(rules, /*b_include_playerindex*/true));
             }
-            macro_rules! forward{($n_ahand_total: expr, $itahand_factory: expr, $fn_take: expr) => {{ // TODORUST generic closures
-                let mut n_ahand_seen = 0;
-                let mut n_ahand_valid = 0;
-                if b_verbose || !b_single_rules {
-                    println!("Rules: {}", SDisplayRules::new(rules, /*b_include_playerindex*/true));
-                }
-                if b_verbose
-                    || 1</*b_single_itahand*/vectplvecocardstr_ahand.len()
-                    || 1<vecotplconstraintstr.len()
-                {
-                    println!("Hand(s): {} {}",
-                        str_ahand,
-                        match otplconstraintstr {
-                            Some((_constraint, str_constraint)) if 1<vecotplconstraintstr.len() => {
-                                format!("[{}]", str_constraint)
-                            },
-                            _ => "".to_string(),
+            if b_verbose
+                || 1</*b_single_itahand*/vectplvecocardstr_ahand.len()
+                || 1<vecotplconstraintstr.len()
+            {
+                println!("Hand(s): {} {}",
+                    str_ahand,
+                    match otplconstraintstr {
+                        Some((_constraint, str_constraint)) if 1<vecotplconstraintstr.len() => {
+                            format!("[{}]", str_constraint)
                         },
-                    );
+                        _ => "".to_string(),
+                    },
+                );
+            }
+            for (i_checkpoint, &n_cards_played_checkpoint) in vecn_cards_played_checkpoint.iter().enumerate() {
+                let checkpoint = SCheckpoint {
+                    i_checkpoint,
+                    n_checkpoints: vecn_cards_played_checkpoint.len(),
+                };
+                // Earlier checkpoints are seen by the player to play next, knowing only cards that have been
+                // given as fixed or that are played later on. Hands are sampled separately for each checkpoint.
+                let gamestate = if i_checkpoint+1==checkpoint.n_checkpoints {
+                    assert_eq!(n_cards_played_checkpoint, gamestate.stichseq().visible_cards().count());
+                    gamestate.clone()
+                } else {
+                    gamestate.checkpoint(n_cards_played_checkpoint)
+                        .map_err(|err| format_err!("{}", err))?
+                };
+                let mapepin_cards_per_hand = gamestate.stichseq().remaining_cards_per_hand();
+                for epi in EPlayerIndex::values() {
+                    assert!(gamestate.known_cards(epi).len() <= mapepin_cards_per_hand[epi]);
                 }
-                fn_with_args(
-                    Box::new(
-                        #[allow(clippy::redundant_closure_call)]
-                        $fn_take($itahand_factory(
-                            &stichseq,
-                            ahand_with_holes.clone(),
-                            rules,
-                            &expensifiers.vecstoss,
-                            /*fn_inspect*/|b_valid_so_far, ahand| {
-                                n_ahand_seen += 1;
-                                let b_valid = b_valid_so_far
-                                    && otplconstraintstr.as_ref().map_or(true, |(constraint, _str_constraint)|
-                                        constraint.eval(&stichseq, ahand, rules.clone())
-                                    );
+                let (handselection, n_ahand_total) = match (&iteratehands, rules.playerindex()) {
+                    (VChooseItAhand::All, _oepi_active) => {
+                        let mut n_cards_unknown = mapepin_cards_per_hand.iter().sum::<usize>()
+                            - EPlayerIndex::values().map(|epi| gamestate.known_cards(epi).len()).sum::<usize>();
+                        let n_ahand_total = EPlayerIndex::values()
+                            .fold(1u64, |n_ahand_total, epi| {
+                                let n_cards_sampled = mapepin_cards_per_hand[epi]-gamestate.known_cards(epi).len();
+                                let n_binom = num_integer::binomial(
+                                    n_cards_unknown.as_num::<u64>(),
+                                    n_cards_sampled.as_num::<u64>(),
+                                );
+                                n_cards_unknown -= n_cards_sampled;
+                                n_ahand_total*n_binom
+                            });
+                        (VHandSelection::All, n_ahand_total)
+                    },
+                    (VChooseItAhand::Sample(n_samples, None), _oepi_active) | (VChooseItAhand::Sample(n_samples, Some(_)), None) => {
+                        (VHandSelection::Sample{n_samples: *n_samples}, n_samples.as_num::<u64>())
+                    },
+                    (VChooseItAhand::Sample(n_samples, Some(n_pool)), Some(_epi_active)) => {
+                        (VHandSelection::SampleLikely{n_samples: *n_samples, n_pool: *n_pool}, n_samples.as_num::<u64>())
+                    },
+                };
+                let (stichseq, expensifiers) = (gamestate.stichseq(), gamestate.expensifiers());
+                let mut n_ahand_seen = 0;
+                let mut n_ahand_valid = 0;
+                let itahand = engine.hands(
+                    &gamestate,
+                    handselection,
+                    /*fn_filter*/move |b_valid_so_far, ahand| {
+                        n_ahand_seen += 1;
+                        let b_valid = b_valid_so_far
+                            && otplconstraintstr.as_ref().is_none_or(|(constraint, _str_constraint)|
+                                constraint.eval(stichseq, ahand, rules.clone(), expensifiers)
+                            );
+                        if b_valid {
+                            n_ahand_valid += 1;
+                        }
+                        if b_verbose {
+                            println!("{} {}/{}/{} {}",
                                 if b_valid {
-                                    n_ahand_valid += 1;
-                                }
-                                if b_verbose {
-                                    println!("{} {}/{}/{} {}",
-                                        if b_valid {
-                                            '>'
-                                        } else {
-                                            '|'
-                                        },
-                                        n_ahand_valid,
-                                        n_ahand_seen,
-                                        $n_ahand_total,
-                                        display_card_slices(&ahand, rules, " | "),
-                                    )
-                                }
-                                b_valid
-                            }
-                        ))
-                    ),
-                    rules,
-                    &stichseq,
-                    &ahand_with_holes,
-                    epi_position,
-                    &expensifiers,
+                                    '>'
+                                } else {
+                                    '|'
+                                },
+                                n_ahand_valid,
+                                n_ahand_seen,
+                                n_ahand_total,
+                                display_card_slices(ahand, rules, " | "),
+                            )
+                        }
+                        b_valid
+                    },
+                );
+                fn_with_args(
+                    &engine,
+                    &gamestate,
+                    itahand,
                     b_verbose,
+                    checkpoint,
                 )?;
-            }}}
-            match (&iteratehands, rules.playerindex()) {
-                (VChooseItAhand::All, _oepi_active) => {
-                    let mut n_cards_unknown = mapepin_cards_per_hand.iter().sum::<usize>()
-                        - ahand_with_holes.iter().map(|hand| hand.cards().len()).sum::<usize>();
-                    let n_ahand_total = EPlayerIndex::values()
-                        .fold(1u64, |n_ahand_total, epi| {
-                            let n_cards_sampled = mapepin_cards_per_hand[epi]-ahand_with_holes[epi].cards().len();
-                            let n_binom = num_integer::binomial(
-                                n_cards_unknown.as_num::<u64>(),
-                                n_cards_sampled.as_num::<u64>(),
-                            );
-                            n_cards_unknown -= n_cards_sampled;
-                            n_ahand_total*n_binom
-                        });
-                    forward!(n_ahand_total, internal_all_possible_hands, |itahand| itahand)
-                },
-                (VChooseItAhand::Sample(n_samples, None), _oepi_active) => {
-                    forward!(n_samples, internal_forever_rand_hands, |itahand| Iterator::take(itahand, *n_samples))
-                },
-                (VChooseItAhand::Sample(n_samples, Some(_n_pool)), None) => {
-                    forward!(n_samples, internal_forever_rand_hands, |itahand| Iterator::take(itahand, *n_samples))
-                },
-                (VChooseItAhand::Sample(n_samples, Some(n_pool)), Some(epi_active)) => {
-                    forward!(
-                        *n_samples,
-                        internal_forever_rand_hands,
-                        |itahand_pool| {
-                            Iterator::take(itahand_pool, *n_pool)
-                                .map(|ahand: EnumMap<EPlayerIndex, SHand>| {
-                                    let payout = SAi::new_simulating(
-                                        /*n_rank_rules_samples*/100,
-                                        /*n_suggest_card_branches*/1,
-                                        /*n_suggest_card_samples*/0,
-                                    ).rank_rules(
-                                        SFullHand::new(
-                                            &stichseq.cards_from_player(
-                                                &ahand[epi_active],
-                                                epi_active,
-                                            ).collect::<Vec<_>>(),
-                                            stichseq.kurzlang(),
-                                        ),
-                                        epi_active,
-                                        rules,
-                                        &expensifiers,
-                                    ).omaxselfishmin.as_ref().unwrap_static_some().avg();
-                                    (ahand, payout)
-                                })
-                                .k_largest_by(*n_samples, |tplahandpayout_lhs, tplahandpayout_rhs| unwrap!(tplahandpayout_lhs.1.partial_cmp(&tplahandpayout_rhs.1)))
-                                .map(|(ahand, _payout)| ahand)
-                        }
-                    )
-                },
-            };
+            }
         }
     }
     Ok(())
 }
 
+#[test]
+fn test_checkpoints_sampled_separately() {
+    let rules = unwrap!(rules_from_str("Herz-Solo von 0"));
+    let mut ahand = EPlayerIndex::map_from_fn(|_epi| SHand::new_from_iter(std::iter::empty::<ECard>()));
+    for (i_card, card) in <ECard as PlainEnum>::values().filter(|card| EKurzLang::Lang.supports_card(*card)).enumerate() {
+        ahand[unwrap!(EPlayerIndex::checked_from_usize(i_card % EPlayerIndex::SIZE))].add_card(card);
+    }
+    let mut stichseq = SStichSequence::new(EKurzLang::Lang);
+    let mut veccard_played = Vec::new();
+    while veccard_played.len() < 24 {
+        let epi = unwrap!(stichseq.current_stich().current_playerindex());
+        let card = rules.all_allowed_cards(&stichseq, &ahand[epi])[0];
+        ahand[epi].play_card(card);
+        stichseq.zugeben(card, &rules);
+        veccard_played.push(card);
+    }
+    let epi_last = unwrap!(stichseq.current_stich().current_playerindex());
+    let str_hand_last = ahand[epi_last].cards().iter().join(" ");
+    // known to the player at the checkpoint: own hand and the cards played afterwards
+    let n_cards_played_checkpoint = 22;
+    let mut stichseq_checkpoint = SStichSequence::new(EKurzLang::Lang);
+    for &card in veccard_played[..n_cards_played_checkpoint].iter() {
+        stichseq_checkpoint.zugeben(card, &rules);
+    }
+    let mut ahand_checkpoint = EPlayerIndex::map_from_fn(|epi| if epi==epi_last {
+        ahand[epi].clone()
+    } else {
+        SHand::new_from_iter(std::iter::empty::<ECard>())
+    });
+    for (epi, &card) in stichseq.visible_cards().skip(n_cards_played_checkpoint) {
+        ahand_checkpoint[epi].add_card(card);
+    }
+    let str_position_checkpoint = SPosition::new(
+        &rules,
+        &stichseq_checkpoint,
+        &ahand_checkpoint,
+        &SExpensifiers::new(/*n_stock*/0, SDoublings::new_full(SStaticEPI0{}, [false; EPlayerIndex::SIZE]), /*vecstoss*/Vec::new()),
+        unwrap!(stichseq_checkpoint.current_stich().current_playerindex()),
+    ).to_string();
+    let collect_calls = |slcstr_arg: &[&str]| {
+        let mut vectplcall = Vec::new();
+        unwrap!(with_common_args(
+            &subcommand_given_game("suggest-card", "").get_matches_from(
+                ["suggest-card", "--simulate-hands", "all"].iter().chain(slcstr_arg.iter())
+            ),
+            |_engine, gamestate, itahand, _b_verbose, checkpoint| {
+                let cards_to_string = |slccard: &[ECard]| slccard.iter().map(|card| card.to_usize()).sorted().join(" ");
+                vectplcall.push((
+                    gamestate.stichseq().visible_cards().map(|(_epi, card)| card).join(" "),
+                    gamestate.position(),
+                    EPlayerIndex::values().map(|epi| cards_to_string(gamestate.known_cards(epi))).join(" | "),
+                    itahand.map(|ahand| ahand.iter().map(|hand| cards_to_string(hand.cards())).join(" | ")).sorted().collect::<Vec<_>>(),
+                    checkpoint,
+                ));
+                Ok(())
+            },
+        ));
+        vectplcall
+    };
+    let vectplcall_checkpointed = collect_calls(&[
+        "--rules", "Herz-Solo von 0",
+        "--hand", &str_hand_last,
+        "--played-cards", &format!("{} | {}",
+            veccard_played[..n_cards_played_checkpoint].iter().join(" "),
+            veccard_played[n_cards_played_checkpoint..].iter().join(" "),
+        ),
+    ]);
+    let vectplcall_single = collect_calls(&["--position-string", &str_position_checkpoint]).into_iter()
+        .chain(collect_calls(&[
+            "--rules", "Herz-Solo von 0",
+            "--hand", &str_hand_last,
+            "--played-cards", &veccard_played.iter().join(" "),
+        ]))
+        .collect::<Vec<_>>();
+    assert_eq!(vectplcall_checkpointed.len(), 2);
+    assert_eq!(vectplcall_single.len(), 2);
+    for (i_checkpoint, (tplcall_checkpointed, tplcall_single)) in vectplcall_checkpointed.iter().zip_eq(vectplcall_single.iter()).enumerate() {
+        assert_eq!(tplcall_checkpointed.4, SCheckpoint{i_checkpoint, n_checkpoints: 2});
+        assert_eq!(tplcall_single.4, SCheckpoint{i_checkpoint: 0, n_checkpoints: 1});
+        assert_eq!(tplcall_checkpointed.0, tplcall_single.0);
+        assert_eq!(tplcall_checkpointed.1, tplcall_single.1);
+        assert_eq!(tplcall_checkpointed.2, tplcall_single.2);
+        assert!(!tplcall_checkpointed.3.is_empty());
+        assert_eq!(tplcall_checkpointed.3, tplcall_single.3);
+    }
+}
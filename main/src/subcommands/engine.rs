@@ -0,0 +1,568 @@
+use openschafkopf_lib::{
+    api::{SBudget, SEngine, SGameState, VApiError, VHandSelection, cards_from_str, games_from_str, position_from_str, rules_from_str},
+    primitives::*,
+    rules::{SDisplayRules, ruleset::VStockOrT},
+};
+use as_num::AsNum;
+use itertools::Itertools;
+use openschafkopf_util::*;
+use plain_enum::PlainEnum;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
+        Mutex,
+    },
+};
+use super::handconstraint::SConstraint;
+
+// JSON-RPC 2.0 (see https://www.jsonrpc.org/specification), one message per line.
+// Requests are processed concurrently (by at most --threads threads), so responses may arrive out of order.
+// Ids of running requests must be unique; a request reusing one is answered with error code -32600.
+// Invalid params are reported with error code -32602, failures within the engine with -32603.
+//
+// Methods (params given as object; positions as described in lib/src/game_analysis/position.rs):
+//   validate_position {position}
+//     -> {valid: true}, or an error describing why the position is invalid
+//   suggest_card {position, samples?, branches?}
+//     -> {card, nAhand, vecCard: [{strCard, avg, histograms}], combined: {avg, histograms} or null}
+//     avg maps each strategy (minmin, maxmin, maxselfishmin, maxselfishmax, max) to the average payout,
+//     histograms maps it to [[payout, count]]; vecCard is empty if no search was needed
+//     sends notifications "progress" {id, nAhand, vecCard} while computing
+//     the hand of the player to move must be known; known cards of other players are respected
+//   rank_rules {hand, playerIndex, rules: [rules], samples?, stock?, doublings?}
+//     -> [{rules, min, avg, max}] for each rules (as accepted by parse_rule_description_simple)
+//     doublings as one bool per player, starting with player 0
+//   analyze_game {game, maxRemainingCards?}
+//     -> {rules, payout, mistakes: [{nCardsPlayed, playerIndex, card, betterCards, payout, mistake}]}
+//     game can be anything the analyze subcommand understands (sauspiel.de html or json, plain text,
+//     NetSchafkopf protocols, game records); only the first game is analyzed
+//   hand_stats {position, inspect: [expression], groupBy?: expression, samples?}
+//     -> the same as hand-stats --json, i.e. {n_total, ostr_group_by, vecstatsline, vecstatsmean}
+//     expressions as in the hand-stats subcommand; hands are sampled with respect to all known cards
+//   cancel {id}
+//     -> {cancelled: bool}; the cancelled request is answered with error code -32800
+pub fn subcommand(str_subcommand: &'static str) -> clap::Command<'static> {
+    clap::Command::new(str_subcommand)
+        .about("Long-running engine server speaking JSON-RPC")
+        .long_about("Long-running engine server speaking JSON-RPC 2.0 (one message per line) on stdin/stdout or on a local TCP port. Supported methods: validate_position, suggest_card, rank_rules, analyze_game, hand_stats, cancel. The protocol is described in main/src/subcommands/engine.rs.")
+        .arg(clap::Arg::new("tcp")
+            .long("tcp")
+            .takes_value(true)
+            .help("Listen on this port on localhost instead of using stdin/stdout")
+        )
+        .arg(clap::Arg::new("threads")
+            .long("threads")
+            .takes_value(true)
+            .help("Number of threads processing requests (defaults to the number of CPUs)")
+            .long_help("Number of threads processing requests, shared by all clients. Requests exceeding this number wait until a thread is free. Defaults to the number of CPUs.")
+        )
+}
+
+const N_ERROR_PARSE : i64 = -32700;
+const N_ERROR_INVALID_REQUEST : i64 = -32600;
+const N_ERROR_METHOD_NOT_FOUND : i64 = -32601;
+const N_ERROR_INVALID_PARAMS : i64 = -32602;
+const N_ERROR_INTERNAL : i64 = -32603;
+const N_ERROR_REQUEST_CANCELLED : i64 = -32800; // as in the language server protocol
+
+struct SRpcError {
+    n_code: i64,
+    str_message: String,
+}
+
+fn invalid_params(str_message: impl std::fmt::Display) -> SRpcError {
+    SRpcError {
+        n_code: N_ERROR_INVALID_PARAMS,
+        str_message: str_message.to_string(),
+    }
+}
+
+fn internal_error(str_message: impl std::fmt::Display) -> SRpcError {
+    SRpcError {
+        n_code: N_ERROR_INTERNAL,
+        str_message: str_message.to_string(),
+    }
+}
+
+fn request_cancelled() -> SRpcError {
+    SRpcError {
+        n_code: N_ERROR_REQUEST_CANCELLED,
+        str_message: "Request cancelled".to_owned(),
+    }
+}
+
+fn param<'json>(jsonval_params: &'json serde_json::Value, str_name: &str) -> Result<&'json serde_json::Value, SRpcError> {
+    jsonval_params.get(str_name)
+        .ok_or_else(|| invalid_params(format!("Missing param: {str_name}")))
+}
+
+fn param_str<'json>(jsonval_params: &'json serde_json::Value, str_name: &str) -> Result<&'json str, SRpcError> {
+    param(jsonval_params, str_name)?.as_str()
+        .ok_or_else(|| invalid_params(format!("{str_name} must be a string")))
+}
+
+fn param_usize_or(jsonval_params: &serde_json::Value, str_name: &str, n_default: usize) -> Result<usize, SRpcError> {
+    match jsonval_params.get(str_name) {
+        None => Ok(n_default),
+        Some(jsonval) => jsonval.as_u64()
+            .and_then(|n| usize::try_from(n).ok())
+            .ok_or_else(|| invalid_params(format!("{str_name} must be a non-negative integer"))),
+    }
+}
+
+fn param_vecstr<'json>(jsonval_params: &'json serde_json::Value, str_name: &str) -> Result<Vec<&'json str>, SRpcError> {
+    param(jsonval_params, str_name)?.as_array()
+        .and_then(|vecjsonval| vecjsonval.iter().map(serde_json::Value::as_str).collect::<Option<Vec<_>>>())
+        .ok_or_else(|| invalid_params(format!("{str_name} must be an array of strings")))
+}
+
+fn param_position(jsonval_params: &serde_json::Value) -> Result<SGameState, SRpcError> {
+    position_from_str(param_str(jsonval_params, "position")?).map_err(invalid_params)
+}
+
+fn send_message(sendjson: &Sender<serde_json::Value>, jsonval: serde_json::Value) {
+    // Errors mean that the client is gone, so we drop the message.
+    let _ = sendjson.send(jsonval);
+}
+
+fn suggest_card(
+    jsonval_id: &serde_json::Value,
+    jsonval_params: &serde_json::Value,
+    b_cancel: &AtomicBool,
+    sendjson: &Sender<serde_json::Value>,
+) -> Result<serde_json::Value, SRpcError> {
+    let gamestate = param_position(jsonval_params)?;
+    let budget = SBudget::new(
+        param_usize_or(jsonval_params, "samples", /*n_default*/1000)?,
+        param_usize_or(jsonval_params, "branches", /*n_default*/2)?,
+    ).prefer_likely_hands();
+    let suggestion = SEngine::new(/*n_rank_rules_samples*/100).suggest_streaming(
+        &gamestate,
+        budget,
+        b_cancel,
+        /*fn_progress*/|progress| send_message(sendjson, json!({
+            "jsonrpc": "2.0",
+            "method": "progress",
+            "params": {
+                "id": jsonval_id,
+                "nAhand": progress.n_ahand,
+                "vecCard": super::webext::json_card_evaluations(&progress.veccardevaluation),
+            },
+        })),
+    ).map_err(|err| match err {
+        VApiError::Cancelled => request_cancelled(),
+        VApiError::NoCardDistribution => internal_error(err),
+        _ => invalid_params(err),
+    })?;
+    Ok(json!({
+        "card": suggestion.card.to_string(),
+        "nAhand": suggestion.n_ahand,
+        "vecCard": super::webext::json_card_evaluations(&suggestion.veccardevaluation),
+        "combined": suggestion.opayouts_combined.as_ref().map(super::webext::json_payouts),
+    }))
+}
+
+fn rank_rules(jsonval_params: &serde_json::Value, b_cancel: &AtomicBool) -> Result<serde_json::Value, SRpcError> {
+    let veccard = cards_from_str(param_str(jsonval_params, "hand")?).map_err(invalid_params)?;
+    let epi_rank = EPlayerIndex::checked_from_usize(param_usize_or(jsonval_params, "playerIndex", /*n_default*/0)?)
+        .ok_or_else(|| invalid_params("playerIndex must be between 0 and 3"))?;
+    let engine = SEngine::new(/*n_rank_rules_samples*/param_usize_or(jsonval_params, "samples", /*n_default*/50)?);
+    let n_stock = param_usize_or(jsonval_params, "stock", /*n_default*/0)?.as_num::<isize>();
+    let ab_doubling = match jsonval_params.get("doublings") {
+        None => [false; EPlayerIndex::SIZE],
+        Some(jsonval_doublings) => jsonval_doublings.as_array()
+            .and_then(|vecjsonval| vecjsonval.iter().map(serde_json::Value::as_bool).collect::<Option<Vec<_>>>())
+            .and_then(|vecb_doubling| vecb_doubling.into_iter().collect_array::<{EPlayerIndex::SIZE}>())
+            .ok_or_else(|| invalid_params(format!("doublings must be {} bools", EPlayerIndex::SIZE)))?,
+    };
+    param_vecstr(jsonval_params, "rules")?.into_iter()
+        .map(|str_rules| {
+            if b_cancel.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+            let rulesevaluation = rules_from_str(str_rules)
+                .and_then(|rules| engine.evaluate_given_rules(&veccard, epi_rank, &rules, n_stock, ab_doubling))
+                .map_err(invalid_params)?;
+            Ok(Some(json!({
+                "rules": rulesevaluation.orules.as_ref().map(|rules| SDisplayRules::new(rules, /*b_include_playerindex*/true).to_string()),
+                "min": rulesevaluation.n_payout_min,
+                "avg": rulesevaluation.f_payout_avg,
+                "max": rulesevaluation.n_payout_max,
+            })))
+        })
+        .collect::<Result<Option<Vec<_>>, _>>()
+        .and_then(|ovecjsonval| ovecjsonval
+            .map(serde_json::Value::from)
+            .ok_or_else(request_cancelled)
+        )
+}
+
+fn analyze_game(jsonval_params: &serde_json::Value, b_cancel: &AtomicBool) -> Result<serde_json::Value, SRpcError> {
+    let str_game = param_str(jsonval_params, "game")?;
+    let n_max_remaining_cards = param_usize_or(jsonval_params, "maxRemainingCards", /*n_default*/4)?;
+    let gameresult = games_from_str(str_game).map_err(invalid_params)?
+        .swap_remove(0); // games_from_str yields at least one game
+    let game = match gameresult.stockorgame {
+        VStockOrT::Stock(_) => return Err(invalid_params("Nothing to analyze.")),
+        VStockOrT::OrT(game) => game,
+    };
+    let gameanalysis = super::analyze::analyze_game(game, n_max_remaining_cards, /*b_simulate_all_hands*/false, b_cancel);
+    if b_cancel.load(Ordering::Relaxed) {
+        return Err(request_cancelled());
+    }
+    Ok(json!({
+        "rules": SDisplayRules::new(&gameanalysis.game.rules, /*b_include_playerindex*/true).to_string(),
+        "payout": gameresult.an_payout.into_raw(),
+        "mistakes": gameanalysis.vecanalysispercard.iter()
+            .filter_map(|analysispercard| analysispercard.oanalysisimpr.as_ref().map(|analysisimpr| {
+                let cardandpayout = &analysisimpr.cardandpayout_cheating;
+                json!({
+                    "nCardsPlayed": analysispercard.stichseq.visible_cards().count(),
+                    "playerIndex": unwrap!(analysispercard.stichseq.current_stich().current_playerindex()).to_usize(),
+                    "card": analysispercard.card_played.to_string(),
+                    "betterCards": cardandpayout.veccard.iter().map(ECard::to_string).collect::<Vec<_>>(),
+                    "payout": cardandpayout.n_payout,
+                    "mistake": format!("{:?}", cardandpayout.emistake),
+                })
+            }))
+            .collect::<Vec<_>>(),
+    }))
+}
+
+fn hand_stats(jsonval_params: &serde_json::Value, b_cancel: &AtomicBool) -> Result<serde_json::Value, SRpcError> {
+    let gamestate = param_position(jsonval_params)?;
+    let parse_constraint = |str_constraint: &str| str_constraint.parse::<SConstraint>()
+        .map_err(|_| invalid_params(format!("Cannot parse expression: {str_constraint}")));
+    let vecconstraint = param_vecstr(jsonval_params, "inspect")?.into_iter()
+        .map(parse_constraint)
+        .collect::<Result<Vec<_>, _>>()?;
+    let oconstraint_group_by = jsonval_params.get("groupBy")
+        .map(|_jsonval_group_by| param_str(jsonval_params, "groupBy").and_then(parse_constraint))
+        .transpose()?;
+    let n_samples = param_usize_or(jsonval_params, "samples", /*n_default*/1000)?;
+    let engine = SEngine::new(/*n_rank_rules_samples*/100);
+    let statsjson = super::hand_stats::hand_stats(
+        &engine,
+        engine.hands(&gamestate, VHandSelection::Sample{n_samples}, /*fn_filter*/|b_valid, _ahand| b_valid)
+            .take_while(|_ahand| !b_cancel.load(Ordering::Relaxed)),
+        &gamestate,
+        &vecconstraint,
+        oconstraint_group_by.as_ref(),
+    );
+    if b_cancel.load(Ordering::Relaxed) {
+        return Err(request_cancelled());
+    }
+    serde_json::to_value(&statsjson).map_err(internal_error)
+}
+
+fn respond(jsonval_id: &serde_json::Value, resjsonval: Result<serde_json::Value, SRpcError>, sendjson: &Sender<serde_json::Value>) {
+    if jsonval_id.is_null() && resjsonval.is_ok() {
+        return; // notifications are not answered
+    }
+    send_message(sendjson, match resjsonval {
+        Ok(jsonval_result) => json!({
+            "jsonrpc": "2.0",
+            "id": jsonval_id,
+            "result": jsonval_result,
+        }),
+        Err(SRpcError{n_code, str_message}) => {
+            warn!("Communicating error: {str_message}");
+            json!({
+                "jsonrpc": "2.0",
+                "id": jsonval_id,
+                "error": {
+                    "code": n_code,
+                    "message": str_message,
+                },
+            })
+        },
+    });
+}
+
+struct SRunningRequest {
+    jsonval_id: serde_json::Value,
+    b_cancel: Arc<AtomicBool>,
+}
+
+// Requests currently running (or waiting for a thread), so that they can be cancelled. Ids are chosen
+// by the client (and are null for notifications), so each request is indexed by a token of its own.
+#[derive(Default)]
+struct SRunningRequests {
+    n_token_next: usize,
+    maprunningrequest: HashMap<usize, SRunningRequest>,
+}
+
+impl SRunningRequests {
+    // Returns the request's token, or None if a running request already uses jsonval_id.
+    fn insert(&mut self, jsonval_id: &serde_json::Value, b_cancel: &Arc<AtomicBool>) -> Option<usize> {
+        if !jsonval_id.is_null() && self.find(jsonval_id).is_some() {
+            return None;
+        }
+        let n_token = self.n_token_next;
+        self.n_token_next += 1;
+        self.maprunningrequest.insert(n_token, SRunningRequest {
+            jsonval_id: jsonval_id.clone(),
+            b_cancel: Arc::clone(b_cancel),
+        });
+        Some(n_token)
+    }
+
+    fn find(&self, jsonval_id: &serde_json::Value) -> Option<&SRunningRequest> {
+        self.maprunningrequest.values().find(|runningrequest| runningrequest.jsonval_id==*jsonval_id)
+    }
+}
+
+fn handle_message(
+    str_line: &str,
+    runningrequests: &Arc<Mutex<SRunningRequests>>,
+    sendjson: &Sender<serde_json::Value>,
+    threadpool: &rayon::ThreadPool,
+) {
+    let jsonval_request = match serde_json::from_str::<serde_json::Value>(str_line) {
+        Ok(jsonval_request) => jsonval_request,
+        Err(err) => {
+            respond(&serde_json::Value::Null, Err(SRpcError{n_code: N_ERROR_PARSE, str_message: err.to_string()}), sendjson);
+            return;
+        },
+    };
+    let jsonval_id = jsonval_request.get("id").cloned().unwrap_or(serde_json::Value::Null);
+    let Some(str_method) = jsonval_request.get("method").and_then(serde_json::Value::as_str) else {
+        respond(&jsonval_id, Err(SRpcError{n_code: N_ERROR_INVALID_REQUEST, str_message: "Missing method".to_owned()}), sendjson);
+        return;
+    };
+    let jsonval_params = jsonval_request.get("params").cloned().unwrap_or_else(|| json!({}));
+    if str_method=="cancel" {
+        respond(&jsonval_id, param(&jsonval_params, "id").map(|jsonval_id_cancel| {
+            let b_cancelled = !jsonval_id_cancel.is_null() // notifications cannot be cancelled
+                && unwrap!(runningrequests.lock()).find(jsonval_id_cancel)
+                    .inspect(|runningrequest| runningrequest.b_cancel.store(true, Ordering::Relaxed))
+                    .is_some();
+            json!({"cancelled": b_cancelled})
+        }), sendjson);
+        return;
+    }
+    let fn_method: fn(&serde_json::Value, &serde_json::Value, &AtomicBool, &Sender<serde_json::Value>)->Result<serde_json::Value, SRpcError> = match str_method {
+        "validate_position" => |_jsonval_id, jsonval_params, _b_cancel, _sendjson| {
+            param_position(jsonval_params).map(|_gamestate| json!({"valid": true}))
+        },
+        "suggest_card" => suggest_card,
+        "rank_rules" => |_jsonval_id, jsonval_params, b_cancel, _sendjson| rank_rules(jsonval_params, b_cancel),
+        "analyze_game" => |_jsonval_id, jsonval_params, b_cancel, _sendjson| analyze_game(jsonval_params, b_cancel),
+        "hand_stats" => |_jsonval_id, jsonval_params, b_cancel, _sendjson| hand_stats(jsonval_params, b_cancel),
+        _ => {
+            respond(&jsonval_id, Err(SRpcError{n_code: N_ERROR_METHOD_NOT_FOUND, str_message: format!("Unknown method: {str_method}")}), sendjson);
+            return;
+        },
+    };
+    let b_cancel = Arc::new(AtomicBool::new(false));
+    let Some(n_token) = unwrap!(runningrequests.lock()).insert(&jsonval_id, &b_cancel) else {
+        respond(&jsonval_id, Err(SRpcError{n_code: N_ERROR_INVALID_REQUEST, str_message: format!("Request {jsonval_id} is still running")}), sendjson);
+        return;
+    };
+    let str_method = str_method.to_owned();
+    let runningrequests = Arc::clone(runningrequests);
+    let sendjson = sendjson.clone();
+    threadpool.spawn(move || {
+        let is_cancelled = || b_cancel.load(Ordering::Relaxed);
+        let resjsonval = if is_cancelled() {
+            Err(request_cancelled()) // cancelled while waiting for a thread
+        } else {
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(||
+                fn_method(&jsonval_id, &jsonval_params, &b_cancel, &sendjson)
+            ))
+                .unwrap_or_else(|_| Err(internal_error(format!("Engine failed on {str_method}"))))
+        };
+        unwrap!(runningrequests.lock()).maprunningrequest.remove(&n_token);
+        respond(&jsonval_id, if is_cancelled() {Err(request_cancelled())} else {resjsonval}, &sendjson);
+    });
+}
+
+// Serves one client until it closes its input.
+fn serve(read: impl BufRead, mut write: impl Write + Send + 'static, threadpool: Arc<rayon::ThreadPool>) {
+    let (sendjson, recvjson) = std::sync::mpsc::channel::<serde_json::Value>();
+    let joinhandle_write = std::thread::spawn(move || {
+        while let Ok(jsonval_out) = recvjson.recv() {
+            let str_json_out = jsonval_out.to_string();
+            info!("Sending \"{str_json_out}\"");
+            if writeln!(write, "{str_json_out}").and_then(|()| write.flush()).is_err() {
+                return;
+            }
+        }
+    });
+    let runningrequests = Arc::new(Mutex::new(SRunningRequests::default()));
+    for resstr_line in read.lines() {
+        let Ok(str_line) = resstr_line else {
+            break;
+        };
+        if !str_line.trim().is_empty() {
+            info!("Received \"{str_line}\"");
+            handle_message(&str_line, &runningrequests, &sendjson, &threadpool);
+        }
+    }
+    info!("Input closed. Cancelling running requests.");
+    for runningrequest in unwrap!(runningrequests.lock()).maprunningrequest.values() {
+        runningrequest.b_cancel.store(true, Ordering::Relaxed);
+    }
+    drop(sendjson);
+    // Cancelled requests are still answered, so we wait until all of them are done.
+    let _ = joinhandle_write.join();
+}
+
+pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
+    let threadpool = Arc::new(rayon::ThreadPoolBuilder::new()
+        .num_threads(clapmatches.value_of("threads").map(str::parse::<usize>).transpose()?.unwrap_or(/*number of CPUs*/0))
+        .build()?);
+    if let Some(str_port) = clapmatches.value_of("tcp") {
+        let tcplistener = std::net::TcpListener::bind(("127.0.0.1", str_port.parse::<u16>()?))?;
+        info!("Listening on {:?}", tcplistener.local_addr());
+        for restcpstream in tcplistener.incoming() {
+            match restcpstream {
+                Ok(tcpstream) => {
+                    let tcpstream_read = tcpstream.try_clone()?;
+                    let threadpool = Arc::clone(&threadpool);
+                    std::thread::spawn(move || serve(BufReader::new(tcpstream_read), tcpstream, threadpool));
+                },
+                Err(err) => warn!("Connection failed: {err}"),
+            }
+        }
+    } else {
+        serve(std::io::stdin().lock(), std::io::stdout(), threadpool);
+    }
+    Ok(())
+}
+
+// Talks to an engine served via pipes, just as a client would via stdin/stdout.
+#[cfg(test)]
+struct SEngineClient {
+    pipewriter: std::io::PipeWriter,
+    itresstr_line: std::io::Lines<BufReader<std::io::PipeReader>>,
+}
+
+#[cfg(test)]
+impl SEngineClient {
+    fn new() -> Self {
+        let (pipereader_in, pipewriter_in) = unwrap!(std::io::pipe());
+        let (pipereader_out, pipewriter_out) = unwrap!(std::io::pipe());
+        let threadpool = Arc::new(unwrap!(rayon::ThreadPoolBuilder::new().num_threads(2).build()));
+        std::thread::spawn(move || serve(BufReader::new(pipereader_in), pipewriter_out, threadpool));
+        Self {
+            pipewriter: pipewriter_in,
+            itresstr_line: BufReader::new(pipereader_out).lines(),
+        }
+    }
+
+    fn send(&mut self, jsonval_id: serde_json::Value, str_method: &str, jsonval_params: serde_json::Value) {
+        unwrap!(writeln!(self.pipewriter, "{}", json!({
+            "jsonrpc": "2.0",
+            "id": jsonval_id,
+            "method": str_method,
+            "params": jsonval_params,
+        })));
+    }
+
+    // Skips progress notifications.
+    fn receive(&mut self) -> serde_json::Value {
+        loop {
+            let jsonval = unwrap!(serde_json::from_str::<serde_json::Value>(&unwrap!(unwrap!(self.itresstr_line.next()))));
+            if jsonval.get("method").is_none() {
+                return jsonval;
+            }
+        }
+    }
+
+    fn request(&mut self, str_method: &str, jsonval_params: serde_json::Value) -> serde_json::Value {
+        self.send(json!(1), str_method, jsonval_params);
+        let jsonval_response = self.receive();
+        assert_eq!(jsonval_response["id"], json!(1), "{jsonval_response}");
+        jsonval_response
+    }
+}
+
+#[test]
+fn test_engine_methods() {
+    let mut engineclient = SEngineClient::new();
+    let str_position_solo = "Herz-Solo von 0; EO GO HO SO EU GU HU SU | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __; ; 1@0; 0000; 0; 0";
+    assert_eq!(
+        engineclient.request("validate_position", json!({"position": str_position_solo}))["result"],
+        json!({"valid": true}),
+    );
+    assert_eq!(
+        engineclient.request("validate_position", json!({"position": str_position_solo.replace("HU", "HO")}))["error"]["code"],
+        json!(N_ERROR_INVALID_PARAMS),
+    );
+    // Gras-Solo after six stichs, player 0 to move with G7 and EZ
+    let jsonval_suggest_card = engineclient.request("suggest_card", json!({
+        "position": "Gras-Solo von 0; G7 EZ | __ __ | __ __ | __ __; EO GK G8 E7 HO HU G9 E8 GO EU SU H7 EA GA E9 EK HA H8 H9 HK SA S7 S8 GZ; ; 0000; 0; 0",
+    }));
+    let mut vecstr_card = unwrap!(jsonval_suggest_card["result"]["vecCard"].as_array()).iter()
+        .map(|jsonval_card| unwrap!(jsonval_card["strCard"].as_str()))
+        .collect::<Vec<_>>();
+    vecstr_card.sort_unstable();
+    assert_eq!(vecstr_card, vec!["EZ", "G7"]);
+    assert!(vecstr_card.contains(&unwrap!(jsonval_suggest_card["result"]["card"].as_str())));
+    let jsonval_rank_rules = engineclient.request("rank_rules", json!({
+        "hand": "EO GO HO SO EU GU HU SU",
+        "playerIndex": 0,
+        "rules": ["Herz-Solo von 0"],
+        "samples": 2,
+        "doublings": [true, false, false, false],
+    }));
+    let jsonval_rulesevaluation = &jsonval_rank_rules["result"][0];
+    assert!(unwrap!(jsonval_rulesevaluation["min"].as_f64()) <= unwrap!(jsonval_rulesevaluation["avg"].as_f64()));
+    assert!(unwrap!(jsonval_rulesevaluation["avg"].as_f64()) <= unwrap!(jsonval_rulesevaluation["max"].as_f64()));
+    let jsonval_analyze_game = engineclient.request("analyze_game", json!({
+        "game": "Rufspiel mit der Gras-Sau von 3: so h7 go eo ho hz hk eu gu h9 su g8 g9 ga gk e9 ea ek ez e7 g7 ha s7 gz sa s9 h8 sz e8 sk hu s8",
+        "maxRemainingCards": 1,
+    }));
+    assert_eq!(unwrap!(jsonval_analyze_game["result"]["payout"].as_array()).len(), EPlayerIndex::SIZE);
+    assert!(jsonval_analyze_game["result"]["mistakes"].is_array());
+    let jsonval_analyze_game_invalid = engineclient.request("analyze_game", json!({"game": "no game"}));
+    assert_eq!(jsonval_analyze_game_invalid["error"]["code"], json!(N_ERROR_INVALID_PARAMS));
+    let str_error = unwrap!(jsonval_analyze_game_invalid["error"]["message"].as_str());
+    for str_format in ["html", "json", "plain text"] {
+        assert!(str_error.contains(str_format), "{str_error}");
+    }
+    let jsonval_hand_stats = engineclient.request("hand_stats", json!({
+        "position": str_position_solo,
+        "inspect": ["ctx.trumpf(0)"],
+        "groupBy": "ctx.ea(0)",
+        "samples": 10,
+    }));
+    assert_eq!(jsonval_hand_stats["result"]["n_total"], json!(10));
+    assert_eq!(jsonval_hand_stats["result"]["vecstatsline"][0]["str_result"], json!("8"));
+    assert_eq!(
+        engineclient.request("hand_stats", json!({"position": str_position_solo, "inspect": ["ctx.("]}))["error"]["code"],
+        json!(N_ERROR_INVALID_PARAMS),
+    );
+    assert_eq!(engineclient.request("play", json!({}))["error"]["code"], json!(N_ERROR_METHOD_NOT_FOUND));
+}
+
+#[test]
+fn test_engine_cancel() {
+    let mut engineclient = SEngineClient::new();
+    let jsonval_params_endless = json!({
+        "position": "Herz-Solo von 0; EO GO HO SO EU GU HU SU | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __ | __ __ __ __ __ __ __ __; ; ; 0000; 0; 0",
+        "inspect": ["ctx.trumpf(1)"],
+        "samples": usize::MAX,
+    });
+    engineclient.send(json!("endless"), "hand_stats", jsonval_params_endless.clone());
+    // ids of running requests must not be reused
+    engineclient.send(json!("endless"), "hand_stats", jsonval_params_endless);
+    let jsonval_duplicate = engineclient.receive();
+    assert_eq!(jsonval_duplicate["id"], json!("endless"));
+    assert_eq!(jsonval_duplicate["error"]["code"], json!(N_ERROR_INVALID_REQUEST));
+    engineclient.send(json!("cancel"), "cancel", json!({"id": "endless"}));
+    let mut vecjsonval_response = vec![engineclient.receive(), engineclient.receive()];
+    vecjsonval_response.sort_by_key(|jsonval_response| jsonval_response["id"].to_string());
+    assert_eq!(vecjsonval_response[0]["id"], json!("cancel"));
+    assert_eq!(vecjsonval_response[0]["result"], json!({"cancelled": true}));
+    assert_eq!(vecjsonval_response[1]["id"], json!("endless"));
+    assert_eq!(vecjsonval_response[1]["error"]["code"], json!(N_ERROR_REQUEST_CANCELLED));
+    // the id is free again, but there is nothing to cancel
+    assert_eq!(engineclient.request("cancel", json!({"id": "endless"}))["result"], json!({"cancelled": false}));
+}
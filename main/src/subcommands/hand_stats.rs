@@ -1,9 +1,16 @@
+use openschafkopf_lib::{
+    api::{SEngine, SGameState, SHandStats},
+    primitives::*,
+};
 use openschafkopf_util::*;
 use itertools::Itertools;
 use super::common_given_game::*;
 use as_num::*;
+use plain_enum::EnumMap;
+use serde::Serialize;
 use std::{
     cmp::Ordering,
+    collections::HashMap,
     fmt::{Display, Formatter},
     hash::{Hash, Hasher},
 };
@@ -15,10 +22,79 @@ pub fn subcommand(str_subcommand: &'static str) -> clap::Command<'static> {
             .takes_value(true)
             .multiple_occurrences(true)
             .help("Describes inspection target")
-            .long_help("Describes what the software will inspect. Example: \"ctx.ea(0)\" checks if player 0 has Eichel-Ass, \"ctx.trumpf(2)\" counts the trumpf cards held by player 2. (Players are numbere from 0 to 3, where 0 is the player to open the first stich (1, 2, 3 follow accordingly).)") // TODO improve docs.
+            .long_help("Describes what the software will inspect. Example: \"ctx.ea(0)\" checks if player 0 has Eichel-Ass, \"ctx.trumpf(2)\" counts the trumpf cards held by player 2. (Players are numbere from 0 to 3, where 0 is the player to open the first stich (1, 2, 3 follow accordingly).) Game outcomes can be inspected, too: \"0<ctx.cheating_payout(0)\" checks whether player 0 wins if all players know all cards (this searches the game tree for each sample, which is slow in early positions).") // TODO improve docs.
+        )
+        .arg(clap::Arg::new("group_by")
+            .long("group-by")
+            .takes_value(true)
+            .help("Groups inspection results")
+            .long_help("Groups samples by the value of this expression (same syntax as --inspect) and shows the statistics of each --inspect for each group separately. Example: \"--inspect ctx.trumpf(0) --group-by ctx.eo(0)\" shows the distribution of player 0's trumpf depending on whether player 0 has Eichel-Ober.")
+        )
+        .arg(clap::Arg::new("json")
+            .long("json")
+            .help("Output result as json")
+        )
+        .arg(clap::Arg::new("csv")
+            .long("csv")
+            .conflicts_with("json")
+            .help("Output result as csv")
+            .long_help("Output one line per inspection result (and group) as csv. Averages are only contained in json output.")
         )
 }
 
+const F_Z_95: f64 = 1.96; // quantile of standard normal distribution for 95% confidence
+
+// Wilson score interval for the proportion n_count/n_total
+fn confidence_interval_proportion(n_count: usize, n_total: usize) -> (f64, f64) {
+    let f_total = n_total.as_num::<f64>();
+    let f_proportion = n_count.as_num::<f64>() / f_total;
+    let f_z_squared = F_Z_95.powi(2);
+    let f_denominator = 1. + f_z_squared / f_total;
+    let f_center = (f_proportion + f_z_squared / (2. * f_total)) / f_denominator;
+    let f_radius = F_Z_95 * (f_proportion * (1. - f_proportion) / f_total + f_z_squared / (4. * f_total.powi(2))).sqrt() / f_denominator;
+    ((f_center - f_radius).max(0.), (f_center + f_radius).min(1.))
+}
+
+#[derive(Serialize)]
+struct SStatsLine {
+    str_inspect: String,
+    ostr_group: Option<String>,
+    str_result: String,
+    n_count: usize,
+    n_total: usize,
+    f_percentage: f64,
+    f_percentage_lo: f64,
+    f_percentage_hi: f64,
+}
+
+#[derive(Serialize)]
+struct SStatsMean {
+    str_inspect: String,
+    ostr_group: Option<String>,
+    str_mean: String,
+}
+
+#[derive(Serialize)]
+pub struct SStatsJson {
+    n_total: usize,
+    ostr_group_by: Option<String>,
+    vecstatsline: Vec<SStatsLine>,
+    vecstatsmean: Vec<SStatsMean>,
+}
+
+// Mean and half-width of its 95% confidence interval
+struct SMeanConfidence {
+    f_mean: f64,
+    f_confidence: f64,
+}
+impl Display for SMeanConfidence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        self.f_mean.fmt(f)?;
+        write!(f, "\u{b1}")?;
+        self.f_confidence.fmt(f)
+    }
+}
+
 pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
     let vecconstraint : Vec<SConstraint> = clapmatches.values_of("inspect")
         .map(|itstr_inspect|
@@ -29,6 +105,44 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
         )
         .transpose()?
         .unwrap_or_default();
+    let oconstraint_group_by = clapmatches.value_of("group_by")
+        .map(|str_group_by| str_group_by.parse::<SConstraint>()
+            .map_err(|_| format_err!("Cannot parse grouping expression."))
+        )
+        .transpose()?;
+    let b_json = clapmatches.is_present("json");
+    let b_csv = clapmatches.is_present("csv");
+    with_common_args(
+        clapmatches,
+        |engine, gamestate, itahand, b_verbose, checkpoint| {
+            if 1<checkpoint.n_checkpoints {
+                return Err(format_err!("hand-stats does not support checkpoints in played cards."));
+            }
+            let statsjson = hand_stats(engine, itahand, gamestate, &vecconstraint, oconstraint_group_by.as_ref());
+            if b_json {
+                println!("{}", unwrap!(serde_json::to_string(&statsjson)));
+            } else {
+                for str_line in if b_csv {
+                    stats_to_csv(&statsjson)
+                } else {
+                    stats_to_text(&statsjson, /*b_show_inspect*/b_verbose || 1<vecconstraint.len())
+                } {
+                    println!("{str_line}");
+                }
+            }
+            Ok(())
+        }
+    )
+}
+
+// Counts the results of each inspection over all hands, separately for each group (see --group-by).
+pub fn hand_stats(
+    engine: &SEngine,
+    itahand: impl Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send,
+    gamestate: &SGameState,
+    slcconstraint: &[SConstraint],
+    oconstraint_group_by: Option<&SConstraint>,
+) -> SStatsJson {
     #[derive(Clone, Copy)]
     struct STotalOrderedFloat(rhai::FLOAT); // TODO good idea?
     impl Display for STotalOrderedFloat {
@@ -57,7 +171,7 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
             self.0.to_bits().hash(state)
         }
     }
-    #[derive(Hash, Eq, PartialEq, Ord, PartialOrd)]
+    #[derive(Hash, Eq, PartialEq, Ord, PartialOrd, Clone)]
     enum VInspectionResult<Number, Unknown> {
         RecognizableAsNumber(Number),
         Array(Vec<VInspectionResult<Number, Unknown>>),
@@ -99,6 +213,22 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
                 },
             }
         }
+        fn zip_numbers<Number2>(&self, inspectionresult: &Self, fn_number: &impl Fn(f64, f64)->Number2) -> VInspectionResult<Number2, SUndefined> {
+            use VInspectionResult::*;
+            match (self, inspectionresult) {
+                (RecognizableAsNumber(number_self), RecognizableAsNumber(number_rhs)) => {
+                    RecognizableAsNumber(fn_number(*number_self, *number_rhs))
+                },
+                (Array(vecinspectionresult_self), Array(vecinspectionresult_rhs)) if vecinspectionresult_self.len()==vecinspectionresult_rhs.len() => {
+                    Array(
+                        itertools::zip_eq(vecinspectionresult_self, vecinspectionresult_rhs)
+                            .map(|(lhs, rhs)| lhs.zip_numbers(rhs, fn_number))
+                            .collect()
+                    )
+                },
+                _ => Unknown(SUndefined),
+            }
+        }
     }
     impl VInspectionResult<VRecognizableAsNumber, String> {
         fn new(dynamic: rhai::Dynamic) -> Self {
@@ -139,7 +269,7 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
             }
         }
     }
-    #[derive(/*TODO? Hash by numeric value?*/Hash, Eq, PartialEq)]
+    #[derive(/*TODO? Hash by numeric value?*/Hash, Eq, PartialEq, Clone)]
     enum VRecognizableAsNumber { // TODO distinction even useful?
         Int(rhai::INT),
         Float(STotalOrderedFloat),
@@ -174,63 +304,239 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
             Ord::cmp(&self.to_total_ordered_float(), &other.to_total_ordered_float())
         }
     }
-    with_common_args(
-        clapmatches,
-        |itahand, rules, stichseq, _ahand_fixed_with_holes, _epi_position, _expensifiers, b_verbose| {
-            let mut vectplmapresinspectionresultnconstraint = vecconstraint
-                .iter()
-                .map(|constraint| (std::collections::HashMap::new(), constraint))
-                .collect::<Vec<_>>();
-            let mut n_ahand_total = 0;
-            for ahand in itahand {
-                // assert_eq!(ahand[epi_position], hand_fixed);
-                for (mapresinspectionresultn, constraint) in vectplmapresinspectionresultnconstraint.iter_mut() {
-                    *mapresinspectionresultn.entry(
-                        constraint.internal_eval(
-                            stichseq,
-                            &ahand,
-                            rules.clone(),
-                        )
-                            .map(VInspectionResult::new)
-                            .map_err(|err| format!("Error: {err:?}")),
-                    ).or_insert(0) += 1;
+    type SResInspectionResult = Result<VInspectionResult<VRecognizableAsNumber, String>, String>;
+    let eval_constraint = |constraint: &SConstraint, ahand: &EnumMap<EPlayerIndex, SHand>| -> SResInspectionResult {
+        constraint.internal_eval(
+            gamestate.stichseq(),
+            ahand,
+            gamestate.rules().clone(),
+            gamestate.expensifiers(),
+        )
+            .map(VInspectionResult::new)
+            .map_err(|err| format!("Error: {err:?}"))
+    };
+    // Without --group-by, all samples belong to the same group (None).
+    let SHandStats{mapgroupn: mapogroupn, vecmaptplgroupinspectionn: vecmapresinspectionresultn} = engine.hand_stats(
+        itahand,
+        /*fn_group*/|ahand| oconstraint_group_by.map(|constraint_group_by| eval_constraint(constraint_group_by, ahand)),
+        &slcconstraint.iter()
+            .map(|constraint| move |ahand: &EnumMap<EPlayerIndex, SHand>| eval_constraint(constraint, ahand))
+            .collect::<Vec<_>>(),
+    );
+    let str_result_or_err = |resinspectionresult: &SResInspectionResult| match resinspectionresult {
+        Ok(inspectionresult) => format!("{inspectionresult}"),
+        Err(str_err) => str_err.clone(),
+    };
+    let mut vecstatsline = Vec::new();
+    let mut vecstatsmean = Vec::new();
+    for (mapresinspectionresultn, constraint) in itertools::zip_eq(vecmapresinspectionresultn, slcconstraint.iter()) {
+        for (ogroup, vectplresinspectionresultn) in mapresinspectionresultn.into_iter()
+            .sorted_unstable_by(|lhs, rhs| Ord::cmp(&lhs.0, &rhs.0))
+            .chunk_by(|((ogroup, _resinspectionresult), _n_count)| ogroup.clone())
+            .into_iter()
+            .map(|(ogroup, ittplresinspectionresultn)| (
+                ogroup,
+                ittplresinspectionresultn
+                    .map(|((_ogroup, resinspectionresult), n_count)| (resinspectionresult, n_count))
+                    .collect::<Vec<_>>(),
+            ))
+            .collect::<Vec<_>>()
+        {
+            let n_ahand_group = mapogroupn[&ogroup];
+            let ostr_group = ogroup.as_ref().map(str_result_or_err);
+            let percentage = |n_count: usize| n_count.as_num::<f64>()/n_ahand_group.as_num::<f64>();
+            let mut otplresinspectionresult_weighted_sum = None;
+            for (resinspectionresult, n_count) in vectplresinspectionresultn {
+                match &resinspectionresult {
+                    Ok(inspectionresult) => {
+                        if let Ok((inspectionresult_weighted_sum, inspectionresult_weighted_sum_squares)) = otplresinspectionresult_weighted_sum.get_or_insert_with(||
+                            Ok((
+                                inspectionresult.map_numbers_remove_unknown(&|_| 0.,), // Determine structure, initialize numbers with 0
+                                inspectionresult.map_numbers_remove_unknown(&|_| 0.,),
+                            ))
+                        ) {
+                            inspectionresult_weighted_sum.accumulate_weighted_sum(
+                                &inspectionresult.map_numbers_remove_unknown(&|number| number.to_total_ordered_float().0),
+                                percentage(n_count),
+                            );
+                            inspectionresult_weighted_sum_squares.accumulate_weighted_sum(
+                                &inspectionresult.map_numbers_remove_unknown(&|number| number.to_total_ordered_float().0.powi(2)),
+                                percentage(n_count),
+                            );
+                        }
+                    },
+                    Err(_str_err) => {
+                        otplresinspectionresult_weighted_sum = Some(Err(())); // Do not show weighted sum if there are errors.
+                    },
                 }
-                n_ahand_total += 1;
+                let (f_percentage_lo, f_percentage_hi) = confidence_interval_proportion(n_count, n_ahand_group);
+                vecstatsline.push(SStatsLine{
+                    str_inspect: constraint.to_string(),
+                    ostr_group: ostr_group.clone(),
+                    str_result: str_result_or_err(&resinspectionresult),
+                    n_count,
+                    n_total: n_ahand_group,
+                    f_percentage: percentage(n_count)*100.,
+                    f_percentage_lo: f_percentage_lo*100.,
+                    f_percentage_hi: f_percentage_hi*100.,
+                });
             }
-            let percentage = |n_count: usize| n_count.as_num::<f64>()/n_ahand_total.as_num::<f64>();
-            for (mapresinspectionresultn, constraint) in vectplmapresinspectionresultnconstraint {
-                if b_verbose || 1<vecconstraint.len() {
-                    println!("{constraint}");
-                }
-                let mut oresinspectionresult_weighted_sum = None;
-                for (resinspectionresult, n_count) in mapresinspectionresultn.into_iter()
-                    .sorted_unstable_by(|lhs, rhs| Ord::cmp(&lhs.0, &rhs.0))
-                {
-                    let str_result_or_err = match resinspectionresult {
-                        Ok(inspectionresult) => {
-                            if let Ok(inspectionresult_weighted_sum) = oresinspectionresult_weighted_sum.get_or_insert_with(||
-                                Ok(inspectionresult.map_numbers_remove_unknown(&|_| 0.,)) // Determine structure, initialize numbers with 0
-                            ) {
-                                inspectionresult_weighted_sum.accumulate_weighted_sum(
-                                    &inspectionresult.map_numbers_remove_unknown(&|number| number.to_total_ordered_float().0),
-                                    percentage(n_count),
-                                );
-                            }
-                            format!("{inspectionresult}")
-                        },
-                        Err(str_err) => {
-                            oresinspectionresult_weighted_sum = Some(Err(())); // Do not show weighted sum if there are errors.
-                            str_err
-                        },
-                    };
-                    println!("{} {} ({:.2}%)", str_result_or_err, n_count, percentage(n_count)*100.);
-                }
-                if let Some(Ok(inspectionresult_weighted_sum))=oresinspectionresult_weighted_sum {
-                    println!("-----");
-                    println!("\u{2300} {inspectionresult_weighted_sum:.4}");
-                }
+            if let Some(Ok((inspectionresult_weighted_sum, inspectionresult_weighted_sum_squares)))=otplresinspectionresult_weighted_sum {
+                let inspectionresult_mean_confidence = inspectionresult_weighted_sum.zip_numbers(
+                    &inspectionresult_weighted_sum_squares,
+                    &|f_mean, f_mean_squares| SMeanConfidence{
+                        f_mean,
+                        f_confidence: F_Z_95 * ((f_mean_squares - f_mean.powi(2)).max(0.) / n_ahand_group.as_num::<f64>()).sqrt(),
+                    },
+                );
+                vecstatsmean.push(SStatsMean{
+                    str_inspect: constraint.to_string(),
+                    ostr_group,
+                    str_mean: format!("{inspectionresult_mean_confidence:.4}"),
+                });
             }
-            Ok(())
         }
-    )
+    }
+    SStatsJson {
+        n_total: mapogroupn.values().sum::<usize>(),
+        ostr_group_by: oconstraint_group_by.map(SConstraint::to_string),
+        vecstatsline,
+        vecstatsmean,
+    }
+}
+
+fn stats_to_text(statsjson: &SStatsJson, b_show_inspect: bool) -> Vec<String> {
+    let mut vecstr_line = Vec::new();
+    for (str_inspect, vecstatsline_inspect) in statsjson.vecstatsline.iter()
+        .chunk_by(|statsline| statsline.str_inspect.clone())
+        .into_iter()
+        .map(|(str_inspect, itstatsline)| (str_inspect, itstatsline.collect::<Vec<_>>()))
+        .collect::<Vec<_>>()
+    {
+        if b_show_inspect {
+            vecstr_line.push(str_inspect.clone());
+        }
+        for (ostr_group, vecstatsline_group) in vecstatsline_inspect.into_iter()
+            .chunk_by(|statsline| statsline.ostr_group.clone())
+            .into_iter()
+            .map(|(ostr_group, itstatsline)| (ostr_group, itstatsline.collect::<Vec<_>>()))
+            .collect::<Vec<_>>()
+        {
+            if let (Some(str_group), Some(str_group_by), Some(statsline_first)) = (&ostr_group, &statsjson.ostr_group_by, vecstatsline_group.first()) {
+                vecstr_line.push(format!("{} = {} ({} samples, {:.2}%)",
+                    str_group_by,
+                    str_group,
+                    statsline_first.n_total,
+                    statsline_first.n_total.as_num::<f64>()/statsjson.n_total.as_num::<f64>()*100.,
+                ));
+            }
+            for statsline in vecstatsline_group {
+                vecstr_line.push(format!("{} {} ({:.2}%, CI {:.2}%..{:.2}%)",
+                    statsline.str_result,
+                    statsline.n_count,
+                    statsline.f_percentage,
+                    statsline.f_percentage_lo,
+                    statsline.f_percentage_hi,
+                ));
+            }
+            if let Some(statsmean) = statsjson.vecstatsmean.iter()
+                .find(|statsmean| statsmean.str_inspect==str_inspect && statsmean.ostr_group==ostr_group)
+            {
+                vecstr_line.push("-----".to_owned());
+                vecstr_line.push(format!("\u{2300} {}", statsmean.str_mean));
+            }
+        }
+    }
+    vecstr_line
+}
+
+fn stats_to_csv(statsjson: &SStatsJson) -> Vec<String> {
+    fn csv_field(str_field: &str) -> String {
+        format!("\"{}\"", str_field.replace('"', "\"\""))
+    }
+    std::iter::once("inspect,group,result,count,total,percentage,percentage_lo,percentage_hi".to_owned())
+        .chain(statsjson.vecstatsline.iter().map(|statsline| format!("{},{},{},{},{},{:.4},{:.4},{:.4}",
+            csv_field(&statsline.str_inspect),
+            csv_field(statsline.ostr_group.as_deref().unwrap_or("")),
+            csv_field(&statsline.str_result),
+            statsline.n_count,
+            statsline.n_total,
+            statsline.f_percentage,
+            statsline.f_percentage_lo,
+            statsline.f_percentage_hi,
+        )))
+        .collect()
+}
+
+#[test]
+fn test_confidence_interval_proportion() {
+    let assert_interval = |n_count, n_total, (f_lo_expected, f_hi_expected): (f64, f64)| {
+        let (f_lo, f_hi) = confidence_interval_proportion(n_count, n_total);
+        assert!((f_lo-f_lo_expected).abs() < 1e-6, "{n_count}/{n_total}: {f_lo}");
+        assert!((f_hi-f_hi_expected).abs() < 1e-6, "{n_count}/{n_total}: {f_hi}");
+    };
+    assert_interval(50, 100, (0.403830, 0.596170));
+    assert_interval(0, 10, (0., 0.277540));
+    assert_interval(10, 10, (0.722460, 1.));
+    assert_interval(1, 1, (0.206543, 1.));
+}
+
+#[test]
+fn test_group_by() {
+    use openschafkopf_lib::{api::SPositionBuilder, rules::parser::parse_rule_description_simple};
+    let rules = unwrap!(parse_rule_description_simple("Rufspiel mit der Eichel-Sau von 0"));
+    let ahand_from_str = |astr_hand: [&str; EPlayerIndex::SIZE]| EPlayerIndex::map_from_raw(
+        astr_hand.map(|str_hand| SHand::new_from_iter(unwrap!(cardvector::parse_cards::<Vec<_>>(str_hand))))
+    );
+    let ahand_eo = ahand_from_str([
+        "eo go ho so eu gu e7 ha",
+        "ea ez ek e9 hu su h7 h8",
+        "ga gz gk g9 g8 g7 hz hk",
+        "sa sz sk s9 s8 s7 h9 e8",
+    ]);
+    let ahand_no_eo = ahand_from_str([
+        "sa go ho so eu gu e7 ha",
+        "ea ez ek e9 hu su h7 h8",
+        "ga gz gk g9 g8 g7 hz hk",
+        "eo sz sk s9 s8 s7 h9 e8",
+    ]);
+    let statsjson = hand_stats(
+        &SEngine::new(/*n_rank_rules_samples*/1), // unused
+        [ahand_eo.clone(), ahand_no_eo, ahand_eo.clone(), ahand_eo].into_iter(),
+        &unwrap!(SPositionBuilder::new(rules).kurzlang(EKurzLang::Lang).build()),
+        &[unwrap!("ctx.trumpf(0)".parse::<SConstraint>())],
+        Some(&unwrap!("ctx.eo(0)".parse::<SConstraint>())),
+    );
+    assert_eq!(statsjson.n_total, 4);
+    assert_eq!(
+        statsjson.vecstatsline.iter()
+            .map(|statsline| (statsline.ostr_group.as_deref(), statsline.str_result.as_str(), statsline.n_count, statsline.n_total))
+            .collect::<Vec<_>>(),
+        vec![(Some("0"), "6", 1, 1), (Some("1"), "7", 3, 3)],
+    );
+    let fmt_interval = |n_count, n_total| {
+        let (f_lo, f_hi) = confidence_interval_proportion(n_count, n_total);
+        format!("CI {:.2}%..{:.2}%", f_lo*100., f_hi*100.)
+    };
+    assert_eq!(
+        stats_to_text(&statsjson, /*b_show_inspect*/true),
+        vec![
+            "ctx.trumpf(0)".to_owned(),
+            "ctx.eo(0) = 0 (1 samples, 25.00%)".to_owned(),
+            format!("6 1 (100.00%, {})", fmt_interval(1, 1)),
+            "-----".to_owned(),
+            "\u{2300} 6.0000\u{b1}0.0000".to_owned(),
+            "ctx.eo(0) = 1 (3 samples, 75.00%)".to_owned(),
+            format!("7 3 (100.00%, {})", fmt_interval(3, 3)),
+            "-----".to_owned(),
+            "\u{2300} 7.0000\u{b1}0.0000".to_owned(),
+        ],
+    );
+    assert_eq!(
+        stats_to_csv(&statsjson)[1..],
+        [
+            format!("\"ctx.trumpf(0)\",\"0\",\"6\",1,1,100.0000,{:.4},100.0000", confidence_interval_proportion(1, 1).0*100.),
+            format!("\"ctx.trumpf(0)\",\"1\",\"7\",3,3,100.0000,{:.4},100.0000", confidence_interval_proportion(3, 3).0*100.),
+        ],
+    );
 }
@@ -1,18 +1,33 @@
 use openschafkopf_lib::{
+    ai::{
+        determine_best_card,
+        gametree::{
+            equivalent_cards_filter,
+            SAlphaBetaPrunerNone,
+            SMinReachablePayoutBase,
+            SNoVisualization,
+            SPrunerViaHint,
+            SSnapshotCacheNone,
+            STplStrategiesOnlyMaxMin,
+        },
+    },
     primitives::*,
     rules::{
         *,
+        card_points::points_stich,
         trumpfdecider::SLaufendeCount,
     },
 };
 use openschafkopf_util::*;
 use plain_enum::{PlainEnum, EnumMap};
 use as_num::*;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub struct SConstraint {
-    engine: rhai::Engine,
+    engine: Arc<rhai::Engine>, // shared by all constraints from the same file
     ast: rhai::AST,
+    str_fn: String,
     str_display: String,
 }
 
@@ -24,6 +39,7 @@ struct SContext {
     stichseq: SStichSequence, // TODO how expensive is this?
     ahand: EnumMap<EPlayerIndex, SHand>,
     rules: SRules,
+    expensifiers: SExpensifiers,
 }
 
 impl SContext {
@@ -37,8 +53,8 @@ impl SContext {
             .as_num::<SRhaiUsize>()
     }
 
-    fn count(&self, i_epi: SRhaiUsize, fn_pred: impl Fn(ECard)->bool) -> SRhaiUsize {
-        self.internal_count(unwrap!(EPlayerIndex::checked_from_usize(i_epi.as_num::<usize>())), fn_pred)
+    fn count(&self, i_epi: SRhaiEPlayerIndex, fn_pred: impl Fn(ECard)->bool) -> Result<SRhaiUsize, Box<rhai::EvalAltResult>> {
+        Ok(self.internal_count(epi_from_rhai(i_epi)?, fn_pred))
     }
 
     fn count_enummap(&self, fn_pred: impl Fn(&Self, ECard)->bool) -> rhai::Array {
@@ -54,10 +70,90 @@ impl SContext {
     }
 
     fn who_has_card(&self, card: ECard) -> SRhaiEPlayerIndex/*or -1*/ {
+        epi_to_rhai(self.who_has_card_internal(card))
+    }
+
+    fn who_played_card_internal(&self, card: ECard) -> Option<EPlayerIndex> {
+        self.stichseq.visible_cards()
+            .find(|&(_epi, card_visible)| card_visible==&card)
+            .map(|(epi, _card)| epi)
+    }
+
+    fn who_had_card_internal(&self, card: ECard) -> Option<EPlayerIndex> {
         self.who_has_card_internal(card)
-            .map(|epi| epi.to_usize().as_num::<SRhaiEPlayerIndex>())
-            .unwrap_or(-1)
+            .or_else(|| self.who_played_card_internal(card))
+    }
+
+    fn stich_winners_internal(&self) -> impl Iterator<Item=(SFullStich<&SStich>, EPlayerIndex)> + '_ {
+        self.stichseq.completed_stichs_winner_index(dbg_argument!(&self.rules))
+    }
+
+    fn points_internal(&self) -> EnumMap<EPlayerIndex, isize> {
+        let mut mapepin_points = EPlayerIndex::map_from_fn(|_epi| 0);
+        for (stich, epi_winner) in self.stich_winners_internal() {
+            mapepin_points[epi_winner] += points_stich(stich);
+        }
+        mapepin_points
+    }
+
+    fn partner_internal(&self) -> Option<EPlayerIndex> {
+        self.rules.rufsau().and_then(|card_rufsau| self.who_had_card_internal(card_rufsau))
+    }
+
+    // Empty if every player plays on their own (e.g. Ramsch).
+    fn primary_party_internal(&self) -> Vec<EPlayerIndex> {
+        self.rules.playerindex().into_iter()
+            .chain(self.partner_internal())
+            .collect()
+    }
+
+    // Payout epi can enforce if all cards are known to everyone and all others play against epi.
+    // Searches like analyze does, i.e. with pruning and skipping equivalent cards.
+    fn cheating_payout_internal(&self, epi: EPlayerIndex) -> isize {
+        unwrap!(determine_best_card(
+            &self.stichseq,
+            Box::new(std::iter::once(self.ahand.clone())) as Box<_>,
+            equivalent_cards_filter(
+                /*n_until_stichseq_len, determined heuristically*/7,
+                self.rules.equivalent_when_on_same_hand(),
+            ),
+            &|_stichseq, _ahand| SMinReachablePayoutBase::<SPrunerViaHint, STplStrategiesOnlyMaxMin, SAlphaBetaPrunerNone>::new(
+                &self.rules,
+                epi,
+                self.expensifiers.clone(),
+            ),
+            /*fn_snapshotcache*/SSnapshotCacheNone::factory(),
+            /*fn_visualizer*/SNoVisualization::factory(),
+            /*fn_inspect*/&|_inspectionpoint, _i_ahand, _ahand| {},
+            /*fn_payout*/&|_stichseq, _ahand, n_payout| (n_payout, ()),
+        )).t_combined.omaxmin.unwrap_static_some().min()
     }
+
+    fn same_party(&self, epi_lhs: EPlayerIndex, epi_rhs: EPlayerIndex) -> bool {
+        let vecepi_primary = self.primary_party_internal();
+        if vecepi_primary.is_empty() {
+            epi_lhs==epi_rhs
+        } else {
+            vecepi_primary.contains(&epi_lhs)==vecepi_primary.contains(&epi_rhs)
+        }
+    }
+}
+
+// Scripts may pass -1 (e.g. ctx.partner() in Ramsch), so this reports an error instead of panicking.
+fn epi_from_rhai(i_epi: SRhaiEPlayerIndex) -> Result<EPlayerIndex, Box<rhai::EvalAltResult>> {
+    usize::try_from(i_epi).ok()
+        .and_then(EPlayerIndex::checked_from_usize)
+        .ok_or_else(|| format!("{i_epi} is not a player index").into())
+}
+
+fn epi_to_rhai(oepi: Option<EPlayerIndex>) -> SRhaiEPlayerIndex/*or -1*/ {
+    oepi
+        .map(|epi| epi.to_usize().as_num::<SRhaiEPlayerIndex>())
+        .unwrap_or(-1)
+}
+
+fn array_from_iter<T: Clone+Send+Sync+'static>(itt: impl IntoIterator<Item=T>) -> rhai::Array {
+    itt.into_iter().map(rhai::Dynamic::from).collect()
 }
 
 impl SConstraint {
@@ -66,16 +162,38 @@ impl SConstraint {
         stichseq: &SStichSequence,
         ahand: &EnumMap<EPlayerIndex, SHand>,
         rules: SRules,
+        expensifiers: &SExpensifiers,
     ) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
         self.engine.call_fn(
             &mut rhai::Scope::new(),
             &self.ast,
-            "inspect",
-            (SContext{stichseq: stichseq.clone(), ahand: ahand.clone(), rules},),
+            &self.str_fn,
+            (SContext{stichseq: stichseq.clone(), ahand: ahand.clone(), rules, expensifiers: expensifiers.clone()},),
         )
     }
-    pub fn eval(&self, stichseq: &SStichSequence, ahand: &EnumMap<EPlayerIndex, SHand>, rules: SRules) -> bool {
-        match self.internal_eval(stichseq, ahand, rules) {
+
+    // Loads a library of named constraints: Each public function taking exactly one parameter (ctx)
+    // yields a constraint named after the function. Private functions can be used as helpers.
+    pub fn vec_from_file(str_path: &str) -> Result<Vec<Self>, Error> {
+        let engine = Arc::new(Self::new_engine());
+        let ast = engine.compile_file(str_path.into())
+            .map_err(|err| format_err!("Cannot compile file {}: {:?}", str_path, err))?;
+        let vecconstraint = ast.iter_functions()
+            .filter(|fnmetadata| fnmetadata.access==rhai::FnAccess::Public && fnmetadata.params.len()==1)
+            .map(|fnmetadata| SConstraint {
+                engine: Arc::clone(&engine),
+                ast: ast.clone(),
+                str_fn: fnmetadata.name.to_owned(),
+                str_display: fnmetadata.name.to_owned(),
+            })
+            .collect::<Vec<_>>();
+        if vecconstraint.is_empty() {
+            return Err(format_err!("No constraints found in {}", str_path));
+        }
+        Ok(vecconstraint)
+    }
+    pub fn eval(&self, stichseq: &SStichSequence, ahand: &EnumMap<EPlayerIndex, SHand>, rules: SRules, expensifiers: &SExpensifiers) -> bool {
+        match self.internal_eval(stichseq, ahand, rules, expensifiers) {
             Ok(dynamic) => {
                 if let Ok(n) = dynamic.as_int() {
                     0 != n
@@ -100,9 +218,8 @@ impl std::fmt::Display for SConstraint {
     }
 }
 
-impl std::str::FromStr for SConstraint {
-    type Err = Error;
-    fn from_str(str_in: &str) -> Result<Self, Self::Err> {
+impl SConstraint {
+    fn new_engine() -> rhai::Engine {
         let mut engine = rhai::Engine::new();
         let mut module_card = rhai::Module::new();
         let mut module_farbe = rhai::Module::new();
@@ -228,33 +345,74 @@ impl std::str::FromStr for SConstraint {
         });
         engine.register_fn("who_has_card", |ctx: SContext, card: ECard| ctx.who_has_card(card));
         engine
-            .register_fn("hand_to_string", |ctx: SContext, i_epi: SRhaiUsize| -> String {
-                format!("{}",
+            .register_fn("hand_to_string", |ctx: SContext, i_epi: SRhaiUsize| -> Result<String, Box<rhai::EvalAltResult>> {
+                Ok(format!("{}",
                     SDisplayCardSlice::new(
-                        ctx.ahand[unwrap!(EPlayerIndex::checked_from_usize(i_epi.as_num::<usize>()))].cards().to_owned(),
+                        ctx.ahand[epi_from_rhai(i_epi)?].cards().to_owned(),
                         &ctx.rules,
                     )
-                )
+                ))
             });
         engine
             .register_fn("laufende", |ctx: SContext| {
                 if let Some(SLaufendeCount{n_laufende, b_primary_party}) = ctx.rules.count_laufende(
                     ctx.stichseq.kurzlang(),
-                    /*fn_who_has_card*/|card| {
-                        unwrap!(
-                            ctx.who_has_card_internal(card)
-                                .or_else(|| ctx.stichseq.visible_cards()
-                                    .find(|&(_epi, card_visible)| card_visible==&card)
-                                    .map(|(epi, _card)| epi)
-                                )
-                        )
-                    },
+                    /*fn_who_has_card*/|card| unwrap!(ctx.who_had_card_internal(card)),
                 ) {
                     rhai::Dynamic::from(n_laufende.as_num::<rhai::INT>().neg_if(!b_primary_party))
                 } else {
                     rhai::Dynamic::from("Rules do not support Laufende")
                 }
             });
+        engine
+            .register_fn("who_played_card", |ctx: SContext, card: ECard| -> SRhaiEPlayerIndex {
+                epi_to_rhai(ctx.who_played_card_internal(card))
+            })
+            .register_fn("trumpforfarbe_of", |ctx: SContext, card: ECard| ctx.rules.trumpforfarbe(card))
+            .register_fn("stichs", |ctx: SContext| -> rhai::Array {
+                ctx.stichseq.completed_stichs().iter()
+                    .map(|stich| rhai::Dynamic::from(array_from_iter(stich.iter().map(|(_epi, card)| *card))))
+                    .collect()
+            })
+            .register_fn("current_stich", |ctx: SContext| -> rhai::Array {
+                array_from_iter(ctx.stichseq.current_stich().iter().map(|(_epi, card)| *card))
+            })
+            .register_fn("stich_winners", |ctx: SContext| -> rhai::Array {
+                array_from_iter(ctx.stich_winners_internal().map(|(_stich, epi_winner)| epi_to_rhai(Some(epi_winner))))
+            })
+            .register_fn("points", |ctx: SContext, i_epi: SRhaiEPlayerIndex| -> Result<SRhaiUsize, Box<rhai::EvalAltResult>> {
+                Ok(ctx.points_internal()[epi_from_rhai(i_epi)?].as_num::<SRhaiUsize>())
+            })
+            .register_fn("points", |ctx: SContext| -> rhai::Array {
+                array_from_iter(ctx.points_internal().into_raw().map(|n_points| n_points.as_num::<SRhaiUsize>()))
+            })
+            .register_fn("points_party", |ctx: SContext, i_epi: SRhaiEPlayerIndex| -> Result<SRhaiUsize, Box<rhai::EvalAltResult>> {
+                let epi = epi_from_rhai(i_epi)?;
+                let mapepin_points = ctx.points_internal();
+                Ok(EPlayerIndex::values()
+                    .filter(|&epi_other| ctx.same_party(epi, epi_other))
+                    .map(|epi_other| mapepin_points[epi_other])
+                    .sum::<isize>()
+                    .as_num::<SRhaiUsize>())
+            })
+            .register_fn("player", |ctx: SContext| -> SRhaiEPlayerIndex {
+                epi_to_rhai(ctx.rules.playerindex())
+            })
+            .register_fn("rufsau", |ctx: SContext| -> rhai::Dynamic {
+                ctx.rules.rufsau().map_or(rhai::Dynamic::UNIT, rhai::Dynamic::from)
+            })
+            .register_fn("partner", |ctx: SContext| -> SRhaiEPlayerIndex {
+                epi_to_rhai(ctx.partner_internal())
+            })
+            .register_fn("primary_party", |ctx: SContext| -> rhai::Array {
+                array_from_iter(ctx.primary_party_internal().into_iter().map(|epi| epi_to_rhai(Some(epi))))
+            })
+            .register_fn("is_primary_party", |ctx: SContext, i_epi: SRhaiEPlayerIndex| -> Result<bool, Box<rhai::EvalAltResult>> {
+                Ok(ctx.primary_party_internal().contains(&epi_from_rhai(i_epi)?))
+            })
+            .register_fn("cheating_payout", |ctx: SContext, i_epi: SRhaiEPlayerIndex| -> Result<SRhaiUsize, Box<rhai::EvalAltResult>> {
+                Ok(ctx.cheating_payout_internal(epi_from_rhai(i_epi)?).as_num::<SRhaiUsize>())
+            });
         engine
             .register_type::<EPlayerIndex>()
             .register_fn("to_string", EPlayerIndex::to_string)
@@ -263,6 +421,14 @@ impl std::str::FromStr for SConstraint {
         engine.register_static_module("farbe", module_farbe.into());
         engine.register_static_module("schlag", module_schlag.into());
         engine.register_static_module("trumpforfarbe", module_trumpforfarbe.into());
+        engine
+    }
+}
+
+impl std::str::FromStr for SConstraint {
+    type Err = Error;
+    fn from_str(str_in: &str) -> Result<Self, Self::Err> {
+        let engine = Self::new_engine();
         engine.compile(format!("fn inspect(ctx) {{ {str_in} }}"))
             .or_else(|_err|
                 str_in.parse()
@@ -272,10 +438,115 @@ impl std::str::FromStr for SConstraint {
                     )
             )
             .map(|ast| SConstraint{
-                engine,
+                engine: Arc::new(engine),
                 ast,
+                str_fn: "inspect".to_owned(),
                 str_display: str_in.to_string(),
             })
     }
 }
 
+
+#[cfg(test)]
+fn test_stichseq_ahand(rules: &SRules) -> (SStichSequence, EnumMap<EPlayerIndex, SHand>) {
+    let mut ahand = EPlayerIndex::map_from_raw([
+        "eo go ho so eu gu e7 ha",
+        "ea ez ek e9 hu su h7 h8",
+        "ga gz gk g9 g8 g7 hz hk",
+        "sa sz sk s9 s8 s7 h9 e8",
+    ].map(|str_hand| SHand::new_from_iter(unwrap!(cardvector::parse_cards::<Vec<_>>(str_hand)))));
+    let mut stichseq = SStichSequence::new(EKurzLang::Lang);
+    for card in unwrap!(cardvector::parse_cards::<Vec<_>>("e7 ea ga e8")) {
+        let epi = unwrap!(stichseq.current_stich().current_playerindex());
+        ahand[epi].play_card(card);
+        stichseq.zugeben(card, rules);
+    }
+    (stichseq, ahand)
+}
+
+#[test]
+fn test_context_functions() {
+    use openschafkopf_lib::rules::parser::parse_rule_description_simple;
+    let expensifiers = SExpensifiers::new(
+        /*n_stock*/0,
+        SDoublings::new_full(SStaticEPI0{}, [false; EPlayerIndex::SIZE]),
+        /*vecstoss*/Vec::new(),
+    );
+    let rules = unwrap!(parse_rule_description_simple("Rufspiel mit der Eichel-Sau von 0"));
+    let (stichseq, ahand) = test_stichseq_ahand(&rules);
+    for str_constraint in [
+        "ctx.trumpf(0)==7",
+        "ctx.trumpf()[1]==4",
+        "ctx.eichel(1)==3",
+        "ctx.ea(1)==0",
+        "ctx.who_has_card(card::ea)==-1",
+        "ctx.who_has_card(card::hz)==2",
+        "ctx.who_played_card(card::ea)==1",
+        "ctx.rufsau()==card::ea",
+        "ctx.player()==0",
+        "ctx.partner()==1",
+        "ctx.primary_party().len()==2",
+        "ctx.is_primary_party(1) && !ctx.is_primary_party(2)",
+        "ctx.stichs().len()==1 && ctx.current_stich().len()==0",
+        "ctx.stich_winners()[0]==1",
+        "ctx.points(1)==22 && ctx.points(0)==0",
+        "ctx.points_party(0)==22 && ctx.points_party(3)==0",
+        "ctx.laufende()==9",
+        "ctx.hand_to_string(2)!=\"\"",
+    ] {
+        let constraint = unwrap!(str_constraint.parse::<SConstraint>());
+        assert!(constraint.eval(&stichseq, &ahand, rules.clone(), &expensifiers), "{}", str_constraint);
+    }
+    // player indices out of range are reported as errors
+    let rules_ramsch = unwrap!(parse_rule_description_simple("Ramsch"));
+    let (stichseq_ramsch, ahand_ramsch) = test_stichseq_ahand(&rules_ramsch);
+    for str_constraint in [
+        "ctx.trumpf(ctx.partner())",
+        "ctx.points(4)",
+        "ctx.points_party(-1)",
+        "ctx.is_primary_party(ctx.player())",
+        "ctx.hand_to_string(-1)",
+    ] {
+        let constraint = unwrap!(str_constraint.parse::<SConstraint>());
+        assert!(constraint.internal_eval(&stichseq_ramsch, &ahand_ramsch, rules_ramsch.clone(), &expensifiers).is_err(), "{}", str_constraint);
+        assert!(!constraint.eval(&stichseq_ramsch, &ahand_ramsch, rules_ramsch.clone(), &expensifiers), "{}", str_constraint);
+    }
+}
+
+#[test]
+fn test_vec_from_file() {
+    use itertools::Itertools;
+    use openschafkopf_lib::rules::parser::parse_rule_description_simple;
+    let path = std::env::temp_dir().join(format!("openschafkopf_test_constraints_{}.rhai", std::process::id()));
+    let str_path = unwrap!(path.to_str());
+    unwrap!(std::fs::write(&path, "
+        private fn at_least(n, n_min) { n_min <= n }
+        fn many_trumpf(ctx) { at_least(ctx.trumpf(0), 7) }
+        fn partner_has_ea(ctx) { ctx.who_has_card(card::ea)==ctx.partner() }
+        fn no_ctx() { true }
+    "));
+    let vecconstraint = SConstraint::vec_from_file(str_path);
+    unwrap!(std::fs::write(&path, "private fn helper(ctx) { true }"));
+    let vecconstraint_empty = SConstraint::vec_from_file(str_path);
+    unwrap!(std::fs::remove_file(&path));
+    let vecconstraint = unwrap!(vecconstraint);
+    assert!(vecconstraint_empty.is_err());
+    assert_eq!(
+        vecconstraint.iter().map(SConstraint::to_string).sorted().collect::<Vec<_>>(),
+        vec!["many_trumpf", "partner_has_ea"],
+    );
+    let rules = unwrap!(parse_rule_description_simple("Rufspiel mit der Eichel-Sau von 0"));
+    let (stichseq, ahand) = test_stichseq_ahand(&rules);
+    let expensifiers = SExpensifiers::new(
+        /*n_stock*/0,
+        SDoublings::new_full(SStaticEPI0{}, [false; EPlayerIndex::SIZE]),
+        /*vecstoss*/Vec::new(),
+    );
+    for constraint in vecconstraint.iter() {
+        assert_eq!(
+            constraint.eval(&stichseq, &ahand, rules.clone(), &expensifiers),
+            constraint.to_string()=="many_trumpf",
+        );
+    }
+    assert!(SConstraint::vec_from_file("/nonexistent/constraints.rhai").is_err());
+}
@@ -1,20 +1,24 @@
 pub mod analyze;
 pub mod cli;
+pub mod engine;
 pub mod hand_stats;
 pub mod parse;
 pub mod suggest_card;
+pub mod text;
 pub mod webext;
 mod handconstraint;
 mod common_given_game;
+mod training_data;
 
 use openschafkopf_util::*;
 use openschafkopf_lib::{
     rules::{SDisplayRules, TRulesPlayerIndex, ruleset::VStockOrT},
-    ai::SAi,
+    ai::{SAi, neuralnetwork::SNeuralNetwork},
     primitives::card::EKurzLang,
     game::*,
 };
 use std::io::Read;
+use std::sync::Arc;
 use plain_enum::PlainEnum;
 
 mod shared_args {
@@ -27,19 +31,50 @@ mod shared_args {
     }
 }
 
-pub fn ai(subcommand_matches: &clap::ArgMatches) -> SAi {
-    match unwrap!(subcommand_matches.value_of("ai")) {
-        "cheating" => SAi::new_cheating(/*n_rank_rules_samples*/50, /*n_suggest_card_branches*/2),
-        "simulating" => 
-            SAi::new_simulating(
+// Reads --ai and --neural-network once, so that all AIs share one network.
+pub struct SAiFactory {
+    str_ai: String,
+    oneuralnetwork: Option<Arc<SNeuralNetwork>>,
+}
+
+impl SAiFactory {
+    pub fn new(subcommand_matches: &clap::ArgMatches) -> Result<Self, SStringifiedError> {
+        Ok(Self {
+            str_ai: unwrap!(subcommand_matches.value_of("ai")).to_string(),
+            oneuralnetwork: subcommand_matches.value_of("neural-network")
+                .map(|str_path| SNeuralNetwork::from_file(std::path::Path::new(str_path)).map(Arc::new))
+                .transpose()?,
+        })
+    }
+
+    pub fn ai(&self) -> Result<SAi, SStringifiedError> {
+        self.ai_from_str(&self.str_ai)
+    }
+
+    pub fn ai_from_str(&self, str_ai: &str) -> Result<SAi, SStringifiedError> {
+        let with_neural_network_filter = |ai: SAi| match &self.oneuralnetwork {
+            Some(neuralnetwork) => ai.with_neural_network_filter(neuralnetwork.clone()),
+            None => ai,
+        };
+        Ok(match str_ai {
+            "cheating" => with_neural_network_filter(SAi::new_cheating(/*n_rank_rules_samples*/50, /*n_suggest_card_branches*/2)),
+            "simulating" => with_neural_network_filter(
+                SAi::new_simulating(
+                    /*n_rank_rules_samples*/50,
+                    /*n_suggest_card_branches*/2,
+                    /*n_suggest_card_samples*/10,
+                )
+            ),
+            "neural-network" => SAi::new_neural_network(
                 /*n_rank_rules_samples*/50,
-                /*n_suggest_card_branches*/2,
-                /*n_suggest_card_samples*/10,
+                self.oneuralnetwork.clone()
+                    .ok_or_else(|| format_err!("AI neural-network requires --neural-network"))?,
             ),
-        _ => {
-            println!("Warning: AI not recognized. Defaulting to 'cheating'");
-            SAi::new_cheating(/*n_rank_rules_samples*/50, /*n_suggest_card_branches*/2)
-        }
+            _ => {
+                println!("Warning: AI not recognized. Defaulting to 'cheating'");
+                with_neural_network_filter(SAi::new_cheating(/*n_rank_rules_samples*/50, /*n_suggest_card_branches*/2))
+            }
+        })
     }
 }
 
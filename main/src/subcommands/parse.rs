@@ -1,5 +1,9 @@
 use openschafkopf_lib::{
-    game_analysis::parser::{analyze_sauspiel_html, analyze_sauspiel_json},
+    ai::neuralnetwork::{card_to_neural_network_input, neural_network_input_to_card},
+    game_analysis::{
+        gamerecord::gamerecords_from_str,
+        parser::{analyze_sauspiel_html, analyze_sauspiel_json},
+    },
     game::*,
     rules::{
         SRuleStateCache,
@@ -25,67 +29,30 @@ pub fn subcommand(str_subcommand: &'static str) -> clap::Command<'static> {
         .arg(clap::Arg::new("raw")
             .long("raw")
         )
-}
-
-macro_rules! card_neural_network_mapping(($macro:ident) => {
-    $macro!(
-        (EA, 1)
-        (GA, 2)
-        (HA, 3)
-        (SA, 4)
-        (EZ, 5)
-        (GZ, 6)
-        (HZ, 7)
-        (SZ, 8)
-        (EK, 9)
-        (GK, 10)
-        (HK, 11)
-        (SK, 12)
-        (EO, 13)
-        (GO, 14)
-        (HO, 15)
-        (SO, 16)
-        (EU, 17)
-        (GU, 18)
-        (HU, 19)
-        (SU, 20)
-        (E9, 21)
-        (G9, 22)
-        (H9, 23)
-        (S9, 24)
-        (E8, 25)
-        (G8, 26)
-        (H8, 27)
-        (S8, 28)
-        (E7, 29)
-        (G7, 30)
-        (H7, 31)
-        (S7, 32)
-    )
-});
-
-fn card_to_neural_network_input(ocard: Option<ECard>) -> usize {
-    if let Some(card) = ocard {
-        macro_rules! inner(($(($ecard:ident, $n:expr))*) => {
-            match card {
-                $(ECard::$ecard => $n,)*
-            }
-        });
-        verify_ne!(card_neural_network_mapping!(inner), 0)
-    } else {
-        0
-    }
-}
-
-fn neural_network_input_to_card(n: usize) -> Result<Option<ECard>, &'static str> {
-    macro_rules! inner(($(($ecard:ident, $n:expr))*) => {
-        match n {
-            0 => Ok(None),
-            $($n => Ok(Some(ECard::$ecard)),)*
-            _/*TODORUST 33..=usize::MAX*/ => Err("Unknown neural network input index"),
-        }
-    });
-    card_neural_network_mapping!(inner)
+        .arg(clap::Arg::new("game-record")
+            .long("game-record")
+            .help("Print games as versioned JSON game records, one per line")
+            .long_help("Print games (from Sauspiel, NetSchafkopf or plain input) as versioned JSON game records, one per line. These can be read by analyze.")
+        )
+        .arg(clap::Arg::new("training-data")
+            .long("training-data")
+            .takes_value(true)
+            .help("Export one sample per played card as NPY files into the given directory")
+            .long_help(super::training_data::STR_TRAINING_DATA_FORMAT)
+        )
+        .arg(clap::Arg::new("training-data-engine")
+            .long("training-data-engine")
+            .takes_value(true)
+            .requires("training-data")
+            .help("Add the engine's payout per card to training data if the player has at most this many cards")
+        )
+        .arg(clap::Arg::new("validation-percent")
+            .long("validation-percent")
+            .takes_value(true)
+            .default_value("10")
+            .requires("training-data")
+            .help("Percentage of games to put into the validation set of training data")
+        )
 }
 
 pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
@@ -163,12 +130,46 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
     let path_neural_network = std::path::PathBuf::from(&format!("neural_network_input/{}",
         chrono::Local::now().format("%Y%m%d%H%M%S"),
     ));
+    let b_game_record = clapmatches.is_present("game-record");
+    let mut otrainingdata = clapmatches.value_of("training-data")
+        .map(|str_path| -> Result<_, Error> {
+            Ok(super::training_data::STrainingData::new(
+                std::path::PathBuf::from(str_path),
+                unwrap!(clapmatches.value_of("validation-percent")).parse()?,
+                clapmatches.value_of("training-data-engine").map(str::parse).transpose()?,
+            ))
+        })
+        .transpose()?;
     super::glob_files_or_read_stdin(
         clapmatches,
         |opath, str_input, i_input| {
+            if b_game_record {
+                let vecgamerecord = gamerecords_from_str(&str_input);
+                if vecgamerecord.is_empty() {
+                    eprintln!("Nothing found in {opath:?}: Trying to continue.");
+                }
+                for gamerecord in vecgamerecord {
+                    println!("{}", unwrap!(serde_json::to_string(&gamerecord)));
+                }
+                return;
+            }
+            if let Some(ref mut trainingdata) = otrainingdata {
+                let vecgamerecord = gamerecords_from_str(&str_input);
+                if vecgamerecord.is_empty() {
+                    eprintln!("Nothing found in {opath:?}: Trying to continue.");
+                }
+                for gamerecord in vecgamerecord {
+                    if let Err(err) = trainingdata.push_gamerecord(&gamerecord) {
+                        eprintln!("Error in {opath:?}: {err:?}. Trying to continue.");
+                    }
+                }
+                return;
+            }
             if let Ok(ref gameresult@SGameResultGeneric{stockorgame: VStockOrT::OrT(ref game), ..}) = analyze_sauspiel_html(&str_input)
                 .map(|game| game.map(|_|(), |_|(), |ruleset| ruleset.kurzlang()))
-                .or_else(|_err| analyze_sauspiel_json(&str_input, |_,_,_,_| {}))
+                .or_else(|_err| analyze_sauspiel_json(&str_input, |_,_,_,_| {})
+                    .map(|game| game.map(|_|(), |_|(), |ruleset| ruleset.kurzlang()))
+                )
             {
                 let mut game_csv = SGame::new(
                     game.aveccard.clone(),
@@ -265,5 +266,8 @@ pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
             unwrap!(file.flush());
         }
     }
+    if let Some(trainingdata) = otrainingdata {
+        trainingdata.write()?;
+    }
     Ok(())
 }
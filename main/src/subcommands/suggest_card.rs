@@ -1,6 +1,7 @@
 use openschafkopf_lib::{
-    ai::{*, gametree::*, stichoracle::SFilterByOracle, cardspartition::*},
-    rules::{SRules, SDisplayRules, TRules, SRuleStateCacheFixed, SExpensifiers},
+    ai::{SPayoutStats, VInspectionPoint, gametree::EMinMaxStrategy},
+    api::{ESingleStrategy, SPayouts, SSearchOptions, VApiError, VBranching},
+    rules::{SDisplayRules, TRules},
     primitives::*,
     game_analysis::determine_best_card_table::{
         table,
@@ -14,22 +15,22 @@ use openschafkopf_util::*;
 use itertools::*;
 use serde::Serialize;
 use derive_new::new;
-use plain_enum::{PlainEnum, EnumMap};
+use plain_enum::PlainEnum;
 use super::common_given_game::*;
 use std::io::IsTerminal;
-use std::sync::{Arc, Mutex};
+use std::sync::Mutex;
 
 // TODO? can we make this a fn of SPayoutStatsTable?
-fn print_payoutstatstable<T: std::fmt::Display, TplStrategies: TTplStrategies>(
-    payoutstatstable: &SPayoutStatsTable<T, TplStrategies>,
+fn print_payoutstatstable<T: std::fmt::Display>(
+    payoutstatstable: &SPayoutStatsTable<T>,
     b_print_table_description_before_table: bool
 ) {
     let slcoutputline = &payoutstatstable.output_lines();
     if b_print_table_description_before_table { // TODO? only for second-level verbosity
         println!("\nInterpreting a line of the following table (taking the first line as an example):");
-        let SOutputLine{vect, perminmaxstrategyatplstrf} = &slcoutputline[0];
+        let SOutputLine{vect, vectplemmstrategyatplstrf} = &slcoutputline[0];
         println!("If you play {}, then:", vect.iter().join(" or "));
-        for (i_strategy, (emmstrategy, atplstrf)) in perminmaxstrategyatplstrf.via_accessors().into_iter().enumerate() {
+        for (i_strategy, (emmstrategy, atplstrf)) in vectplemmstrategyatplstrf.iter().enumerate() {
             let astr = atplstrf.clone().map(|tplstrf| tplstrf.0);
             let [str_payout_min, str_payout_avg, str_payout_max, str_stats] = &astr;
             println!("* Columns {i_strategy_1_based}.1 to {i_strategy_1_based}.{n_subcolumns} show tell what happens if all other players play {str_play}:",
@@ -58,14 +59,14 @@ fn print_payoutstatstable<T: std::fmt::Display, TplStrategies: TTplStrategies>(
         assign_gt(&mut n_width_id, str_id.len());
         vecstr_id.push(str_id);
     }
-    for (str_id, SOutputLine{vect:_, perminmaxstrategyatplstrf}) in itertools::zip_eq(
+    for (str_id, SOutputLine{vect:_, vectplemmstrategyatplstrf}) in itertools::zip_eq(
         vecstr_id.iter(),
         slcoutputline.iter(),
     ) {
         print!("{str_id:<n_width_id$}: ");
         for ((_emmstrategy_atplstrf, atplstrf), (_emmstrategy_aformatinfo, aformatinfo)) in itertools::zip_eq(
-            perminmaxstrategyatplstrf.via_accessors(),
-            payoutstatstable.format_infos().via_accessors(),
+            vectplemmstrategyatplstrf.iter(),
+            payoutstatstable.format_infos().iter(),
         ) {
             for ((str_num, f), SFormatInfo{f_min, f_max, n_width}) in itertools::zip_eq(
                 atplstrf.iter(),
@@ -150,26 +151,61 @@ pub fn subcommand(str_subcommand: &'static str) -> clap::Command<'static> {
         // TODO support "compute optimal game tree"
 }
 
+// Serializes like SPerMinMaxStrategyGeneric: Strategies not explored are null.
+#[derive(Serialize)]
+struct SJsonPerStrategy<T> {
+    ominmin: Option<T>,
+    omaxmin: Option<T>,
+    omaxselfishmin: Option<T>,
+    omaxselfishmax: Option<T>,
+    omaxmax: Option<T>,
+}
+
+impl<T> SJsonPerStrategy<T> {
+    fn new(itemmstrategyt: impl IntoIterator<Item=(EMinMaxStrategy, T)>) -> Self {
+        let mut jsonperstrategy = Self {
+            ominmin: None,
+            omaxmin: None,
+            omaxselfishmin: None,
+            omaxselfishmax: None,
+            omaxmax: None,
+        };
+        for (emmstrategy, t) in itemmstrategyt {
+            *match emmstrategy {
+                EMinMaxStrategy::MinMin => &mut jsonperstrategy.ominmin,
+                EMinMaxStrategy::MaxMin => &mut jsonperstrategy.omaxmin,
+                EMinMaxStrategy::MaxSelfishMin => &mut jsonperstrategy.omaxselfishmin,
+                EMinMaxStrategy::MaxSelfishMax => &mut jsonperstrategy.omaxselfishmax,
+                EMinMaxStrategy::Max => &mut jsonperstrategy.omaxmax,
+            } = Some(t);
+        }
+        jsonperstrategy
+    }
+}
+
 #[derive(new, Serialize)]
-struct SJsonTableLine<TplStrategies: TTplStrategies>
-    where
-        SPerMinMaxStrategyGeneric<Vec<((isize/*n_payout*/, char/*chr_loss_or_win*/), usize/*n_count*/)>, TplStrategies>: Serialize,
-{
+struct SJsonTableLine {
     ostr_header: Option<String>,
-    perminmaxstrategyvecpayout_histogram: SPerMinMaxStrategyGeneric<Vec<((isize/*n_payout*/, char/*chr_loss_or_win*/), usize/*n_count*/)>, TplStrategies>,
+    perminmaxstrategyvecpayout_histogram: SJsonPerStrategy<Vec<((isize/*n_payout*/, char/*chr_loss_or_win*/), usize/*n_count*/)>>,
 }
 
 #[derive(new, Serialize)]
-struct SJson<TplStrategies: TTplStrategies> {
+struct SJson {
     str_rules: String,
     astr_hand: [String; EPlayerIndex::SIZE],
-    vectableline: Vec<SJsonTableLine<TplStrategies>>,
+    vectableline: Vec<SJsonTableLine>,
+}
+
+#[derive(new, Serialize)]
+struct SJsonCheckpoint {
+    str_played_cards: String,
+    n_epi_position: usize,
+    json: serde_json::Value,
 }
 
-fn json_histograms<TplStrategies: TTplStrategies>(payoutstatsperstrategy: &SPerMinMaxStrategyGeneric<SPayoutStats<std::cmp::Ordering>, TplStrategies>)
-    -> SPerMinMaxStrategyGeneric<Vec<((isize, char), usize)>, TplStrategies>
-{
-    payoutstatsperstrategy.map(|payoutstats| 
+fn json_histograms(payouts: &SPayouts) -> SJsonPerStrategy<Vec<((isize, char), usize)>> {
+    SJsonPerStrategy::new(payouts.payout_stats().into_iter().map(|(emmstrategy, payoutstats)| (
+        emmstrategy,
         payoutstats.histogram().iter()
             .map(|((n_payout, ord_vs_0), n_count)| ( 
                 (
@@ -182,205 +218,12 @@ fn json_histograms<TplStrategies: TTplStrategies>(payoutstatsperstrategy: &SPerM
                 ),
                 *n_count,
             ))
-            .collect()
-    )
-}
-
-enum EBranching {
-    Branching(usize, usize),
-    Equivalent(usize, SCardsPartition),
-    Oracle,
-    OnePerWinnerIndex(Option<EPlayerIndex>),
-}
-
-#[derive(Clone)]
-enum ESingleStrategy {
-    MaxMin,
-    MaxSelfishMin,
-}
-
-fn make_snapshot_cache<TplStrategies: TTplStrategies>(rules: &SRules) -> impl Fn(&SRuleStateCacheFixed) -> Box<dyn TSnapshotCache<SPerMinMaxStrategyRawPayout<TplStrategies>>> + '_ {
-    move |rulestatecache| rules.snapshot_cache::<TplStrategies>(rulestatecache)
-}
-
-#[allow(clippy::extra_unused_type_parameters)]
-fn make_snapshot_cache_none<TplStrategies>(_rules: &SRules) -> impl Fn(&SRuleStateCacheFixed)->SSnapshotCacheNone {
-    SSnapshotCacheNone::factory()
+            .collect(),
+    )))
 }
 
-fn run_internal<
-    'stichseq,
-    'rules,
-    FilterAllowedCards: TFilterAllowedCards,
-    TplStrategies: TTplStrategies,
-    AlphaBetaPruner: TAlphaBetaPruner+Sync,
-    Pruner: TPruner+Sync,
-    SnapshotCache: TSnapshotCache<<SMinReachablePayoutBase<'rules, Pruner, TplStrategies, AlphaBetaPruner> as TForEachSnapshot>::Output>,
-    OSnapshotCache: Into<Option<SnapshotCache>>,
-    SnapshotVisualizer: TSnapshotVisualizer<<SMinReachablePayoutBase<'rules, Pruner, TplStrategies, AlphaBetaPruner> as TForEachSnapshot>::Output>,
-    OFilterAllowedCards: Into<Option<FilterAllowedCards>>,
->(
-    b_verbose: bool,
-    clapmatches: &clap::ArgMatches,
-    ahand_fixed_with_holes: &EnumMap<EPlayerIndex, SHand>,
-    rules: &SRules,
-    epi_position: EPlayerIndex,
-    expensifiers: &SExpensifiers,
-
-    stichseq: &'stichseq SStichSequence,
-    itahand: Box<dyn Iterator<Item=EnumMap<EPlayerIndex, SHand>> + Send + 'stichseq>,
-    fn_make_filter: impl Fn(&SStichSequence, &EnumMap<EPlayerIndex, SHand>)->OFilterAllowedCards + std::marker::Sync,
-
-    fn_alphabetapruner: impl Fn(&SStichSequence, &EnumMap<EPlayerIndex, SHand>)->AlphaBetaPruner + std::marker::Sync,
-
-    fn_snapshotcache: impl Fn(&SRuleStateCacheFixed) -> OSnapshotCache + std::marker::Sync,
-    fn_visualizer: impl Fn(usize, &EnumMap<EPlayerIndex, SHand>, Option<ECard>) -> SnapshotVisualizer + std::marker::Sync,
-    fn_payout: &(impl Fn(&SStichSequence, &EnumMap<EPlayerIndex, SHand>, isize)->(isize, std::cmp::Ordering) + Sync),
-) -> Result<(), Error>
-{
-    let fn_loss_or_win = |_n_payout, ord_vs_0| ord_vs_0;
-    let n_repeat_hand = clapmatches.value_of("repeat_hands").unwrap_or("1").parse()?;
-    let ovecinterimres_verbose = if_then_some!(b_verbose, Arc::new(Mutex::new(Vec::<SInterimResult<TplStrategies>>::new())));
-    let determinebestcardresult = determine_best_card(
-        stichseq,
-        Box::new(
-            itahand
-                .flat_map(|ahand| {
-                    std::iter::repeat_n(
-                        ahand,
-                        n_repeat_hand,
-                    )
-                })
-        ) as Box<_>,
-        fn_make_filter,
-        /*fn_make_foreachsnapshot*/&|stichseq, ahand| <SMinReachablePayoutBase<Pruner, TplStrategies, _>>::new_with_pruner(
-            rules,
-            epi_position,
-            expensifiers.clone(),
-            fn_alphabetapruner(stichseq, ahand),
-        ),
-        fn_snapshotcache,
-        fn_visualizer,
-        /*fn_inspect*/&|inspectionpoint, i_ahand, ahand| {
-            if let Some(ref vecinterimres) = ovecinterimres_verbose {
-                match inspectionpoint {
-                    VInspectionPoint::Card{b_before, card} => {
-                        println!(" {} {} ({}): {}",
-                            if *b_before {'>'} else {'<'},
-                            i_ahand+1, // TODO use same hand counters as in common_given_game
-                            card,
-                            display_card_slices(ahand, rules, " | "),
-                        );
-                    },
-                    VInspectionPoint::AfterHand(mapcardopayoutstats) => {
-                        let fn_cmp_interim_result = |lhs: &SInterimResult<TplStrategies>, rhs: &SInterimResult<TplStrategies>| {
-                            rhs.payoutstats.compare_canonical(&lhs.payoutstats, fn_loss_or_win)
-                        };
-                        let mut vecinterimres = unwrap!(vecinterimres.lock());
-                        let n_count_before = vecinterimres.len();
-                        assert!(vecinterimres.is_sorted_by(fn_cmp_to_fn_le(fn_cmp_interim_result)));
-                        // Remember old ranks and positions
-                        let mut mapcardoposandrank_old = ECard::map_from_fn(|_| None);
-                        for_each_interim_result(&mut vecinterimres, fn_cmp_interim_result, |posandrank, interimres| {
-                            verify!(mapcardoposandrank_old[interimres.card].replace(posandrank).is_none()); // Implies that each card occured only once
-                        });
-                        // Copy over/update values that have been present in previous iteration
-                        for (card, payoutstats) in internal_cards_and_ts(mapcardopayoutstats) {
-                            if let Some(posandrank) = &mapcardoposandrank_old[card] {
-                                assert_eq!(vecinterimres[posandrank.i_position].card, card);
-                                vecinterimres[posandrank.i_position].payoutstats = payoutstats.clone(); // Update to new value
-                            } else {
-                                vecinterimres.push(SInterimResult{ornkchg: None, card, payoutstats: payoutstats.clone()});
-                            }
-                        }
-                        // Compute rank changes - only on already known entries.
-                        let slcinterimres_already_present = &mut vecinterimres[0..n_count_before];
-                        slcinterimres_already_present.sort_by(&fn_cmp_interim_result);
-                        for_each_interim_result(slcinterimres_already_present, fn_cmp_interim_result, |posandrank, interimres| {
-                            interimres.ornkchg = Some(match posandrank.n_rank.cmp(&unwrap!(mapcardoposandrank_old[interimres.card].as_ref()).n_rank) {
-                                std::cmp::Ordering::Less => VRankChange::Change(ELoHi::Lo),
-                                std::cmp::Ordering::Greater => VRankChange::Change(ELoHi::Hi),
-                                std::cmp::Ordering::Equal => VRankChange::Equal(match interimres.ornkchg {
-                                    None | Some(VRankChange::Change(_)) => 1,
-                                    Some(VRankChange::Equal(n_iterations)) => n_iterations + 1,
-                                })
-                            });
-                        });
-                        if n_count_before<vecinterimres.len() {
-                            vecinterimres.sort_by(&fn_cmp_interim_result);
-                        } else {
-                            assert!(vecinterimres.is_sorted_by(fn_cmp_to_fn_le(fn_cmp_interim_result)));
-                        }
-                        print_payoutstatstable::<_,TplStrategies>(
-                            &internal_table(
-                                vecinterimres.iter()
-                                    .map(|SInterimResult{ornkchg, card, payoutstats}| (
-                                        format!("{} {}",
-                                            match ornkchg {
-                                                None => "".to_string(),
-                                                Some(VRankChange::Change(ELoHi::Lo)) => "^".to_string(),
-                                                Some(VRankChange::Equal(n_iterations)) => format!("=({n_iterations})"),
-                                                Some(VRankChange::Change(ELoHi::Hi)) => "v".to_string(),
-                                            },
-                                            card,
-                                        ),
-                                        payoutstats,
-                                    ))
-                                    .collect(),
-                                /*b_group*/false,
-                                &fn_loss_or_win,
-                            ),
-                            /*b_print_table_description_before_table*/false,
-                        );
-                    },
-                }
-            }
-        },
-        fn_payout,
-    ).ok_or_else(||format_err!("Could not determine best card. Apparently could not generate valid hands."))?;
-    if clapmatches.is_present("json") {
-        println!("{}", unwrap!(serde_json::to_string(
-            &SJson::new(
-                /*str_rules*/SDisplayRules::new(rules, /*b_include_playerindex*/true).to_string(),
-                /*str_hand*/ahand_fixed_with_holes.map(|hand|
-                    SDisplayCardSlice::new(hand.cards().clone(), rules).to_string()
-                ).into_raw(),
-                /*vectableline*/itertools::chain(
-                    determinebestcardresult.cards_and_ts()
-                        .map(|(card, payoutstatsperstrategy)|
-                            SJsonTableLine::new(
-                                /*ostr_header*/Some(card.to_string()),
-                                /*perminmaxstrategyvecpayout_histogram*/json_histograms::<TplStrategies>(payoutstatsperstrategy),
-                            )
-                        ),
-                    std::iter::once(SJsonTableLine::new(
-                        /*ostr_header*/Some("no-details".to_string()),
-                        /*perminmaxstrategyvecpayout_histogram*/json_histograms::<TplStrategies>(&determinebestcardresult.t_combined),
-                    )),
-                ).collect::<Vec<SJsonTableLine<TplStrategies>>>(),
-            ),
-        )));
-    } else {
-        let payoutstatstable = table(
-            &determinebestcardresult,
-            rules,
-            &fn_loss_or_win,
-        );
-        print_payoutstatstable::<_,TplStrategies>(
-            &payoutstatstable,
-            /*b_print_table_description_before_table*/b_verbose,
-        );
-        println!("-----");
-        print_payoutstatstable::<_,TplStrategies>(
-            &internal_table(
-                vec!(("no-details", determinebestcardresult.t_combined)),
-                /*b_group*/false,
-                &fn_loss_or_win,
-            ),
-            /*b_print_table_description_before_table*/false,
-        );
-    }
-    Ok(())
+fn fn_loss_or_win(_n_payout: isize, ord_vs_0: std::cmp::Ordering) -> std::cmp::Ordering {
+    ord_vs_0
 }
 
 #[derive(Debug, Clone)]
@@ -389,10 +232,10 @@ enum VRankChange { // Lower ranks considered better.
     Equal(usize/*n_iterations*/),
 }
 
-struct SInterimResult<TplStrategies: TTplStrategies> {
+struct SInterimResult {
     ornkchg: Option<VRankChange>,
     card: ECard,
-    payoutstats: SPerMinMaxStrategyGeneric<SPayoutStats<std::cmp::Ordering>, TplStrategies>,
+    payouts: SPayouts,
 }
 
 struct SPositionAndRank {
@@ -400,10 +243,10 @@ struct SPositionAndRank {
     n_rank: usize,
 }
 
-fn for_each_interim_result<TplStrategies: TTplStrategies>( // TODORUST generic closure
-    slcinterimres: &mut [SInterimResult<TplStrategies>], // TODO Taking mut here is unfortunate, but I did not see a simple way out of this without duplication
-    fn_cmp_interim_result: impl FnMut(&SInterimResult<TplStrategies>, &SInterimResult<TplStrategies>)->std::cmp::Ordering,
-    mut fn_callback: impl FnMut(SPositionAndRank, &mut SInterimResult<TplStrategies>),
+fn for_each_interim_result(
+    slcinterimres: &mut [SInterimResult], // TODO Taking mut here is unfortunate, but I did not see a simple way out of this without duplication
+    fn_cmp_interim_result: impl FnMut(&SInterimResult, &SInterimResult)->std::cmp::Ordering,
+    mut fn_callback: impl FnMut(SPositionAndRank, &mut SInterimResult),
 ) {
     let mut n_rank = 0;
     for slcinterimres_chunk in slcinterimres
@@ -416,194 +259,218 @@ fn for_each_interim_result<TplStrategies: TTplStrategies>( // TODORUST generic c
     }
 }
 
+fn payout_stats_table_lines<'payouts, T>(
+    itpayouts: impl Iterator<Item=(T, &'payouts SPayouts)>,
+) -> Vec<(T, Vec<(EMinMaxStrategy, &'payouts SPayoutStats<std::cmp::Ordering>)>)> {
+    itpayouts
+        .map(|(t, payouts)| (t, payouts.payout_stats()))
+        .collect()
+}
+
 pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), Error> {
+    let oesinglestrategy = match clapmatches.value_of("strategy") {
+        Some("maxmin") => Ok(Some(ESingleStrategy::MaxMin)),
+        Some("maxselfishmin") => Ok(Some(ESingleStrategy::MaxSelfishMin)),
+        None => Ok(None),
+        Some(_) => Err(format_err!("Could not understand strategy.")),
+    }?;
+    let branching = if let Some(str_branching) = clapmatches.value_of("branching") {
+        if str_branching.is_empty() {
+            VBranching::All
+        } else if str_branching=="oracle" {
+            VBranching::Oracle
+        } else if let Some(oepi_unfiltered) = str_branching.strip_prefix("oneperwinnerindex")
+            .map(|str_oepi_unfiltered| str_oepi_unfiltered.parse().ok())
+        {
+            VBranching::OnePerWinnerIndex(oepi_unfiltered)
+        } else if let Some(n_until_stichseq_len) = str_branching.strip_prefix("equiv")
+            .and_then(|str_n_until_remaining_cards| str_n_until_remaining_cards.parse().ok())
+        {
+            VBranching::Equivalent{n_until_stichseq_len}
+        } else {
+            let [str_lo, str_hi] = str_branching
+                .split(',')
+                .collect_array()
+                .ok_or_else(|| format_err!("Could not parse branching"))?;
+            let (n_lo, n_hi) = (str_lo.trim().parse::<usize>()?, str_hi.trim().parse::<usize>()?);
+            VBranching::Random{n_lo, n_hi} // TODO we should avoid branching in case n_lo is greater than all hand's fixed cards
+        }
+    } else {
+        VBranching::All
+    };
+    // we are interested in payout => single-card-optimization useless
+    let searchoptions = SSearchOptions {
+        branching,
+        oesinglestrategy,
+        b_prune_via_hints: clapmatches.value_of("prune")==Some("hint"),
+        b_alpha_beta_pruning: clapmatches.is_present("abprune"),
+        b_snapshot_cache: clapmatches.is_present("snapshotcache"), // TODO customizable depth
+        b_points: clapmatches.is_present("points"),
+        n_repeat_hands: clapmatches.value_of("repeat_hands").unwrap_or("1").parse()?,
+    };
+    let b_json = clapmatches.is_present("json");
+    let mut vecjsoncheckpoint = Vec::new();
     with_common_args(
         clapmatches,
-        |itahand, rules, stichseq, ahand_fixed_with_holes, epi_position, expensifiers, b_verbose| {
-            let otplrulesfn_points_as_payout = if clapmatches.is_present("points") {
-                if let Some(tplrulesfn_points_as_payout) = rules.points_as_payout() {
-                    Some(tplrulesfn_points_as_payout)
-                } else {
-                    if b_verbose { // TODO? dispatch statically
-                        println!("Rules {} do not support point based variant.", SDisplayRules::new(rules, /*b_include_playerindex*/false));
-                    }
-                    None
+        |engine, gamestate, itahand, b_verbose, checkpoint| {
+            let rules = gamestate.rules();
+            let epi_position = gamestate.position();
+            if b_verbose && searchoptions.b_points && rules.points_as_payout().is_none() { // TODO? dispatch statically
+                println!("Rules {} do not support point based variant.", SDisplayRules::new(rules, /*b_include_playerindex*/false));
+            }
+            if b_verbose && searchoptions.b_alpha_beta_pruning && !searchoptions.alpha_beta_pruning_applicable(rules) {
+                println!("Warning: abprune not supported strategy/rules combination. Continuing without.");
+            }
+            let str_played_cards = gamestate.stichseq().visible_cards().map(|(_epi, card)| card).join(" ");
+            if 1<checkpoint.n_checkpoints && !b_json {
+                if 0<checkpoint.i_checkpoint {
+                    println!();
                 }
+                println!("Checkpoint {}: {} (player {})", checkpoint.i_checkpoint+1, str_played_cards, epi_position);
+            }
+            let ovecinterimres_verbose = if_then_some!(b_verbose, Mutex::new(Vec::<SInterimResult>::new()));
+            let suggestion = engine.evaluate_cards(
+                gamestate,
+                itahand,
+                &searchoptions,
+                /*fn_inspect*/|inspectionpoint, i_ahand, ahand| {
+                    if let Some(ref vecinterimres) = ovecinterimres_verbose {
+                        match inspectionpoint {
+                            VInspectionPoint::Card{b_before, card} => {
+                                println!(" {} {} ({}): {}",
+                                    if *b_before {'>'} else {'<'},
+                                    i_ahand+1, // TODO use same hand counters as in common_given_game
+                                    card,
+                                    display_card_slices(ahand, rules, " | "),
+                                );
+                            },
+                            VInspectionPoint::AfterHand(progress) => {
+                                let fn_cmp_interim_result = |lhs: &SInterimResult, rhs: &SInterimResult| {
+                                    rhs.payouts.compare_canonical(&lhs.payouts)
+                                };
+                                let mut vecinterimres = unwrap!(vecinterimres.lock());
+                                let n_count_before = vecinterimres.len();
+                                assert!(vecinterimres.is_sorted_by(fn_cmp_to_fn_le(fn_cmp_interim_result)));
+                                // Remember old ranks and positions
+                                let mut mapcardoposandrank_old = ECard::map_from_fn(|_| None);
+                                for_each_interim_result(&mut vecinterimres, fn_cmp_interim_result, |posandrank, interimres| {
+                                    verify!(mapcardoposandrank_old[interimres.card].replace(posandrank).is_none()); // Implies that each card occured only once
+                                });
+                                // Copy over/update values that have been present in previous iteration
+                                for cardevaluation in progress.veccardevaluation.iter() {
+                                    let card = cardevaluation.card;
+                                    if let Some(posandrank) = &mapcardoposandrank_old[card] {
+                                        assert_eq!(vecinterimres[posandrank.i_position].card, card);
+                                        vecinterimres[posandrank.i_position].payouts = cardevaluation.payouts.clone(); // Update to new value
+                                    } else {
+                                        vecinterimres.push(SInterimResult{ornkchg: None, card, payouts: cardevaluation.payouts.clone()});
+                                    }
+                                }
+                                // Compute rank changes - only on already known entries.
+                                let slcinterimres_already_present = &mut vecinterimres[0..n_count_before];
+                                slcinterimres_already_present.sort_by(&fn_cmp_interim_result);
+                                for_each_interim_result(slcinterimres_already_present, fn_cmp_interim_result, |posandrank, interimres| {
+                                    interimres.ornkchg = Some(match posandrank.n_rank.cmp(&unwrap!(mapcardoposandrank_old[interimres.card].as_ref()).n_rank) {
+                                        std::cmp::Ordering::Less => VRankChange::Change(ELoHi::Lo),
+                                        std::cmp::Ordering::Greater => VRankChange::Change(ELoHi::Hi),
+                                        std::cmp::Ordering::Equal => VRankChange::Equal(match interimres.ornkchg {
+                                            None | Some(VRankChange::Change(_)) => 1,
+                                            Some(VRankChange::Equal(n_iterations)) => n_iterations + 1,
+                                        })
+                                    });
+                                });
+                                if n_count_before<vecinterimres.len() {
+                                    vecinterimres.sort_by(&fn_cmp_interim_result);
+                                } else {
+                                    assert!(vecinterimres.is_sorted_by(fn_cmp_to_fn_le(fn_cmp_interim_result)));
+                                }
+                                print_payoutstatstable(
+                                    &internal_table(
+                                        payout_stats_table_lines(vecinterimres.iter()
+                                            .map(|SInterimResult{ornkchg, card, payouts}| (
+                                                format!("{} {}",
+                                                    match ornkchg {
+                                                        None => "".to_string(),
+                                                        Some(VRankChange::Change(ELoHi::Lo)) => "^".to_string(),
+                                                        Some(VRankChange::Equal(n_iterations)) => format!("=({n_iterations})"),
+                                                        Some(VRankChange::Change(ELoHi::Hi)) => "v".to_string(),
+                                                    },
+                                                    card,
+                                                ),
+                                                payouts,
+                                            ))
+                                        ),
+                                        /*b_group*/false,
+                                        &fn_loss_or_win,
+                                    ),
+                                    /*b_print_table_description_before_table*/false,
+                                );
+                            },
+                        }
+                    }
+                },
+            ).map_err(|apierror| match apierror {
+                VApiError::NoCardDistribution => format_err!("Could not determine best card. Apparently could not generate valid hands."),
+                apierror => format_err!("Could not determine best card: {}", apierror),
+            })?;
+            let payouts_combined = unwrap!(suggestion.opayouts_combined.as_ref());
+            let ojson = if b_json {
+                Some(unwrap!(serde_json::to_value(
+                    SJson::new(
+                        /*str_rules*/SDisplayRules::new(rules, /*b_include_playerindex*/true).to_string(),
+                        /*str_hand*/EPlayerIndex::map_from_fn(|epi|
+                            SDisplayCardSlice::new(gamestate.known_cards(epi).to_vec(), rules).to_string()
+                        ).into_raw(),
+                        /*vectableline*/itertools::chain(
+                            suggestion.veccardevaluation.iter()
+                                .map(|cardevaluation|
+                                    SJsonTableLine::new(
+                                        /*ostr_header*/Some(cardevaluation.card.to_string()),
+                                        /*perminmaxstrategyvecpayout_histogram*/json_histograms(&cardevaluation.payouts),
+                                    )
+                                ),
+                            std::iter::once(SJsonTableLine::new(
+                                /*ostr_header*/Some("no-details".to_string()),
+                                /*perminmaxstrategyvecpayout_histogram*/json_histograms(payouts_combined),
+                            )),
+                        ).collect::<Vec<SJsonTableLine>>(),
+                    ),
+                )))
             } else {
+                let payoutstatstable = table(
+                    suggestion.veccardevaluation.iter()
+                        .map(|cardevaluation| (cardevaluation.card, cardevaluation.payouts.payout_stats())),
+                    rules,
+                    &fn_loss_or_win,
+                );
+                print_payoutstatstable(
+                    &payoutstatstable,
+                    /*b_print_table_description_before_table*/b_verbose,
+                );
+                println!("-----");
+                print_payoutstatstable(
+                    &internal_table(
+                        payout_stats_table_lines(std::iter::once(("no-details", payouts_combined))),
+                        /*b_group*/false,
+                        &fn_loss_or_win,
+                    ),
+                    /*b_print_table_description_before_table*/false,
+                );
                 None
             };
-            let rules = if let Some((rules, _fn_payout_to_points)) = &otplrulesfn_points_as_payout {
-                rules.clone()
+            if checkpoint.n_checkpoints==1 {
+                if let Some(json) = ojson {
+                    println!("{json}");
+                }
             } else {
-                rules.clone()
-            };
-            let rules = &rules;
-            let fn_human_readable_payout = |stichseq: &SStichSequence, ahand: &EnumMap<EPlayerIndex, SHand>, epi_position: EPlayerIndex, n_payout: isize| -> (isize, std::cmp::Ordering) {
-                if let Some((_rules, fn_payout_to_points)) = &otplrulesfn_points_as_payout {
-                    (
-                        fn_payout_to_points(
-                            stichseq,
-                            ahand,
-                            epi_position,
-                            n_payout,
-                        ),
-                        n_payout.cmp(&0), // Human readable payout may not indicate loss or win: In Rufspiel, if epi_position has 60, it may mean win or loss, depending on whether epi_position is co-player.
-                    )
-                } else {
-                    (n_payout, n_payout.cmp(&0))
+                if let Some(json) = ojson {
+                    vecjsoncheckpoint.push(SJsonCheckpoint::new(str_played_cards, epi_position.to_usize(), json));
                 }
-            };
-            use EBranching::*;
-            let oesinglestrategy = match clapmatches.value_of("strategy") {
-                Some("maxmin") => Ok(Some(ESingleStrategy::MaxMin)),
-                Some("maxselfishmin") => Ok(Some(ESingleStrategy::MaxSelfishMin)),
-                None => Ok(None),
-                Some(_) => Err(format_err!("Could not understand strategy.")),
-            }?;
-            // we are interested in payout => single-card-optimization useless
-            macro_rules! forward{(
-                (($($func_filter_allowed_cards_ty: tt)*), $func_filter_allowed_cards: expr),
-                ($pruner:ident),
-                ($TplStrategies:ident, $fn_alphabetapruner:expr,),
-                $fn_snapshotcache:ident,
-                $fn_visualizer: expr,
-            ) => {{ // TODORUST generic closures
-                run_internal::<$($func_filter_allowed_cards_ty)*,$TplStrategies,_,$pruner,_,_,_,_>( // TODO avoid explicit types
-                    b_verbose,
-                    clapmatches,
-                    ahand_fixed_with_holes,
-                    rules,
-                    epi_position,
-                    expensifiers,
-                    stichseq,
-                    itahand,
-                    $func_filter_allowed_cards,
-                    $fn_alphabetapruner,
-                    $fn_snapshotcache::<$TplStrategies>(rules),
-                    $fn_visualizer,
-                    /*fn_payout*/&|stichseq, ahand, n_payout| fn_human_readable_payout(
-                        stichseq,
-                        ahand,
-                        epi_position,
-                        n_payout,
-                    ),
-                )?
-            }}}
-            let oebranching = if let Some(str_branching) = clapmatches.value_of("branching") {
-                if str_branching.is_empty() {
-                    None
-                } else if str_branching=="oracle" {
-                    Some(Oracle)
-                } else if let Some(oepi_unfiltered) = str_branching.strip_prefix("oneperwinnerindex")
-                    .map(|str_oepi_unfiltered| str_oepi_unfiltered.parse().ok())
-                {
-                    Some(OnePerWinnerIndex(oepi_unfiltered))
-                } else if let Some(n_until_stichseq_len) = str_branching.strip_prefix("equiv")
-                    .and_then(|str_n_until_remaining_cards| str_n_until_remaining_cards.parse().ok())
-                {
-                    Some(Equivalent(n_until_stichseq_len, rules.equivalent_when_on_same_hand()))
-                } else {
-                    let [str_lo, str_hi] = str_branching
-                        .split(',')
-                        .collect_array()
-                        .ok_or_else(|| format_err!("Could not parse branching"))?;
-                    let (n_lo, n_hi) = (str_lo.trim().parse::<usize>()?, str_hi.trim().parse::<usize>()?);
-                    Some(Branching(n_lo, n_hi)) // TODO we should avoid branching in case n_lo is greater than all hand's fixed cards
+                if checkpoint.i_checkpoint+1==checkpoint.n_checkpoints && b_json {
+                    println!("{}", unwrap!(serde_json::to_string(&vecjsoncheckpoint)));
+                    vecjsoncheckpoint.clear();
                 }
-            } else {
-                None
-            };
-            cartesian_match!(
-                forward,
-                match (oebranching) {
-                    None => ((_), SNoFilter::factory()),
-                    Some(Branching(n_lo, n_hi)) => ((_), {
-                        let n_lo = n_lo.max(1);
-                        SBranchingFactor::factory(n_lo, n_hi.max(n_lo+1))
-                    }),
-                    Some(Equivalent(n_until_stichseq_len, cardspartition)) => (
-                        (_),
-                        equivalent_cards_filter(
-                            n_until_stichseq_len,
-                            cardspartition.clone(),
-                        )
-                    ),
-                    Some(Oracle) => ((SFilterByOracle), |stichseq, ahand| {
-                        SFilterByOracle::new(rules, ahand, stichseq)
-                    }),
-                    Some(OnePerWinnerIndex(oepi_unfiltered)) => ((_), |_stichseq, _ahand| {
-                        SFilterOnePerWinnerIndex::new(
-                            oepi_unfiltered,
-                            rules,
-                        )
-                    }),
-                },
-                match (clapmatches.value_of("prune")) {
-                    // Some("hint") => (SPrunerViaHint), // TODO re-enable
-                    _ => (SPrunerNothing),
-                },
-                match ((oesinglestrategy, clapmatches.is_present("abprune"), rules.alpha_beta_pruner_lohi_values())) {
-                    (None, b_abprune, _) => (
-                        STplStrategiesAll,
-                        {
-                            if b_abprune && b_verbose {
-                                println!("Warning: abprune not supported strategy/rules combination. Continuing without.");
-                            }
-                            |_stichseq, _ahand| SAlphaBetaPrunerNone
-                        },
-                    ),
-                    (Some(ESingleStrategy::MaxMin), false, _) => (
-                        STplStrategiesOnlyMaxMin,
-                        |_stichseq, _ahand| SAlphaBetaPrunerNone,
-                    ),
-                    (Some(ESingleStrategy::MaxMin), true, _) => (
-                        STplStrategiesOnlyMaxMin,
-                        (|_stichseq, _ahand| SAlphaBetaPruner::new({
-                            let mut mapepilohi = EPlayerIndex::map_from_fn(|_| ELoHi::Lo);
-                            mapepilohi[epi_position] = ELoHi::Hi;
-                            mapepilohi
-                        })),
-                    ),
-                    (Some(ESingleStrategy::MaxSelfishMin), b_abprune@false, _) | (Some(ESingleStrategy::MaxSelfishMin), b_abprune@true, None) => (
-                        STplStrategiesOnlyMaxSelfishMin,
-                        {
-                            if b_abprune && b_verbose {
-                                println!("Warning: abprune not supported strategy/rules combination. Continuing without.");
-                            }
-                            |_stichseq, _ahand| SAlphaBetaPrunerNone
-                        },
-                    ),
-                    (Some(ESingleStrategy::MaxSelfishMin), true, Some(fn_alpha_beta_pruner_lohi_values)) => (
-                        STplStrategiesOnlyMaxSelfishMin,
-                        (|stichseq, ahand| SAlphaBetaPruner::new({
-                            let mut mapepilohi = fn_alpha_beta_pruner_lohi_values(
-                                &SRuleStateCacheFixed::new(ahand, stichseq),
-                            );
-                            if mapepilohi[epi_position]==ELoHi::Lo {
-                                for lohi in mapepilohi.iter_mut() {
-                                    *lohi = -*lohi;
-                                }
-                            }
-                            assert_eq!(mapepilohi[epi_position], ELoHi::Hi);
-                            mapepilohi
-                        })),
-                    ),
-                },
-                match (clapmatches.is_present("snapshotcache")) { // TODO customizable depth
-                    true => make_snapshot_cache,
-                    false => make_snapshot_cache_none,
-                },
-                match (clapmatches.value_of("visualize")) {
-                    _ => (SNoVisualization::factory()),
-                    // Some(str_path) => { // TODO re-enable
-                    //     visualizer_factory(
-                    //         std::path::Path::new(str_path).to_path_buf(),
-                    //         rules,
-                    //         epi_position,
-                    //     )
-                    // },
-                },
-            );
+            }
             Ok(())
         }
     )
@@ -0,0 +1,40 @@
+use openschafkopf_lib::{
+    game::run::*,
+    player::{*, playercomputer::*, playertext::*},
+    primitives::*,
+};
+use openschafkopf_util::*;
+use plain_enum::PlainEnum;
+
+pub fn subcommand(str_subcommand: &'static str) -> clap::Command<'static> {
+    clap::Command::new(str_subcommand)
+        .about("Play via a line-based text protocol")
+        .long_about("Play via a line-based text protocol on stdin/stdout (similar to UCI in chess), e.g. from scripts, external GUIs or screen readers. The protocol is described in lib/src/player/playertext.rs. Players move on by one seat after each game.")
+        .arg(openschafkopf_shared_args::ruleset_arg())
+        .args(openschafkopf_shared_args::ai_args())
+        .arg(openschafkopf_shared_args::clap_arg("numgames", "4")
+            .help("Number of games to play")
+        )
+}
+
+pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
+    let aifactory = super::SAiFactory::new(clapmatches)?;
+    let mut itai = std::iter::repeat_with(|| aifactory.ai())
+        .take(EPlayerIndex::SIZE)
+        .collect::<Result<Vec<_>,_>>()?
+        .into_iter();
+    run_simple_game_loop(
+        EPlayerIndex::map_from_fn(|epi| -> Box<dyn TPlayer> {
+            let ai = unwrap!(itai.next());
+            if epi==EPlayerIndex::EPI1 {
+                Box::new(SPlayerText::new("You".to_string(), std::io::stdin().lock(), std::io::stdout()))
+            } else {
+                Box::new(SPlayerComputer{ai, str_name: format!("SPlayerComputer {epi}")})
+            }
+        }),
+        unwrap!(clapmatches.value_of("numgames")).parse::<usize>().unwrap_or(4),
+        openschafkopf_shared_args::get_ruleset(clapmatches)?,
+        /*fn_print_account_balance*/|_,_| {/* sent to SPlayerText as VGameEvent::Balance */},
+    )?;
+    Ok(())
+}
@@ -0,0 +1,292 @@
+use openschafkopf_lib::{
+    ai::{gametree::*, neuralnetwork::*, *},
+    game::*,
+    game_analysis::gamerecord::SGameRecord,
+    primitives::*,
+    rules::{SDisplayRules, TRules, ruleset::VStockOrT},
+};
+use openschafkopf_util::*;
+use as_num::AsNum;
+use itertools::Itertools;
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+pub const STR_TRAINING_DATA_FORMAT: &str = "\
+Training data is grouped by deck and rules (without player index): <dir>/<kurz|lang>/<rules>/<train|validation>/. \
+Each group contains NPY files with one row per decision point, i.e. per card played:
+  x.npy (uint8, N x 130): \
+column 0 is the seat of the observing (i.e. playing) player, column 1 the seat of the active player (4 if there is none); \
+seats count from 0 (first card of the game) to 3. \
+Columns 2..34 and 34..66 tell (0/1) whether card i is in the observer's hand and whether it may be played, respectively. \
+Columns 66..98 contain the cards played so far in order (0 for none), columns 98..130 the respective seats plus 1 (0 for none).
+  card.npy (uint8, N): The card actually played.
+  payout.npy (int64, N): The observer's payout at the end of the game.
+  engine.npy (float32, N x 32, only if requested): The payout the engine expects per card if all hands are known; NaN for cards that may not be played and for decision points not analyzed.
+Cards are numbered as follows (columns and values use i-1 and i, respectively): \
+1 EA, 2 GA, 3 HA, 4 SA, 5 EZ, 6 GZ, 7 HZ, 8 SZ, 9 EK, 10 GK, 11 HK, 12 SK, 13 EO, 14 GO, 15 HO, 16 SO, \
+17 EU, 18 GU, 19 HU, 20 SU, 21 E9, 22 G9, 23 H9, 24 S9, 25 E8, 26 G8, 27 H8, 28 S8, 29 E7, 30 G7, 31 H7, 32 S7. \
+A game goes entirely to either train or validation, decided by a hash of its cards, so splits are reproducible.";
+
+#[derive(Default)]
+struct STrainingDataSplit {
+    n_rows: usize,
+    vecu8_x: Vec<u8>,
+    vecu8_card: Vec<u8>,
+    vecu8_payout: Vec<u8>,
+    vecu8_engine: Vec<u8>,
+}
+
+pub struct STrainingData {
+    path_out: PathBuf,
+    n_validation_percent: u64,
+    on_max_remaining_cards_engine: Option<usize>,
+    mappathasplit: HashMap<PathBuf, [STrainingDataSplit; 2]>,
+}
+
+// FNV-1a, as std's hashers do not guarantee stable results across releases.
+fn stable_hash(itu8: impl IntoIterator<Item=u8>) -> u64 {
+    itu8.into_iter().fold(0xcbf29ce484222325, |n_hash, u8| (n_hash ^ u64::from(u8)).wrapping_mul(0x100000001b3))
+}
+
+fn write_npy(path: &Path, str_descr: &str, slcn_shape: &[usize], slcu8_data: &[u8]) -> Result<(), Error> {
+    let mut str_header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': ({}{}), }}",
+        str_descr,
+        slcn_shape.iter().join(", "),
+        if 1==slcn_shape.len() {","} else {""},
+    );
+    // magic string, version and header length take 10 bytes; header is padded to a multiple of 64 and ends with newline
+    str_header.push_str(&" ".repeat((64 - (10 + str_header.len() + 1) % 64) % 64));
+    str_header.push('\n');
+    let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    file.write_all(b"\x93NUMPY\x01\x00")?;
+    file.write_all(&u16::try_from(str_header.len())?.to_le_bytes())?;
+    file.write_all(str_header.as_bytes())?;
+    file.write_all(slcu8_data)?;
+    file.flush()?;
+    Ok(())
+}
+
+impl STrainingData {
+    pub fn new(path_out: PathBuf, n_validation_percent: u64, on_max_remaining_cards_engine: Option<usize>) -> Self {
+        Self {
+            path_out,
+            n_validation_percent,
+            on_max_remaining_cards_engine,
+            mappathasplit: HashMap::new(),
+        }
+    }
+
+    pub fn push_gamerecord(&mut self, gamerecord: &SGameRecord) -> Result<(), Error> {
+        let gameresult = gamerecord.gameresult()?;
+        let VStockOrT::OrT(game_in) = gameresult.stockorgame else {
+            return Ok(()); // no decisions in Stock
+        };
+        let path_rules = PathBuf::from(match game_in.kurzlang() {
+            EKurzLang::Kurz => "kurz",
+            EKurzLang::Lang => "lang",
+        }).join(SDisplayRules::new(&game_in.rules, /*b_include_playerindex*/false).to_string());
+        let b_validation = stable_hash(
+            game_in.stichseq.visible_cards()
+                .map(|(_epi, &card)| card_to_neural_network_input(Some(card)).as_num::<u8>())
+        ) % 100 < self.n_validation_percent;
+        let split = &mut self.mappathasplit.entry(path_rules).or_default()[usize::from(b_validation)];
+        let on_max_remaining_cards_engine = self.on_max_remaining_cards_engine;
+        let card_to_column = |card| card_to_neural_network_input(Some(card)) - 1;
+        SGame::new(
+            game_in.aveccard.clone(),
+            SExpensifiersNoStoss::new_with_doublings(
+                game_in.expensifiers.n_stock,
+                game_in.expensifiers.doublings.clone(),
+            ),
+            game_in.rules.clone(),
+        ).play_cards_and_stoss(
+            &game_in.expensifiers.vecstoss,
+            game_in.stichseq.visible_cards(),
+            /*fn_before_zugeben*/|game, _i_stich, epi, card_played| {
+                split.vecu8_x.extend_from_slice(&neural_network_input(&game.rules, &game.stichseq, &game.ahand[epi], epi));
+                split.vecu8_card.push(card_to_neural_network_input(Some(card_played)).as_num::<u8>());
+                split.vecu8_payout.extend_from_slice(&gameresult.an_payout[epi].as_num::<i64>().to_le_bytes());
+                if let Some(n_max_remaining_cards_engine) = on_max_remaining_cards_engine {
+                    let mut af_engine = [f32::NAN; N_NEURAL_NETWORK_CARDS];
+                    if game.stichseq.remaining_cards_per_hand()[epi] <= n_max_remaining_cards_engine {
+                        let determinebestcardresult = unwrap!(determine_best_card(
+                            &game.stichseq,
+                            Box::new(std::iter::once(game.ahand.clone())) as Box<_>,
+                            equivalent_cards_filter(
+                                /*n_until_stichseq_len, determined heuristically*/7,
+                                game.rules.equivalent_when_on_same_hand(),
+                            ),
+                            &|_stichseq, _ahand| SMinReachablePayout::new(
+                                &game.rules,
+                                epi,
+                                game.expensifiers.clone(),
+                            ),
+                            /*fn_snapshotcache*/SSnapshotCacheNone::factory(),
+                            /*fn_visualizer*/SNoVisualization::factory(),
+                            /*fn_inspect*/&|_inspectionpoint, _i_ahand, _ahand| {},
+                            /*fn_payout*/&|_stichseq, _ahand, n_payout| (n_payout, ()),
+                        ));
+                        for (card, payoutstats) in determinebestcardresult.cards_and_ts() {
+                            af_engine[card_to_column(card)] = payoutstats.omaxselfishmin.as_ref().unwrap_static_some().avg();
+                        }
+                    }
+                    for f_engine in af_engine {
+                        split.vecu8_engine.extend_from_slice(&f_engine.to_le_bytes());
+                    }
+                }
+                split.n_rows += 1;
+            },
+        )?;
+        Ok(())
+    }
+
+    pub fn write(self) -> Result<(), Error> {
+        std::fs::create_dir_all(&self.path_out)?;
+        std::fs::write(self.path_out.join("README.txt"), STR_TRAINING_DATA_FORMAT)?;
+        for (path_rules, asplit) in self.mappathasplit {
+            for (str_split, split) in ["train", "validation"].into_iter().zip_eq(asplit) {
+                let path_split = self.path_out.join(&path_rules).join(str_split);
+                std::fs::create_dir_all(&path_split)?;
+                write_npy(&path_split.join("x.npy"), "|u1", &[split.n_rows, N_NEURAL_NETWORK_INPUT], &split.vecu8_x)?;
+                write_npy(&path_split.join("card.npy"), "|u1", &[split.n_rows], &split.vecu8_card)?;
+                write_npy(&path_split.join("payout.npy"), "<i8", &[split.n_rows], &split.vecu8_payout)?;
+                if self.on_max_remaining_cards_engine.is_some() {
+                    write_npy(&path_split.join("engine.npy"), "<f4", &[split.n_rows, N_NEURAL_NETWORK_CARDS], &split.vecu8_engine)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn read_npy(path: &Path) -> (String, Vec<u8>) {
+    let vecu8 = unwrap!(std::fs::read(path));
+    assert_eq!(&vecu8[0..8], b"\x93NUMPY\x01\x00");
+    let n_header_len = usize::from(u16::from_le_bytes([vecu8[8], vecu8[9]]));
+    assert_eq!((10 + n_header_len) % 64, 0);
+    let str_header = unwrap!(std::str::from_utf8(&vecu8[10..10+n_header_len])).to_string();
+    assert!(str_header.ends_with('\n'));
+    (str_header.trim_end().to_string(), vecu8[10+n_header_len..].to_vec())
+}
+
+#[cfg(test)]
+fn path_test_out(str_test: &str) -> PathBuf {
+    let path_out = std::env::temp_dir().join(format!("openschafkopf_{}_{}", str_test, std::process::id()));
+    if path_out.exists() {
+        unwrap!(std::fs::remove_dir_all(&path_out));
+    }
+    path_out
+}
+
+#[test]
+fn test_write_npy() {
+    let path_out = path_test_out("test_write_npy");
+    unwrap!(std::fs::create_dir_all(&path_out));
+    for (str_descr, slcn_shape, str_shape, vecu8_data) in [
+        ("|u1", &[3] as &[usize], "(3,)", vec![1, 2, 3]),
+        ("|u1", &[2, 3] as &[usize], "(2, 3)", vec![1, 2, 3, 4, 5, 6]),
+        ("<i8", &[2] as &[usize], "(2,)", [-20i64, 40].iter().flat_map(|n| n.to_le_bytes()).collect::<Vec<u8>>()),
+        ("|u1", &[0, 130] as &[usize], "(0, 130)", vec![]),
+    ] {
+        let path_npy = path_out.join("test.npy");
+        unwrap!(write_npy(&path_npy, str_descr, slcn_shape, &vecu8_data));
+        assert_eq!(
+            read_npy(&path_npy),
+            (format!("{{'descr': '{str_descr}', 'fortran_order': False, 'shape': {str_shape}, }}"), vecu8_data),
+        );
+    }
+    unwrap!(std::fs::remove_dir_all(&path_out));
+}
+
+#[test]
+fn test_stable_hash() {
+    // reference values of FNV-1a (64 bit)
+    assert_eq!(stable_hash([]), 0xcbf29ce484222325);
+    assert_eq!(stable_hash(*b"a"), 0xaf63dc4c8601ec8c);
+    assert_eq!(stable_hash(*b"foobar"), 0x85944171f73967e8);
+}
+
+#[test]
+fn test_training_data() {
+    use openschafkopf_lib::{
+        game_analysis::gamerecord::gamerecords_from_plain,
+        rules::parser::parse_rule_description_simple,
+    };
+    let str_game = "Rufspiel Blaue von 3: so h7 go eo ho hz hk eu gu h9 su g8 g9 ga gk e9 ea ek ez e7 g7 ha s7 gz sa s9 h8 sz e8 sk hu s8";
+    let gamerecord = unwrap!(unwrap!(gamerecords_from_plain(str_game).exactly_one().ok()));
+    let gameresult = unwrap!(gamerecord.gameresult());
+    let VStockOrT::OrT(game) = &gameresult.stockorgame else {
+        panic!("Expected game, got Stock.");
+    };
+    let veccard = game.stichseq.visible_cards().map(|(_epi, &card)| card).collect::<Vec<_>>();
+    let path_rules = PathBuf::from("lang").join(SDisplayRules::new(&unwrap!(parse_rule_description_simple("Rufspiel Blaue von 3")), /*b_include_playerindex*/false).to_string());
+    let n_cards = EPlayerIndex::SIZE * EKurzLang::Lang.cards_per_player();
+    // The split only depends on the game's cards, and a game goes to validation iff its hash modulo 100 is below the percentage.
+    let n_hash_percent = stable_hash(veccard.iter().map(|&card| card_to_neural_network_input(Some(card)).as_num::<u8>())) % 100;
+    for (n_validation_percent, b_validation) in [(0, false), (100, true), (n_hash_percent, false), (n_hash_percent+1, true)] {
+        let mut trainingdata = STrainingData::new(PathBuf::new(), n_validation_percent, /*on_max_remaining_cards_engine*/None);
+        unwrap!(trainingdata.push_gamerecord(&gamerecord));
+        let asplit = unwrap!(trainingdata.mappathasplit.get(&path_rules));
+        assert_eq!(asplit[usize::from(b_validation)].n_rows, n_cards);
+        assert_eq!(asplit[usize::from(!b_validation)].n_rows, 0);
+    }
+    let path_out = path_test_out("test_training_data");
+    let mut trainingdata = STrainingData::new(path_out.clone(), /*n_validation_percent*/0, /*on_max_remaining_cards_engine*/Some(2));
+    unwrap!(trainingdata.push_gamerecord(&gamerecord));
+    unwrap!(trainingdata.write());
+    let path_split = path_out.join(&path_rules).join("train");
+    // x: one row per card played, encoding the player's view before playing it
+    let (str_header_x, vecu8_x) = read_npy(&path_split.join("x.npy"));
+    assert!(str_header_x.contains(&format!("'shape': ({n_cards}, {N_NEURAL_NETWORK_INPUT})")));
+    let (_str_header_card, vecu8_card) = read_npy(&path_split.join("card.npy"));
+    assert_eq!(vecu8_card, veccard.iter().map(|&card| card_to_neural_network_input(Some(card)).as_num::<u8>()).collect::<Vec<_>>());
+    let (str_header_payout, vecu8_payout) = read_npy(&path_split.join("payout.npy"));
+    assert!(str_header_payout.contains("'descr': '<i8'"));
+    let (str_header_engine, vecu8_engine) = read_npy(&path_split.join("engine.npy"));
+    assert!(str_header_engine.contains(&format!("'shape': ({n_cards}, {N_NEURAL_NETWORK_CARDS})")));
+    for (i_row, ((slcu8_x, (slcu8_payout, slcu8_engine)), (epi, &card))) in vecu8_x.chunks_exact(N_NEURAL_NETWORK_INPUT)
+        .zip_eq(vecu8_payout.chunks_exact(8).zip_eq(vecu8_engine.chunks_exact(4 * N_NEURAL_NETWORK_CARDS)))
+        .zip_eq(game.stichseq.visible_cards())
+        .enumerate()
+    {
+        let i_column_card = card_to_neural_network_input(Some(card)) - 1;
+        assert_eq!(usize::from(slcu8_x[0]), epi.to_usize());
+        assert_eq!(slcu8_x[1], 3); // active player
+        // hand: the cards not yet played by epi
+        assert_eq!(
+            slcu8_x[2..2+N_NEURAL_NETWORK_CARDS].iter().map(|&u8_card| usize::from(u8_card)).sum::<usize>(),
+            EKurzLang::Lang.cards_per_player() - i_row / EPlayerIndex::SIZE,
+        );
+        assert_eq!(slcu8_x[2 + i_column_card], 1);
+        // allowed cards: subset of hand, containing the card played
+        assert!((0..N_NEURAL_NETWORK_CARDS).all(|i_column| slcu8_x[2 + N_NEURAL_NETWORK_CARDS + i_column] <= slcu8_x[2 + i_column]));
+        assert_eq!(slcu8_x[2 + N_NEURAL_NETWORK_CARDS + i_column_card], 1);
+        // cards played so far, with seats plus 1
+        for (i_card, (epi_played, &card_played)) in game.stichseq.visible_cards().enumerate() {
+            let (u8_card, u8_seat) = (slcu8_x[2 + 2*N_NEURAL_NETWORK_CARDS + i_card], slcu8_x[2 + 3*N_NEURAL_NETWORK_CARDS + i_card]);
+            if i_card < i_row {
+                assert_eq!(usize::from(u8_card), card_to_neural_network_input(Some(card_played)));
+                assert_eq!(usize::from(u8_seat), epi_played.to_usize() + 1);
+            } else {
+                assert_eq!((u8_card, u8_seat), (0, 0));
+            }
+        }
+        assert_eq!(i64::from_le_bytes(unwrap!(slcu8_payout.try_into())), gameresult.an_payout[epi].as_num::<i64>());
+        // engine: analyzed only if at most 2 cards remain per hand, and only for allowed cards
+        let vecf_engine = slcu8_engine.chunks_exact(4)
+            .map(|slcu8_f| f32::from_le_bytes(unwrap!(slcu8_f.try_into())))
+            .collect::<Vec<_>>();
+        let b_analyzed = EKurzLang::Lang.cards_per_player() - i_row / EPlayerIndex::SIZE <= 2;
+        for (i_column, f_engine) in vecf_engine.iter().enumerate() {
+            assert_eq!(!f_engine.is_nan(), b_analyzed && 1==slcu8_x[2 + N_NEURAL_NETWORK_CARDS + i_column]);
+        }
+    }
+    assert!(path_out.join("README.txt").exists());
+    assert!(read_npy(&path_out.join(&path_rules).join("validation").join("x.npy")).1.is_empty());
+    unwrap!(std::fs::remove_dir_all(&path_out));
+}
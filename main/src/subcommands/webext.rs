@@ -4,16 +4,27 @@ use itertools::Itertools;
 //use openschafkopf_logging::*;
 //use openschafkopf_util::*;
 use serde_json::json;
-use std::io::{Read, Write};
-use openschafkopf_lib::{
-    primitives::{
-        cardvector::parse_cards,
-        eplayerindex::EPlayerIndex,
+use std::{
+    io::{Read, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+        Arc,
     },
-    rules::{
-        TRules,
-        parser::parse_rule_description_simple,
+};
+use openschafkopf_lib::{
+    ai::gametree::EMinMaxStrategy,
+    api::{
+        SBudget,
+        SCardEvaluation,
+        SEngine,
+        SPayouts,
+        SPositionBuilder,
+        VApiError,
+        cards_from_str,
+        rules_from_str,
     },
+    primitives::*,
 };
 use openschafkopf_util::*;
 use plain_enum::PlainEnum;
@@ -24,13 +35,50 @@ pub fn subcommand(str_subcommand: &'static str) -> clap::Command<'static> {
         .about("Backend of a web-extension suggesting a card for a given game state")
 }
 
+fn str_strategy(emmstrategy: EMinMaxStrategy) -> &'static str {
+    match emmstrategy {
+        EMinMaxStrategy::MinMin => "minmin",
+        EMinMaxStrategy::MaxMin => "maxmin",
+        EMinMaxStrategy::MaxSelfishMin => "maxselfishmin",
+        EMinMaxStrategy::MaxSelfishMax => "maxselfishmax",
+        EMinMaxStrategy::Max => "max",
+    }
+}
+
+// {avg: {strategy: f_payout_avg}, histograms: {strategy: [[n_payout, n_count]]}}
+pub(super) fn json_payouts(payouts: &SPayouts) -> serde_json::Value {
+    json!({
+        "avg": payouts.strategies()
+            .map(|(emmstrategy, payoutsummary)| (str_strategy(emmstrategy).to_owned(), json!(payoutsummary.f_payout_avg)))
+            .collect::<serde_json::Map<_,_>>(),
+        "histograms": payouts.strategies()
+            .map(|(emmstrategy, payoutsummary)| (str_strategy(emmstrategy).to_owned(), json!(payoutsummary.vectplnn_histogram)))
+            .collect::<serde_json::Map<_,_>>(),
+    })
+}
+
+// [{strCard, avg, histograms}], see json_payouts
+pub(super) fn json_card_evaluations(slccardevaluation: &[SCardEvaluation]) -> serde_json::Value {
+    slccardevaluation.iter()
+        .map(|cardevaluation| {
+            let mut jsonval = json_payouts(&cardevaluation.payouts);
+            jsonval["strCard"] = json!(cardevaluation.card.to_string());
+            jsonval
+        })
+        .collect()
+}
+
 pub fn run(_clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
-    use std::sync::{Arc, Mutex};
-    let ocmd_openschafkopf: Arc<Mutex<Option<std::process::Child>>> = Arc::new(Mutex::new(None));
-    let (sendstr, recvstr) = std::sync::mpsc::channel();
+    let mut ob_cancel_running: Option<Arc<AtomicBool>> = None; // cancels the computation for the previous request
+    let cancel_running = |ob_cancel_running: &mut Option<Arc<AtomicBool>>| {
+        if let Some(b_cancel) = ob_cancel_running.take() {
+            b_cancel.store(true, Ordering::Relaxed);
+        }
+    };
+    let (sendjson, recvjson) = std::sync::mpsc::channel::<serde_json::Value>();
     std::thread::spawn(move || {
-        while let Ok(str_openschafkopf_out) = recvstr.recv() {
-            let str_json_out = json!({ "strOpenschafkopfOut": str_openschafkopf_out }).to_string();
+        while let Ok(json_out) = recvjson.recv() {
+            let str_json_out = json_out.to_string();
             info!("Trying to send \"{str_json_out}\"");
             unwrap!(std::io::stdout().write_all(
                 &via_out_param(|abyte_buffer_msg_len: &mut [u8; 4]| {
@@ -55,11 +103,7 @@ pub fn run(_clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
             match n_bytes_read {
                 0 => {
                     info!("Received 0 bytes. Exiting.");
-                    if let Some(mut cmd_openschafkopf) = unwrap!(ocmd_openschafkopf.lock()).take()
-                        && let Ok(()) = cmd_openschafkopf.kill()
-                    {
-                        info!("Could not kill openschafkopf upon exiting.");
-                    }
+                    cancel_running(&mut ob_cancel_running);
                     return Ok(());
                 }
                 N_BYTES_FOR_MSG_LEN => {
@@ -77,20 +121,19 @@ pub fn run(_clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
                 _ => panic!("Unexpected value for n_bytes_read: {n_bytes_read}"),
             }
         };
-        fn internal_communicate_error(sendstr: &std::sync::mpsc::Sender<String>, str_error_msg: &str, str_json_in: &str) {
+        fn internal_communicate_error(sendjson: &Sender<serde_json::Value>, str_error_msg: &str, str_json_in: &str) {
             warn!("Communicating error: {str_error_msg}");
-            unwrap!(sendstr.send(
+            unwrap!(sendjson.send(
                 json!({
                     "Err": {
                         "strErrorMsg": str_error_msg,
                         "strInput": str_json_in
                     }
                 })
-                .to_string() /*TODO? better to avoid digression via json value?*/
             ));
         }
         let communicate_error = |str_error_msg: &str| {
-            internal_communicate_error(&sendstr, str_error_msg, &str_json_in)
+            internal_communicate_error(&sendjson, str_error_msg, &str_json_in)
         };
         match serde_json::de::from_str::<serde_json::Value>(&str_json_in) {
             Ok(jsonval) => {
@@ -107,13 +150,30 @@ pub fn run(_clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
                         continue;
                     }
                 });
+                // Optional fields: activePosition, tout (bool), doublings (one bool per position),
+                // stoss (objects with position and nCardsPlayed), kurzlang ("kurz" or "lang").
                 let str_cards_as_played = json_get!("strCardsAsPlayed", as_str);
                 let str_hand = json_get!("strHand", as_str);
-                let n_hand_cards = unwrap!(parse_cards::<Vec<_>>(str_hand)).len();
                 let str_selected_game_name = json_get!("selectedGameName", as_str);
                 let jsonarr_announcement = json_get!("announcements", as_array);
                 let n_epi_first = json_get!("firstPosition", as_u64).as_num::<usize>();
-                let n_epi_active = {
+                // Positions in the protocol are absolute, epi are relative to firstPosition.
+                let position_to_epi = |n_position: usize| EPlayerIndex::wrapped_from_usize(
+                    n_position + EPlayerIndex::SIZE - n_epi_first % EPlayerIndex::SIZE
+                );
+                let b_ramsch = "Ramsch"==str_selected_game_name;
+                let oepi_active = if b_ramsch {
+                    None
+                } else if let Some(jsonval_position_active) = jsonval.get("activePosition") {
+                    // needed if several players announced a game
+                    match jsonval_position_active.as_u64() {
+                        Some(n_position_active) => Some(position_to_epi(n_position_active.as_num::<usize>())),
+                        None => {
+                            communicate_error(&format!("activePosition not extractable as_u64: {jsonval_position_active}"));
+                            continue;
+                        },
+                    }
+                } else {
                     match jsonarr_announcement
                         .iter()
                         .cycle()
@@ -123,111 +183,160 @@ pub fn run(_clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
                         .filter(|&(_n_epi, jsonval_announcement)| jsonval_announcement.is_string())
                         .exactly_one()
                     {
-                        Ok((n_epi_active, _str_announcement)) => n_epi_active,
+                        Ok((n_epi_active, _str_announcement)) => Some(EPlayerIndex::wrapped_from_usize(n_epi_active)),
                         Err(e) => {
                             communicate_error(&format!("No single announcement: {e:?}"));
                             continue;
                         }
                     }
                 };
-                let sendstr = sendstr.clone();
-                let str_rules = &format!("{} von {}",
-                    {
-                        macro_rules! extract_farbe(() => {
-                            match json_get!("selectedGameSuit", as_str) {
-                                "E" => "Eichel",
-                                "G" => "Gras",
-                                "H" => "Herz",
-                                "S" => "Schellen",
-                                str_selected_game_suit => {
-                                    communicate_error(&format!("Bad farbe: {}", str_selected_game_suit));
-                                    continue;
-                                }
+                let str_rules = &{
+                    macro_rules! extract_farbe(() => {
+                        match json_get!("selectedGameSuit", as_str) {
+                            "E" => "Eichel",
+                            "G" => "Gras",
+                            "H" => "Herz",
+                            "S" => "Schellen",
+                            str_selected_game_suit => {
+                                communicate_error(&format!("Bad farbe: {}", str_selected_game_suit));
+                                continue;
                             }
-                        });
-                        match str_selected_game_name {
-                            "Sauspiel" => format!("Sauspiel auf die {}", extract_farbe!()),
-                            "Solo"|"Farbwenz" => format!("{}-{}", extract_farbe!(), str_selected_game_name),
-                            "Wenz"|"Geier" => str_selected_game_name.to_owned(),
-                            _ => {
-                                communicate_error(&format!("Unknown game type: {str_selected_game_name}"));
+                        }
+                    });
+                    let b_tout = match jsonval.get("tout") {
+                        None => false,
+                        Some(jsonval_tout) => if let Some(b_tout) = jsonval_tout.as_bool() {
+                            b_tout
+                        } else {
+                            communicate_error(&format!("tout not extractable as_bool: {jsonval_tout}"));
+                            continue;
+                        },
+                    };
+                    let str_tout = if b_tout {" Tout"} else {""};
+                    let str_rules_no_playerindex = match str_selected_game_name {
+                        "Sauspiel" => format!("Sauspiel auf die {}", extract_farbe!()),
+                        "Solo"|"Farbwenz"|"Farbgeier" => format!("{}-{}{}", extract_farbe!(), str_selected_game_name, str_tout),
+                        "Wenz"|"Geier" => format!("{str_selected_game_name}{str_tout}"),
+                        "Sie" => "Sie".to_owned(),
+                        "Bettel" => "Bettel normal".to_owned(),
+                        "Ramsch" => "Ramsch".to_owned(),
+                        _ => {
+                            communicate_error(&format!("Unknown game type: {str_selected_game_name}"));
+                            continue;
+                        },
+                    };
+                    match oepi_active {
+                        None => str_rules_no_playerindex,
+                        Some(epi_active) => format!("{str_rules_no_playerindex} von {epi_active}"),
+                    }
+                };
+                let rules = match rules_from_str(str_rules) {
+                    Ok(rules) => rules,
+                    Err(err) => {
+                        communicate_error(&format!("Could not parse rules {str_rules}: {err}"));
+                        continue;
+                    },
+                };
+                let ab_doubling = match jsonval.get("doublings") {
+                    None => [false; EPlayerIndex::SIZE],
+                    Some(jsonval_doublings) => {
+                        // doublings given per position
+                        match jsonval_doublings.as_array()
+                            .and_then(|jsonarr_doubling| jsonarr_doubling.iter()
+                                .map(serde_json::Value::as_bool)
+                                .collect::<Option<Vec<_>>>()
+                            )
+                            .and_then(|vecb_doubling| vecb_doubling.into_iter().collect_array::<{EPlayerIndex::SIZE}>())
+                        {
+                            Some(ab_doubling) => EPlayerIndex::map_from_fn(|epi|
+                                ab_doubling[(epi.to_usize() + n_epi_first) % EPlayerIndex::SIZE]
+                            ).into_raw(),
+                            None => {
+                                communicate_error(&format!("doublings must be {} bools: {jsonval_doublings}", EPlayerIndex::SIZE));
                                 continue;
                             },
                         }
                     },
-                    n_epi_active,
-                );
-                const N_AHAND_POOL : usize = 1000;
-                let str_simulate_hands = if n_hand_cards<=3 {
-                    "all".to_owned()
-                } else if let Some(f_occurence_probability)=
-                    unwrap!(parse_rule_description_simple(str_rules))
-                        .heuristic_active_occurence_probability()
-                {
-                    assert!(0. <= f_occurence_probability);
-                    assert!(f_occurence_probability <= 1.);
-                    format!("{}/{}",
-                        (N_AHAND_POOL.as_num::<f64>() * f_occurence_probability).ceil(),
-                        N_AHAND_POOL
-                    )
-                } else {
-                    format!("{N_AHAND_POOL}")
                 };
-                let str_branching = if n_hand_cards<=4 {
-                    ""
-                } else {
-                    "1,3"
+                let vecstoss = match jsonval.get("stoss") {
+                    None => Vec::new(),
+                    Some(jsonval_stoss) => {
+                        match jsonval_stoss.as_array()
+                            .and_then(|jsonarr_stoss| jsonarr_stoss.iter()
+                                .map(|jsonval_stoss| Some((
+                                    position_to_epi(jsonval_stoss.get("position")?.as_u64()?.as_num::<usize>()),
+                                    /*n_cards_played*/jsonval_stoss.get("nCardsPlayed")?.as_u64()?.as_num::<usize>(),
+                                )))
+                                .collect::<Option<Vec<_>>>()
+                            )
+                        {
+                            Some(vecstoss) => vecstoss,
+                            None => {
+                                communicate_error(&format!("stoss must be objects with position and nCardsPlayed: {jsonval_stoss}"));
+                                continue;
+                            },
+                        }
+                    },
                 };
-                let str_repeat_hands = if n_hand_cards<=3 {
-                    "1"
-                } else {
-                    "10"
+                let (Ok(veccard_played), Ok(veccard_hand)) = (
+                    cards_from_str(str_cards_as_played),
+                    cards_from_str(str_hand),
+                ) else {
+                    communicate_error(&format!("Could not parse cards: {str_cards_as_played} / {str_hand}"));
+                    continue;
                 };
-                let mut cmd_openschafkopf = debug_verify!(
-                    std::process::Command::new({
-                        let path_self = unwrap!(std::env::current_exe());
-                        assert!(!unwrap!(path_self.symlink_metadata()) // "Queries the metadata about a file without following symlinks" (https://doc.rust-lang.org/std/path/struct.PathBuf.html#method.symlink_metadata)
-                            .file_type()
-                            .is_symlink()
-                        );
-                        path_self
-                    })
-                        .args([
-                            "suggest-card",
-                            "--rules",
-                            str_rules,
-                            "--hand",
-                            str_hand,
-                            "--played-cards",
-                            str_cards_as_played,
-                            "--simulate-hands",
-                            &str_simulate_hands,
-                            "--branching",
-                            str_branching,
-                            "--repeat-hands",
-                            str_repeat_hands,
-                        ])
-                        .stdout(std::process::Stdio::piped())
-                        .spawn()
-                ).expect("Could not spawn process");
-                let stdout = unwrap!(cmd_openschafkopf.stdout.take());
-                let ocmd_openschafkopf = ocmd_openschafkopf.clone();
-                {
-                    let mut ocmd_openschafkopf = unwrap!(ocmd_openschafkopf.lock());
-                    if let Some(mut cmd_openschafkopf) = ocmd_openschafkopf.take()
-                        && let Ok(()) = cmd_openschafkopf.kill()
-                    {
-                        communicate_error("Process did not finish early enough.");
-                    }
-                    *ocmd_openschafkopf = Some(cmd_openschafkopf);
+                let mut positionbuilder = SPositionBuilder::new(rules)
+                    .played_cards(veccard_played)
+                    .hand_current(veccard_hand) // position defaults to the player to move
+                    .doublings(ab_doubling);
+                for (epi, n_cards_played) in vecstoss {
+                    positionbuilder = positionbuilder.stoss(epi, n_cards_played);
+                }
+                match jsonval.get("kurzlang") {
+                    None => {}, // SPositionBuilder infers it from cards
+                    Some(jsonval_kurzlang) => match jsonval_kurzlang.as_str() {
+                        Some("kurz") => positionbuilder = positionbuilder.kurzlang(EKurzLang::Kurz),
+                        Some("lang") => positionbuilder = positionbuilder.kurzlang(EKurzLang::Lang),
+                        _ => {
+                            communicate_error(&format!("kurzlang must be \"kurz\" or \"lang\": {jsonval_kurzlang}"));
+                            continue;
+                        },
+                    },
                 }
+                let gamestate = match positionbuilder.build() {
+                    Ok(gamestate) => gamestate,
+                    Err(err) => {
+                        communicate_error(&format!("Invalid game state: {err}"));
+                        continue;
+                    },
+                };
+                cancel_running(&mut ob_cancel_running);
+                let b_cancel = Arc::new(AtomicBool::new(false));
+                ob_cancel_running = Some(Arc::clone(&b_cancel));
+                let sendjson = sendjson.clone();
+                let str_json_in = str_json_in.clone();
                 std::thread::spawn(move || {
-                    if let Ok(str_openschafkopf_out) = std::io::read_to_string(stdout) {
-                        if str_openschafkopf_out.trim().is_empty() {
-                            internal_communicate_error(&sendstr, "openschafkopf returned empty string", "N/A");
-                        } else {
-                            unwrap!(sendstr.send(str_openschafkopf_out));
-                        }
+                    // Sends interim results while searching; once cancelled, no final result is sent.
+                    match SEngine::new(/*n_rank_rules_samples*/100).suggest_streaming(
+                        &gamestate,
+                        SBudget::new(/*n_samples*/1000, /*n_branches*/2).prefer_likely_hands(),
+                        &b_cancel,
+                        /*fn_progress*/|progress| unwrap!(sendjson.send(json!({
+                            "Interim": {
+                                "nAhand": progress.n_ahand,
+                                "vecCard": json_card_evaluations(&progress.veccardevaluation),
+                            }
+                        }))),
+                    ) {
+                        Ok(suggestion) => unwrap!(sendjson.send(json!({
+                            "Ok": {
+                                "nAhand": suggestion.n_ahand,
+                                "vecCard": json_card_evaluations(&suggestion.veccardevaluation),
+                                "combined": suggestion.opayouts_combined.as_ref().map(json_payouts),
+                            }
+                        }))),
+                        Err(VApiError::Cancelled) => {},
+                        Err(err) => internal_communicate_error(&sendjson, &err.to_string(), &str_json_in),
                     }
                 });
             }
@@ -7,6 +7,7 @@ use openschafkopf_lib::{
     },
     game::{SGame, SExpensifiersNoStoss, TGamePhase},
     primitives::{EKurzLang, EPlayerIndex, EFarbe, ESchlag, ECard, SStichSequence, SHand, SDisplayCardSlice, display_card_slices, SStaticEPI0},
+    game_analysis::parser::N_STOSS_MAX_NETSCHAFKOPF,
     rules::{
         SDoublings,
         SStoss,
@@ -79,7 +80,6 @@ use winapi::{
     },
 };
 use std::{
-    borrow::Cow,
     fs::{
         self,
         File,
@@ -1134,174 +1134,331 @@ make_redirect_function!(
 
 const N_BYTES_PER_NETSCHAFKOPF_CARD: usize = 3;
 
-unsafe fn interpret_as_cards(pbyte: *const u8, n_cards_max: usize) -> Vec<ECard> {
-    let slcbyte = unsafe{std::slice::from_raw_parts(pbyte, n_cards_max * N_BYTES_PER_NETSCHAFKOPF_CARD)};
-    let mut veccard = Vec::new();
-    while veccard.len() < n_cards_max && {
-        let i_byte = veccard.len() * N_BYTES_PER_NETSCHAFKOPF_CARD;
-        bytes_are_card(unwrap!(slcbyte[i_byte..i_byte+N_BYTES_PER_NETSCHAFKOPF_CARD].try_into()))
-            .map(|card| veccard.push(card))
-            .is_some()
-    } {}
-    veccard
+fn interpret_as_cards(slcbyte: &[u8]) -> Vec<ECard> {
+    slcbyte.chunks_exact(N_BYTES_PER_NETSCHAFKOPF_CARD)
+        .map_while(|slcbyte_card| bytes_are_card(unwrap!(slcbyte_card.try_into())))
+        .collect()
+}
+
+// Read access to NetSchafkopf's memory, so that extracting the game can also be done on recorded dumps.
+trait TNetSchafkopfMemory {
+    fn bytes(&self, n_address: usize, n_bytes: usize) -> &[u8];
+
+    fn number(&self, n_address: usize) -> usize {
+        // NetSchafkopf is a 32 bit program
+        u32::from_le_bytes(unwrap!(self.bytes(n_address, 4).try_into())).as_num::<usize>()
+    }
+
+    fn string(&self, n_address: usize, n_bytes_max: usize) -> String {
+        String::from_utf8_lossy(
+            unwrap!(self.bytes(n_address, n_bytes_max).split(|&byte| byte==0).next())
+        ).into_owned()
+    }
+
+    fn cards(&self, n_address: usize, n_cards_max: usize) -> Vec<ECard> {
+        interpret_as_cards(self.bytes(n_address, n_cards_max * N_BYTES_PER_NETSCHAFKOPF_CARD))
+    }
+}
+
+// The memory of the process we are injected into.
+struct SNetSchafkopfProcessMemory;
+
+impl TNetSchafkopfMemory for SNetSchafkopfProcessMemory {
+    fn bytes(&self, n_address: usize, n_bytes: usize) -> &[u8] {
+        unsafe{std::slice::from_raw_parts(as_ptr!(u8, n_address), n_bytes)}
+    }
+}
+
+// Memory regions as (address, bytes), written as one line "<address> <bytes in hex>" per region.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct SNetSchafkopfMemoryDump {
+    vectplnvecbyte_region: Vec<(usize, Vec<u8>)>,
+}
+
+impl SNetSchafkopfMemoryDump {
+    fn add_region(&mut self, n_address: usize, slcbyte: &[u8]) {
+        self.vectplnvecbyte_region.push((n_address, slcbyte.to_vec()));
+    }
+}
+
+impl TNetSchafkopfMemory for SNetSchafkopfMemoryDump {
+    fn bytes(&self, n_address: usize, n_bytes: usize) -> &[u8] {
+        let (n_address_region, vecbyte_region) = self.vectplnvecbyte_region.iter()
+            .rev() // prefer most recently added regions
+            .find(|(n_address_region, vecbyte_region)|
+                *n_address_region<=n_address && n_address+n_bytes<=n_address_region+vecbyte_region.len()
+            )
+            .unwrap_or_else(|| panic!("Memory dump does not contain {n_bytes} bytes at {n_address:#010x}"));
+        &vecbyte_region[n_address-n_address_region..][..n_bytes]
+    }
+}
+
+impl std::fmt::Display for SNetSchafkopfMemoryDump {
+    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (n_address, vecbyte) in self.vectplnvecbyte_region.iter() {
+            writeln!(fmt, "{:#010x} {}", n_address, vecbyte.iter().map(|byte| format!("{byte:02x}")).join(""))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for SNetSchafkopfMemoryDump {
+    type Err = String;
+    fn from_str(str_dump: &str) -> Result<Self, Self::Err> {
+        str_dump.lines()
+            .map(str::trim)
+            .filter(|str_line| !str_line.is_empty())
+            .map(|str_line| {
+                let (str_address, str_bytes) = str_line.split_once(' ')
+                    .ok_or_else(|| format!("Expected address and bytes: {str_line}"))?;
+                let n_address = usize::from_str_radix(str_address.trim_start_matches("0x"), 16)
+                    .map_err(|err| format!("Invalid address {str_address}: {err}"))?;
+                let vecbyte = str_bytes.as_bytes().chunks(2)
+                    .map(|slcch_byte| std::str::from_utf8(slcch_byte).ok()
+                        .filter(|str_byte| str_byte.len()==2)
+                        .and_then(|str_byte| u8::from_str_radix(str_byte, 16).ok())
+                        .ok_or_else(|| format!("Invalid bytes at {str_address}: {str_bytes}"))
+                    )
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok((n_address, vecbyte))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|vectplnvecbyte_region| Self{vectplnvecbyte_region})
+    }
+}
+
+// Wraps another memory and remembers all regions read, so that they can be logged as a dump.
+struct SRecordingMemory<'memory, Memory> {
+    memory: &'memory Memory,
+    memorydump: std::cell::RefCell<SNetSchafkopfMemoryDump>,
+}
+
+impl<'memory, Memory: TNetSchafkopfMemory> SRecordingMemory<'memory, Memory> {
+    fn new(memory: &'memory Memory) -> Self {
+        Self {
+            memory,
+            memorydump: Default::default(),
+        }
+    }
+}
+
+impl<Memory> Drop for SRecordingMemory<'_, Memory> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            error!("Memory dump:\n{}", self.memorydump.borrow());
+        }
+    }
+}
+
+impl<Memory: TNetSchafkopfMemory> TNetSchafkopfMemory for SRecordingMemory<'_, Memory> {
+    fn bytes(&self, n_address: usize, n_bytes: usize) -> &[u8] {
+        let slcbyte = self.memory.bytes(n_address, n_bytes);
+        self.memorydump.borrow_mut().add_region(n_address, slcbyte);
+        slcbyte
+    }
+}
+
+// Prices, maximum number of stoss, Stock, doublings and stoss of the game.
+// TODO extract from NetSchafkopf: We do not yet know where NetSchafkopf keeps these (maybe in OABYTE_REGELDATEN?), so we assume NetSchafkopf's defaults.
+#[derive(Debug, Clone)]
+struct SNetSchafkopfSettings {
+    tpln_tarif: (/*n_tarif_extra*/isize, /*n_tarif_ruf*/isize, /*n_tarif_solo*/isize),
+    n_stoss_max: usize,
+    n_stock: isize,
+    n_doubling: usize,
+    n_stoss: usize,
+}
+
+impl Default for SNetSchafkopfSettings {
+    fn default() -> Self {
+        Self {
+            tpln_tarif: (/*n_tarif_extra*/10, /*n_tarif_ruf*/20, /*n_tarif_solo*/50),
+            n_stoss_max: N_STOSS_MAX_NETSCHAFKOPF,
+            n_stock: 0,
+            n_doubling: 0,
+            n_stoss: 0,
+        }
+    }
 }
 
 static mut B_LOG_GAME : bool = true;
+// Logging the memory read for each game is verbose, so we only do it to record dumps for tests (dumps are always logged upon panic).
+static B_LOG_MEMORY_DUMP: bool = false;
 
 fn log_game() -> Option<(EnumMap<EPlayerIndex, Vec<ECard>>, SGame, EPlayerIndex/*epi_gast*/)> {
     log_in_out_cond("log_game", (), |_| if_then_some!(unsafe{B_LOG_GAME},()), || {
-        let pbyte_card_stack = as_ptr!(u8, 0x004bd500);
-        const N_CARDS_STACK : usize = 33;
-        info!("Card stack including 0: {}",
-            unsafe{interpret_as_cards(
-                pbyte_card_stack,
-                /*n_cards_max*/N_CARDS_STACK
-            ).iter().join(" ")},
-        );
-        info!("Card stack excluding 0: {}",
-            unsafe{interpret_as_cards(
-                pbyte_card_stack.add(N_BYTES_PER_NETSCHAFKOPF_CARD),
-                /*n_cards_max*/N_CARDS_STACK-1
-            ).iter().join(" ")},
-        );
-        let astr_player = ["links", "oben", "rechts", "gast"];
-        let aveccard_hand = as_ptr!(u8, 0x4b5e67, 0x4b5e8b, 0x4b5eaf, 0x4b5ed3).map(|pbyte_hand|
-            unsafe {interpret_as_cards(pbyte_hand, /*n_cards_max*/8)}
+        let memory = SRecordingMemory::new(&SNetSchafkopfProcessMemory);
+        let otplaveccardgameepi = extract_game(&memory, &SNetSchafkopfSettings::default());
+        if B_LOG_MEMORY_DUMP {
+            info!("Memory dump:\n{}", memory.memorydump.borrow());
+        }
+        otplaveccardgameepi
+    })
+}
+
+fn extract_game(
+    memory: &impl TNetSchafkopfMemory,
+    settings: &SNetSchafkopfSettings,
+) -> Option<(EnumMap<EPlayerIndex, Vec<ECard>>, SGame, EPlayerIndex/*epi_gast*/)> {
+    let n_address_card_stack = 0x004bd500;
+    const N_CARDS_STACK : usize = 33;
+    info!("Card stack including 0: {}",
+        memory.cards(n_address_card_stack, /*n_cards_max*/N_CARDS_STACK).iter().join(" "),
+    );
+    info!("Card stack excluding 0: {}",
+        memory.cards(n_address_card_stack + N_BYTES_PER_NETSCHAFKOPF_CARD, /*n_cards_max*/N_CARDS_STACK-1).iter().join(" "),
+    );
+    let astr_player = ["links", "oben", "rechts", "gast"];
+    let aveccard_hand = AN_ADDRESS_HAND.map(|n_address_hand|
+        memory.cards(n_address_hand, /*n_cards_max*/8)
+    );
+    let aveccard_played = AN_ADDRESS_PLAYED.map(|n_address_played|
+        memory.cards(n_address_played, /*n_cards_max*/8)
+    );
+    for (str_player, veccard_hand) in itertools::zip_eq(
+        astr_player.iter(),
+        aveccard_hand.iter(),
+    ) {
+        info!("Hand von {}: {}",
+            str_player,
+            veccard_hand.iter().join(" "),
         );
-        let aveccard_played = as_ptr!(u8, 0x4c60de, 0x4c60f9, 0x4c6114, 0x4c612f).map(|pbyte_played|
-            unsafe {interpret_as_cards(pbyte_played, /*n_cards_max*/8)}
+    }
+    for (str_player, veccard_played) in itertools::zip_eq(
+        astr_player.iter(),
+        aveccard_played.iter(),
+    ) {
+        info!("Gespielte Karten von {}: {}",
+            str_player,
+            veccard_played.iter().join(" "),
         );
-        for (str_player, veccard_hand) in itertools::zip_eq(
-            astr_player.iter(),
-            aveccard_hand.iter(),
-        ) {
-            info!("Hand von {}: {}",
-                str_player,
-                veccard_hand.iter().join(" "),
-            );
+    }
+    let i_netschafkopf_geber = memory.number(N_ADDRESS_GEBER);
+    info!("Geber: {i_netschafkopf_geber}");
+    let n_stichs_completed = memory.number(N_ADDRESS_STICHS_COMPLETED);
+    info!("# komplette Stiche: {n_stichs_completed}");
+    let n_current_stich_size = memory.number(N_ADDRESS_CURRENT_STICH_SIZE);
+    info!("# played cards in current stich: {n_current_stich_size}");
+    info!("g_iEPIPresumablyNextCard: {}",
+        memory.number(0x004b596c)
+    );
+    let str_rules_pri = memory.string(N_ADDRESS_RULES, 260);
+    let str_active_player = memory.string(N_ADDRESS_ACTIVE_PLAYER, 260);
+    let (str_rules_pri, ostr_active_player) = if str_rules_pri=="Jungfrau" || str_rules_pri=="hat Stich" {
+        ("Ramsch".to_string(), None)
+    } else {
+        (str_rules_pri, Some(str_active_player))
+    };
+    let str_rules = format!("{}{}", str_rules_pri, if let Some(str_active_player)=ostr_active_player {
+        format!(" von {str_active_player}")
+    } else {
+        "".to_string()
+    });
+    info!("Rules: {str_rules}");
+    let to_openschafkopf_playerindex = |i_netschafkopf_player: usize| {
+        assert!(1 <= i_netschafkopf_geber);
+        assert!(i_netschafkopf_geber <= 4);
+        assert!(1 <= i_netschafkopf_player);
+        assert!(i_netschafkopf_player <= 4);
+        unwrap!(EPlayerIndex::checked_from_usize(
+            (i_netschafkopf_player + 8 - (i_netschafkopf_geber+1)) % 4
+        ))
+    };
+    let epi_to_netschafkopf_playerindex = |epi: EPlayerIndex| {
+        let mut i_netschafkopf = (i_netschafkopf_geber + 1 + epi.to_usize())%4;
+        if i_netschafkopf==0 {
+            i_netschafkopf = 4;
         }
-        for (str_player, veccard_played) in itertools::zip_eq(
-            astr_player.iter(),
-            aveccard_played.iter(),
-        ) {
-            info!("Gespielte Karten von {}: {}",
-                str_player,
-                veccard_played.iter().join(" "),
+        i_netschafkopf
+    };
+    let n_stichs_remaining = memory.number(0x004963b8);
+    info!("n_stichs_remaining: {n_stichs_remaining}");
+    let n_presumably_total_games = memory.number(N_ADDRESS_TOTAL_GAMES);
+    info!("n_presumably_total_games: {n_presumably_total_games}");
+    info!("{settings:?}");
+    if_then_some!("Normal"!=str_rules_pri, {
+        let rules = unwrap!(parse_rule_description(
+            &str_rules,
+            settings.tpln_tarif,
+            SStossParams::new(settings.n_stoss_max),
+            /*fn_player_to_epi*/|str_player| Ok(to_openschafkopf_playerindex(match str_player {
+                // TODO extract from NetSchafkopf
+                "PcLinks" => 1,
+                "PcOben" => 2,
+                "PcRechts" => 3,
+                "Du selbst" => 4,
+                _ => panic!("Unknown value for str_player: {str_player}"),
+            }))
+        ));
+        info!("{}", SDisplayRules::new(&rules, /*b_include_playerindex*/true));
+        let ekurzlang = /*g_bKurzeKarte*/match memory.bytes(N_ADDRESS_KURZE_KARTE, 1)[0] {
+            0 => EKurzLang::Lang,
+            1 => EKurzLang::Kurz,
+            n_kurze_karte_unsupported => panic!("Unknown value for g_bKurzeKarte: {n_kurze_karte_unsupported}"),
+        };
+        let mut stichseq = SStichSequence::new(ekurzlang);
+        for _i_card in 0..n_stichs_completed*EPlayerIndex::SIZE + n_current_stich_size {
+            stichseq.zugeben(
+                aveccard_played
+                    [epi_to_netschafkopf_playerindex(unwrap!(stichseq.current_stich().current_playerindex()))-1]
+                    [stichseq.completed_stichs().len()],
+                &rules,
             );
         }
-        let i_netschafkopf_geber = unsafe{*as_ptr!(usize, 0x004ca578)};
-        info!("Geber: {i_netschafkopf_geber}");
-        let n_stichs_completed = unsafe{*as_ptr!(usize, 0x004b5988)};
-        info!("# komplette Stiche: {n_stichs_completed}");
-        let n_current_stich_size = unsafe{*as_ptr!(usize, 0x004963e4)};
-        info!("# played cards in current stich: {n_current_stich_size}");
-        info!("g_iEPIPresumablyNextCard: {}",
-            unsafe{*as_ptr!(isize, 0x004b596c)}
-        );
-        let str_rules_pri = String::from_utf8_lossy(
-            unsafe{scan_until_0(as_ptr!(u8, 0x004ad0cc), 260)}
-        );
-        let str_active_player = String::from_utf8_lossy(
-            unsafe{scan_until_0(as_ptr!(u8, 0x004ad1d0), 260)}
-        );
-        let (str_rules_pri, ostr_active_player) = if str_rules_pri=="Jungfrau" || str_rules_pri=="hat Stich" {
-            (Cow::Borrowed("Ramsch"), None)
-        } else {
-            (str_rules_pri, Some(str_active_player))
-        };
-        let str_rules = format!("{}{}", str_rules_pri, if let Some(str_active_player)=ostr_active_player {
-            format!(" von {str_active_player}")
-        } else {
-            "".to_string()
+        info!("{stichseq:?}");
+        let an_cards_hand = stichseq.remaining_cards_per_hand();
+        let aveccard_netschafkopf = EPlayerIndex::map_from_fn(|epi| {
+            aveccard_hand[epi_to_netschafkopf_playerindex(epi)-1][0..an_cards_hand[epi]]
+                .to_vec()
         });
-        info!("Rules: {str_rules}");
-        let to_openschafkopf_playerindex = |i_netschafkopf_player: usize| {
-            assert!(1 <= i_netschafkopf_geber);
-            assert!(i_netschafkopf_geber <= 4);
-            assert!(1 <= i_netschafkopf_player);
-            assert!(i_netschafkopf_player <= 4);
-            unwrap!(EPlayerIndex::checked_from_usize(
-                (i_netschafkopf_player + 8 - (i_netschafkopf_geber+1)) % 4
-            ))
-        };
-        let epi_to_netschafkopf_playerindex = |epi: EPlayerIndex| {
-            let mut i_netschafkopf = (i_netschafkopf_geber + 1 + epi.to_usize())%4;
-            if i_netschafkopf==0 {
-                i_netschafkopf = 4;
-            }
-            i_netschafkopf
-        };
-        let n_stichs_remaining = unsafe{*as_ptr!(usize, 0x004963b8)};
-        info!("n_stichs_remaining: {n_stichs_remaining}");
-        let n_presumably_total_games = unsafe{*PN_TOTAL_GAMES};
-        info!("n_presumably_total_games: {n_presumably_total_games}");
-        if_then_some!("Normal"!=str_rules_pri, {
-            let rules = unwrap!(parse_rule_description(
-                &str_rules,
-                (/*n_tarif_extra*/10, /*n_tarif_ruf*/20, /*n_tarif_solo*/50), // TODO extract from NetSchafkopf
-                SStossParams::new(/*n_stoss_max*/4), // TODO extract from NetSchafkopf
-                /*fn_player_to_epi*/|str_player| Ok(to_openschafkopf_playerindex(match str_player {
-                    // TODO extract from NetSchafkopf
-                    "PcLinks" => 1,
-                    "PcOben" => 2,
-                    "PcRechts" => 3,
-                    "Du selbst" => 4,
-                    _ => panic!("Unknown value for str_player: {str_player}"),
-                }))
-            ));
-            info!("{}", SDisplayRules::new(&rules, /*b_include_playerindex*/true));
-            let ekurzlang = /*g_bKurzeKarte*/match unsafe{*as_ptr!(u8, 0x004ca5c8)} {
-                0 => EKurzLang::Lang,
-                1 => EKurzLang::Kurz,
-                n_kurze_karte_unsupported => panic!("Unknown value for g_bKurzeKarte: {n_kurze_karte_unsupported}"),
-            };
-            let mut stichseq = SStichSequence::new(ekurzlang);
-            for _i_card in 0..n_stichs_completed*EPlayerIndex::SIZE + n_current_stich_size {
-                stichseq.zugeben(
-                    aveccard_played
-                        [epi_to_netschafkopf_playerindex(unwrap!(stichseq.current_stich().current_playerindex()))-1]
-                        [stichseq.completed_stichs().len()],
-                    &rules,
-                );
-            }
-            info!("{stichseq:?}");
-            let an_cards_hand = stichseq.remaining_cards_per_hand();
-            let aveccard_netschafkopf = EPlayerIndex::map_from_fn(|epi| {
-                aveccard_hand[epi_to_netschafkopf_playerindex(epi)-1][0..an_cards_hand[epi]]
-                    .to_vec()
-            });
-            let ahand = aveccard_netschafkopf.map(|veccard| SHand::new_from_iter(veccard));
-            info!("{}", display_card_slices(&ahand, &rules, " | "));
-            // TODO extract stoss from NetSchafkopf
-            (
-                aveccard_netschafkopf,
-                unwrap!(
-                    SGame::new_with(
-                        /*aveccard*/EPlayerIndex::map_from_fn(|epi| // TODO extract from NetSchafkopf - should be the cards in order they were dealt
-                            stichseq.cards_from_player(&ahand[epi], epi).collect()
-                        ),
-                        SExpensifiersNoStoss::new_with_doublings(
-                            /*n_stock*/0, // TODO extract from NetSchafkopf
-                            /*doublings*/SDoublings::new(SStaticEPI0{}), // TODO extract from NetSchafkopf
-                        ),
-                        rules,
-                        /*ruleset*/(), // TODO extract from NetSchafkopf
-                        /*mapepigameannouncement*/EPlayerIndex::map_from_fn(|_epi| ()), // TODO extract from NetSchafkopf
-                        /*determinerules*/(), // TODO extract from NetSchafkopf
-                    )
-                    .play_cards_and_stoss(
-                        /*itstoss*/std::iter::empty::<SStoss>(), // TODO extract from NetSchafkopf
-                        /*ittplepicard*/stichseq.visible_cards(),
-                        /*fn_before_zugeben*/|_,_,_,_|(),
-                    )
+        let ahand = aveccard_netschafkopf.map(|veccard| SHand::new_from_iter(veccard));
+        info!("{}", display_card_slices(&ahand, &rules, " | "));
+        let mut game = SGame::new_with(
+            /*aveccard*/EPlayerIndex::map_from_fn(|epi| // TODO extract from NetSchafkopf - should be the cards in order they were dealt
+                stichseq.cards_from_player(&ahand[epi], epi).collect()
+            ),
+            SExpensifiersNoStoss::new_with_doublings(
+                settings.n_stock,
+                // Who doubled does not influence the payout, only how often it happened.
+                /*doublings*/SDoublings::new_full(
+                    SStaticEPI0{},
+                    EPlayerIndex::map_from_fn(|epi| epi.to_usize() < settings.n_doubling).into_raw(),
                 ),
-                to_openschafkopf_playerindex(N_INDEX_GAST.as_num::<usize>()),
-            )
-        })
+            ),
+            rules,
+            /*ruleset*/(), // TODO extract from NetSchafkopf
+            /*mapepigameannouncement*/EPlayerIndex::map_from_fn(|_epi| ()), // TODO extract from NetSchafkopf
+            /*determinerules*/(), // TODO extract from NetSchafkopf
+        );
+        // Stoss is given before the first card, and parties take turns, so we let the first permitted player give each stoss.
+        for _i_stoss in 0..settings.n_stoss {
+            let epi_stoss = unwrap!(game.which_player_can_do_something()
+                .and_then(|(_epi_card, vecepi_stoss)| vecepi_stoss.first().copied())
+            );
+            unwrap!(game.stoss(epi_stoss));
+        }
+        (
+            aveccard_netschafkopf,
+            unwrap!(game.play_cards_and_stoss(
+                /*itstoss*/std::iter::empty::<SStoss>(),
+                /*ittplepicard*/stichseq.visible_cards(),
+                /*fn_before_zugeben*/|_,_,_,_|(),
+            )),
+            to_openschafkopf_playerindex(N_INDEX_GAST.as_num::<usize>()),
+        )
     })
 }
 
-const PN_TOTAL_GAMES : *mut usize = as_ptr!(mut usize, 0x004c60ac);
+// NetSchafkopf's players in the order links, oben, rechts, gast
+const AN_ADDRESS_HAND : [usize; 4] = [0x4b5e67, 0x4b5e8b, 0x4b5eaf, 0x4b5ed3];
+const AN_ADDRESS_PLAYED : [usize; 4] = [0x4c60de, 0x4c60f9, 0x4c6114, 0x4c612f];
+const N_ADDRESS_GEBER : usize = 0x004ca578;
+const N_ADDRESS_STICHS_COMPLETED : usize = 0x004b5988;
+const N_ADDRESS_CURRENT_STICH_SIZE : usize = 0x004963e4;
+const N_ADDRESS_RULES : usize = 0x004ad0cc;
+const N_ADDRESS_ACTIVE_PLAYER : usize = 0x004ad1d0;
+const N_ADDRESS_KURZE_KARTE : usize = 0x004ca5c8;
+const N_ADDRESS_TOTAL_GAMES : usize = 0x004c60ac;
 
 fn initialize() {
     let path_user_data = unwrap!(dirs::data_dir());
@@ -1354,3 +1511,53 @@ fn initialize() {
 
     info!("initialize ->");
 }
+
+// Constructed by hand in the format the DLL logs (see B_LOG_MEMORY_DUMP), as we have no recording from NetSchafkopf yet.
+#[cfg(test)]
+fn memory_dump_rufspiel() -> SNetSchafkopfMemoryDump {
+    unwrap!(include_str!("memory_dump_rufspiel.txt").parse())
+}
+
+#[test]
+fn test_memory_dump_roundtrip() {
+    let memorydump = memory_dump_rufspiel();
+    assert_eq!(unwrap!(memorydump.to_string().parse::<SNetSchafkopfMemoryDump>()), memorydump);
+    assert_eq!(memorydump.number(N_ADDRESS_GEBER), 4);
+    assert_eq!(memorydump.string(N_ADDRESS_ACTIVE_PLAYER, 260), "PcLinks");
+    assert_eq!(
+        memorydump.cards(AN_ADDRESS_HAND[1], /*n_cards_max*/2),
+        vec![ECard::new(EFarbe::Gras, ESchlag::S7), ECard::new(EFarbe::Gras, ESchlag::S8)],
+    );
+    assert!("0x004ca578 0".parse::<SNetSchafkopfMemoryDump>().is_err());
+}
+
+#[test]
+fn test_extract_game() {
+    // Each player holds one Farbe, given in NetSchafkopf's order links, oben, rechts, gast.
+    let memorydump = memory_dump_rufspiel();
+    let settings = SNetSchafkopfSettings {
+        tpln_tarif: (/*n_tarif_extra*/5, /*n_tarif_ruf*/10, /*n_tarif_solo*/25),
+        n_stoss_max: 2,
+        n_stock: 30,
+        n_doubling: 1,
+        n_stoss: 2,
+    };
+    let (aveccard_netschafkopf, game, epi_gast) = unwrap!(extract_game(&memorydump, &settings));
+    // Geber is NetSchafkopf's player 4 (gast), so links plays first.
+    assert_eq!(epi_gast, EPlayerIndex::EPI3);
+    assert_eq!(
+        aveccard_netschafkopf[EPlayerIndex::EPI0],
+        b"789ZUOKA".iter()
+            .map(|&byte_schlag| ECard::new(EFarbe::Eichel, unwrap!(byte_is_schlag(byte_schlag))))
+            .collect::<Vec<_>>(),
+    );
+    {
+        use openschafkopf_lib::rules::TRulesPlayerIndex;
+        assert_eq!(game.rules.playerindex(), Some(EPlayerIndex::EPI0));
+    }
+    assert_eq!(game.rules.rufsau(), Some(ECard::new(EFarbe::Gras, ESchlag::Ass)));
+    assert_eq!(game.expensifiers.n_stock, 30);
+    assert_eq!(game.expensifiers.doublings.iter().filter(|(_epi, b_doubling)| **b_doubling).count(), 1);
+    assert_eq!(game.expensifiers.vecstoss.len(), 2);
+    assert_eq!(game.stichseq.visible_cards().count(), 0);
+}
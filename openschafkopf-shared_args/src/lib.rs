@@ -7,11 +7,19 @@ pub fn clap_arg(str_long: &'static str, str_default: &'static str) -> clap::Arg<
         .default_value(str_default)
 }
 
-pub fn ai_arg() -> clap::Arg<'static> {
-    clap_arg("ai", "cheating")
-        .help("Describes whether AI has access to all players' cards")
-        .long_help("Describes whether the AI plays fair or has access to all players' cards.")
-        .possible_values(["cheating", "simulating"]) // TODO custom validator?
+// --ai and --neural-network belong together, so they are only registered as a pair
+pub fn ai_args() -> [clap::Arg<'static>; 2] {
+    [
+        clap_arg("ai", "cheating")
+            .help("Describes whether AI has access to all players' cards")
+            .long_help("Describes whether the AI plays fair or has access to all players' cards. neural-network plays fair without searching, using the weights given by --neural-network.")
+            .possible_values(["cheating", "simulating", "neural-network"]), // TODO custom validator?
+        clap::Arg::new("neural-network")
+            .long("neural-network")
+            .takes_value(true)
+            .help("Path to neural network weights")
+            .long_help("Path to neural network weights. Required by --ai neural-network. For cheating and simulating AIs, the network decides which cards are explored when searching.")
+    ]
 }
 
 pub fn ruleset_arg() -> clap::Arg<'static> {
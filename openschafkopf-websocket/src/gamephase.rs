@@ -1,10 +1,12 @@
 use openschafkopf_lib::{
     primitives::*,
     game::*,
+    player::TPlayer,
     rules::*,
     rules::{
+        payoutdecider::ESchneiderSchwarz,
         ruleset::{SRuleSet, SRuleGroup, allowed_rules, VStockOrT},
-        trumpfdecider::STrumpfDecider,
+        trumpfdecider::{STrumpfDecider, SLaufendeCount},
     },
 };
 use openschafkopf_util::*;
@@ -16,6 +18,7 @@ use rand::{
 use itertools::Itertools;
 use derive_new::new;
 use plain_enum::{EnumMap, PlainEnum};
+use std::sync::mpsc;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum VGamePhaseGeneric<DealCards, GamePreparations, DetermineRules, Game> {
@@ -140,6 +143,58 @@ pub struct STimeoutAction {
     pub gamephaseaction_timeout: VGamePhaseAction,
 }
 
+// Everything that is disclosed to the table once a game is finished.
+#[derive(Debug)]
+pub struct SGameReveal {
+    pub mapepiveccard: EnumMap<EPlayerIndex, Vec<ECard>>, // original hands
+    pub mapepipointstichcount: EnumMap<EPlayerIndex, SPointStichCount>,
+    pub omapepib_primary: Option<EnumMap<EPlayerIndex, bool>>, // None if there are no parties (Ramsch)
+    pub oeschneiderschwarz: Option<ESchneiderSchwarz>, // only for games paying Schneider/Schwarz
+    pub olaufendecount: Option<SLaufendeCount>, // None if the rules know no Laufende (Ramsch)
+    pub n_stoss: usize,
+    pub n_doubling: usize,
+    pub n_stoss_doubling_factor: isize,
+    pub an_payout: EnumMap<EPlayerIndex, isize>,
+}
+
+impl SGameReveal {
+    fn new(gameresult: &SGameResult<SRuleSet>) -> Option<Self> {
+        let VStockOrT::OrT(game) = &gameresult.stockorgame else {
+            return None;
+        };
+        let rulestatecache = SRuleStateCache::new_from_gamefinishedstiche(
+            SStichSequenceGameFinished::new(&game.stichseq),
+            dbg_argument!(&game.rules),
+        );
+        let omapepib_primary = game.rules.playerindex().map(|epi_active| {
+            let oepi_coplayer = game.rules.rufsau().map(|card| rulestatecache.fixed.who_has_card(card));
+            EPlayerIndex::map_from_fn(|epi| epi==epi_active || Some(epi)==oepi_coplayer)
+        });
+        let oeschneiderschwarz = game.rules.schneiderschwarz(
+            SStichSequenceGameFinished::new(&game.stichseq),
+            &rulestatecache,
+        );
+        Some(Self {
+            mapepiveccard: EPlayerIndex::map_from_fn(|epi| {
+                let mut veccard = game.stichseq.completed_cards_by(epi).collect::<Vec<_>>();
+                game.rules.sort_cards(&mut veccard);
+                veccard
+            }),
+            olaufendecount: game.rules.count_laufende(
+                game.kurzlang(),
+                |card| rulestatecache.fixed.who_has_card(card),
+            ),
+            mapepipointstichcount: rulestatecache.changing.mapepipointstichcount,
+            omapepib_primary,
+            oeschneiderschwarz,
+            n_stoss: game.expensifiers.vecstoss.len(),
+            n_doubling: game.expensifiers.doublings.iter().filter(|&(_epi, &b_doubling)| b_doubling).count(),
+            n_stoss_doubling_factor: game.expensifiers.stoss_doubling_factor(),
+            an_payout: gameresult.an_payout,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct SSendToPlayers {
     pub vecstich: Vec<SStich>, // TODO? Could borrow?
@@ -148,6 +203,7 @@ pub struct SSendToPlayers {
     pub mapepiomsg_active: EnumMap<EPlayerIndex, Option<VMessage>>,
     pub msg_inactive: VMessage,
     pub otimeoutaction: Option<STimeoutAction>, // TODO can we avoid Option here?
+    pub ogamereveal: Option<SGameReveal>, // only set once the game is finished
 }
 
 impl SSendToPlayers {
@@ -176,6 +232,7 @@ impl SSendToPlayers {
             mapepiomsg_active: EPlayerIndex::map_from_fn(fn_msg_active),
             msg_inactive,
             otimeoutaction: otimeoutaction.into(),
+            ogamereveal: None,
         }
     }
 }
@@ -257,6 +314,72 @@ impl VGamePhase {
         }
     }
 
+    // Asks player what epi should do, if epi can do something at all.
+    pub fn ask_player(&self, epi: EPlayerIndex, player: &impl TPlayer) -> Option<VGamePhaseAction> {
+        fn communicate_via_channel<T>(f: impl FnOnce(mpsc::Sender<T>)) -> T {
+            let (txt, rxt) = mpsc::channel::<T>();
+            f(txt);
+            unwrap!(rxt.recv())
+        }
+        match self {
+            VGamePhaseGeneric::DealCards(dealcards) => if_then_some!(dealcards.which_player_can_do_something()==Some(epi),
+                VGamePhaseAction::DealCards(communicate_via_channel(|txb_doubling| {
+                    player.ask_for_doubling(dealcards.first_hand_for(epi), txb_doubling);
+                }))
+            ),
+            VGamePhaseGeneric::GamePreparations(gamepreparations) => if_then_some!(gamepreparations.which_player_can_do_something()==Some(epi),
+                VGamePhaseAction::GamePreparations(communicate_via_channel(|txorules| {
+                    player.ask_for_game(
+                        epi,
+                        gamepreparations.fullhand(epi),
+                        &gamepreparations.gameannouncements,
+                        &gamepreparations.ruleset.avecrulegroup[epi],
+                        &gamepreparations.expensifiers.clone().into_with_stoss(),
+                        /*otplepiprio*/None,
+                        txorules,
+                    );
+                }).map(SActivelyPlayableRulesIdentifier::new))
+            ),
+            VGamePhaseGeneric::DetermineRules(determinerules) => determinerules.which_player_can_do_something()
+                .filter(|(epi_active, _vecrulegroup)| epi==*epi_active)
+                .map(|(_epi_active, vecrulegroup)| {
+                    VGamePhaseAction::DetermineRules(communicate_via_channel(|txorules| {
+                        player.ask_for_game(
+                            epi,
+                            determinerules.fullhand(epi),
+                            /*gameannouncements*/&SPlayersInRound::new(SStaticEPI0{}),
+                            &vecrulegroup,
+                            &determinerules.expensifiers.clone().into_with_stoss(),
+                            Some(determinerules.currently_offered_prio()),
+                            txorules,
+                        );
+                    }).map(SActivelyPlayableRulesIdentifier::new))
+                }),
+            VGamePhaseGeneric::Game(game) => game.which_player_can_do_something()
+                .and_then(|(epi_card, vecepi_stoss)| {
+                    if vecepi_stoss.contains(&epi) && communicate_via_channel(|txb_stoss| {
+                        player.ask_for_stoss(
+                            epi,
+                            &game.rules,
+                            &game.ahand[epi],
+                            &game.stichseq,
+                            &game.expensifiers,
+                            txb_stoss,
+                        );
+                    }) {
+                        Some(VGameAction::Stoss)
+                    } else {
+                        if_then_some!(epi==epi_card,
+                            VGameAction::Zugeben(communicate_via_channel(|txcard| {
+                                player.ask_for_card(game, txcard);
+                            }))
+                        )
+                    }
+                })
+                .map(VGamePhaseAction::Game),
+        }
+    }
+
     #[allow(clippy::result_large_err)]
     fn forward_to_blocking_gamephase(mut self) -> (VGamePhaseOrResultGeneric<Self, SGameResult<SRuleSet>>, SSendToPlayers) {
         use VGamePhaseGeneric::*;
@@ -392,7 +515,7 @@ impl VGamePhase {
                 },
                 Game(game) => match game.finish() {
                     Ok(gameresult) => {
-                        let sendtoplayers = SSendToPlayers::new(
+                        let mut sendtoplayers = SSendToPlayers::new(
                             /*vecstich*/if let VStockOrT::OrT(ref game) = gameresult.stockorgame {
                                 game.stichseq.completed_stichs().to_vec()
                             } else {
@@ -413,6 +536,7 @@ impl VGamePhase {
                             /*msg_inactive*/VMessage::Info("Game finished".into()),
                             /*otimeoutaction*/None, // Players do not need to actively confirm finished game
                         );
+                        sendtoplayers.ogamereveal = SGameReveal::new(&gameresult);
                         return (GameResult(gameresult), sendtoplayers);
                     },
                     Err((game, (epi_card, vecepi_stoss))) => {
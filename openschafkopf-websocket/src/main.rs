@@ -1,14 +1,48 @@
 mod websocket;
 pub mod gamephase;
+mod persistence;
 
 fn main() -> Result<(), openschafkopf_util::SStringifiedError> {
     websocket::run(
         &clap::Command::new("openschafkopf-websocket")
             .about("Play in the browser")
-            .arg(openschafkopf_shared_args::ruleset_arg())
+            .arg(openschafkopf_shared_args::ruleset_arg()
+                .help("Path to a ruleset TOML file used for the default table.")
+            )
             .arg(clap::Arg::new("with-bots")
                 .long("with-bots")
-                .help("Allow playing against bots")
+                .help("Allow playing against bots on the default table")
+            )
+            .arg(openschafkopf_shared_args::clap_arg("bot-strength", "simulating")
+                .help("How bots on the default table decide")
+                .long_help("Describes how bots on the default table decide: Random bots play whatever is allowed, simulating bots play fair, cheating bots have access to all players' cards.")
+                .possible_values(["random", "simulating", "cheating"])
+            )
+            .arg(openschafkopf_shared_args::clap_arg("bot-timeout", "30000")
+                .help("Milliseconds a bot may think before a default action is taken for it")
+            )
+            .arg(openschafkopf_shared_args::clap_arg("spectator-delay", "10000")
+                .help("Milliseconds by which spectators seeing all hands lag behind")
+            )
+            .arg(clap::Arg::new("game-log")
+                .long("game-log")
+                .takes_value(true)
+                .help("File to which finished games are appended")
+                .long_help("File to which finished games are appended, one game per line (each line consisting of the rules, followed by a colon and the cards in the order they have been played), so that they can be analyzed right away. Players and payouts are noted in comment lines.")
+            )
+            .arg(clap::Arg::new("balances")
+                .long("balances")
+                .takes_value(true)
+                .help("JSON file storing the players' balances across server restarts")
+            )
+            .arg(openschafkopf_shared_args::clap_arg("ruleset-dir", "rulesets")
+                .help("Directory containing the ruleset TOML files available for new tables")
+            )
+            .arg(openschafkopf_shared_args::clap_arg("address", "127.0.0.1")
+                .help("Address to listen on")
+            )
+            .arg(openschafkopf_shared_args::clap_arg("port", "8080")
+                .help("Port to listen on")
             )
             .get_matches()
     )
@@ -0,0 +1,155 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::Mutex,
+};
+use openschafkopf_lib::{
+    game::SGameResult,
+    rules::{parser::rule_description, ruleset::{SRuleSet, VStockOrT}},
+    primitives::*,
+};
+use openschafkopf_util::*;
+use itertools::Itertools;
+use plain_enum::{EnumMap, PlainEnum};
+
+#[derive(Debug)]
+pub struct SPersistence {
+    opath_game_log: Option<PathBuf>,
+    opath_balances: Option<PathBuf>,
+    mapstrn_balance: Mutex<BTreeMap<String, isize>>, // keyed by player id, not by the (changeable, non-unique) name
+    mutex_file_balances: Mutex<()>, // serializes writers so that older snapshots do not overwrite newer ones
+}
+
+impl SPersistence {
+    pub fn new(opath_game_log: Option<PathBuf>, opath_balances: Option<PathBuf>) -> Result<Self, SStringifiedError> {
+        let mapstrn_balance = match &opath_balances {
+            Some(path_balances) if path_balances.exists() => {
+                serde_json::from_reader(File::open(path_balances)?)?
+            },
+            _ => BTreeMap::new(),
+        };
+        Ok(Self {
+            opath_game_log,
+            opath_balances,
+            mapstrn_balance: Mutex::new(mapstrn_balance),
+            mutex_file_balances: Mutex::new(()),
+        })
+    }
+
+    pub fn balance(&self, str_player_id: &str) -> isize {
+        unwrap!(self.mapstrn_balance.lock()).get(str_player_id).copied().unwrap_or(0)
+    }
+
+    // Only updates the balance in memory; call save_balances to write it to disk.
+    pub fn add_to_balance(&self, str_player_id: &str, n_payout: isize) -> isize {
+        let mut mapstrn_balance = unwrap!(self.mapstrn_balance.lock());
+        let n_balance = mapstrn_balance.entry(str_player_id.to_string()).or_insert(0);
+        *n_balance += n_payout;
+        *n_balance
+    }
+
+    // Writes to a temporary file first so that a crash does not leave truncated balances behind.
+    pub fn save_balances(&self) {
+        let Some(path_balances) = &self.opath_balances else {
+            return;
+        };
+        let _lock = unwrap!(self.mutex_file_balances.lock());
+        let mapstrn_balance = unwrap!(self.mapstrn_balance.lock()).clone();
+        let mut osstr_file_name_tmp = path_balances.file_name().unwrap_or_default().to_os_string();
+        osstr_file_name_tmp.push(".tmp");
+        let path_balances_tmp = path_balances.with_file_name(osstr_file_name_tmp);
+        let _ = verify_or_println!(File::create(&path_balances_tmp)
+            .map_err(SStringifiedError::from)
+            .and_then(|file| Ok(serde_json::to_writer_pretty(file, &mapstrn_balance)?))
+            .and_then(|()| Ok(fs::rename(&path_balances_tmp, path_balances)?))
+        );
+    }
+
+    pub fn append_to_game_log(&self, str_log: &str) {
+        let Some(path_game_log) = &self.opath_game_log else {
+            return;
+        };
+        let _ = verify_or_println!(OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path_game_log)
+            .and_then(|mut file| file.write_all(str_log.as_bytes()))
+        );
+    }
+}
+
+// Formats the game in a format understood by analyze_plain, preceded by a comment line naming players and payouts.
+pub fn game_log_entry(gameresult: &SGameResult<SRuleSet>, mapepistr_name: &EnumMap<EPlayerIndex, String>, n_stock: isize) -> String {
+    let mut str_log = format!("# {}, Stock: {}\n",
+        EPlayerIndex::values()
+            .map(|epi| format!("{epi}: {} ({:+})", mapepistr_name[epi], gameresult.an_payout[epi]))
+            .join(", "),
+        n_stock,
+    );
+    match &gameresult.stockorgame {
+        VStockOrT::Stock(_) => str_log += "# Stock\n",
+        VStockOrT::OrT(game) => {
+            str_log += &format!("{}: {}\n",
+                rule_description(&game.rules),
+                game.stichseq.visible_cards().map(|(_epi, card)| card).join(" "),
+            );
+        },
+    }
+    str_log
+}
+
+#[test]
+fn test_game_log_entry_analyze_plain() {
+    use crate::gamephase::{VGamePhase, VGamePhaseOrResultGeneric};
+    use openschafkopf_lib::{
+        game::SGameGeneric,
+        game_analysis::parser::analyze_plain,
+        player::playerrandom::SPlayerRandom,
+    };
+    let ruleset = unwrap!(SRuleSet::from_string("base-price = 10\nsolo-price = 50\nlauf-min = 3\ndeck = \"lang\"\n[rufspiel]\n[solo]\n"));
+    let playerrandom = SPlayerRandom::new(/*fn_check_ask_for_card*/|_game: &SGameGeneric<SRuleSet, (), ()>| {});
+    let mapepistr_name = EPlayerIndex::map_from_fn(|epi| format!("Player {epi}"));
+    let mut n_games = 0;
+    for _i_game in 0..100 {
+        let (mut gamephaseorresult, _sendtoplayers) = VGamePhase::new(ruleset.clone(), /*n_stock*/0);
+        let gameresult = loop {
+            match gamephaseorresult {
+                VGamePhaseOrResultGeneric::GamePhase(gamephase) => {
+                    let (epi, gamephaseaction) = unwrap!(EPlayerIndex::values()
+                        .find_map(|epi| gamephase.ask_player(epi, &playerrandom).map(|gamephaseaction| (epi, gamephaseaction)))
+                    );
+                    (gamephaseorresult, _) = unwrap!(gamephase.action(epi, gamephaseaction));
+                },
+                VGamePhaseOrResultGeneric::GameResult(gameresult) => break gameresult,
+            }
+        };
+        let str_log = game_log_entry(&gameresult, &mapepistr_name, /*n_stock*/0);
+        assert!(str_log.starts_with("# Player 0"));
+        let vecgame = unwrap!(analyze_plain(&str_log).collect::<Result<Vec<_>, _>>());
+        match &gameresult.stockorgame {
+            VStockOrT::Stock(_) => assert!(vecgame.is_empty()),
+            VStockOrT::OrT(game) => {
+                let [game_parsed] = unwrap!(vecgame.try_into().ok());
+                assert_eq!(rule_description(&game_parsed.rules), rule_description(&game.rules));
+                assert!(game_parsed.stichseq.visible_cards().eq(game.stichseq.visible_cards()));
+                n_games += 1;
+            },
+        }
+    }
+    assert!(0 < n_games);
+}
+
+#[test]
+fn test_save_balances() {
+    let path_balances = std::env::temp_dir().join(format!("openschafkopf_test_balances_{}.json", std::process::id()));
+    let persistence = unwrap!(SPersistence::new(/*opath_game_log*/None, Some(path_balances.clone())));
+    assert_eq!(persistence.add_to_balance("id", 30), 30);
+    assert_eq!(persistence.add_to_balance("id", -10), 20);
+    assert!(!path_balances.exists()); // written only upon save_balances
+    persistence.save_balances();
+    assert!(!path_balances.with_file_name(format!("openschafkopf_test_balances_{}.json.tmp", std::process::id())).exists());
+    assert_eq!(unwrap!(SPersistence::new(/*opath_game_log*/None, Some(path_balances.clone()))).balance("id"), 20);
+    unwrap!(fs::remove_file(&path_balances));
+}
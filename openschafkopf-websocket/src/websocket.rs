@@ -1,13 +1,17 @@
 // adapted from https://github.com/sdroege/async-tungstenite/blob/master/examples/server.rs
 
 use std::{
+    collections::{BTreeMap, HashMap},
     net::SocketAddr,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
-    time::Duration,
+    time::{Duration, Instant},
 };
 use openschafkopf_lib::{
-    game::SGameResult,
-    rules::{SDisplayRules, TRulesPlayerIndex, ruleset::{SRuleSet}},
+    ai::SAi,
+    game::{SGameGeneric, SGameResult},
+    player::{playercomputer::SPlayerComputer, playerrandom::SPlayerRandom},
+    rules::{SDisplayRules, TRulesPlayerIndex, payoutdecider::ESchneiderSchwarz, ruleset::{SRuleSet}},
     primitives::*,
 };
 use openschafkopf_util::*;
@@ -27,7 +31,9 @@ use async_tungstenite::{
     tungstenite::protocol::Message,
 };
 use plain_enum::{EnumMap, PlainEnum};
+use rand::{rng, prelude::*};
 
+use crate::persistence::{game_log_entry, SPersistence};
 use crate::gamephase::{
     SSendToPlayers,
     VGamePhase,
@@ -40,7 +46,62 @@ use crate::gamephase::{
 #[derive(Serialize, Deserialize)]
 enum VPlayerCmd {
     GamePhaseAction(VGamePhaseAction),
-    PlayerLogin{str_player_name: String},
+    PlayerLogin{str_player_name: String, ostr_session_token: Option<String>, #[serde(default)] ostr_player_id: Option<String>},
+    ListTables,
+    CreateTable{str_table_name: String, str_ruleset: String, obotstrength: Option<EBotStrength>},
+    JoinTable{str_table_name: String},
+    Spectate{str_table_name: String, spectatorview: VSpectatorView},
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+enum VSpectatorView {
+    Seat(usize), // sees what the player on this seat sees, but cannot act
+    AllHands, // trainer view: sees all hands, but delayed so that it cannot be used to help players
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub enum EBotStrength {
+    Random, // bots choose randomly among the allowed actions
+    Simulating,
+    Cheating,
+}
+
+enum VBot {
+    Random(SPlayerRandom<fn(&SGameGeneric<SRuleSet, (), ()>)>),
+    Computer(SPlayerComputer),
+}
+
+impl EBotStrength {
+    fn bot(self) -> VBot {
+        let playercomputer = |ai| VBot::Computer(SPlayerComputer{ai, str_name: format!("{self:?}")});
+        match self {
+            EBotStrength::Random => VBot::Random(SPlayerRandom::new(/*fn_check_ask_for_card*/|_game| {/* no checks */})),
+            EBotStrength::Simulating => playercomputer(SAi::new_simulating(
+                /*n_rank_rules_samples*/50,
+                /*n_suggest_card_branches*/2,
+                /*n_suggest_card_samples*/10,
+            )),
+            EBotStrength::Cheating => playercomputer(SAi::new_cheating(/*n_rank_rules_samples*/50, /*n_suggest_card_branches*/2)),
+        }
+    }
+}
+
+impl VBot {
+    fn ask(&self, gamephase: &VGamePhase, epi: EPlayerIndex) -> Option<VGamePhaseAction> {
+        match self {
+            VBot::Random(playerrandom) => gamephase.ask_player(epi, playerrandom),
+            VBot::Computer(playercomputer) => gamephase.ask_player(epi, playercomputer),
+        }
+    }
+}
+
+impl std::fmt::Debug for VBot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            VBot::Random(_playerrandom) => write!(f, "Random"),
+            VBot::Computer(playercomputer) => write!(f, "{}", playercomputer.str_name),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -48,20 +109,100 @@ struct STimeoutCmd {
     gamephaseaction: VGamePhaseAction,
     aborthandle: future::AbortHandle,
     epi: EPlayerIndex,
+    instant_timeout: Instant,
 }
 
-#[derive(Debug)]
-struct SPeer {
+#[derive(Debug, Clone)]
+struct SConnection {
     sockaddr: SocketAddr,
     txmsg: UnboundedSender<Message>,
+}
+
+#[derive(Debug)]
+struct SPeer {
+    str_session_token: String,
+    str_player_id: String, // kept by the client across sessions, identifies the balance
+    oconnection: Option<SConnection>, // None while the player is disconnected
     n_money: isize,
     str_name: String,
 }
 
+impl SPeer {
+    fn is_connected_via(&self, sockaddr: &SocketAddr) -> bool {
+        self.oconnection.as_ref().map(|connection| connection.sockaddr)==Some(*sockaddr)
+    }
+}
+
+#[derive(Debug)]
+struct SSpectator {
+    peer: SPeer,
+    perspective: VPerspective,
+    odelayedsender: Option<SDelayedSender>, // only for AllHands, so that they cannot help players in real time
+}
+
+impl SSpectator {
+    fn new(peer: SPeer, perspective: VPerspective, duration_delay_all_hands: Duration) -> Self {
+        let oconnection = peer.oconnection.clone();
+        let mut spectator = Self{peer, perspective, odelayedsender: None};
+        spectator.set_connection(oconnection, duration_delay_all_hands);
+        spectator
+    }
+
+    fn set_connection(&mut self, oconnection: Option<SConnection>, duration_delay_all_hands: Duration) {
+        self.odelayedsender = match (&oconnection, self.perspective) {
+            (Some(connection), VPerspective::AllHands) if !duration_delay_all_hands.is_zero() => {
+                Some(SDelayedSender::new(connection.txmsg.clone(), duration_delay_all_hands))
+            },
+            _ => None,
+        };
+        self.peer.oconnection = oconnection;
+    }
+}
+
+// Forwards messages in order, each one after the given delay, using one task per connection.
+#[derive(Debug)]
+struct SDelayedSender {
+    txinstantstr: UnboundedSender<(Instant, String)>,
+    duration_delay: Duration,
+}
+
+impl SDelayedSender {
+    fn new(txmsg: UnboundedSender<Message>, duration_delay: Duration) -> Self {
+        let (txinstantstr, rxinstantstr) = unbounded::<(Instant, String)>();
+        task::spawn(rxinstantstr.for_each(move |(instant_send, str_msg)| {
+            let txmsg = txmsg.clone();
+            async move {
+                task::sleep(instant_send.saturating_duration_since(Instant::now())).await;
+                send_to_connection(&txmsg, str_msg);
+            }
+        }));
+        Self{txinstantstr, duration_delay}
+    }
+
+    fn send(&self, str_msg: String) {
+        // Only fails if the forwarding task is gone, in which case the connection is gone as well.
+        let _ = self.txinstantstr.unbounded_send((Instant::now() + self.duration_delay, str_msg));
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SDelays {
+    duration_bot_timeout: Duration, // fallback if a bot takes too long
+    duration_spectator_delay: Duration, // by which spectators seeing all hands lag behind
+}
+
 #[derive(Default, Debug)]
 struct SPlayers {
     mapepiopeer_active: EnumMap<EPlayerIndex, Option<SPeer>>,
     vecpeer_inactive: Vec<SPeer>,
+    vecspectator: Vec<SSpectator>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum VPerspective {
+    Seat{epi: EPlayerIndex, b_may_act: bool},
+    Inactive,
+    AllHands,
 }
 #[derive(Debug)]
 struct STable{
@@ -70,30 +211,40 @@ struct STable{
     otimeoutcmd: Option<STimeoutCmd>, // TODO? tie to ogamephaseorresult?
     n_stock: isize, // TODO would that be better within VGamePhase?
     ruleset: SRuleSet,
-    b_with_bots: bool,
+    obotstrength: Option<EBotStrength>, // None: only play if all seats are taken by humans
+    obot: Option<Arc<VBot>>, // built from obotstrength
+    delays: SDelays,
+    n_gamephase_generation: u64, // incremented upon each state change, so that late bot answers can be recognized
+    osendtoplayers: Option<SSendToPlayers>, // most recent state, used to resync reconnecting players
+    persistence: Arc<SPersistence>,
 }
 
 impl STable {
-    fn new(ruleset: SRuleSet, b_with_bots: bool) -> Self {
+    fn new(ruleset: SRuleSet, obotstrength: Option<EBotStrength>, delays: SDelays, persistence: Arc<SPersistence>) -> Self {
         Self {
             players: SPlayers::default(),
             ogamephaseorresult: None,
             otimeoutcmd: None,
+            osendtoplayers: None,
             n_stock: 0,
             ruleset,
-            b_with_bots,
+            obotstrength,
+            obot: obotstrength.map(|botstrength| Arc::new(botstrength.bot())),
+            delays,
+            n_gamephase_generation: 0,
+            persistence,
         }
     }
 
     fn start_new_game(&mut self, self_mutex: Arc<Mutex<Self>>) {
         assert!(self.ogamephaseorresult.is_none()); // Otherwise we'd collide with a running game.
-        let mut itopeer = self.players.mapepiopeer_active.iter();
-        if (self.b_with_bots && itopeer.any(Option::is_some))
-            || itopeer.all(Option::is_some)
+        let n_humans = self.players.mapepiopeer_active.iter().filter(|opeer| opeer.is_some()).count();
+        if (self.obotstrength.is_some() && 0<n_humans)
+            || n_humans==EPlayerIndex::SIZE
         {
             let (gamephaseorresult, sendtoplayers) = VGamePhase::new(self.ruleset.clone(), self.n_stock);
             self.ogamephaseorresult = Some(gamephaseorresult);
-            self.communicate_to_players_and_set_timeoutaction(self_mutex, &sendtoplayers); // Trigger game logic.
+            self.communicate_to_players_and_set_timeoutaction(self_mutex, sendtoplayers); // Trigger game logic.
         }
     }
 
@@ -114,25 +265,326 @@ impl STable {
         }
     }
 
-    fn remove(&mut self, sockaddr: &SocketAddr) {
-        for epi in EPlayerIndex::values() {
-            if self.players.mapepiopeer_active[epi].as_ref().map(|peer| peer.sockaddr)==Some(*sockaddr) {
-                self.players.mapepiopeer_active[epi] = None;
+    fn insert_spectator(&mut self, peer: SPeer, perspective: VPerspective) {
+        let spectator = SSpectator::new(peer, perspective, self.delays.duration_spectator_delay);
+        if let Some(sendtoplayers) = &self.osendtoplayers {
+            self.players.communicate_to_spectator(
+                &spectator,
+                sendtoplayers,
+                self.otimeoutcmd.as_ref().map(STimeoutCmd::duration_remaining),
+            );
+        }
+        self.players.vecspectator.push(spectator);
+    }
+
+    // Returns the removed peer, so that it can take its money to another table.
+    fn remove(&mut self, str_session_token: &str) -> Option<SPeer> {
+        let mut opeer_removed = None;
+        for opeer in self.players.mapepiopeer_active.iter_mut() {
+            if opeer.as_ref().is_some_and(|peer| peer.str_session_token==str_session_token) {
+                opeer_removed = opeer.take();
+            }
+        }
+        if let Some(i_peer) = self.players.vecpeer_inactive.iter().position(|peer| peer.str_session_token==str_session_token) {
+            opeer_removed = Some(self.players.vecpeer_inactive.remove(i_peer));
+        }
+        if let Some(i_spectator) = self.players.vecspectator.iter().position(|spectator| spectator.peer.str_session_token==str_session_token) {
+            opeer_removed = Some(self.players.vecspectator.remove(i_spectator).peer);
+        }
+        opeer_removed
+    }
+
+    fn disconnect(&mut self, sockaddr: &SocketAddr) {
+        if self.ogamephaseorresult.is_some() {
+            // Keep the seat so that the player can reconnect. In the meantime, timeout actions act for them.
+            for peer in self.players.mapepiopeer_active.iter_mut().flatten() {
+                if peer.is_connected_via(sockaddr) {
+                    peer.oconnection = None;
+                }
+            }
+        } else {
+            for opeer in self.players.mapepiopeer_active.iter_mut() {
+                if opeer.as_ref().is_some_and(|peer| peer.is_connected_via(sockaddr)) {
+                    *opeer = None;
+                }
+            }
+        }
+        self.players.vecpeer_inactive.retain(|peer| !peer.is_connected_via(sockaddr));
+        // Keep spectators so that they can reconnect; disconnected ones are dropped when the next game starts.
+        for spectator in self.players.vecspectator.iter_mut() {
+            if spectator.peer.is_connected_via(sockaddr) {
+                spectator.set_connection(None, self.delays.duration_spectator_delay);
+            }
+        }
+    }
+
+    fn reconnect(&mut self, str_session_token: &str, connection: SConnection, str_player_name: String, str_player_id: &str) -> bool {
+        if let Some(i_spectator) = self.players.vecspectator.iter().position(|spectator| spectator.peer.str_session_token==str_session_token) {
+            self.players.vecspectator[i_spectator].set_connection(Some(connection), self.delays.duration_spectator_delay);
+            self.set_player(str_session_token, str_player_name, str_player_id);
+            if let Some(sendtoplayers) = &self.osendtoplayers {
+                self.players.communicate_to_spectator(
+                    &self.players.vecspectator[i_spectator],
+                    sendtoplayers,
+                    self.otimeoutcmd.as_ref().map(STimeoutCmd::duration_remaining),
+                );
             }
+            return true;
+        }
+        let Some((oepi, peer)) = self.players.mapepiopeer_active.iter_mut()
+            .zip(EPlayerIndex::values())
+            .filter_map(|(opeer, epi)| opeer.as_mut().map(|peer| (Some(epi), peer)))
+            .chain(self.players.vecpeer_inactive.iter_mut().map(|peer| (None, peer)))
+            .find(|(_oepi, peer)| peer.str_session_token==str_session_token)
+        else {
+            return false;
+        };
+        peer.oconnection = Some(connection);
+        self.set_player(str_session_token, str_player_name, str_player_id);
+        if let Some(sendtoplayers) = &self.osendtoplayers {
+            self.players.communicate_to_peer(
+                oepi,
+                unwrap!(self.players.peer(str_session_token)),
+                sendtoplayers,
+                self.otimeoutcmd.as_ref().map(STimeoutCmd::duration_remaining),
+            );
+        }
+        true
+    }
+
+    fn set_player(&mut self, str_session_token: &str, str_player_name: String, str_player_id: &str) {
+        if let Some(peer) = self.players.mapepiopeer_active.iter_mut().flatten()
+            .chain(self.players.vecpeer_inactive.iter_mut())
+            .chain(self.players.vecspectator.iter_mut().map(|spectator| &mut spectator.peer))
+            .find(|peer| peer.str_session_token==str_session_token)
+        {
+            peer.str_name = str_player_name;
+            if peer.str_player_id!=str_player_id {
+                peer.str_player_id = str_player_id.to_string();
+                peer.n_money = self.persistence.balance(str_player_id);
+            }
+        }
+    }
+}
+
+impl STimeoutCmd {
+    fn duration_remaining(&self) -> Duration {
+        self.instant_timeout.saturating_duration_since(Instant::now())
+    }
+}
+
+#[derive(Debug)]
+struct SLobbyTable {
+    str_ruleset: String,
+    table: Arc<Mutex<STable>>,
+}
+
+#[derive(Debug)]
+struct SLobby {
+    mapstrruleset: BTreeMap<String, SRuleSet>,
+    mapstrlobbytable: BTreeMap<String, SLobbyTable>,
+    mapstrinstant_session: HashMap<String, Instant>, // time of last activity per session token
+    delays: SDelays,
+    persistence: Arc<SPersistence>,
+}
+
+impl SLobby {
+    fn new(mapstrruleset: BTreeMap<String, SRuleSet>, delays: SDelays, persistence: SPersistence) -> Self {
+        Self {
+            mapstrruleset,
+            mapstrlobbytable: BTreeMap::new(),
+            mapstrinstant_session: HashMap::new(),
+            delays,
+            persistence: Arc::new(persistence),
+        }
+    }
+
+    fn login(&mut self, ostr_session_token: Option<String>, instant_now: Instant) -> String {
+        self.expire_sessions(instant_now);
+        let str_session_token = match ostr_session_token {
+            Some(str_session_token) if self.mapstrinstant_session.contains_key(&str_session_token) => str_session_token,
+            _ => random_token(),
+        };
+        self.touch_session(&str_session_token, instant_now);
+        str_session_token
+    }
+
+    fn touch_session(&mut self, str_session_token: &str, instant_now: Instant) {
+        self.mapstrinstant_session.insert(str_session_token.to_string(), instant_now);
+    }
+
+    // Forgets sessions that have been inactive for too long, unless they are still connected to a table.
+    fn expire_sessions(&mut self, instant_now: Instant) {
+        const DURATION_SESSION_EXPIRY : Duration = Duration::from_secs(24 * 60 * 60);
+        let vecstr_session_token_expired = self.mapstrinstant_session.iter()
+            .filter(|(_str_session_token, instant_active)| DURATION_SESSION_EXPIRY <= instant_now.saturating_duration_since(**instant_active))
+            .map(|(str_session_token, _instant_active)| str_session_token.clone())
+            .filter(|str_session_token| self.mapstrlobbytable.values().all(|lobbytable|
+                unwrap!(lobbytable.table.lock()).players.peer(str_session_token)
+                    .is_none_or(|peer| peer.oconnection.is_none())
+            ))
+            .collect::<Vec<_>>();
+        for str_session_token in vecstr_session_token_expired {
+            verify!(self.mapstrinstant_session.remove(&str_session_token).is_some());
+            for lobbytable in self.mapstrlobbytable.values() {
+                unwrap!(lobbytable.table.lock()).remove(&str_session_token);
+            }
+        }
+    }
+
+    fn table_of_session(&self, str_session_token: &str) -> Option<Arc<Mutex<STable>>> {
+        self.mapstrlobbytable.values()
+            .map(|lobbytable| &lobbytable.table)
+            .find(|table| unwrap!(table.lock()).players.peer(str_session_token).is_some())
+            .cloned()
+    }
+
+    fn create_table(&mut self, str_table_name: String, str_ruleset: String, obotstrength: Option<EBotStrength>) -> Result<Arc<Mutex<STable>>, SStringifiedError> {
+        if str_table_name.trim().is_empty() {
+            return Err(format_err!("Table name must not be empty."));
+        }
+        if self.mapstrlobbytable.contains_key(&str_table_name) {
+            return Err(format_err!("Table {str_table_name} already exists."));
+        }
+        let ruleset = self.mapstrruleset.get(&str_ruleset)
+            .ok_or_else(|| format_err!("Unknown ruleset {str_ruleset}."))?
+            .clone();
+        let table = Arc::new(Mutex::new(STable::new(ruleset, obotstrength, self.delays, self.persistence.clone())));
+        self.mapstrlobbytable.insert(str_table_name, SLobbyTable{
+            str_ruleset,
+            table: table.clone(),
+        });
+        Ok(table)
+    }
+
+    fn table(&self, str_table_name: &str) -> Result<Arc<Mutex<STable>>, SStringifiedError> {
+        self.mapstrlobbytable.get(str_table_name)
+            .map(|lobbytable| lobbytable.table.clone())
+            .ok_or_else(|| format_err!("Unknown table {str_table_name}."))
+    }
+
+    fn send_lobby_state(&self, txmsg: &UnboundedSender<Message>, ostr_error: Option<String>) {
+        #[derive(Serialize)]
+        struct STableInfo {
+            str_table_name: String,
+            str_ruleset: String,
+            obotstrength: Option<EBotStrength>,
+            vecstr_player: Vec<String>,
+            n_players_inactive: usize,
+            n_spectators: usize,
+            n_stock: isize,
+        }
+        #[derive(new, Serialize)]
+        struct SLobbyState {
+            vecstr_ruleset: Vec<String>,
+            vectableinfo: Vec<STableInfo>,
+            ostr_error: Option<String>,
+        }
+        if let Err(err) = txmsg.unbounded_send(
+            unwrap!(serde_json::to_string(&SLobbyState::new(
+                self.mapstrruleset.keys().cloned().collect(),
+                self.mapstrlobbytable.iter()
+                    .map(|(str_table_name, lobbytable)| {
+                        let table = unwrap!(lobbytable.table.lock());
+                        STableInfo {
+                            str_table_name: str_table_name.clone(),
+                            str_ruleset: lobbytable.str_ruleset.clone(),
+                            obotstrength: table.obotstrength,
+                            vecstr_player: table.players.mapepiopeer_active.iter()
+                                .flatten()
+                                .map(|peer| peer.str_name.clone())
+                                .collect(),
+                            n_players_inactive: table.players.vecpeer_inactive.len(),
+                            n_spectators: table.players.vecspectator.len(),
+                            n_stock: table.n_stock,
+                        }
+                    })
+                    .collect(),
+                ostr_error,
+            ))).into()
+        ) {
+            assert!(err.is_disconnected());
         }
-        self.players.vecpeer_inactive.retain(|peer| peer.sockaddr!=*sockaddr);
     }
 }
 
 impl SPlayers {
-    fn communicate_to_players(&self, sendtoplayers: &SSendToPlayers) {
+    fn peer(&self, str_session_token: &str) -> Option<&SPeer> {
+        self.mapepiopeer_active.iter().flatten()
+            .chain(self.vecpeer_inactive.iter())
+            .chain(self.vecspectator.iter().map(|spectator| &spectator.peer))
+            .find(|peer| peer.str_session_token==str_session_token)
+    }
+
+    fn communicate_to_players(&self, sendtoplayers: &SSendToPlayers, oduration_timeout: Option<Duration>) {
+        for epi in EPlayerIndex::values() {
+            if let Some(peer_active) = self.mapepiopeer_active[epi].as_ref() {
+                self.communicate_to_peer(Some(epi), peer_active, sendtoplayers, oduration_timeout);
+            }
+        }
+        for peer in self.vecpeer_inactive.iter() {
+            self.communicate_to_peer(None, peer, sendtoplayers, oduration_timeout);
+        }
+        for spectator in self.vecspectator.iter() {
+            self.communicate_to_spectator(spectator, sendtoplayers, oduration_timeout);
+        }
+    }
+
+    fn communicate_to_peer(&self, oepi: Option<EPlayerIndex>, peer: &SPeer, sendtoplayers: &SSendToPlayers, oduration_timeout: Option<Duration>) {
+        let Some(connection) = &peer.oconnection else {
+            return; // disconnected players are resynced upon reconnect
+        };
+        send_to_connection(
+            &connection.txmsg,
+            self.site_state(
+                match oepi {
+                    Some(epi) => VPerspective::Seat{epi, b_may_act: true},
+                    None => VPerspective::Inactive,
+                },
+                sendtoplayers,
+                oduration_timeout,
+            ),
+        );
+    }
+
+    fn communicate_to_spectator(&self, spectator: &SSpectator, sendtoplayers: &SSendToPlayers, oduration_timeout: Option<Duration>) {
+        let Some(connection) = &spectator.peer.oconnection else {
+            return;
+        };
+        match &spectator.odelayedsender {
+            None => send_to_connection(
+                &connection.txmsg,
+                self.site_state(spectator.perspective, sendtoplayers, oduration_timeout),
+            ),
+            Some(delayedsender) => delayedsender.send(
+                self.site_state(spectator.perspective, sendtoplayers, /*oduration_timeout*/None),
+            ),
+        }
+    }
+
+    fn site_state(&self, perspective: VPerspective, sendtoplayers: &SSendToPlayers, oduration_timeout: Option<Duration>) -> String {
         let mapepistr_name = self.mapepiopeer_active.map(|opeer_active| // TODO can we avoid temporary storage?
             opeer_active
                 .as_ref()
                 .map(|peer| peer.str_name.clone())
                 .unwrap_or_else(||"<BOT>".to_string())
         );
-        let communicate = |oepi: Option<EPlayerIndex>, veccard: Vec<ECard>, msg, peer: &SPeer| {
+        let (oepi, veccard, b_may_act, msg) = match perspective {
+            VPerspective::Seat{epi, b_may_act} => (
+                Some(epi),
+                sendtoplayers.mapepiveccard[epi].clone(), // TODO? avoid clone
+                b_may_act,
+                sendtoplayers.mapepiomsg_active[epi]
+                    .as_ref()
+                    .filter(|_| b_may_act)
+                    .unwrap_or(&sendtoplayers.msg_inactive)
+                    .clone(),
+            ),
+            VPerspective::Inactive | VPerspective::AllHands => {
+                (None, vec![], /*b_may_act*/false, sendtoplayers.msg_inactive.clone(/*TODO? needed?*/))
+            },
+        };
+        {
             let i_epi_relative = oepi.unwrap_or(EPlayerIndex::EPI0).to_usize();
             let playerindex_server_to_client = |epi: EPlayerIndex| {
                 epi.wrapping_add(EPlayerIndex::SIZE - i_epi_relative)
@@ -154,81 +606,116 @@ impl SPlayers {
                 stichcurrent: SDisplayedStichCurrent,
                 ostichprev: Option<SDisplayedStichPrev>,
             }
+            #[derive(Serialize)]
+            struct SDisplayedGameReveal {
+                mapepivecstr_card: [Vec<String>; EPlayerIndex::SIZE],
+                mapepin_point: [isize; EPlayerIndex::SIZE],
+                mapepin_stich: [usize; EPlayerIndex::SIZE],
+                omapepib_primary: Option<[bool; EPlayerIndex::SIZE]>,
+                oeschneiderschwarz: Option<ESchneiderSchwarz>,
+                otplnb_laufende: Option<(usize, /*b_primary_party*/bool)>,
+                n_stoss: usize,
+                n_doubling: usize,
+                n_stoss_doubling_factor: isize,
+                mapepin_payout: [isize; EPlayerIndex::SIZE],
+            }
             #[derive(new, Serialize)]
             struct SSiteState {
-                vectplstrstr_caption_message_zugeben: Vec<(String, VGamePhaseAction)>,
+                vectplstrstr_caption_message_zugeben: Vec<(String, Option<VGamePhaseAction>)>, // None if card must not be clicked
                 msg: VMessage,
                 odisplayedstichs: Option<SDisplayedStichs>,
                 mapepistr: [String; EPlayerIndex::SIZE],
                 otplepistr_rules: Option<(EPlayerIndex, String)>,
                 oepi_timeout: Option<EPlayerIndex>,
+                on_millis_timeout: Option<u128>,
+                omapepivecstr_card: Option<[Vec<String>; EPlayerIndex::SIZE]>, // all hands, only for trainer view
+                ogamereveal: Option<SDisplayedGameReveal>,
             }
             let card_in_stich = |stich: &SStich, epi| {
                 stich.get(playerindex_client_to_server(epi)).map(ECard::to_string)
             };
-            if let Err(err) = peer.txmsg.unbounded_send(
-                unwrap!(serde_json::to_string(&SSiteState::new(
-                    veccard.into_iter()
-                        .map(|card| (card.to_string(), VGamePhaseAction::Game(VGameAction::Zugeben(card))))
-                        .collect::<Vec<_>>(),
-                    msg,
-                    /*odisplayedstichs*/sendtoplayers.vecstich
-                        .split_last()
-                        .map(|(stich_current, slcstich_up_to_last)| {
-                            SDisplayedStichs{
-                                stichcurrent: SDisplayedStichCurrent {
-                                    epi_first: playerindex_server_to_client(stich_current.first_playerindex()),
-                                    vecstr_card: stich_current
-                                        .iter()
-                                        .map(|(_epi, card)| card.to_string())
-                                        .collect(),
-                                },
-                                ostichprev: slcstich_up_to_last
-                                    .last()
-                                    .map(|stich_prev| SDisplayedStichPrev{
-                                        mapepistr_card: EPlayerIndex
-                                            ::map_from_fn(|epi| unwrap!(card_in_stich(stich_prev, epi)))
-                                            .into_raw()
-                                    })
-                            }
-                        }),
-                    EPlayerIndex::map_from_fn(|epi| 
-                        format!("{} ({})",
-                            mapepistr_name[playerindex_client_to_server(epi)],
-                            playerindex_client_to_server(epi).to_usize(),
-                        )
+            unwrap!(serde_json::to_string(&SSiteState::new(
+                veccard.into_iter()
+                    .map(|card| (card.to_string(), if_then_some!(b_may_act, VGamePhaseAction::Game(VGameAction::Zugeben(card)))))
+                    .collect::<Vec<_>>(),
+                msg,
+                /*odisplayedstichs*/sendtoplayers.vecstich
+                    .split_last()
+                    .map(|(stich_current, slcstich_up_to_last)| {
+                        SDisplayedStichs{
+                            stichcurrent: SDisplayedStichCurrent {
+                                epi_first: playerindex_server_to_client(stich_current.first_playerindex()),
+                                vecstr_card: stich_current
+                                    .iter()
+                                    .map(|(_epi, card)| card.to_string())
+                                    .collect(),
+                            },
+                            ostichprev: slcstich_up_to_last
+                                .last()
+                                .map(|stich_prev| SDisplayedStichPrev{
+                                    mapepistr_card: EPlayerIndex
+                                        ::map_from_fn(|epi| unwrap!(card_in_stich(stich_prev, epi)))
+                                        .into_raw()
+                                })
+                        }
+                    }),
+                EPlayerIndex::map_from_fn(|epi| 
+                    format!("{} ({})",
+                        mapepistr_name[playerindex_client_to_server(epi)],
+                        playerindex_client_to_server(epi).to_usize(),
+                    )
+                ).into_raw(),
+                sendtoplayers.orules.as_ref().map(|rules| (
+                    playerindex_server_to_client(rules.playerindex().unwrap_or(EPlayerIndex::EPI3)), // geber designates rules if no active
+                    format!("{}", SDisplayRules::new(rules, /*b_include_playerindex*/true)),
+                )),
+                sendtoplayers.otimeoutaction
+                    .as_ref()
+                    .map(|timeoutaction| playerindex_server_to_client(timeoutaction.epi)),
+                oduration_timeout.map(|duration_timeout| duration_timeout.as_millis()),
+                /*omapepivecstr_card*/if_then_some!(matches!(perspective, VPerspective::AllHands),
+                    EPlayerIndex::map_from_fn(|epi|
+                        sendtoplayers.mapepiveccard[playerindex_client_to_server(epi)].iter()
+                            .map(ECard::to_string)
+                            .collect()
+                    ).into_raw()
+                ),
+                sendtoplayers.ogamereveal.as_ref().map(|gamereveal| SDisplayedGameReveal {
+                    mapepivecstr_card: EPlayerIndex::map_from_fn(|epi|
+                        gamereveal.mapepiveccard[playerindex_client_to_server(epi)].iter()
+                            .map(ECard::to_string)
+                            .collect()
                     ).into_raw(),
-                    sendtoplayers.orules.as_ref().map(|rules| (
-                        playerindex_server_to_client(rules.playerindex().unwrap_or(EPlayerIndex::EPI3)), // geber designates rules if no active
-                        format!("{}", SDisplayRules::new(rules, /*b_include_playerindex*/true)),
-                    )),
-                    sendtoplayers.otimeoutaction
-                        .as_ref()
-                        .map(|timeoutaction| playerindex_server_to_client(timeoutaction.epi)),
-                ))).into()
-            ) {
-                assert!(err.is_disconnected());
-            }
-        };
-        for epi in EPlayerIndex::values() {
-            if let Some(peer_active) = self.mapepiopeer_active[epi].as_ref() {
-                communicate(
-                    Some(epi),
-                    sendtoplayers.mapepiveccard[epi].clone(), // TODO? avoid clone
-                    sendtoplayers.mapepiomsg_active[epi]
-                        .as_ref()
-                        .unwrap_or(&sendtoplayers.msg_inactive)
-                        .clone(),
-                    peer_active
-                );
-            }
-        }
-        for peer in self.vecpeer_inactive.iter() {
-            communicate(None, vec![], /*msg*/sendtoplayers.msg_inactive.clone(/*TODO? needed?*/), peer);
+                    mapepin_point: EPlayerIndex::map_from_fn(|epi|
+                        gamereveal.mapepipointstichcount[playerindex_client_to_server(epi)].point_count()
+                    ).into_raw(),
+                    mapepin_stich: EPlayerIndex::map_from_fn(|epi|
+                        gamereveal.mapepipointstichcount[playerindex_client_to_server(epi)].stich_count()
+                    ).into_raw(),
+                    omapepib_primary: gamereveal.omapepib_primary.as_ref().map(|mapepib_primary|
+                        EPlayerIndex::map_from_fn(|epi| mapepib_primary[playerindex_client_to_server(epi)]).into_raw()
+                    ),
+                    oeschneiderschwarz: gamereveal.oeschneiderschwarz,
+                    otplnb_laufende: gamereveal.olaufendecount.as_ref()
+                        .map(|laufendecount| (laufendecount.n_laufende, laufendecount.b_primary_party)),
+                    n_stoss: gamereveal.n_stoss,
+                    n_doubling: gamereveal.n_doubling,
+                    n_stoss_doubling_factor: gamereveal.n_stoss_doubling_factor,
+                    mapepin_payout: EPlayerIndex::map_from_fn(|epi|
+                        gamereveal.an_payout[playerindex_client_to_server(epi)]
+                    ).into_raw(),
+                }),
+            )))
         }
     }
 }
 
+fn send_to_connection(txmsg: &UnboundedSender<Message>, str_msg: String) {
+    if let Err(err) = txmsg.unbounded_send(str_msg.into()) {
+        assert!(err.is_disconnected());
+    }
+}
+
 impl STable {
     fn on_incoming_gamephaseaction(&mut self, /*TODO avoid this parameter*/self_mutex: Arc<Mutex<Self>>, epi: EPlayerIndex, gamephaseaction: VGamePhaseAction) {
         println!("on_incoming_gamephaseaction({epi:?}, {gamephaseaction:?})");
@@ -251,15 +738,25 @@ impl STable {
                                     let table_mutex = self_mutex.clone();
                                     task::spawn(async move {
                                         task::sleep(Duration::from_millis(5000)).await;
-                                        let mut table = unwrap!(table_mutex.lock());
-                                        let table : &mut STable = &mut table;
+                                        let mut guard_table = unwrap!(table_mutex.lock());
+                                        let table : &mut STable = &mut guard_table;
                                         let Some(VGamePhaseOrResultGeneric::GameResult(gameresult)) = table.ogamephaseorresult.take() else {
                                             panic!("GameResult unexpectedly taken out.");
                                         };
+                                        let str_game_log = game_log_entry(
+                                            &gameresult,
+                                            &table.players.mapepiopeer_active.map(|opeer_active|
+                                                opeer_active
+                                                    .as_ref()
+                                                    .map(|peer| peer.str_name.clone())
+                                                    .unwrap_or_else(||"<BOT>".to_string())
+                                            ),
+                                            /*n_stock*/table.n_stock - gameresult.an_payout.iter().sum::<isize>(),
+                                        );
                                         // TODO it seems fishy that apply_payout runs *after* the task slept.
                                         gameresult.apply_payout(&mut table.n_stock, |epi, n_payout| {
                                             if let Some(peer) = &mut table.players.mapepiopeer_active[epi] {
-                                                peer.n_money += n_payout;
+                                                peer.n_money = table.persistence.add_to_balance(&peer.str_player_id, n_payout);
                                             }
                                         });
                                         { // advance to next game
@@ -279,6 +776,14 @@ impl STable {
                                                 table.players.vecpeer_inactive.push(peer_epi3);
                                             }
                                             // Players: E1 E2 E3 -- [S0 S1 S2 ... SN E0] (E1, E2, E3 may be None)
+                                            // Players that did not come back until now give up their seats.
+                                            for opeer in mapepiopeer_active.iter_mut() {
+                                                if opeer.as_ref().is_some_and(|peer| peer.oconnection.is_none()) {
+                                                    *opeer = None;
+                                                }
+                                            }
+                                            table.players.vecpeer_inactive.retain(|peer| peer.oconnection.is_some());
+                                            table.players.vecspectator.retain(|spectator| spectator.peer.oconnection.is_some());
                                             // Fill up players one after another
                                             assert!(mapepiopeer_active[EPlayerIndex::EPI3].is_none());
                                             for epi in EPlayerIndex::values() {
@@ -291,6 +796,11 @@ impl STable {
                                             assert!(table.ogamephaseorresult.is_none());
                                             table.start_new_game(table_mutex.clone());
                                         }
+                                        // File I/O must not block the table.
+                                        let persistence = table.persistence.clone();
+                                        drop(guard_table);
+                                        persistence.append_to_game_log(&str_game_log);
+                                        persistence.save_balances();
                                     });
                                 },
                             }
@@ -306,11 +816,11 @@ impl STable {
             }
         );
         if let Some(sendtoplayers) = osendtoplayers {
-            self.communicate_to_players_and_set_timeoutaction(self_mutex, &sendtoplayers);
-        }
-        if self.ogamephaseorresult.is_none() {
-            self.players.communicate_to_players(
-                &SSendToPlayers::new(
+            self.communicate_to_players_and_set_timeoutaction(self_mutex, sendtoplayers);
+        } else if self.ogamephaseorresult.is_none() {
+            self.communicate_to_players_and_set_timeoutaction(
+                self_mutex,
+                SSendToPlayers::new(
                     /*vecstich*/Vec::new(),
                     /*orules*/None,
                     /*fn_cards*/|_epi| std::iter::empty::<ECard>(),
@@ -322,24 +832,24 @@ impl STable {
         }
     }
 
-    fn communicate_to_players_and_set_timeoutaction(&mut self, self_mutex: Arc<Mutex<Self>>, sendtoplayers: &SSendToPlayers) {
-        self.players.communicate_to_players(sendtoplayers);
+    fn communicate_to_players_and_set_timeoutaction(&mut self, self_mutex: Arc<Mutex<Self>>, sendtoplayers: SSendToPlayers) {
+        self.n_gamephase_generation += 1;
         if let Some(timeoutaction) = &sendtoplayers.otimeoutaction {
             let epi_timeoutaction = timeoutaction.epi;
             let gamephaseaction_timeout = timeoutaction.gamephaseaction_timeout.clone(); // TODO clone needed?
-            let b_timeout_player_is_connected = self.players.mapepiopeer_active[epi_timeoutaction].is_some();
+            let duration_timeout = match &self.players.mapepiopeer_active[epi_timeoutaction] {
+                Some(peer) if peer.oconnection.is_some() => {
+                    // TODO Improve timeout duration:
+                    // * choice of active rules disappears too early
+                    // * earlier cards should allow more time than later cards
+                    Duration::from_millis(2000)
+                },
+                None if self.obot.is_some() => self.delays.duration_bot_timeout,
+                _ => Duration::from_millis(750), // TODO? Improve (randomize?) timeout duration?
+            };
+            let table_mutex = self_mutex.clone();
             let (timerfuture, aborthandle) = future::abortable(async move {
-                task::sleep(Duration::from_millis(
-                    if b_timeout_player_is_connected {
-                        // TODO Improve timeout duration:
-                        // * choice of active rules disappears too early
-                        // * earlier cards should allow more time than later cards
-                        2000
-                    } else {
-                        750 // TODO? Improve (randomize?) timeout duration?
-                    }
-                )).await;
-                let table_mutex = self_mutex.clone();
+                task::sleep(duration_timeout).await;
                 let mut table = unwrap!(table_mutex.lock());
                 if let Some(timeoutcmd) = table.otimeoutcmd.take_if(|timeoutcmd| timeoutcmd.epi==epi_timeoutaction) {
                     table.on_incoming_gamephaseaction(table_mutex.clone(), verify_eq!(timeoutcmd.epi, epi_timeoutaction), timeoutcmd.gamephaseaction);
@@ -352,27 +862,70 @@ impl STable {
                 gamephaseaction: timeoutaction.gamephaseaction_timeout.clone(/*TODO needed?*/),
                 aborthandle,
                 epi: epi_timeoutaction,
+                instant_timeout: Instant::now() + duration_timeout,
             });
             task::spawn(timerfuture);
         }
+        self.players.communicate_to_players(
+            &sendtoplayers,
+            sendtoplayers.otimeoutaction.as_ref()
+                .and(self.otimeoutcmd.as_ref())
+                .map(STimeoutCmd::duration_remaining),
+        );
+        self.ask_bots(self_mutex, &sendtoplayers);
+        self.osendtoplayers = Some(sendtoplayers);
     }
+
+    fn ask_bots(&self, self_mutex: Arc<Mutex<Self>>, sendtoplayers: &SSendToPlayers) {
+        let (Some(VGamePhaseOrResultGeneric::GamePhase(gamephase)), Some(bot)) = (&self.ogamephaseorresult, &self.obot) else {
+            return;
+        };
+        for epi in EPlayerIndex::values() {
+            if self.players.mapepiopeer_active[epi].is_none()
+                && (sendtoplayers.mapepiomsg_active[epi].is_some()
+                    || sendtoplayers.otimeoutaction.as_ref().is_some_and(|timeoutaction| timeoutaction.epi==epi)
+                )
+            {
+                let gamephase = gamephase.clone();
+                let bot = bot.clone();
+                let n_gamephase_generation = self.n_gamephase_generation;
+                let table_mutex = self_mutex.clone();
+                task::spawn(async move {
+                    // AI computations are expensive, so keep them away from the executor.
+                    if let Some(gamephaseaction) = task::spawn_blocking(move || bot.ask(&gamephase, epi)).await {
+                        let mut table = unwrap!(table_mutex.lock());
+                        table.on_bot_gamephaseaction(table_mutex.clone(), n_gamephase_generation, epi, gamephaseaction);
+                    }
+                });
+            }
+        }
+    }
+
+    // Bots answer without holding the lock, so timeouts or other players may have changed the game in the meantime.
+    fn on_bot_gamephaseaction(&mut self, self_mutex: Arc<Mutex<Self>>, n_gamephase_generation: u64, epi: EPlayerIndex, gamephaseaction: VGamePhaseAction) {
+        if n_gamephase_generation==self.n_gamephase_generation {
+            self.on_incoming_gamephaseaction(self_mutex, epi, gamephaseaction);
+        } else {
+            println!("Dropping stale bot action ({epi:?}, {gamephaseaction:?})");
+        }
+    }
+}
+
+fn random_token() -> String {
+    format!("{:032x}", rng().random::<u128>())
 }
 
-async fn handle_connection(table: Arc<Mutex<STable>>, tcpstream: TcpStream, sockaddr: SocketAddr) {
+async fn handle_connection(lobby: Arc<Mutex<SLobby>>, tcpstream: TcpStream, sockaddr: SocketAddr) {
     println!("Incoming TCP connection from: {sockaddr}");
     let Ok(wsstream) = verify_or_println!(accept_async(tcpstream).await) else {
         return;
     };
     println!("WebSocket connection established: {sockaddr}");
-    // Insert the write part of this peer to the peer map.
     let (txmsg, rxmsg) = unbounded();
-    let table_mutex = table.clone();
-    unwrap!(table.lock()).insert(table_mutex.clone(), SPeer{
-        sockaddr,
-        txmsg,
-        n_money: 0,
-        str_name: "<Name>".into(), // TODO can we initialize it right away?
-    });
+    let connection = SConnection{sockaddr, txmsg};
+    let mut str_player_name = "<Name>".to_string(); // TODO can we initialize it right away?
+    let mut ostr_session : Option<(/*str_session_token*/String, /*str_player_id*/String)> = None;
+    let mut otable : Option<Arc<Mutex<STable>>> = None;
     let (sink_ws_out, stream_ws_in) = wsstream.split();
     let broadcast_incoming = stream_ws_in
         .try_filter(|msg| {
@@ -382,24 +935,127 @@ async fn handle_connection(table: Arc<Mutex<STable>>, tcpstream: TcpStream, sock
         })
         .try_for_each(|msg| {
             let str_msg = unwrap!(msg.to_text());
-            let mut table = unwrap!(table.lock());
-            let oepi = EPlayerIndex::values()
-                .find(|epi| table.players.mapepiopeer_active[*epi].as_ref().map(|peer| peer.sockaddr)==Some(sockaddr));
-            println!("Received a message from {sockaddr} ({oepi:?}): {str_msg}");
+            println!("Received a message from {sockaddr}: {str_msg}");
             if let Ok(playercmd) = verify_or_println!(serde_json::from_str(str_msg)) {
+                let join_table = |otable: &mut Option<Arc<Mutex<STable>>>, table_new: Arc<Mutex<STable>>, (str_session_token, str_player_id): (&str, &str), str_player_name: &str, operspective_spectator: Option<VPerspective>| {
+                    let opeer_old = otable.take()
+                        .and_then(|table_old| unwrap!(table_old.lock()).remove(str_session_token));
+                    let mut table = unwrap!(table_new.lock());
+                    let n_money = match opeer_old {
+                        Some(peer_old) => peer_old.n_money, // money stays with the player when switching tables
+                        None => table.persistence.balance(str_player_id),
+                    };
+                    let peer = SPeer{
+                        str_session_token: str_session_token.to_string(),
+                        str_player_id: str_player_id.to_string(),
+                        oconnection: Some(connection.clone()),
+                        n_money,
+                        str_name: str_player_name.to_string(),
+                    };
+                    if let Some(perspective) = operspective_spectator {
+                        table.insert_spectator(peer, perspective);
+                    } else {
+                        table.insert(table_new.clone(), peer);
+                    }
+                    drop(table);
+                    *otable = Some(table_new);
+                };
                 match playercmd {
                     VPlayerCmd::GamePhaseAction(gamephaseaction) => {
-                        if let Some(epi) = oepi {
-                            table.on_incoming_gamephaseaction(table_mutex.clone(), epi, gamephaseaction);
+                        if let Some(table_mutex) = &otable {
+                            let mut table = unwrap!(table_mutex.lock());
+                            if let Some(epi) = EPlayerIndex::values()
+                                .find(|epi| table.players.mapepiopeer_active[*epi].as_ref().is_some_and(|peer| peer.is_connected_via(&sockaddr)))
+                            {
+                                table.on_incoming_gamephaseaction(table_mutex.clone(), epi, gamephaseaction);
+                            }
                         }
                     },
-                    VPlayerCmd::PlayerLogin{str_player_name} => {
-                        if let Some(ref epi)=oepi {
-                            if let Some(ref mut peer) = table.players.mapepiopeer_active[*epi] {
-                                peer.str_name = str_player_name;
+                    VPlayerCmd::PlayerLogin{str_player_name: str_player_name_new, ostr_session_token: ostr_session_token_client, ostr_player_id: ostr_player_id_client} => {
+                        str_player_name = str_player_name_new;
+                        let str_player_id = ostr_player_id_client
+                            .filter(|str_player_id| !str_player_id.is_empty())
+                            .or_else(|| ostr_session.as_ref().map(|(_str_session_token, str_player_id)| str_player_id.clone()))
+                            .unwrap_or_else(random_token);
+                        if let Some((str_session_token, _str_player_id)) = &ostr_session {
+                            // Already logged in via this connection: only name and player id may change.
+                            if let Some(table) = &otable {
+                                unwrap!(table.lock()).set_player(str_session_token, str_player_name.clone(), &str_player_id);
+                            }
+                            ostr_session = Some((str_session_token.clone(), str_player_id));
+                        } else {
+                            let (str_session_token, otable_session) = {
+                                let mut lobby = unwrap!(lobby.lock());
+                                let str_session_token = lobby.login(ostr_session_token_client, Instant::now());
+                                let otable_session = lobby.table_of_session(&str_session_token);
+                                (str_session_token, otable_session)
+                            };
+                            #[derive(Serialize)]
+                            struct SSessionInfo<'str> {
+                                str_session_token: &'str str,
+                                str_player_id: &'str str,
+                            }
+                            if let Err(err) = connection.txmsg.unbounded_send(
+                                unwrap!(serde_json::to_string(&SSessionInfo{str_session_token: &str_session_token, str_player_id: &str_player_id})).into()
+                            ) {
+                                assert!(err.is_disconnected());
                             }
-                        } else if let Some(ref mut peer) = table.players.vecpeer_inactive.iter_mut().find(|peer| peer.sockaddr==sockaddr) {
-                            peer.str_name = str_player_name;
+                            if let Some(table) = otable_session.filter(|table|
+                                unwrap!(table.lock()).reconnect(&str_session_token, connection.clone(), str_player_name.clone(), &str_player_id)
+                            ) {
+                                otable = Some(table);
+                            } else {
+                                unwrap!(lobby.lock()).send_lobby_state(&connection.txmsg, /*ostr_error*/None);
+                            }
+                            ostr_session = Some((str_session_token, str_player_id));
+                        }
+                    },
+                    VPlayerCmd::ListTables => {
+                        unwrap!(lobby.lock()).send_lobby_state(&connection.txmsg, /*ostr_error*/None);
+                    },
+                    VPlayerCmd::CreateTable{str_table_name, str_ruleset, obotstrength} => {
+                        let mut lobby = unwrap!(lobby.lock());
+                        match ostr_session.as_ref()
+                            .ok_or_else(|| format_err!("Not logged in."))
+                            .and_then(|(str_session_token, str_player_id)| Ok((
+                                (str_session_token.as_str(), str_player_id.as_str()),
+                                lobby.create_table(str_table_name, str_ruleset, obotstrength)?,
+                            )))
+                        {
+                            Ok((tplstr_session, table_new)) => {
+                                drop(lobby);
+                                join_table(&mut otable, table_new, tplstr_session, &str_player_name, /*operspective_spectator*/None);
+                            },
+                            Err(err) => {
+                                lobby.send_lobby_state(&connection.txmsg, Some(err.0));
+                            },
+                        }
+                    },
+                    VPlayerCmd::JoinTable{ref str_table_name} | VPlayerCmd::Spectate{ref str_table_name, ..} => {
+                        let lobby = unwrap!(lobby.lock());
+                        match ostr_session.as_ref()
+                            .ok_or_else(|| format_err!("Not logged in."))
+                            .and_then(|(str_session_token, str_player_id)| Ok((
+                                (str_session_token.as_str(), str_player_id.as_str()),
+                                lobby.table(str_table_name)?,
+                                match &playercmd {
+                                    VPlayerCmd::Spectate{spectatorview: VSpectatorView::Seat(i_epi), ..} => Some(VPerspective::Seat{
+                                        epi: EPlayerIndex::checked_from_usize(*i_epi)
+                                            .ok_or_else(|| format_err!("Invalid seat {i_epi}."))?,
+                                        b_may_act: false,
+                                    }),
+                                    VPlayerCmd::Spectate{spectatorview: VSpectatorView::AllHands, ..} => Some(VPerspective::AllHands),
+                                    _ => None,
+                                },
+                            )))
+                        {
+                            Ok((tplstr_session, table_new, operspective_spectator)) => {
+                                drop(lobby);
+                                join_table(&mut otable, table_new, tplstr_session, &str_player_name, operspective_spectator);
+                            },
+                            Err(err) => {
+                                lobby.send_lobby_state(&connection.txmsg, Some(err.0));
+                            },
                         }
                     },
                 }
@@ -409,26 +1065,309 @@ async fn handle_connection(table: Arc<Mutex<STable>>, tcpstream: TcpStream, sock
     let receive_from_others = rxmsg.map(Ok).forward(sink_ws_out);
     future::select(broadcast_incoming, receive_from_others).await;
     println!("{} disconnected", &sockaddr);
-    unwrap!(table.lock()).remove(&sockaddr);
+    if let Some((str_session_token, _str_player_id)) = &ostr_session {
+        unwrap!(lobby.lock()).touch_session(str_session_token, Instant::now()); // expiry counts from disconnecting
+    }
+    if let Some(table) = otable {
+        unwrap!(table.lock()).disconnect(&sockaddr);
+    }
+}
+
+fn rulesets_from_directory(path_dir: &Path) -> Result<BTreeMap<String, SRuleSet>, SStringifiedError> {
+    let mut mapstrruleset = BTreeMap::new();
+    for direntry in std::fs::read_dir(path_dir)? {
+        let path_ruleset = direntry?.path();
+        if path_ruleset.extension().is_some_and(|osstr_ext| osstr_ext=="toml")
+            && let Some(str_ruleset) = path_ruleset.file_stem().and_then(|osstr_stem| osstr_stem.to_str())
+        {
+            mapstrruleset.insert(str_ruleset.to_string(), SRuleSet::from_file(&path_ruleset)?);
+        }
+    }
+    Ok(mapstrruleset)
 }
 
-async fn internal_run(ruleset: SRuleSet, b_with_bots: bool) -> Result<(), SStringifiedError> {
-    let str_addr = "127.0.0.1:8080";
-    let table = Arc::new(Mutex::new(STable::new(ruleset, b_with_bots)));
+// Relative paths (such as the defaults) are tried relative to the working directory first, then relative to
+// the executable's directory and its ancestors, so that the server also finds the repository's rulesets
+// if it is started from elsewhere.
+fn resolve_path(path: &Path) -> Result<PathBuf, SStringifiedError> {
+    if path.is_absolute() || path.exists() {
+        return Ok(path.to_path_buf());
+    }
+    std::env::current_exe()?
+        .ancestors()
+        .skip(1) // the executable itself
+        .map(|path_ancestor| path_ancestor.join(path))
+        .find(|path_candidate| path_candidate.exists())
+        .ok_or_else(|| format_err!("Could not find {}, neither relative to the working directory nor relative to the executable.", path.display()))
+}
+
+async fn internal_run(str_addr: String, mut mapstrruleset: BTreeMap<String, SRuleSet>, (str_ruleset_default, ruleset_default): (String, SRuleSet), obotstrength: Option<EBotStrength>, delays: SDelays, persistence: SPersistence) -> Result<(), SStringifiedError> {
+    // The default table's ruleset is offered for new tables as well (replacing a ruleset of the same name from the ruleset directory).
+    mapstrruleset.insert(str_ruleset_default.clone(), ruleset_default);
+    let mut lobby = SLobby::new(mapstrruleset, delays, persistence);
+    lobby.create_table(
+        /*str_table_name*/"default".into(),
+        str_ruleset_default,
+        obotstrength,
+    )?;
+    let lobby = Arc::new(Mutex::new(lobby));
     // Create the event loop and TCP listener we'll accept connections on.
-    let listener = unwrap!(TcpListener::bind(&str_addr).await);
+    let listener = TcpListener::bind(&str_addr).await?;
     println!("Listening on: {str_addr}");
     // Let's spawn the handling of each connection in a separate task.
     while let Ok((tcpstream, sockaddr)) = listener.accept().await {
-        task::spawn(handle_connection(table.clone(), tcpstream, sockaddr));
+        task::spawn(handle_connection(lobby.clone(), tcpstream, sockaddr));
     }
     Ok(())
 }
 
 pub fn run(clapmatches: &clap::ArgMatches) -> Result<(), SStringifiedError> {
     task::block_on(internal_run(
-        openschafkopf_shared_args::get_ruleset(clapmatches)?,
-        /*b_with_bots*/clapmatches.is_present("with-bots"),
+        /*str_addr*/format!("{}:{}",
+            unwrap!(clapmatches.value_of("address")),
+            clapmatches.value_of_t::<u16>("port")?,
+        ),
+        rulesets_from_directory(&resolve_path(Path::new(unwrap!(clapmatches.value_of("ruleset-dir"))))?)?,
+        {
+            let path_ruleset = resolve_path(Path::new(unwrap!(clapmatches.value_of("ruleset"))))?;
+            (
+                path_ruleset.file_stem()
+                    .and_then(|osstr_stem| osstr_stem.to_str())
+                    .ok_or_else(|| format_err!("Cannot name ruleset {}.", path_ruleset.display()))?
+                    .to_string(),
+                SRuleSet::from_file(&path_ruleset)?,
+            )
+        },
+        /*obotstrength*/if_then_some!(clapmatches.is_present("with-bots"),
+            match unwrap!(clapmatches.value_of("bot-strength")) {
+                "random" => EBotStrength::Random,
+                "simulating" => EBotStrength::Simulating,
+                "cheating" => EBotStrength::Cheating,
+                str_bot_strength => panic!("Unknown bot strength: {str_bot_strength}"), // prevented by possible_values
+            }
+        ),
+        SDelays {
+            duration_bot_timeout: Duration::from_millis(clapmatches.value_of_t("bot-timeout")?),
+            duration_spectator_delay: Duration::from_millis(clapmatches.value_of_t("spectator-delay")?),
+        },
+        SPersistence::new(
+            /*opath_game_log*/clapmatches.value_of("game-log").map(PathBuf::from),
+            /*opath_balances*/clapmatches.value_of("balances").map(PathBuf::from),
+        )?,
     ))
 }
 
+#[cfg(test)]
+fn lobby_for_test() -> SLobby {
+    SLobby::new(
+        BTreeMap::from([(
+            "default".to_string(),
+            unwrap!(SRuleSet::from_string("base-price = 10\nsolo-price = 50\nlauf-min = 3\ndeck = \"lang\"\n[rufspiel]\n[solo]\n")),
+        )]),
+        SDelays {
+            duration_bot_timeout: Duration::from_millis(100),
+            duration_spectator_delay: Duration::from_millis(100),
+        },
+        unwrap!(SPersistence::new(/*opath_game_log*/None, /*opath_balances*/None)),
+    )
+}
+
+#[test]
+fn test_lobby_tables() {
+    let mut lobby = lobby_for_test();
+    assert!(lobby.create_table(" ".into(), "default".into(), /*obotstrength*/None).is_err());
+    assert!(lobby.create_table("table".into(), "unknown".into(), /*obotstrength*/None).is_err());
+    let table = unwrap!(lobby.create_table("table".into(), "default".into(), /*obotstrength*/None));
+    assert!(lobby.create_table("table".into(), "default".into(), /*obotstrength*/None).is_err());
+    assert!(Arc::ptr_eq(&table, &unwrap!(lobby.table("table"))));
+    assert!(lobby.table("unknown").is_err());
+    let mut table = unwrap!(table.lock());
+    table.players.vecpeer_inactive.push(SPeer{
+        str_session_token: "token".into(),
+        str_player_id: "id".into(),
+        oconnection: None,
+        n_money: 42,
+        str_name: "name".into(),
+    });
+    assert!(table.remove("other").is_none());
+    assert_eq!(table.remove("token").map(|peer| peer.n_money), Some(42));
+    assert!(table.players.peer("token").is_none());
+}
+
+#[test]
+fn test_rulesets_and_paths() {
+    let path_manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    assert_eq!(unwrap!(resolve_path(path_manifest)), path_manifest);
+    assert!(resolve_path(Path::new("does/not/exist.toml")).is_err());
+    assert!(unwrap!(rulesets_from_directory(&path_manifest.join("../rulesets"))).contains_key("default"));
+}
+
+#[cfg(test)]
+fn connection_for_test() -> SConnection {
+    SConnection {
+        sockaddr: unwrap!("127.0.0.1:1234".parse()),
+        txmsg: unbounded().0,
+    }
+}
+
+#[test]
+fn test_session_expiry() {
+    let mut lobby = lobby_for_test();
+    let instant_now = Instant::now();
+    let str_session_token = lobby.login(/*ostr_session_token*/None, instant_now);
+    assert_eq!(lobby.login(Some(str_session_token.clone()), instant_now), str_session_token);
+    assert_ne!(lobby.login(Some("unknown".into()), instant_now), "unknown");
+    let instant_later = instant_now + Duration::from_secs(2 * 24 * 60 * 60);
+    // sessions connected to a table do not expire
+    let str_session_token_seated = lobby.login(/*ostr_session_token*/None, instant_now);
+    let table = unwrap!(lobby.create_table("table".into(), "default".into(), /*obotstrength*/None));
+    unwrap!(table.lock()).players.vecpeer_inactive.push(SPeer{
+        str_session_token: str_session_token_seated.clone(),
+        str_player_id: "id".into(),
+        oconnection: Some(connection_for_test()),
+        n_money: 0,
+        str_name: "name".into(),
+    });
+    assert_eq!(lobby.login(Some(str_session_token_seated.clone()), instant_later), str_session_token_seated);
+    assert_ne!(lobby.login(Some(str_session_token.clone()), instant_later), str_session_token);
+    assert!(!lobby.mapstrinstant_session.contains_key(&str_session_token));
+    // disconnected players are removed from their table once their session expires
+    unwrap!(table.lock()).disconnect(&connection_for_test().sockaddr);
+    lobby.expire_sessions(instant_later + Duration::from_secs(2 * 24 * 60 * 60));
+    assert!(!lobby.mapstrinstant_session.contains_key(&str_session_token_seated));
+    assert!(lobby.table_of_session(&str_session_token_seated).is_none());
+}
+
+#[test]
+fn test_reconnect_spectator() {
+    let mut lobby = lobby_for_test();
+    let table = unwrap!(lobby.create_table("table".into(), "default".into(), /*obotstrength*/None));
+    let mut table = unwrap!(table.lock());
+    table.insert_spectator(
+        SPeer{
+            str_session_token: "token".into(),
+            str_player_id: "id".into(),
+            oconnection: Some(connection_for_test()),
+            n_money: 0,
+            str_name: "name".into(),
+        },
+        VPerspective::AllHands,
+    );
+    table.disconnect(&connection_for_test().sockaddr);
+    assert!(unwrap!(table.players.peer("token")).oconnection.is_none());
+    assert!(!table.reconnect("other", connection_for_test(), "other".into(), "id"));
+    assert!(table.reconnect("token", connection_for_test(), "renamed".into(), "id"));
+    let peer = unwrap!(table.players.peer("token"));
+    assert!(peer.oconnection.is_some());
+    assert_eq!(peer.str_name, "renamed");
+}
+
+#[test]
+fn test_random_bot_plays_game() {
+    let bot = EBotStrength::Random.bot();
+    let ruleset = unwrap!(lobby_for_test().mapstrruleset.remove("default"));
+    for _i_game in 0..10 {
+        let (mut gamephaseorresult, _sendtoplayers) = VGamePhase::new(ruleset.clone(), /*n_stock*/0);
+        while let VGamePhaseOrResultGeneric::GamePhase(gamephase) = gamephaseorresult {
+            let (epi, gamephaseaction) = unwrap!(EPlayerIndex::values()
+                .find_map(|epi| bot.ask(&gamephase, epi).map(|gamephaseaction| (epi, gamephaseaction)))
+            );
+            (gamephaseorresult, _) = unwrap!(gamephase.action(epi, gamephaseaction));
+        }
+    }
+}
+
+#[test]
+fn test_drop_stale_bot_action() {
+    let mut lobby = lobby_for_test();
+    let table_mutex = unwrap!(lobby.create_table("table".into(), "default".into(), Some(EBotStrength::Random)));
+    let mut table = unwrap!(table_mutex.lock());
+    let gamephaseaction = VGamePhaseAction::Game(VGameAction::Stoss);
+    table.on_bot_gamephaseaction(table_mutex.clone(), /*n_gamephase_generation*/0, EPlayerIndex::EPI0, gamephaseaction.clone());
+    assert_eq!(table.n_gamephase_generation, 1); // table communicated its (unchanged) state
+    table.on_bot_gamephaseaction(table_mutex.clone(), /*n_gamephase_generation*/0, EPlayerIndex::EPI0, gamephaseaction.clone());
+    assert_eq!(table.n_gamephase_generation, 1); // stale action dropped
+    table.on_bot_gamephaseaction(table_mutex.clone(), /*n_gamephase_generation*/1, EPlayerIndex::EPI0, gamephaseaction);
+    assert_eq!(table.n_gamephase_generation, 2);
+}
+
+#[test]
+fn test_no_bots_only_game() {
+    let mut lobby = lobby_for_test();
+    let table_mutex = unwrap!(lobby.create_table("table".into(), "default".into(), Some(EBotStrength::Random)));
+    let mut table = unwrap!(table_mutex.lock());
+    table.start_new_game(table_mutex.clone());
+    assert!(table.ogamephaseorresult.is_none());
+    table.insert(
+        table_mutex.clone(),
+        SPeer{
+            str_session_token: "token".into(),
+            str_player_id: "id".into(),
+            oconnection: Some(connection_for_test()),
+            n_money: 0,
+            str_name: "name".into(),
+        },
+    );
+    assert!(table.ogamephaseorresult.is_some());
+    // game ends, and the last human leaves before the next one starts
+    table.ogamephaseorresult = None;
+    table.disconnect(&connection_for_test().sockaddr);
+    assert!(table.players.mapepiopeer_active.iter().all(Option::is_none));
+    table.start_new_game(table_mutex.clone());
+    assert!(table.ogamephaseorresult.is_none());
+}
+
+#[test]
+fn test_balance_by_player_id() {
+    let mut lobby = lobby_for_test();
+    lobby.persistence.add_to_balance("id_other", 7);
+    let table = unwrap!(lobby.create_table("table".into(), "default".into(), /*obotstrength*/None));
+    let mut table = unwrap!(table.lock());
+    table.players.vecpeer_inactive.push(SPeer{
+        str_session_token: "token".into(),
+        str_player_id: "id".into(),
+        oconnection: None,
+        n_money: 0,
+        str_name: "<Name>".into(),
+    });
+    table.set_player("token", "renamed".into(), "id");
+    assert_eq!(unwrap!(table.players.peer("token")).n_money, 0);
+    table.set_player("token", "renamed".into(), "id_other");
+    let peer = unwrap!(table.players.peer("token"));
+    assert_eq!((peer.str_name.as_str(), peer.n_money), ("renamed", 7));
+}
+
+#[test]
+fn test_seat_spectator_cannot_act() {
+    let (_gamephaseorresult, sendtoplayers) = VGamePhase::new(unwrap!(lobby_for_test().mapstrruleset.remove("default")), /*n_stock*/0);
+    let epi = unwrap!(sendtoplayers.otimeoutaction.as_ref()).epi;
+    let players = SPlayers::default();
+    let site_state = |b_may_act| unwrap!(serde_json::from_str::<serde_json::Value>(
+        &players.site_state(VPerspective::Seat{epi, b_may_act}, &sendtoplayers, /*oduration_timeout*/None)
+    ));
+    let site_state_player = site_state(/*b_may_act*/true);
+    let site_state_spectator = site_state(/*b_may_act*/false);
+    assert!(site_state_player["msg"].get("Ask").is_some());
+    assert!(site_state_spectator["msg"].get("Ask").is_none());
+    let vectplstrstr_caption_message_zugeben = |site_state: &serde_json::Value| unwrap!(
+        site_state["vectplstrstr_caption_message_zugeben"].as_array()
+    ).clone();
+    assert!(!vectplstrstr_caption_message_zugeben(&site_state_spectator).is_empty());
+    assert!(vectplstrstr_caption_message_zugeben(&site_state_spectator).iter().all(|tplstrstr| tplstrstr[1].is_null()));
+    assert!(vectplstrstr_caption_message_zugeben(&site_state_player).iter().all(|tplstrstr| !tplstrstr[1].is_null()));
+}
+
+#[test]
+fn test_delayed_sender() {
+    let (txmsg, mut rxmsg) = unbounded();
+    let duration_delay = Duration::from_millis(50);
+    let delayedsender = SDelayedSender::new(txmsg, duration_delay);
+    let instant_send = Instant::now();
+    delayedsender.send("first".into());
+    delayedsender.send("second".into());
+    task::block_on(async {
+        assert_eq!(unwrap!(rxmsg.next().await), Message::from("first"));
+        assert!(duration_delay <= instant_send.elapsed());
+        assert_eq!(unwrap!(rxmsg.next().await), Message::from("second"));
+    });
+}
@@ -7,10 +7,11 @@ use wasm_bindgen::prelude::*;
 use openschafkopf_util::*;
 use openschafkopf_lib::{
     ai::{SDetermineBestCardResult, SPayoutStats, determine_best_card, gametree::{SPerMinMaxStrategyGeneric, SMaxSelfishMinStrategy, SAlphaBetaPrunerNone, SGenericMinReachablePayout, SNoVisualization, STplStrategiesOnlyMaxSelfishMin}, stichoracle::SFilterByOracle},
+    api::{SBudget, SEngine, SGameState, SPositionBuilder, VApiError, cards_from_str, position_from_str, rules_from_str},
     game::{first_hand_for, SGameResultGeneric},
-    game_analysis::{html_payout_table, html_copy_button, parser::{SGameAnnouncementAnonymous, internal_analyze_sauspiel_html, TSauspielHtmlDocument, TSauspielHtmlNode, VSauspielHtmlData}},
-    rules::{SDisplayRules, SRules, TRules, TRulesPlayerIndex, ruleset::VStockOrT, SExpensifiers, trumpfdecider::STrumpfDecider, VTrumpfOrFarbe, card_points::points_stich},
-    primitives::{ECard, EFarbe, ESchlag, EPlayerIndex, SHand, SStichSequence, TCardSorter},
+    game_analysis::{html_payout_table, html_copy_button, parser::{SGameAnnouncementAnonymous, internal_analyze_sauspiel_html, TSauspielHtmlDocument, TSauspielHtmlNode, VSauspielHtmlData}, position::SPosition},
+    rules::{SDisplayRules, SRules, SRuleStateCache, TRules, TRulesPlayerIndex, payoutdecider::ESchneiderSchwarz, ruleset::VStockOrT, SExpensifiers, trumpfdecider::STrumpfDecider, VTrumpfOrFarbe, card_points::points_stich},
+    primitives::{ECard, EFarbe, ESchlag, EPlayerIndex, SHand, SStichSequence, SStichSequenceGameFinished, TCardSorter},
 };
 use crate::utils::*;
 use std::fmt::Debug;
@@ -107,7 +108,8 @@ impl TSauspielHtmlNode<'_> for SWebsysElement {
         match self.0.node_type() {
             3 => VSauspielHtmlData::Text(self.text()),
             1 => VSauspielHtmlData::Element,
-            _ => VSauspielHtmlData::Comment, // TODO unsupported types
+            8 => VSauspielHtmlData::Comment,
+            _ => VSauspielHtmlData::Unsupported,
         }
     }
     fn name(&self) -> Option<String> {
@@ -150,8 +152,67 @@ fn output_card_sauspiel_img(card: ECard, b_highlight: bool) -> impl html_generat
     )
 }
 
-#[wasm_bindgen(start)]
-pub fn greet() {
+// Computes payout stats for each card the current player of position could play, seen from this player's
+// perspective (i.e. other players' cards are ignored). This may take a while, depending on budget.
+fn internal_suggest_card_html(gamestate: &SGameState, budget: SBudget) -> Result<String, VApiError> {
+    let epi_current = gamestate.player_to_move().ok_or(VApiError::GameFinished)?;
+    let suggestion = SEngine::new(/*n_rank_rules_samples*/1) // unused
+        .suggest(
+            &gamestate.seen_by(epi_current), // hints must not use other players' cards
+            budget,
+        )?;
+    use html_generator::*;
+    Ok(html_display_children(table(tbody((
+        tr((
+            th("Karte"),
+            th("Durchschnitt (eigennützig)"),
+            th("Durchschnitt (pessimistisch)"),
+            th("Min"),
+            th("Max"),
+        )),
+        suggestion.veccardevaluation.iter()
+            .map(|cardevaluation| {
+                let payoutsummary_selfish = cardevaluation.payouts.selfish();
+                tr((
+                    td(output_card_sauspiel_img(cardevaluation.card, /*b_highlight*/cardevaluation.card==suggestion.card)),
+                    td(format!("{:.1}", payoutsummary_selfish.f_payout_avg)),
+                    td(format!("{:.1}", cardevaluation.payouts.pessimistic().f_payout_avg)),
+                    td(format!("{}", payoutsummary_selfish.n_payout_min)),
+                    td(format!("{}", payoutsummary_selfish.n_payout_max)),
+                ))
+            })
+            .collect::<Vec<_>>(),
+        if_then_some!(suggestion.veccardevaluation.is_empty(), tr(td(output_card_sauspiel_img(suggestion.card, /*b_highlight*/true)))),
+    )))).to_string())
+}
+
+// Hint for a position of a finished game. Intended to be run in a Web Worker.
+#[wasm_bindgen]
+pub fn suggest_card_html(str_position: &str, n_suggest_card_samples: usize, n_suggest_card_branches: usize) -> Result<String, JsValue> {
+    set_panic_hook();
+    position_from_str(str_position)
+        .and_then(|gamestate| internal_suggest_card_html(&gamestate, SBudget::new(n_suggest_card_samples, n_suggest_card_branches)))
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+// Hint during play, where the user enters the rules, the cards played so far and the own remaining cards.
+// Intended to be run in a Web Worker.
+#[wasm_bindgen]
+pub fn suggest_card_html_live(str_rules: &str, str_cards_played: &str, str_hand: &str, n_suggest_card_samples: usize, n_suggest_card_branches: usize) -> Result<String, JsValue> {
+    set_panic_hook();
+    rules_from_str(str_rules)
+        .and_then(|rules| Ok(SPositionBuilder::new(rules)
+            .played_cards(cards_from_str(str_cards_played)?)
+            .hand_current(cards_from_str(str_hand)?)
+        ))
+        .and_then(SPositionBuilder::build)
+        .and_then(|gamestate| internal_suggest_card_html(&gamestate, SBudget::new(n_suggest_card_samples, n_suggest_card_branches)))
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+// Called by the content script once the wasm module is loaded (not run on start, so that Web Workers can load the module without a document).
+#[wasm_bindgen]
+pub fn analyze_game_page() {
     set_panic_hook();
 
     fn determine_best_card_sauspiel(ahand: EnumMap<EPlayerIndex, SHand>, stichseq: &SStichSequence, epi: EPlayerIndex, rules: &SRules, expensifiers: &SExpensifiers) -> SDetermineBestCardResult<SPerMinMaxStrategyGeneric<SPayoutStats<()>, STplStrategiesOnlyMaxSelfishMin>> {
@@ -191,11 +252,11 @@ pub fn greet() {
     }
     match internal_analyze_sauspiel_html(
         SWebsysDocument(document.clone()),
-        /*fn_gameannouncement*/|_, ogameannouncement, node_gameannouncement| (ogameannouncement.clone(), node_gameannouncement),
-        /*fn_determinerules_step*/|epi, oeobslcschlagoefarbe_trumpf, SWebsysElement(node_determinerules)| SDetermineRulesStep {
-            epi,
-            resslcschlag_trumpf: oeobslcschlagoefarbe_trumpf.clone().and_then(EitherOrBoth::left).ok_or(()),
-            resoefarbe_trumpf: oeobslcschlagoefarbe_trumpf.clone().and_then(EitherOrBoth::right).ok_or(()),
+        /*fn_gameannouncement*/|_, gameannouncement, node_gameannouncement| (gameannouncement.ogameannouncement.clone(), node_gameannouncement),
+        /*fn_determinerules_step*/|determinerulesstep, SWebsysElement(node_determinerules)| SDetermineRulesStep {
+            epi: determinerulesstep.epi,
+            resslcschlag_trumpf: determinerulesstep.trumpf().and_then(EitherOrBoth::left).ok_or(()),
+            resoefarbe_trumpf: determinerulesstep.trumpf().and_then(EitherOrBoth::right).ok_or(()),
             node_determinerules,
         },
         /*fn_before_play_card*/|game, card, epi, element_played_card| {
@@ -207,7 +268,7 @@ pub fn greet() {
             ));
         },
     ) {
-        Ok((SGameResultGeneric{stockorgame: VStockOrT::OrT(game_finished), an_payout}, mapepistr_username)) => {
+        Ok((SGameResultGeneric{stockorgame: VStockOrT::OrT(game_finished), an_payout}, mapepistr_username, _odeal_stock)) => {
             let (_ogameannouncement, SWebsysElement(node_gameannouncement_epi0)) = &game_finished.mapepigameannouncement[EPlayerIndex::EPI0];
             fn epi_to_sauspiel_position(epi: EPlayerIndex) -> usize {
                 epi.to_usize() + 1
@@ -233,7 +294,7 @@ pub fn greet() {
                 let (ogameannouncement, SWebsysElement(node_gameannouncement)) = &game_finished.mapepigameannouncement[epi];
                 let mut itdeterminerulesstep_epi = game_finished.determinerules.iter()
                     .filter(|determinerulesstep| determinerulesstep.epi==epi);
-                let (slcschlag_trumpf_gameannouncement, oefarbe_trumpf_gameannouncement) = if let Some(SGameAnnouncementAnonymous) = ogameannouncement {
+                let (slcschlag_trumpf_gameannouncement, oefarbe_trumpf_gameannouncement) = if let Some(SGameAnnouncementAnonymous{}) = ogameannouncement {
                     let mut vecdeterminerulesstep = itdeterminerulesstep_epi.cloned().collect::<Vec<_>>(); // TODO can we work directly on game.determinerules?
                     assert!(!vecdeterminerulesstep.is_empty());
                     { // Sauspiel reveals slcschlag_trumpf first, so we propagate that forwards
@@ -396,11 +457,24 @@ pub fn greet() {
                     }
                 });
                 vecepicardocardseverity.push((epi, card_played, ocardseverity));
+                { // live hint, computed on demand by the content script
+                    let div_hint = unwrap!(document.create_element("div"));
+                    unwrap!(div_hint.set_attribute("class", "openschafkopf-hint"));
+                    unwrap!(div_hint.set_attribute("data-openschafkopf-position", &SPosition::new(
+                        rules,
+                        &stichseq,
+                        &ahand,
+                        &game_finished.expensifiers,
+                        epi,
+                    ).to_string()));
+                    append_sibling(&element_played_card, &div_hint);
+                }
                 let div_button = unwrap!(document.create_element("div"));
                 div_button.set_inner_html(&html_copy_button(
                     rules,
                     &ahand,
                     &stichseq,
+                    &game_finished.expensifiers,
                     /*str_openschafkopf_executable*/"openschafkopf",
                 ));
                 append_sibling(&element_played_card, &div_button);
@@ -464,28 +538,80 @@ pub fn greet() {
                 })
             }
             const STR_STYLE_ACTIVE_PLAYER_BACKGROUND_COLOR : &str = "background-color: #11111111";
-            let points_cell_style = |b_border_top: bool, epi: EPlayerIndex| {
+            let who_has_card = |card| unwrap!(EPlayerIndex::values().find(|epi| game_finished.aveccard[*epi].contains(&card)));
+            let oepi_partner = rules.rufsau().map(who_has_card);
+            let oi_stich_partner_known = rules.rufsau().and_then(|card_rufsau| // TODO partner is also known if the Rufsau is searched and the partner runs away
+                game_finished.stichseq.completed_stichs().iter()
+                    .position(|stich| stich.iter().any(|(_epi, card)| *card==card_rufsau))
+            );
+            let is_primary_party = |epi| rules.playerindex()==Some(epi) || oepi_partner==Some(epi);
+            // oi_stich: None after the game, Some(i_stich) only reveals the partner if known after i_stich
+            let is_known_primary_party = |epi, oi_stich: Option<usize>| {
+                rules.playerindex()==Some(epi)
+                    || (oepi_partner==Some(epi) && oi_stich.is_none_or(|i_stich| oi_stich_partner_known.is_some_and(|i_stich_known| i_stich_known<=i_stich)))
+            };
+            let points_cell_style = |b_border_top: bool, epi: EPlayerIndex, oi_stich: Option<usize>| {
                 let mut str_style = "padding: 5px;".to_string(); // TODO could html_generator solve this nicely?
                 if b_border_top {
                     str_style += "border-top: 1px solid black;";
                 }
-                if rules.playerindex() == Some(epi) { // TODO support "Mitspieler" for Rufspiel
+                if is_known_primary_party(epi, oi_stich) {
                     str_style += STR_STYLE_ACTIVE_PLAYER_BACKGROUND_COLOR;
                 }
                 attributes::style(str_style)
             };
+            // points of primary (true) and secondary (false) party after each stich, if the game has parties
+            let ovecmapbn_points_party = if_then_some!(rules.playerindex().is_some(), {
+                let mut mapbn_points_party = bool::map_from_fn(|_b_primary| 0);
+                game_finished.stichseq.completed_stichs_winner_index(dbg_argument!(&game_finished.rules))
+                    .map(|(stich, epi_winner)| {
+                        mapbn_points_party[is_primary_party(epi_winner)] += points_stich(stich);
+                        mapbn_points_party.clone()
+                    })
+                    .collect::<Vec<_>>()
+            });
+            let ostr_summary = ovecmapbn_points_party.as_ref().map(|vecmapbn_points_party| {
+                let mapbn_points_party = unwrap!(vecmapbn_points_party.last());
+                let party_to_str = |b_primary| if b_primary {"Spieler"} else {"Gegenspieler"};
+                let str_laufende = rules.count_laufende(game_finished.kurzlang(), who_has_card)
+                    .map(|laufendecount| format!("{} Laufende ({})", laufendecount.n_laufende, party_to_str(laufendecount.b_primary_party)))
+                    .unwrap_or_default();
+                let stichseq_finished = SStichSequenceGameFinished::new(&game_finished.stichseq);
+                let ostr_schneider = rules.schneiderschwarz(
+                    stichseq_finished,
+                    &SRuleStateCache::new_from_gamefinishedstiche(stichseq_finished, dbg_argument!(rules)),
+                ).map(|schneiderschwarz| {
+                    // Schneider/Schwarz always refers to the party that lost the game
+                    let b_primary_lost = an_payout[unwrap!(rules.playerindex())] < 0;
+                    match schneiderschwarz {
+                        ESchneiderSchwarz::Nichts => "Kein Schneider".to_string(),
+                        ESchneiderSchwarz::Schneider => format!("{} schneider", party_to_str(b_primary_lost)),
+                        ESchneiderSchwarz::Schwarz => format!("{} schwarz", party_to_str(b_primary_lost)),
+                    }
+                });
+                format!("{}: {} Augen, {}: {} Augen. {}.{}",
+                    party_to_str(true),
+                    mapbn_points_party[true],
+                    party_to_str(false),
+                    mapbn_points_party[false],
+                    str_laufende,
+                    ostr_schneider.map(|str_schneider| format!(" {}.", str_schneider)).unwrap_or_default(),
+                )
+            });
             node_whole_game.set_inner_html(&html_display_children((
                 div((
                     attributes::style("font-weight: bold"),
                     format!("{}", SDisplayRules::new(rules, |epi, fmt: &mut std::fmt::Formatter| {
                         write!(fmt, "{}", mapepistr_username[epi])
                     })),
+                    oepi_partner.map(|epi_partner| format!(" mit {}", mapepistr_username[epi_partner])),
                 )),
+                ostr_summary.map(div),
                 table((
                     attributes::style("border-collapse: separate; border-spacing: 0 5px;"), // space between lines
                     tbody((
                         html_iter(EPlayerIndex::values().map(|epi_hand| tr((
-                            if_then_some!(Some(epi_hand)==rules.playerindex(),
+                            if_then_some!(is_primary_party(epi_hand),
                                 attributes::style(STR_STYLE_ACTIVE_PLAYER_BACKGROUND_COLOR)
                             ),
                             (
@@ -514,6 +640,10 @@ pub fn greet() {
                             colspan(format!("{}", EPlayerIndex::SIZE)),
                             "Augen", // "Augen" as used by sauspiel.de
                         )),
+                        ovecmapbn_points_party.as_ref().map(|_| (
+                            html_table_gap_cell.clone(),
+                            th((colspan("2"), "Parteien")),
+                        )),
                     )),
                     tr((
                         th(()), // empty cell to match subsequent rows
@@ -538,10 +668,15 @@ pub fn greet() {
                         html_table_gap_cell.clone(),
                         html_iter(EPlayerIndex::values().map(|epi_points|
                             th((
-                                points_cell_style(/*b_border_top*/false, epi_points),
+                                points_cell_style(/*b_border_top*/false, epi_points, /*oi_stich*/None),
                                 format!("{}", epi_to_sauspiel_position(epi_points)),
                             ))
                         )),
+                        ovecmapbn_points_party.as_ref().map(|_| (
+                            html_table_gap_cell.clone(),
+                            th((attributes::style(format!("padding: 5px;{STR_STYLE_ACTIVE_PLAYER_BACKGROUND_COLOR}")), "Spieler")),
+                            th((attributes::style("padding: 5px;"), "Gegner")),
+                        )),
                     )),
                     itertools::zip_eq(
                         vecepicardocardseverity.chunks(EPlayerIndex::SIZE),
@@ -569,10 +704,15 @@ pub fn greet() {
                             html_table_gap_cell.clone(),
                             html_iter(EPlayerIndex::values().map(move |epi_points| 
                                 td((
-                                    points_cell_style(/*b_border_top*/false, epi_points),
+                                    points_cell_style(/*b_border_top*/false, epi_points, Some(i_stich)),
                                     if_then_some!(epi_points==epi_winner, format!("{}", points_stich(stich))),
                                 ))
                             )),
+                            ovecmapbn_points_party.as_ref().map(|vecmapbn_points_party| (
+                                html_table_gap_cell.clone(),
+                                td((attributes::style(format!("padding: 5px;{STR_STYLE_ACTIVE_PLAYER_BACKGROUND_COLOR}")), format!("{}", vecmapbn_points_party[i_stich][true]))),
+                                td((attributes::style("padding: 5px;"), format!("{}", vecmapbn_points_party[i_stich][false]))),
+                            )),
                         ))
                     })
                     .collect::<Vec<_>>(), // TODO avoid
@@ -585,7 +725,7 @@ pub fn greet() {
                         ))),
                         html_iter(EPlayerIndex::values().map(|epi_points| {
                             td((
-                                points_cell_style(/*b_border_top*/true, epi_points),
+                                points_cell_style(/*b_border_top*/true, epi_points, /*oi_stich*/None),
                                 format!("{}",
                                     game_finished.stichseq.completed_stichs_winner_index(dbg_argument!(&game_finished.rules))
                                         .filter_map(|(stich, epi_winner)|
@@ -601,10 +741,21 @@ pub fn greet() {
             ))).to_string());
             unwrap!(node_gameannouncements.append_with_node_1(&node_whole_game));
         },
-        Ok((SGameResultGeneric{stockorgame: VStockOrT::Stock(_), an_payout:_}, _mapepistr_username)) => {
+        Ok((SGameResultGeneric{stockorgame: VStockOrT::Stock(_), an_payout:_}, _mapepistr_username, _odeal_stock)) => {
             // Nothing to analyze for Stock.
         },
-        Err(err_html) => { // TODO we should distinguish between "Game not visible/found/accessible" and "HTML not understood"
+        Err(err_html) => {
+            // Sauspiel only lists the participants if the game is visible to the user.
+            let str_error = if document.get_elements_by_class_name("game-participants").length()==0 {
+                "Spiel nicht sichtbar (privates Spiel, nicht gefunden oder nicht eingeloggt?)"
+            } else {
+                "Spielseite nicht verstanden (hat sich das Layout von sauspiel.de geändert?)"
+            };
+            web_sys::console::error_1(&format!("openschafkopf: {str_error}: {err_html:?}").into());
+            let div_error = unwrap!(document.create_element("div"));
+            unwrap!(div_error.set_attribute("style", "background-color: #ffcccc; padding: 5px;"));
+            div_error.set_text_content(Some(&format!("openschafkopf: {str_error}")));
+            unwrap!(unwrap!(document.body()).prepend_with_node_1(&div_error));
             #[cfg(not(feature="sauspiel_webext_use_json"))] {
                 dbg_alert!(&format!("Error parsing document:\n{err_html:?}"));
             }
@@ -670,6 +821,7 @@ pub fn greet() {
                                     &game_finished.rules,
                                     &ahand,
                                     &stichseq,
+                                    &game_finished.expensifiers,
                                     /*str_openschafkopf_executable*/"openschafkopf",
                                 )),
                             )
@@ -699,3 +851,37 @@ pub fn greet() {
 }
 
 
+
+#[test]
+fn test_suggest_card_html() {
+    // Gras-Solo after six stichs, player 0 to move with G7 and EZ
+    let str_cards_played = "eo gk g8 e7 ho hu g9 e8 go eu su h7 ea ga e9 ek ha h8 h9 hk sa s7 s8 gz";
+    let positionbuilder = SPositionBuilder::new(unwrap!(rules_from_str("Gras-Solo von 0")))
+        .played_cards(unwrap!(cards_from_str(str_cards_played)));
+    let gamestate = unwrap!(positionbuilder.clone()
+        .hand_current(unwrap!(cards_from_str("g7 ez")))
+        .build());
+    let str_html = unwrap!(internal_suggest_card_html(&gamestate, SBudget::new(/*n_samples*/1, /*n_branches*/1)));
+    for str_card in ["G7", "EX"] {
+        assert!(str_html.contains(&format!("card-icon-{str_card}")), "{str_html}");
+    }
+    // the hint must not depend on other players' cards
+    let gamestate_all_known = unwrap!(positionbuilder.clone()
+        .hand_current(unwrap!(cards_from_str("g7 ez")))
+        .hand(EPlayerIndex::EPI1, unwrap!(cards_from_str("so s9")))
+        .hand(EPlayerIndex::EPI2, unwrap!(cards_from_str("gu sk")))
+        .hand(EPlayerIndex::EPI3, unwrap!(cards_from_str("sz hz")))
+        .build());
+    assert_eq!(
+        unwrap!(internal_suggest_card_html(&gamestate_all_known, SBudget::new(/*n_samples*/1, /*n_branches*/1))),
+        str_html,
+    );
+    let gamestate_finished = unwrap!(positionbuilder
+        .played_cards(unwrap!(cards_from_str("g7 so gu sz s9 sk hz ez")))
+        .position(EPlayerIndex::EPI0)
+        .build());
+    assert!(matches!(
+        internal_suggest_card_html(&gamestate_finished, SBudget::new(/*n_samples*/1, /*n_branches*/1)),
+        Err(VApiError::GameFinished),
+    ));
+}
@@ -1,4 +1,131 @@
+const STR_KEY_HINT_SAMPLES = "openschafkopf_hint_samples";
+const STR_KEY_HINT_BRANCHES = "openschafkopf_hint_branches";
+
+// Lets the user choose how much time hints may take.
+function append_hint_budget_settings() {
+    const div_settings = document.createElement("div");
+    div_settings.style = "position: fixed; bottom: 5px; right: 5px; padding: 5px; background: white; border: 1px solid black; z-index: 1000;";
+    div_settings.append("openschafkopf hints: ");
+    for (const [str_key, str_label, n_default] of [
+        [STR_KEY_HINT_SAMPLES, "Samples", 20],
+        [STR_KEY_HINT_BRANCHES, "Branches", 2],
+    ]) {
+        const input = document.createElement("input");
+        input.type = "number";
+        input.min = 1;
+        input.style = "width: 4em;";
+        input.value = localStorage.getItem(str_key) ?? n_default;
+        input.addEventListener("change", function() {
+            localStorage.setItem(str_key, input.value);
+        });
+        div_settings.append(" " + str_label + " ", input);
+    }
+    document.body.append(div_settings);
+}
+
+function hint_budget(str_key, n_default) {
+    const n = parseInt(localStorage.getItem(str_key));
+    return Number.isInteger(n) && 0 < n ? n : n_default;
+}
+
+// Starts the Web Worker computing hints (so that the page stays responsive) from a Blob URL, as
+// content scripts may not start workers from extension URLs.
+async function make_hint_worker() {
+    const fetch_extension_file = (str_file) => fetch(browser.runtime.getURL(str_file));
+    const [str_js_bindgen, str_js_worker, wasm_module] = await Promise.all([
+        fetch_extension_file("sauspiel_webext.js").then(response => response.text()),
+        fetch_extension_file("sauspiel_webext_worker.js").then(response => response.text()),
+        fetch_extension_file("sauspiel_webext_bg.wasm").then(response => response.arrayBuffer()),
+    ]);
+    const worker = new Worker(URL.createObjectURL(new Blob([str_js_bindgen, str_js_worker], {type: "text/javascript"})));
+    worker.postMessage({wasm_module: wasm_module}, [wasm_module]);
+    return worker;
+}
+
+// Sends requests to the worker and shows the results in the given divs.
+function make_hint_requester(worker) {
+    const mapi_request_div_result = new Map();
+    let i_request = 0;
+    worker.onmessage = function(event) {
+        const div_result = mapi_request_div_result.get(event.data.i_request);
+        mapi_request_div_result.delete(event.data.i_request);
+        if (event.data.str_html !== undefined) {
+            div_result.innerHTML = event.data.str_html;
+        } else {
+            div_result.textContent = "Error: " + event.data.str_error;
+        }
+    };
+    return function(div_result, request) {
+        div_result.textContent = "Berechne...";
+        i_request += 1;
+        mapi_request_div_result.set(i_request, div_result);
+        worker.postMessage({
+            i_request: i_request,
+            n_suggest_card_samples: hint_budget(STR_KEY_HINT_SAMPLES, 20),
+            n_suggest_card_branches: hint_budget(STR_KEY_HINT_BRANCHES, 2),
+            ...request,
+        });
+    };
+}
+
+// Finished games: a hint for each played card.
+function setup_game_hints(request_hint) {
+    for (const div_hint of document.getElementsByClassName("openschafkopf-hint")) {
+        const button = document.createElement("button");
+        button.textContent = "Tipp";
+        const div_result = document.createElement("div");
+        button.addEventListener("click", function() {
+            request_hint(div_result, {str_position: div_hint.dataset.openschafkopfPosition});
+        });
+        div_hint.append(button, div_result);
+    }
+}
+
+// During play: The live table is not parsed (yet), so the user enters the situation.
+function setup_live_hints(request_hint) {
+    const div_live = document.createElement("div");
+    div_live.style = "position: fixed; bottom: 45px; right: 5px; padding: 5px; background: white; border: 1px solid black; z-index: 1000;";
+    const mapstrinput = {};
+    for (const [str_key, str_label, str_placeholder] of [
+        ["str_rules", "Spiel", "Rufspiel mit der Gras-Sau von 1"],
+        ["str_cards_played", "Gespielte Karten", "EA E7 EK E9"],
+        ["str_hand", "Meine Karten", "EO GO HA HZ SA S7 G9"],
+    ]) {
+        const input = document.createElement("input");
+        input.placeholder = str_placeholder;
+        mapstrinput[str_key] = input;
+        const div_row = document.createElement("div");
+        div_row.append(str_label + ": ", input);
+        div_live.append(div_row);
+    }
+    const button = document.createElement("button");
+    button.textContent = "Tipp";
+    const div_result = document.createElement("div");
+    button.addEventListener("click", function() {
+        request_hint(div_result, Object.fromEntries(
+            Object.entries(mapstrinput).map(([str_key, input]) => [str_key, input.value])
+        ));
+    });
+    div_live.append(button, div_result);
+    document.body.append(div_live);
+}
+
+function is_game_page() {
+    return location.protocol == "file:" || location.pathname.startsWith("/spiele/");
+}
+
 async function run() {
     await wasm_bindgen(browser.runtime.getURL("sauspiel_webext_bg.wasm"));
+    const b_game_page = is_game_page();
+    if (b_game_page) {
+        wasm_bindgen.analyze_game_page();
+    }
+    append_hint_budget_settings();
+    const request_hint = make_hint_requester(await make_hint_worker());
+    if (b_game_page) {
+        setup_game_hints(request_hint);
+    } else {
+        setup_live_hints(request_hint);
+    }
 }
 run();
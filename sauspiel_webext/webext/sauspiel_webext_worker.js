@@ -0,0 +1,26 @@
+// Content scripts may not start Web Workers from extension URLs (they run with the page's origin), so the
+// content script starts this worker from a Blob URL, with sauspiel_webext.js prepended. The wasm module
+// comes with the first message.
+let promise_initialized = null;
+
+onmessage = async function(event) {
+    if (event.data.wasm_module !== undefined) {
+        promise_initialized = wasm_bindgen(event.data.wasm_module);
+        return;
+    }
+    await promise_initialized;
+    const {i_request, str_position, str_rules, str_cards_played, str_hand, n_suggest_card_samples, n_suggest_card_branches} = event.data;
+    try {
+        postMessage({
+            i_request: i_request,
+            str_html: str_position !== undefined
+                ? wasm_bindgen.suggest_card_html(str_position, n_suggest_card_samples, n_suggest_card_branches)
+                : wasm_bindgen.suggest_card_html_live(str_rules, str_cards_played, str_hand, n_suggest_card_samples, n_suggest_card_branches),
+        });
+    } catch (err) {
+        postMessage({
+            i_request: i_request,
+            str_error: String(err),
+        });
+    }
+};